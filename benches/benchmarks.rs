@@ -1,14 +1,22 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::hint::black_box;
 use tokio::runtime::Runtime;
 use std::time::Duration;
 use thrustbench::config::{HttpConfig, TcpConfig, UdsConfig};
+use thrustbench::protocol::{Echo, Protocol, Request};
 use thrustbench::runner::{HttpRunner, TcpRunner, UdsRunner};
+use thrustbench::server::{self, ServerOptions};
+use thrustbench::stats::{self, Statistics};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use tokio::net::TcpListener;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
 use tokio::task;
 use tokio::net::UnixListener;
 use std::path::PathBuf;
 use std::{thread, fs};
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 // HTTP benchmarks
 fn bench_http(c: &mut Criterion) {
@@ -22,20 +30,9 @@ fn bench_http(c: &mut Criterion) {
             
             println!("HTTP server listening on 127.0.0.1:8080");
             
-            loop {
-                let (mut stream, _) = listener.accept().await.unwrap();
-                
-                task::spawn(async move {
-                    let mut buf = [0; 1024];
-                    
-                    // Read request
-                    let _ = stream.read(&mut buf).await.unwrap();
-                    
-                    // Send response
-                    let response = "HTTP/1.1 200 OK\r\nContent-Length: 13\r\nContent-Type: text/plain\r\n\r\nHello, World!";
-                    let _ = stream.write_all(response.as_bytes()).await;
-                });
-            }
+            // Answer with a 13 byte body, the size of "Hello, World!"
+            let options = ServerOptions { response_size: Some(13), ..Default::default() };
+            server::serve_http(listener, options).await;
         });
     });
     
@@ -55,16 +52,17 @@ fn bench_http(c: &mut Criterion) {
         None,
         Some(10),
         Some(1000),
-        Some(30),
+        Some(Duration::from_secs(30)),
         Some(1000),
         false,
     );
     
+    // One client runtime for every iteration, the way `--runs` shares one
+    let client = Runtime::new().unwrap();
     group.bench_function("http_get", |b| {
         b.iter(|| {
             let runner = HttpRunner::new(config.clone());
-            let rt = Runtime::new().unwrap();
-            black_box(rt.block_on(async {
+            black_box(client.block_on(async {
                 runner.run().await.unwrap()
             }));
         });
@@ -85,19 +83,7 @@ fn bench_tcp(c: &mut Criterion) {
             
             println!("TCP server listening on 127.0.0.1:8081");
             
-            loop {
-                let (mut stream, _) = listener.accept().await.unwrap();
-                
-                task::spawn(async move {
-                    let mut buf = [0; 1024];
-                    
-                    // Read data
-                    let n = stream.read(&mut buf).await.unwrap();
-                    
-                    // Echo back
-                    let _ = stream.write_all(&buf[..n]).await;
-                });
-            }
+            server::serve_tcp(listener, ServerOptions::default()).await;
         });
     });
     
@@ -116,16 +102,16 @@ fn bench_tcp(c: &mut Criterion) {
         Some("hello".to_string()),
         Some(10),
         Some(1000),
-        Some(30),
+        Some(Duration::from_secs(30)),
         Some(1000),
         false,
     );
     
+    let client = Runtime::new().unwrap();
     group.bench_function("tcp_echo", |b| {
         b.iter(|| {
             let runner = TcpRunner::new(config.clone());
-            let rt = Runtime::new().unwrap();
-            black_box(rt.block_on(async {
+            black_box(client.block_on(async {
                 runner.run().await.unwrap()
             }));
         });
@@ -134,6 +120,118 @@ fn bench_tcp(c: &mut Criterion) {
     group.finish();
 }
 
+/// Stream that counts the reads returning data
+struct CountingReads {
+    inner: DuplexStream,
+    reads: usize,
+}
+
+impl AsyncRead for CountingReads {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if buf.filled().len() > before {
+            self.reads += 1;
+        }
+        poll
+    }
+}
+
+impl AsyncWrite for CountingReads {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+    
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+    
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Reads one 4 MiB response with the default protocol, returning the number of reads
+async fn read_large_response(response_size: Option<usize>) -> usize {
+    const SIZE: usize = 4 * 1024 * 1024;
+    let (client, mut server) = tokio::io::duplex(1024 * 1024);
+    
+    task::spawn(async move {
+        let _ = server.write_all(&vec![b'x'; SIZE]).await;
+        let _ = server.shutdown().await;
+    });
+    
+    let mut conn = CountingReads { inner: client, reads: 0 };
+    let request = Request {
+        data: None,
+        expect: None,
+        expect_length: None,
+        require_non_empty: false,
+        timeout: Duration::from_secs(5),
+        buffer_size: 8192,
+        response_size,
+        half_close: false,
+        offload_above: None,
+    };
+    let response = Echo.exchange(&mut conn, &request).await.unwrap();
+    assert_eq!(response.data.len(), SIZE);
+    conn.reads
+}
+
+// Response size hint benchmarks
+fn bench_response_size_hint(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    
+    let mut group = c.benchmark_group("response_size_hint");
+    group.measurement_time(Duration::from_secs(5));
+    group.sample_size(10);
+    
+    for (name, hint) in [("no_hint", None), ("hint_4mb", Some(4 * 1024 * 1024))] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                black_box(rt.block_on(read_large_response(hint)));
+            });
+        });
+    }
+    
+    group.finish();
+}
+
+/// Largest run the aggregation benchmark covers
+const AGGREGATION_SAMPLES: usize = 10_000_000;
+
+/// Unsorted response times with a long tail, like a real run collects them
+fn collected_latencies(count: usize) -> Vec<Duration> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..count)
+        .map(|_| Duration::from_nanos(100_000 + (rng.random::<f64>().powi(4) * 50e6) as u64))
+        .collect()
+}
+
+/// The end-of-run aggregation: sort, then compute the report's statistics
+fn aggregate(mut latencies: Vec<Duration>) -> Statistics {
+    stats::sort_latencies(&mut latencies);
+    let count = latencies.len();
+    Statistics::from_samples(&latencies, Duration::from_secs(60), count, count)
+}
+
+// End-of-run aggregation benchmarks
+fn bench_aggregation(c: &mut Criterion) {
+    let latencies = collected_latencies(AGGREGATION_SAMPLES);
+    
+    let mut group = c.benchmark_group("aggregation");
+    group.sample_size(10);
+    
+    for count in [100_000, 1_000_000, AGGREGATION_SAMPLES] {
+        let latencies = &latencies[..count];
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter_batched(|| latencies.to_vec(), aggregate, BatchSize::LargeInput);
+        });
+    }
+    
+    group.finish();
+}
+
 // Unix Domain Socket benchmarks (skipped on Windows)
 #[cfg(unix)]
 fn bench_uds(c: &mut Criterion) {
@@ -159,19 +257,7 @@ fn bench_uds(c: &mut Criterion) {
             
             println!("UDS server listening on {}", socket_path);
             
-            loop {
-                let (mut stream, _) = listener.accept().await.unwrap();
-                
-                task::spawn(async move {
-                    let mut buf = [0; 1024];
-                    
-                    // Read data
-                    let n = stream.read(&mut buf).await.unwrap();
-                    
-                    // Echo back
-                    let _ = stream.write_all(&buf[..n]).await;
-                });
-            }
+            server::serve_uds(listener, ServerOptions::default()).await;
         });
     });
     
@@ -190,16 +276,16 @@ fn bench_uds(c: &mut Criterion) {
         Some("hello".to_string()),
         Some(10),
         Some(1000),
-        Some(30),
+        Some(Duration::from_secs(30)),
         Some(1000),
         false,
     );
     
+    let client = Runtime::new().unwrap();
     group.bench_function("uds_echo", |b| {
         b.iter(|| {
             let runner = UdsRunner::new(config.clone());
-            let rt = Runtime::new().unwrap();
-            black_box(rt.block_on(async {
+            black_box(client.block_on(async {
                 runner.run().await.unwrap()
             }));
         });
@@ -213,5 +299,5 @@ fn bench_uds(_c: &mut Criterion) {
     // Skip on non-Unix platforms
 }
 
-criterion_group!(benches, bench_http, bench_tcp, bench_uds);
+criterion_group!(benches, bench_http, bench_tcp, bench_response_size_hint, bench_aggregation, bench_uds);
 criterion_main!(benches);
\ No newline at end of file
@@ -1,6 +1,15 @@
-use std::path::PathBuf;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::Duration;
 use std::fs;
+use hyper::Uri;
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+use crate::error::BenchmarkError;
+use crate::http;
+use crate::protocol::Pattern;
 
 const DEFAULT_CONCURRENCY: usize = 1;
 const DEFAULT_REQUESTS: usize = 100;
@@ -17,20 +26,701 @@ pub trait BenchmarkConfig {
     fn is_keep_alive(&self) -> bool;
 }
 
-#[derive(Clone)]
+/// How the send times of a `--rate` paced run are spaced (`--arrival`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arrival {
+    /// Evenly spaced, exactly `1 / rate` apart
+    #[default]
+    Constant,
+    /// Exponentially distributed gaps, as from many independent clients
+    Poisson,
+    /// Gaps drawn uniformly between 0 and `2 / rate`
+    Uniform,
+}
+
+impl FromStr for Arrival {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(Arrival::Constant),
+            "poisson" => Ok(Arrival::Poisson),
+            "uniform" => Ok(Arrival::Uniform),
+            other => Err(format!("Unknown arrival distribution '{}' (expected constant, poisson or uniform)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Arrival {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Arrival::Constant => "constant",
+            Arrival::Poisson => "poisson",
+            Arrival::Uniform => "uniform",
+        })
+    }
+}
+
+/// `--rate-ramp`: a paced run whose target rate moves linearly from `start`
+/// to `end` requests/sec over the run's duration
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateRamp {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl RateRamp {
+    /// The target rate `elapsed` into a ramp lasting `duration`, holding
+    /// `end` once the ramp is over
+    pub fn rate_at(&self, elapsed: Duration, duration: Duration) -> f64 {
+        let progress = if duration.is_zero() { 1.0 } else { (elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0) };
+        self.start + (self.end - self.start) * progress
+    }
+    
+    /// Time into a ramp lasting `duration` by which `requests` requests are
+    /// due at the ramping rate: the inverse of the rate integrated over time
+    pub fn time_of(&self, requests: f64, duration: Duration) -> Duration {
+        let duration = duration.as_secs_f64();
+        // Requests due over the whole ramp; after it the rate holds at `end`
+        let ramped = (self.start + self.end) / 2.0 * duration;
+        let secs = if requests >= ramped {
+            duration + (requests - ramped) / self.end
+        } else {
+            let slope = (self.end - self.start) / duration;
+            if slope == 0.0 {
+                requests / self.start
+            } else {
+                (-self.start + (self.start * self.start + 2.0 * slope * requests).sqrt()) / slope
+            }
+        };
+        Duration::from_secs_f64(secs)
+    }
+}
+
+/// Parses a `--rate-ramp` such as `100:5000` (requests/sec at the start and
+/// at the end of the run). Used as a clap value parser.
+pub fn parse_rate_ramp(s: &str) -> Result<RateRamp, String> {
+    let invalid = || format!("invalid rate ramp '{}', expected START:END in requests/sec such as 100:5000", s);
+    let (start, end) = s.split_once(':').ok_or_else(invalid)?;
+    let rate = |r: &str| r.trim().parse::<f64>().ok().filter(|r| *r > 0.0 && r.is_finite());
+    match (rate(start), rate(end)) {
+        (Some(start), Some(end)) => Ok(RateRamp { start, end }),
+        _ => Err(invalid()),
+    }
+}
+
+/// Where `--cache-bust` puts the random value that makes every request
+/// unique to caches between the client and the origin
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBust {
+    /// A query parameter, `?_cb=<random>`
+    Query,
+    /// The named header
+    Header(String),
+}
+
+/// PROXY protocol header version sent first on every TCP connection
+/// (`--proxy-protocol`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyProtocol {
+    /// Human-readable `PROXY TCP4 ...` line
+    V1,
+    /// Binary header
+    V2,
+}
+
+impl FromStr for ProxyProtocol {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1" | "1" => Ok(ProxyProtocol::V1),
+            "v2" | "2" => Ok(ProxyProtocol::V2),
+            other => Err(format!("Unknown PROXY protocol version '{}' (expected v1 or v2)", other)),
+        }
+    }
+}
+
+/// Durations in the `--output-dir` `config.json`, as whole microseconds
+mod micros {
+    use std::time::Duration;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_micros() as u64)
+    }
+
+    pub fn serialize_option<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&(duration.as_micros() as u64)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+/// One send/expect pair of a TCP/UDS script
+#[derive(Clone, Debug, Serialize)]
+pub struct ScriptStep {
+    pub send: Vec<u8>,
+    pub expect: Option<Pattern>,
+}
+
+/// Loads a `--script` file. Each non-empty line not starting with `#` is one
+/// exchange: the data to send, optionally followed by a tab and the expected
+/// response pattern (regex). `\r`, `\n`, `\t` and `\\` are unescaped in the
+/// data. The last exchange is the measured one; the rest are setup. The
+/// patterns are compiled here, so an invalid one fails before the run.
+pub fn load_script(path: &Path) -> Result<Vec<ScriptStep>, BenchmarkError> {
+    let contents = fs::read_to_string(path)?;
+
+    let steps: Vec<ScriptStep> = contents.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (send, expect) = match line.split_once('\t') {
+                Some((send, expect)) => (send, Some(Pattern::new(expect)?)),
+                None => (line, None),
+            };
+            Ok(ScriptStep {
+                send: unescape(send).into_bytes(),
+                expect,
+            })
+        })
+        .collect::<Result<_, BenchmarkError>>()?;
+
+    if steps.is_empty() {
+        return Err(BenchmarkError::Config(format!(
+            "Script {:?} contains no exchanges",
+            path
+        )));
+    }
+
+    Ok(steps)
+}
+
+/// Parses a byte size such as `512`, `64kb` or `1mb` (case-insensitive,
+/// binary multiples). Used as a clap value parser.
+pub fn parse_size(s: &str) -> Result<usize, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let (number, multiplier) = if let Some(n) = lower.strip_suffix("kb") {
+        (n, 1024)
+    } else if let Some(n) = lower.strip_suffix("mb") {
+        (n, 1024 * 1024)
+    } else if let Some(n) = lower.strip_suffix("gb") {
+        (n, 1024 * 1024 * 1024)
+    } else if let Some(n) = lower.strip_suffix('b') {
+        (n, 1)
+    } else {
+        (lower.as_str(), 1)
+    };
+
+    number.trim().parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size '{}', expected e.g. 512, 64kb or 1mb", s))
+}
+
+/// Parses a `--duration` such as `500ms`, `1.5s`, `2m` or `1m30s`; a bare
+/// number is in seconds, and `0` sets no time limit. Used as a clap value
+/// parser.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let trimmed = s.trim();
+    let unit_start = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(unit_start);
+    let seconds = match unit {
+        "" => Some(1.0),
+        "ns" => Some(1e-9),
+        "us" => Some(1e-6),
+        "ms" => Some(1e-3),
+        "s" => Some(1.0),
+        "m" => Some(60.0),
+        "h" => Some(3600.0),
+        _ => None,
+    };
+    // Fractions of a single unit, like `1.5s`, which humantime doesn't take
+    let duration = match (number.parse::<f64>(), seconds) {
+        (Ok(n), Some(seconds)) if (n * seconds).is_finite() => Some(Duration::from_secs_f64(n * seconds)),
+        _ => humantime::parse_duration(trimmed).ok(),
+    };
+    duration.ok_or_else(|| format!("invalid duration '{}', expected seconds or e.g. 500ms, 1.5s, 1m30s", s))
+}
+
+/// Parses a latency such as `500ms`, `2s` or `250us`; a bare number is in
+/// milliseconds, like `--timeout`. Used as a clap value parser.
+pub fn parse_latency(s: &str) -> Result<Duration, String> {
+    let trimmed = s.trim();
+    let latency = match trimmed.parse::<f64>() {
+        Ok(ms) if ms.is_finite() && ms >= 0.0 => Some(Duration::from_secs_f64(ms / 1000.0)),
+        Ok(_) => None,
+        Err(_) => humantime::parse_duration(trimmed).ok(),
+    };
+    latency
+        .filter(|latency| !latency.is_zero())
+        .ok_or_else(|| format!("invalid latency '{}', expected e.g. 500ms or 2s", s))
+}
+
+/// Parses a latency percentile such as `99` or `99.9`, between 0 (exclusive)
+/// and 100. Used as a clap value parser.
+pub fn parse_percentile(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(p) if p > 0.0 && p <= 100.0 => Ok(p),
+        _ => Err(format!("invalid percentile '{}', expected a number in (0, 100]", s)),
+    }
+}
+
+/// Parses a `--sample-rate` fraction such as `0.01`, between 0 (exclusive)
+/// and 1. Used as a clap value parser.
+pub fn parse_sample_rate(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate <= 1.0 => Ok(rate),
+        _ => Err(format!("invalid sample rate '{}', expected a fraction in (0, 1]", s)),
+    }
+}
+
+/// Parses a regression tolerance such as `10%` or `2.5` (percent) into a
+/// fraction. Used as a clap value parser.
+pub fn parse_tolerance(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim();
+    let number = trimmed.strip_suffix('%').unwrap_or(trimmed);
+    match number.trim().parse::<f64>() {
+        Ok(p) if p >= 0.0 && p.is_finite() => Ok(p / 100.0),
+        _ => Err(format!("invalid tolerance '{}', expected a percentage such as 10%", s)),
+    }
+}
+
+/// Parses a `--warmup-cv` percentage such as `5` or `2.5%`, which must be
+/// above 0. Used as a clap value parser.
+pub fn parse_warmup_cv(s: &str) -> Result<f64, String> {
+    let trimmed = s.trim();
+    let number = trimmed.strip_suffix('%').unwrap_or(trimmed);
+    match number.trim().parse::<f64>() {
+        Ok(p) if p > 0.0 && p.is_finite() => Ok(p),
+        _ => Err(format!("invalid coefficient of variation '{}', expected a percentage above 0", s)),
+    }
+}
+
+/// Loads the `--data-file` payloads separated by `delimiter` (with `\r`,
+/// `\n`, `\t` and `\\` unescaped), skipping empty records.
+pub fn load_payloads(path: &Path, delimiter: &str) -> Result<Vec<Vec<u8>>, BenchmarkError> {
+    let delimiter = unescape(delimiter).into_bytes();
+    if delimiter.is_empty() {
+        return Err(BenchmarkError::Config("Payload delimiter must not be empty".to_string()));
+    }
+
+    let contents = fs::read(path)?;
+    let mut payloads = Vec::new();
+    let mut rest = &contents[..];
+    while let Some(pos) = rest.windows(delimiter.len()).position(|w| w == delimiter) {
+        payloads.push(rest[..pos].to_vec());
+        rest = &rest[pos + delimiter.len()..];
+    }
+    payloads.push(rest.to_vec());
+    payloads.retain(|p| !p.is_empty());
+
+    if payloads.is_empty() {
+        return Err(BenchmarkError::Config(format!("{:?} contains no payloads", path)));
+    }
+
+    Ok(payloads)
+}
+
+/// Loads a `--hosts-file`: one `host:port` per non-empty line, ignoring
+/// lines starting with `#`. IPv6 addresses are written `[::1]:8080`.
+pub fn load_hosts(path: &Path) -> Result<Vec<String>, BenchmarkError> {
+    let contents = fs::read_to_string(path)?;
+
+    let hosts: Vec<String> = contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    for host in &hosts {
+        // An IPv6 address must be bracketed, or its last group reads as the port
+        let valid = host.rsplit_once(':')
+            .is_some_and(|(name, port)| {
+                let bracketed = name.len() > 2 && name.starts_with('[') && name.ends_with(']');
+                !name.is_empty() && (bracketed || !name.contains(':')) && port.parse::<u16>().is_ok()
+            });
+        if !valid {
+            return Err(BenchmarkError::Config(format!(
+                "Invalid host '{}' in {:?}; expected host:port, with IPv6 addresses as [addr]:port",
+                host,
+                path
+            )));
+        }
+    }
+
+    if hosts.is_empty() {
+        return Err(BenchmarkError::Config(format!("{:?} contains no hosts", path)));
+    }
+
+    Ok(hosts)
+}
+
+/// One line of a `--url-file`: a share of the requests, with the method and
+/// body they are sent with
+#[derive(Debug, Clone, Serialize)]
+pub struct Endpoint {
+    /// Relative share of the requests; `1` unless the line starts with a number
+    pub weight: u32,
+    /// Method for this URL; `--method` when the line names none
+    pub method: Option<String>,
+    pub url: String,
+    /// Body for this URL; the request body options when the line has none
+    pub body: Option<String>,
+}
+
+/// Loads a `--url-file` of `[WEIGHT] [METHOD] URL [BODY]` lines, e.g.
+/// `3 POST /items {"name":"x"}` or `GET /items`. A URL starting with `/` is
+/// resolved against the scheme and authority of `base`; the body is the rest
+/// of the line.
+pub fn load_endpoints(path: &Path, base: &str) -> Result<Vec<Endpoint>, BenchmarkError> {
+    let base: Uri = base.parse()
+        .map_err(|_| BenchmarkError::Config(format!("Invalid URL: {}", base)))?;
+    let contents = fs::read_to_string(path)?;
+    let invalid = |line: &str| BenchmarkError::Config(format!(
+        "Invalid line '{}' in {:?}; expected [WEIGHT] [METHOD] URL [BODY] with a positive weight",
+        line,
+        path
+    ));
+
+    let mut endpoints = Vec::new();
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (mut word, mut rest) = split_word(line);
+        let weight = match word.parse::<u32>() {
+            Ok(weight) => {
+                (word, rest) = split_word(rest);
+                weight
+            },
+            Err(_) => 1,
+        };
+        // A method is an upper-case word with a URL after it
+        let method = if !rest.is_empty() && word.bytes().all(|b| b.is_ascii_uppercase()) {
+            let method = word.to_string();
+            (word, rest) = split_word(rest);
+            Some(method)
+        } else {
+            None
+        };
+
+        let url = if word.starts_with('/') {
+            format!("{}{}", http::origin(&base), word)
+        } else if word.parse::<Uri>().is_ok_and(|uri| uri.scheme().is_some() && uri.authority().is_some()) {
+            word.to_string()
+        } else {
+            return Err(invalid(line));
+        };
+        if weight == 0 {
+            return Err(invalid(line));
+        }
+        endpoints.push(Endpoint {
+            weight,
+            method,
+            url,
+            body: (!rest.is_empty()).then(|| rest.to_string()),
+        });
+    }
+
+    if endpoints.is_empty() {
+        return Err(BenchmarkError::Config(format!("{:?} contains no URLs", path)));
+    }
+
+    Ok(endpoints)
+}
+
+/// The first whitespace-separated word of `s` and the rest after it
+fn split_word(s: &str) -> (&str, &str) {
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+/// Checks that a run has a limit. Zero `requests` means no request limit and
+/// a zero `duration` no time limit, so at most one of them can be zero.
+pub fn check_limits(requests: usize, duration: Duration) -> Result<(), BenchmarkError> {
+    if requests == 0 && duration.is_zero() {
+        return Err(BenchmarkError::Config(
+            "--requests 0 and --duration 0 leave the run without a limit; set a request count or a positive duration".to_string()
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that there is either a single expected pattern, applying to every
+/// payload, or one per payload, and compiles them once for the whole run so
+/// an invalid pattern fails before the first request.
+pub fn compile_expects(payloads: &[Vec<u8>], expects: &[String]) -> Result<Vec<Pattern>, BenchmarkError> {
+    if expects.len() > 1 && expects.len() != payloads.len() {
+        return Err(BenchmarkError::Config(format!(
+            "{} expected patterns given for {} payloads; give one, or one per payload",
+            expects.len(),
+            payloads.len()
+        )));
+    }
+    expects.iter().map(|pattern| Pattern::new(pattern)).collect()
+}
+
+/// Picks the payload and expected pattern of the `n`th request, cycling
+/// through the payloads round-robin.
+pub fn payload_for<'a>(payloads: &'a [Vec<u8>], expects: &'a [Pattern], n: usize) -> (Option<&'a [u8]>, Option<&'a Pattern>) {
+    if payloads.is_empty() {
+        return (None, expects.first());
+    }
+
+    let index = n % payloads.len();
+    let expect = if expects.len() == 1 { expects.first() } else { expects.get(index) };
+    (Some(&payloads[index]), expect)
+}
+
+/// Bounds on the length of every response (`--expect-length`,
+/// `--expect-length-min`, `--expect-length-max`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ExpectedLength {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl ExpectedLength {
+    /// Combines the flags, `exact` setting both bounds; `None` when none is given
+    pub fn new(exact: Option<usize>, min: Option<usize>, max: Option<usize>) -> Result<Option<Self>, BenchmarkError> {
+        let (min, max) = match exact {
+            Some(exact) => (Some(exact), Some(exact)),
+            None => (min, max),
+        };
+        if let (Some(min), Some(max)) = (min, max)
+            && min > max
+        {
+            return Err(BenchmarkError::Config(format!(
+                "--expect-length-min {} is above --expect-length-max {}",
+                min, max
+            )));
+        }
+        Ok((min.is_some() || max.is_some()).then_some(ExpectedLength { min, max }))
+    }
+
+    /// Fails a response of `len` bytes outside the bounds
+    pub fn check(&self, len: usize) -> Result<(), BenchmarkError> {
+        let expected = match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max && len != min => format!("{}", min),
+            (Some(min), _) if len < min => format!("at least {}", min),
+            (_, Some(max)) if len > max => format!("at most {}", max),
+            _ => return Ok(()),
+        };
+        Err(BenchmarkError::ResponseValidation(format!("Response is {} bytes, expected {}", len, expected)))
+    }
+}
+
+/// Reading response bodies chunk by chunk as they arrive (`--stream-response`),
+/// for streaming endpoints such as server-sent events
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct StreamResponse {
+    /// Stop reading after this many chunks (`--max-chunks`); otherwise the
+    /// body is read to its end, the timeout or the end of the run
+    pub max_chunks: Option<usize>,
+}
+
+/// Parses an `--expect-json` assertion of the form `path=value`. Used as a
+/// clap value parser.
+pub fn parse_json_expectation(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((path, value)) if !path.trim().is_empty() => Ok((path.trim().to_string(), value.to_string())),
+        _ => Err(format!("invalid JSON expectation '{}', expected path=value", s)),
+    }
+}
+
+/// Generates `size` random bytes for a request body
+pub fn random_payload<R: Rng>(rng: &mut R, size: usize) -> Vec<u8> {
+    let mut payload = vec![0u8; size];
+    rng.fill(&mut payload[..]);
+    payload
+}
+
+/// Request bytes and expected response pattern (regex) for common TCP
+/// services, selected with `tcp --preset`
+pub const PRESETS: &[(&str, &str, &str)] = &[
+    ("redis-ping", "PING\r\n", r"^\+PONG\r\n"),
+    ("redis-info", "INFO server\r\n", r"redis_version:"),
+    ("memcached-get", "get thrustbench\r\n", r"END\r\n"),
+    ("memcached-stats", "stats\r\n", r"END\r\n"),
+    ("memcached-version", "version\r\n", r"^VERSION "),
+    ("http-get", "GET / HTTP/1.0\r\n\r\n", r"^HTTP/1\.[01] \d{3}"),
+];
+
+/// Looks up a preset's `(data, expect)` pair by name
+pub fn preset(name: &str) -> Option<(&'static str, &'static str)> {
+    PRESETS.iter()
+        .find(|(preset, _, _)| *preset == name)
+        .map(|(_, data, expect)| (*data, *expect))
+}
+
+/// Splits `key:value` header arguments, skipping malformed ones
+fn parse_headers(headers: Option<Vec<String>>) -> Vec<(String, String)> {
+    match headers {
+        Some(h) => h.iter()
+            .filter_map(|h| {
+                let parts: Vec<&str> = h.splitn(2, ':').collect();
+                if parts.len() == 2 {
+                    Some((parts[0].trim().to_string(), parts[1].trim().to_string()))
+                } else {
+                    None
+                }
+            })
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            },
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[derive(Clone, Serialize)]
 pub struct HttpConfig {
     pub url: String,
+    /// Further target URLs; each request picks one of `url` and these at random
+    pub extra_urls: Vec<String>,
+    /// `host:port` addresses (`--hosts-file`) the requests for `url` are sent
+    /// to round-robin; `Host` and SNI still name the URL's host
+    pub hosts: Vec<String>,
+    /// Report statistics for each of `hosts` (`--per-host`)
+    pub per_host: bool,
+    /// Weighted URLs with their own method and body (`--url-file`); each
+    /// request picks one of them instead of `url`
+    pub endpoints: Vec<Endpoint>,
+    /// Report statistics for each method of `endpoints` (`--per-method`)
+    pub per_method: bool,
+    /// Seed for the per-worker RNGs so random choices repeat across runs
+    pub seed: Option<u64>,
     pub method: String,
     pub headers: Vec<(String, String)>,
+    /// Overrides the `Host` header; defaults to the URL's host
+    pub host: Option<String>,
+    /// Overrides the TLS server name (SNI); defaults to the URL's host
+    pub sni: Option<String>,
+    /// Skip TLS certificate verification
+    pub insecure: bool,
+    /// Resume TLS sessions on a worker's later connections (`--tls-resumption`)
+    pub tls_resumption: bool,
+    /// Report when the server certificate expires (`--check-cert`)
+    pub check_cert: bool,
+    /// Fail the run when the certificate expires in fewer days than this
+    /// (`--cert-min-days`)
+    pub cert_min_days: Option<u64>,
     pub body: Option<Vec<u8>>,
+    /// Number of requests written per batch on a persistent connection before
+    /// reading the responses (HTTP/1.1 pipelining)
+    pub pipeline: Option<usize>,
+    /// Size of a generated request body streamed in chunks instead of `body`
+    pub stream_body_size: Option<usize>,
+    /// Size of a random request body generated for every request
+    pub random_body: Option<usize>,
+    /// Generate the `random_body` payload once and reuse it
+    pub fixed_random_body: bool,
+    /// Classify responses as cache hits/misses from their headers
+    pub cache_stats: bool,
+    /// Average the phases of `Server-Timing` response headers
+    pub server_timing: bool,
+    /// Time responses to their headers and skip downloading the body
+    pub no_body: bool,
+    /// Read response bodies chunk by chunk and time each chunk
+    pub stream_response: Option<StreamResponse>,
+    /// Regex every response body must match
+    pub expect_body: Option<String>,
+    /// `(path, value)` pairs every JSON response body must contain
+    pub expect_json: Vec<(String, String)>,
+    /// Bounds on the length of every response body
+    pub expect_length: Option<ExpectedLength>,
+    /// Count responses with an empty body as failed (`--require-non-empty`)
+    pub require_non_empty: bool,
+    /// Check response bodies of at least this many bytes against the
+    /// expectations on the blocking thread pool
+    pub offload_validation_above: Option<usize>,
+    /// Send requests over HTTP/2: ALPN `h2` for `https`, prior knowledge
+    /// (h2c) for `http`
+    pub http2: bool,
+    /// Header carrying a unique id in every request (`--request-id-header`);
+    /// the slowest requests' ids are reported
+    pub request_id_header: Option<String>,
+    /// Send a random value with every request to defeat caches (`--cache-bust`)
+    pub cache_bust: Option<CacheBust>,
+    /// Header telling the server the request's timeout in milliseconds
+    /// (`--deadline-header`)
+    pub deadline_header: Option<String>,
     pub concurrency: usize,
     pub requests: usize,
+    #[serde(rename = "duration_us", serialize_with = "micros::serialize")]
     pub duration: Duration,
+    #[serde(rename = "timeout_us", serialize_with = "micros::serialize")]
     pub timeout: Duration,
+    /// Timeout for establishing the connection; defaults to `timeout`
+    #[serde(rename = "connect_timeout_us", serialize_with = "micros::serialize")]
+    pub connect_timeout: Duration,
+    /// Count responses whose total latency exceeds this as timeouts, while
+    /// `timeout` still bounds each request (`--timeout-percentile`)
+    #[serde(rename = "strict_timeout_us", serialize_with = "micros::serialize_option")]
+    pub strict_timeout: Option<Duration>,
+    /// Start at `timeout` and tighten toward this multiple of the observed
+    /// median latency as the run progresses (`--adaptive-timeout`)
+    pub adaptive_timeout: Option<f64>,
+    /// Stop the whole run at the first failed request (`--fail-fast`)
+    pub fail_fast: bool,
+    /// Report requests and latency for each worker and flag uneven load
+    /// (`--per-worker-stats`)
+    pub per_worker_stats: bool,
+    /// Stop the run as soon as the p99 of the last second or so of responses
+    /// exceeds this (`--abort-if-p99-above`)
+    #[serde(rename = "abort_p99_above_us", serialize_with = "micros::serialize_option")]
+    pub abort_p99_above: Option<Duration>,
+    /// Summarize response times into histograms as they arrive instead of
+    /// keeping a sample per request (`--low-memory`)
+    pub low_memory: bool,
+    /// Keep each distinct error with its first message for the report
+    /// (`--only-errors`)
+    pub only_errors: bool,
+    /// Only open and close connections, sending no requests, to measure
+    /// connection setup (`--connect-only`)
+    pub connect_only: bool,
     pub keep_alive: bool,
+    /// Close and reopen a kept-alive connection after this many requests
+    pub keepalive_requests: Option<usize>,
+    pub rate: Option<f64>,
+    /// Distribution of the gaps between `rate` paced send times
+    pub arrival: Arrival,
+    /// Moves the paced rate from `start` to `end` over `duration`; `rate`
+    /// then holds the start rate
+    pub rate_ramp: Option<RateRamp>,
+    pub correct_co: bool,
+    /// Maximum new connections per second across all workers
+    pub connect_rate: Option<f64>,
+    /// Source IP the client sockets bind to before connecting (`--local-addr`)
+    pub local_addr: Option<IpAddr>,
 }
 
 impl HttpConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         url: String,
         method: Option<String>,
@@ -39,24 +729,12 @@ impl HttpConfig {
         body_file: Option<PathBuf>,
         concurrency: Option<usize>,
         requests: Option<usize>,
-        duration: Option<u64>,
+        duration: Option<Duration>,
         timeout: Option<u64>,
         keep_alive: bool,
     ) -> Self {
         // Process headers
-        let headers = match headers {
-            Some(h) => h.iter()
-                .filter_map(|h| {
-                    let parts: Vec<&str> = h.splitn(2, ':').collect();
-                    if parts.len() == 2 {
-                        Some((parts[0].trim().to_string(), parts[1].trim().to_string()))
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-            None => Vec::new(),
-        };
+        let headers = parse_headers(headers);
         
         // Process body
         let body = if let Some(b) = body {
@@ -69,14 +747,59 @@ impl HttpConfig {
         
         HttpConfig {
             url,
+            extra_urls: Vec::new(),
+            hosts: Vec::new(),
+            per_host: false,
+            endpoints: Vec::new(),
+            per_method: false,
+            seed: None,
             method: method.unwrap_or_else(|| DEFAULT_METHOD.to_string()),
             headers,
+            host: None,
+            sni: None,
+            insecure: false,
+            tls_resumption: false,
+            check_cert: false,
+            cert_min_days: None,
             body,
+            pipeline: None,
+            stream_body_size: None,
+            random_body: None,
+            fixed_random_body: false,
+            cache_stats: false,
+            server_timing: false,
+            no_body: false,
+            stream_response: None,
+            expect_body: None,
+            expect_json: Vec::new(),
+            expect_length: None,
+            require_non_empty: false,
+            offload_validation_above: None,
+            http2: false,
+            request_id_header: None,
+            cache_bust: None,
+            deadline_header: None,
             concurrency: concurrency.unwrap_or(DEFAULT_CONCURRENCY),
             requests: requests.unwrap_or(DEFAULT_REQUESTS),
-            duration: Duration::from_secs(duration.unwrap_or(DEFAULT_DURATION)),
+            duration: duration.unwrap_or(Duration::from_secs(DEFAULT_DURATION)),
             timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            connect_timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            strict_timeout: None,
+            adaptive_timeout: None,
+            fail_fast: false,
+            per_worker_stats: false,
+            abort_p99_above: None,
+            low_memory: false,
+            only_errors: false,
+            connect_only: false,
             keep_alive,
+            keepalive_requests: None,
+            rate: None,
+            rate_ramp: None,
+            arrival: Arrival::Constant,
+            correct_co: false,
+            connect_rate: None,
+            local_addr: None,
         }
     }
 }
@@ -103,19 +826,94 @@ impl BenchmarkConfig for HttpConfig {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct TcpConfig {
     pub address: String,
-    pub data: Option<Vec<u8>>,
-    pub expect: Option<String>,
+    /// `host:port` addresses (`--hosts-file`) connected to round-robin
+    /// instead of `address`
+    pub hosts: Vec<String>,
+    /// Report statistics for each of `hosts` (`--per-host`)
+    pub per_host: bool,
+    /// Payloads sent round-robin, one per request
+    pub payloads: Vec<Vec<u8>>,
+    /// Expected response patterns (regex): one for every payload, or one per
+    /// payload in the same order
+    pub expects: Vec<String>,
+    /// Bounds on the length of every response
+    pub expect_length: Option<ExpectedLength>,
+    /// Count empty responses as failed (`--require-non-empty`)
+    pub require_non_empty: bool,
+    /// Match the expected pattern against responses of at least this many
+    /// bytes on the blocking thread pool
+    pub offload_validation_above: Option<usize>,
+    /// Exchanges run on each connection before the measured one (from `--script`)
+    pub setup: Vec<ScriptStep>,
+    /// Times the payload is sent, each awaiting its response, on every
+    /// connection (`--repeat`); the exchanges are measured together
+    pub repeat: usize,
+    /// Expected response size, used to size reads (`--expected-response-size`)
+    pub response_size: Option<usize>,
+    /// Shut down the write half of the connection after sending the payload
+    /// (`--half-close`), for servers that answer at end of input
+    pub half_close: bool,
+    /// Send a PROXY protocol header of this version right after connecting
+    /// (`--proxy-protocol`), as an L4 load balancer in front of the target would
+    pub proxy_protocol: Option<ProxyProtocol>,
+    /// Client address the PROXY header claims; defaults to the socket's own
+    /// address (`--proxy-source`)
+    pub proxy_source: Option<SocketAddr>,
     pub concurrency: usize,
     pub requests: usize,
+    #[serde(rename = "duration_us", serialize_with = "micros::serialize")]
     pub duration: Duration,
+    #[serde(rename = "timeout_us", serialize_with = "micros::serialize")]
     pub timeout: Duration,
+    /// Timeout for establishing the connection; defaults to `timeout`
+    #[serde(rename = "connect_timeout_us", serialize_with = "micros::serialize")]
+    pub connect_timeout: Duration,
+    /// Count responses whose total latency exceeds this as timeouts, while
+    /// `timeout` still bounds each request (`--timeout-percentile`)
+    #[serde(rename = "strict_timeout_us", serialize_with = "micros::serialize_option")]
+    pub strict_timeout: Option<Duration>,
+    /// Start at `timeout` and tighten toward this multiple of the observed
+    /// median latency as the run progresses (`--adaptive-timeout`)
+    pub adaptive_timeout: Option<f64>,
+    /// Stop the whole run at the first failed request (`--fail-fast`)
+    pub fail_fast: bool,
+    /// Report requests and latency for each worker and flag uneven load
+    /// (`--per-worker-stats`)
+    pub per_worker_stats: bool,
+    /// Stop the run as soon as the p99 of the last second or so of responses
+    /// exceeds this (`--abort-if-p99-above`)
+    #[serde(rename = "abort_p99_above_us", serialize_with = "micros::serialize_option")]
+    pub abort_p99_above: Option<Duration>,
+    /// Summarize response times into histograms as they arrive instead of
+    /// keeping a sample per request (`--low-memory`)
+    pub low_memory: bool,
+    /// Keep each distinct error with its first message for the report
+    /// (`--only-errors`)
+    pub only_errors: bool,
+    /// Only open and close connections, sending no requests, to measure
+    /// connection setup (`--connect-only`)
+    pub connect_only: bool,
     pub keep_alive: bool,
+    pub rate: Option<f64>,
+    /// Distribution of the gaps between `rate` paced send times
+    pub arrival: Arrival,
+    /// Seed for the `arrival` gaps, so `--arrival poisson|uniform` repeats across runs
+    pub seed: Option<u64>,
+    /// Moves the paced rate from `start` to `end` over `duration`; `rate`
+    /// then holds the start rate
+    pub rate_ramp: Option<RateRamp>,
+    pub correct_co: bool,
+    /// Maximum new connections per second across all workers
+    pub connect_rate: Option<f64>,
+    /// Source IP the client sockets bind to before connecting (`--local-addr`)
+    pub local_addr: Option<IpAddr>,
 }
 
 impl TcpConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         address: String,
         data: Option<String>,
@@ -123,28 +921,55 @@ impl TcpConfig {
         expect: Option<String>,
         concurrency: Option<usize>,
         requests: Option<usize>,
-        duration: Option<u64>,
+        duration: Option<Duration>,
         timeout: Option<u64>,
         keep_alive: bool,
     ) -> Self {
         // Process data
-        let data = if let Some(d) = data {
-            Some(d.into_bytes())
+        let payloads = if let Some(d) = data {
+            vec![d.into_bytes()]
         } else if let Some(path) = data_file {
-            fs::read(&path).ok()
+            fs::read(&path).ok().into_iter().collect()
         } else {
-            None
+            Vec::new()
         };
         
         TcpConfig {
             address,
-            data,
-            expect,
+            hosts: Vec::new(),
+            per_host: false,
+            payloads,
+            expects: expect.into_iter().collect(),
+            expect_length: None,
+            require_non_empty: false,
+            offload_validation_above: None,
+            setup: Vec::new(),
+            repeat: 1,
+            response_size: None,
+            half_close: false,
+            proxy_protocol: None,
+            proxy_source: None,
             concurrency: concurrency.unwrap_or(DEFAULT_CONCURRENCY),
             requests: requests.unwrap_or(DEFAULT_REQUESTS),
-            duration: Duration::from_secs(duration.unwrap_or(DEFAULT_DURATION)),
+            duration: duration.unwrap_or(Duration::from_secs(DEFAULT_DURATION)),
             timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            connect_timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            strict_timeout: None,
+            adaptive_timeout: None,
+            fail_fast: false,
+            per_worker_stats: false,
+            abort_p99_above: None,
+            low_memory: false,
+            only_errors: false,
+            connect_only: false,
             keep_alive,
+            rate: None,
+            rate_ramp: None,
+            arrival: Arrival::Constant,
+            seed: None,
+            correct_co: false,
+            connect_rate: None,
+            local_addr: None,
         }
     }
 }
@@ -171,19 +996,78 @@ impl BenchmarkConfig for TcpConfig {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct UdsConfig {
     pub path: PathBuf,
-    pub data: Option<Vec<u8>>,
-    pub expect: Option<String>,
+    /// Payloads sent round-robin, one per request
+    pub payloads: Vec<Vec<u8>>,
+    /// Expected response patterns (regex): one for every payload, or one per
+    /// payload in the same order
+    pub expects: Vec<String>,
+    /// Bounds on the length of every response
+    pub expect_length: Option<ExpectedLength>,
+    /// Count empty responses as failed (`--require-non-empty`)
+    pub require_non_empty: bool,
+    /// Match the expected pattern against responses of at least this many
+    /// bytes on the blocking thread pool
+    pub offload_validation_above: Option<usize>,
+    /// Exchanges run on each connection before the measured one (from `--script`)
+    pub setup: Vec<ScriptStep>,
+    /// Times the payload is sent, each awaiting its response, on every
+    /// connection (`--repeat`); the exchanges are measured together
+    pub repeat: usize,
+    /// Expected response size, used to size reads (`--expected-response-size`)
+    pub response_size: Option<usize>,
+    /// Shut down the write half of the connection after sending the payload
+    /// (`--half-close`), for servers that answer at end of input
+    pub half_close: bool,
     pub concurrency: usize,
     pub requests: usize,
+    #[serde(rename = "duration_us", serialize_with = "micros::serialize")]
     pub duration: Duration,
+    #[serde(rename = "timeout_us", serialize_with = "micros::serialize")]
     pub timeout: Duration,
+    /// Timeout for establishing the connection; defaults to `timeout`
+    #[serde(rename = "connect_timeout_us", serialize_with = "micros::serialize")]
+    pub connect_timeout: Duration,
+    /// Count responses whose total latency exceeds this as timeouts, while
+    /// `timeout` still bounds each request (`--timeout-percentile`)
+    #[serde(rename = "strict_timeout_us", serialize_with = "micros::serialize_option")]
+    pub strict_timeout: Option<Duration>,
+    /// Start at `timeout` and tighten toward this multiple of the observed
+    /// median latency as the run progresses (`--adaptive-timeout`)
+    pub adaptive_timeout: Option<f64>,
+    /// Stop the whole run at the first failed request (`--fail-fast`)
+    pub fail_fast: bool,
+    /// Report requests and latency for each worker and flag uneven load
+    /// (`--per-worker-stats`)
+    pub per_worker_stats: bool,
+    /// Stop the run as soon as the p99 of the last second or so of responses
+    /// exceeds this (`--abort-if-p99-above`)
+    #[serde(rename = "abort_p99_above_us", serialize_with = "micros::serialize_option")]
+    pub abort_p99_above: Option<Duration>,
+    /// Summarize response times into histograms as they arrive instead of
+    /// keeping a sample per request (`--low-memory`)
+    pub low_memory: bool,
+    /// Keep each distinct error with its first message for the report
+    /// (`--only-errors`)
+    pub only_errors: bool,
     pub keep_alive: bool,
+    pub rate: Option<f64>,
+    /// Distribution of the gaps between `rate` paced send times
+    pub arrival: Arrival,
+    /// Seed for the `arrival` gaps, so `--arrival poisson|uniform` repeats across runs
+    pub seed: Option<u64>,
+    /// Moves the paced rate from `start` to `end` over `duration`; `rate`
+    /// then holds the start rate
+    pub rate_ramp: Option<RateRamp>,
+    pub correct_co: bool,
+    /// Maximum new connections per second across all workers
+    pub connect_rate: Option<f64>,
 }
 
 impl UdsConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         path: PathBuf,
         data: Option<String>,
@@ -191,28 +1075,49 @@ impl UdsConfig {
         expect: Option<String>,
         concurrency: Option<usize>,
         requests: Option<usize>,
-        duration: Option<u64>,
+        duration: Option<Duration>,
         timeout: Option<u64>,
         keep_alive: bool,
     ) -> Self {
         // Process data
-        let data = if let Some(d) = data {
-            Some(d.into_bytes())
+        let payloads = if let Some(d) = data {
+            vec![d.into_bytes()]
         } else if let Some(path) = data_file {
-            fs::read(&path).ok()
+            fs::read(&path).ok().into_iter().collect()
         } else {
-            None
+            Vec::new()
         };
         
         UdsConfig {
             path,
-            data,
-            expect,
+            payloads,
+            expects: expect.into_iter().collect(),
+            expect_length: None,
+            require_non_empty: false,
+            offload_validation_above: None,
+            setup: Vec::new(),
+            repeat: 1,
+            response_size: None,
+            half_close: false,
             concurrency: concurrency.unwrap_or(DEFAULT_CONCURRENCY),
             requests: requests.unwrap_or(DEFAULT_REQUESTS),
-            duration: Duration::from_secs(duration.unwrap_or(DEFAULT_DURATION)),
+            duration: duration.unwrap_or(Duration::from_secs(DEFAULT_DURATION)),
             timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            connect_timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            strict_timeout: None,
+            adaptive_timeout: None,
+            fail_fast: false,
+            per_worker_stats: false,
+            abort_p99_above: None,
+            low_memory: false,
+            only_errors: false,
             keep_alive,
+            rate: None,
+            rate_ramp: None,
+            arrival: Arrival::Constant,
+            seed: None,
+            correct_co: false,
+            connect_rate: None,
         }
     }
 }
@@ -237,4 +1142,285 @@ impl BenchmarkConfig for UdsConfig {
     fn is_keep_alive(&self) -> bool {
         self.keep_alive
     }
-}
\ No newline at end of file
+}
+
+#[derive(Clone, Serialize)]
+pub struct GrpcConfig {
+    /// Full URL of the method, e.g. `http://localhost:50051/pkg.Service/Method`
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Serialized protobuf request message, without the gRPC framing
+    pub message: Vec<u8>,
+    pub concurrency: usize,
+    pub requests: usize,
+    #[serde(rename = "duration_us", serialize_with = "micros::serialize")]
+    pub duration: Duration,
+    #[serde(rename = "timeout_us", serialize_with = "micros::serialize")]
+    pub timeout: Duration,
+    /// Timeout for establishing the connection; defaults to `timeout`
+    #[serde(rename = "connect_timeout_us", serialize_with = "micros::serialize")]
+    pub connect_timeout: Duration,
+    /// Count responses whose total latency exceeds this as timeouts, while
+    /// `timeout` still bounds each request (`--timeout-percentile`)
+    #[serde(rename = "strict_timeout_us", serialize_with = "micros::serialize_option")]
+    pub strict_timeout: Option<Duration>,
+    /// Start at `timeout` and tighten toward this multiple of the observed
+    /// median latency as the run progresses (`--adaptive-timeout`)
+    pub adaptive_timeout: Option<f64>,
+    /// Stop the whole run at the first failed request (`--fail-fast`)
+    pub fail_fast: bool,
+    /// Report requests and latency for each worker and flag uneven load
+    /// (`--per-worker-stats`)
+    pub per_worker_stats: bool,
+    /// Stop the run as soon as the p99 of the last second or so of responses
+    /// exceeds this (`--abort-if-p99-above`)
+    #[serde(rename = "abort_p99_above_us", serialize_with = "micros::serialize_option")]
+    pub abort_p99_above: Option<Duration>,
+    /// Summarize response times into histograms as they arrive instead of
+    /// keeping a sample per request (`--low-memory`)
+    pub low_memory: bool,
+    /// Keep each distinct error with its first message for the report
+    /// (`--only-errors`)
+    pub only_errors: bool,
+    pub keep_alive: bool,
+    pub rate: Option<f64>,
+    /// Distribution of the gaps between `rate` paced send times
+    pub arrival: Arrival,
+    /// Seed for the `arrival` gaps, so `--arrival poisson|uniform` repeats across runs
+    pub seed: Option<u64>,
+    /// Moves the paced rate from `start` to `end` over `duration`; `rate`
+    /// then holds the start rate
+    pub rate_ramp: Option<RateRamp>,
+    pub correct_co: bool,
+    /// Source IP the client sockets bind to before connecting (`--local-addr`)
+    pub local_addr: Option<IpAddr>,
+    /// Maximum new connections per second across all workers
+    pub connect_rate: Option<f64>,
+}
+
+impl GrpcConfig {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        target: String,
+        method: String,
+        headers: Option<Vec<String>>,
+        message: Vec<u8>,
+        concurrency: Option<usize>,
+        requests: Option<usize>,
+        duration: Option<Duration>,
+        timeout: Option<u64>,
+        keep_alive: bool,
+    ) -> Self {
+        let url = format!("{}/{}", target.trim_end_matches('/'), method.trim_start_matches('/'));
+        
+        GrpcConfig {
+            url,
+            headers: parse_headers(headers),
+            message,
+            concurrency: concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+            requests: requests.unwrap_or(DEFAULT_REQUESTS),
+            duration: duration.unwrap_or(Duration::from_secs(DEFAULT_DURATION)),
+            timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            connect_timeout: Duration::from_millis(timeout.unwrap_or(DEFAULT_TIMEOUT)),
+            strict_timeout: None,
+            adaptive_timeout: None,
+            fail_fast: false,
+            per_worker_stats: false,
+            abort_p99_above: None,
+            low_memory: false,
+            only_errors: false,
+            keep_alive,
+            rate: None,
+            rate_ramp: None,
+            arrival: Arrival::Constant,
+            seed: None,
+            correct_co: false,
+            connect_rate: None,
+            local_addr: None,
+        }
+    }
+}
+
+impl BenchmarkConfig for GrpcConfig {
+    fn get_concurrency(&self) -> usize {
+        self.concurrency
+    }
+    
+    fn get_requests(&self) -> usize {
+        self.requests
+    }
+    
+    fn get_duration(&self) -> Duration {
+        self.duration
+    }
+    
+    fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+    
+    fn is_keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Writes `contents` to a fresh file in the temp directory
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("thrustbench-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+    
+    #[test]
+    fn load_script_compiles_the_patterns() {
+        let path = temp_file("script.txt", "# login\nAUTH secret\\r\\n\t^OK\nGET key\\r\\n\n");
+        let steps = load_script(&path).unwrap();
+        
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].send, b"AUTH secret\r\n");
+        assert_eq!(steps[0].expect.as_ref().map(Pattern::as_str), Some("^OK"));
+        assert_eq!(steps[1].send, b"GET key\r\n");
+        assert!(steps[1].expect.is_none());
+    }
+    
+    #[test]
+    fn load_script_rejects_an_invalid_pattern() {
+        let path = temp_file("bad-script.txt", "AUTH\t(unclosed\nGET\n");
+        assert!(matches!(load_script(&path), Err(BenchmarkError::Parse(_))));
+    }
+    
+    #[test]
+    fn load_endpoints_reads_weights_methods_and_bodies() {
+        let path = temp_file("urls.txt", concat!(
+            "# mix\n",
+            "3 POST /items {\"name\": \"x\"}\n",
+            "GET /items?page=2\n",
+            "2 https://other.example:8443/health\n",
+        ));
+        let endpoints = load_endpoints(&path, "http://[::1]:8080/base?q=1").unwrap();
+        
+        let lines: Vec<_> = endpoints.iter()
+            .map(|e| (e.weight, e.method.as_deref(), e.url.as_str(), e.body.as_deref()))
+            .collect();
+        assert_eq!(lines, [
+            (3, Some("POST"), "http://[::1]:8080/items", Some("{\"name\": \"x\"}")),
+            (1, Some("GET"), "http://[::1]:8080/items?page=2", None),
+            (2, None, "https://other.example:8443/health", None),
+        ]);
+    }
+    
+    #[test]
+    fn load_endpoints_rejects_a_zero_weight_or_a_relative_url() {
+        for (name, contents) in [("zero.txt", "0 GET /items\n"), ("relative.txt", "GET items\n"), ("empty.txt", "# nothing\n")] {
+            let path = temp_file(name, contents);
+            assert!(matches!(load_endpoints(&path, "http://localhost/"), Err(BenchmarkError::Config(_))), "{}", contents);
+        }
+    }
+    
+    #[test]
+    fn compile_expects_takes_one_pattern_or_one_per_payload() {
+        let payloads = vec![b"a".to_vec(), b"b".to_vec()];
+        let strings = |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        
+        assert_eq!(compile_expects(&payloads, &strings(&["^OK"])).unwrap().len(), 1);
+        assert_eq!(compile_expects(&payloads, &strings(&["^A", "^B"])).unwrap().len(), 2);
+        assert!(matches!(compile_expects(&payloads, &strings(&["^A", "^B", "^C"])), Err(BenchmarkError::Config(_))));
+        assert!(matches!(compile_expects(&payloads, &strings(&["(unclosed"])), Err(BenchmarkError::Parse(_))));
+    }
+    
+    #[test]
+    fn parse_duration_takes_seconds_fractions_and_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        // A bare number is in seconds, as before
+        assert_eq!(parse_duration("10"), Ok(Duration::from_secs(10)));
+        assert_eq!(parse_duration("0"), Ok(Duration::ZERO));
+        assert!(parse_duration("fast").is_err());
+        assert!(parse_duration("5 parsecs").is_err());
+    }
+    
+    #[test]
+    fn check_limits_needs_a_request_count_or_a_duration() {
+        assert!(check_limits(100, Duration::ZERO).is_ok());
+        assert!(check_limits(0, Duration::from_secs(10)).is_ok());
+        assert!(matches!(check_limits(0, Duration::ZERO), Err(BenchmarkError::Config(_))));
+    }
+    
+    #[test]
+    fn expected_length_checks_exact_under_and_over() {
+        let exact = ExpectedLength::new(Some(5), Some(1), None).unwrap().unwrap();
+        assert_eq!(exact, ExpectedLength { min: Some(5), max: Some(5) });
+        assert!(exact.check(5).is_ok());
+        assert!(matches!(exact.check(4), Err(BenchmarkError::ResponseValidation(m)) if m.ends_with("expected 5")));
+        assert!(matches!(exact.check(6), Err(BenchmarkError::ResponseValidation(m)) if m.ends_with("expected 5")));
+        
+        let bounds = ExpectedLength::new(None, Some(2), Some(4)).unwrap().unwrap();
+        assert!(bounds.check(2).is_ok() && bounds.check(4).is_ok());
+        assert!(matches!(bounds.check(1), Err(BenchmarkError::ResponseValidation(m)) if m.ends_with("at least 2")));
+        assert!(matches!(bounds.check(5), Err(BenchmarkError::ResponseValidation(m)) if m.ends_with("at most 4")));
+        
+        assert_eq!(ExpectedLength::new(None, None, None).unwrap(), None);
+        assert!(ExpectedLength::new(None, Some(5), Some(4)).is_err());
+    }
+    
+    #[test]
+    fn load_hosts_skips_comments_and_keeps_bracketed_ipv6() {
+        let path = temp_file("hosts.txt", "# backends\n10.0.0.1:8080\n\n  backend-2:80  \n[::1]:9000\n");
+        assert_eq!(load_hosts(&path).unwrap(), ["10.0.0.1:8080", "backend-2:80", "[::1]:9000"]);
+    }
+    
+    #[test]
+    fn load_hosts_rejects_unbracketed_ipv6_and_missing_ports() {
+        for contents in ["::1:9000\n", "fe80::1\n", "backend\n", "backend:http\n", ":80\n", "# only a comment\n"] {
+            let path = temp_file("bad-hosts.txt", contents);
+            assert!(matches!(load_hosts(&path), Err(BenchmarkError::Config(_))), "{:?}", contents);
+        }
+    }
+    
+    #[test]
+    fn warmup_cv_must_be_above_zero() {
+        assert_eq!(parse_warmup_cv("5"), Ok(5.0));
+        assert_eq!(parse_warmup_cv("2.5%"), Ok(2.5));
+        assert!(parse_warmup_cv("0").is_err());
+        assert!(parse_warmup_cv("-1").is_err());
+        assert!(parse_warmup_cv("inf").is_err());
+        assert!(parse_warmup_cv("abc").is_err());
+    }
+    
+    #[test]
+    fn rate_ramp_moves_linearly_then_holds() {
+        let ramp = parse_rate_ramp("100:300").unwrap();
+        let duration = Duration::from_secs(10);
+        
+        assert_eq!(ramp.rate_at(Duration::ZERO, duration), 100.0);
+        assert_eq!(ramp.rate_at(Duration::from_secs(5), duration), 200.0);
+        assert_eq!(ramp.rate_at(Duration::from_secs(20), duration), 300.0);
+        assert_eq!(ramp.rate_at(Duration::from_secs(1), Duration::ZERO), 300.0);
+        assert!(parse_rate_ramp("0:100").is_err());
+        assert!(parse_rate_ramp("100").is_err());
+    }
+    
+    #[test]
+    fn rate_ramp_time_of_inverts_the_integrated_rate() {
+        let close = |a: Duration, secs: f64| (a.as_secs_f64() - secs).abs() < 1e-9;
+        let duration = Duration::from_secs(10);
+        
+        // 100 -> 300 over 10s: 2000 requests are due during the ramp
+        let up = RateRamp { start: 100.0, end: 300.0 };
+        assert!(close(up.time_of(0.0, duration), 0.0));
+        // 100t + 10t² = 1000
+        assert!(close(up.time_of(1000.0, duration), 125f64.sqrt() - 5.0));
+        assert!(close(up.time_of(2000.0, duration), 10.0));
+        assert!(close(up.time_of(2300.0, duration), 11.0));
+        
+        let down = RateRamp { start: 300.0, end: 100.0 };
+        assert!(close(down.time_of(2000.0, duration), 10.0));
+        let flat = RateRamp { start: 50.0, end: 50.0 };
+        assert!(close(flat.time_of(25.0, duration), 0.5));
+    }
+}
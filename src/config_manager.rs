@@ -91,4 +91,14 @@ pub fn get_default_config_path() -> Result<PathBuf> {
     let dir = dirs::config_dir().context("Couldn't find config dir")?.join("thrustbench");
     fs::create_dir_all(&dir).with_context(|| format!("Make dir {:?}", &dir))?;
     Ok(dir.join("configs.json"))
-}
\ No newline at end of file
+}
+
+pub fn get_last_session_path() -> Result<PathBuf> {
+    let config_path = get_default_config_path()?;
+    Ok(config_path.with_file_name("last_session.json"))
+}
+
+pub fn get_history_path() -> Result<PathBuf> {
+    let config_path = get_default_config_path()?;
+    Ok(config_path.with_file_name("history.jsonl"))
+}
@@ -1,18 +1,36 @@
 use thiserror::Error;
+use std::error::Error as _;
 use std::io;
 use std::time::Duration;
 
 #[derive(Debug, Error)]
 pub enum BenchmarkError {
     #[error("IO error: {0}")]
-    Io(#[from] io::Error),
+    Io(io::Error),
     
     #[error("HTTP error: {0}")]
-    Http(#[from] hyper::Error),
+    Http(hyper::Error),
     
     #[error("Connection refused")]
     ConnectionRefused,
     
+    /// No local port was left to connect from (`AddrNotAvailable`): the
+    /// machine running the benchmark hit its ephemeral port limit, which is
+    /// no failure of the server
+    #[error("Local ports exhausted: {0}")]
+    PortsExhausted(io::Error),
+    
+    /// The peer reset the connection (or it broke while writing), as
+    /// opposed to closing it cleanly
+    #[error("Connection reset: {0}")]
+    ConnectionReset(io::Error),
+    
+    #[error("TLS handshake failed: {0}")]
+    TlsHandshake(String),
+    
+    #[error("Certificate verification failed: {0}")]
+    CertificateVerification(String),
+    
     #[error("Connection timed out after {0:?}")]
     ConnectionTimeout(Duration),
     
@@ -28,10 +46,108 @@ pub enum BenchmarkError {
     #[error("Parse error: {0}")]
     Parse(String),
     
+    /// A run cut short by `--fail-fast` or Ctrl-C
+    #[error("{0}")]
+    Stopped(String),
+    
     #[error("Unexpected error: {0}")]
     Other(String),
 }
 
+impl BenchmarkError {
+    /// Every name `kind` returns, in declaration order
+    pub const KINDS: [&'static str; 14] = [
+        "io", "http", "connection_refused", "ports_exhausted", "connection_reset",
+        "tls_handshake", "certificate_verification", "connection_timeout",
+        "request_timeout", "config", "response_validation", "parse", "stopped", "other",
+    ];
+    
+    /// Short, stable name of the error variant for logs and reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BenchmarkError::Io(_) => "io",
+            BenchmarkError::Http(_) => "http",
+            BenchmarkError::ConnectionRefused => "connection_refused",
+            BenchmarkError::PortsExhausted(_) => "ports_exhausted",
+            BenchmarkError::ConnectionReset(_) => "connection_reset",
+            BenchmarkError::TlsHandshake(_) => "tls_handshake",
+            BenchmarkError::CertificateVerification(_) => "certificate_verification",
+            BenchmarkError::ConnectionTimeout(_) => "connection_timeout",
+            BenchmarkError::RequestTimeout(_) => "request_timeout",
+            BenchmarkError::Config(_) => "config",
+            BenchmarkError::ResponseValidation(_) => "response_validation",
+            BenchmarkError::Parse(_) => "parse",
+            BenchmarkError::Stopped(_) => "stopped",
+            BenchmarkError::Other(_) => "other",
+        }
+    }
+    
+    /// Whether the request failed before its connection was established:
+    /// refused, timed out connecting, or a failed TLS handshake. Many of
+    /// these point at a saturated accept backlog.
+    pub fn is_connect_failure(&self) -> bool {
+        match self {
+            BenchmarkError::ConnectionRefused
+            | BenchmarkError::PortsExhausted(_)
+            | BenchmarkError::ConnectionTimeout(_)
+            | BenchmarkError::TlsHandshake(_)
+            | BenchmarkError::CertificateVerification(_) => true,
+            // A Unix socket nobody listens on refuses through a plain IO error
+            BenchmarkError::Io(e) => e.kind() == io::ErrorKind::ConnectionRefused,
+            _ => false,
+        }
+    }
+    
+    /// The error of a failed connect: `PortsExhausted` when no local port
+    /// was available, `ConnectionRefused` otherwise
+    pub fn from_connect(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::AddrNotAvailable => BenchmarkError::PortsExhausted(e),
+            _ => BenchmarkError::ConnectionRefused,
+        }
+    }
+    
+    /// Whether the request failed reading or writing an established
+    /// connection, pointing at slow or overloaded request processing
+    pub fn is_io_failure(&self) -> bool {
+        match self {
+            BenchmarkError::Http(_) | BenchmarkError::ConnectionReset(_) | BenchmarkError::RequestTimeout(_) => true,
+            BenchmarkError::Io(e) => e.kind() != io::ErrorKind::ConnectionRefused,
+            _ => false,
+        }
+    }
+}
+
+/// Whether an IO error means the peer dropped the connection abruptly
+fn is_reset(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe
+    )
+}
+
+impl From<io::Error> for BenchmarkError {
+    fn from(e: io::Error) -> Self {
+        if is_reset(&e) {
+            BenchmarkError::ConnectionReset(e)
+        } else {
+            BenchmarkError::Io(e)
+        }
+    }
+}
+
+impl From<hyper::Error> for BenchmarkError {
+    /// Resets surfacing through hyper are reported as `ConnectionReset` too
+    fn from(e: hyper::Error) -> Self {
+        match e.source().and_then(|source| source.downcast_ref::<io::Error>()) {
+            Some(io_error) if is_reset(io_error) => {
+                BenchmarkError::ConnectionReset(io::Error::new(io_error.kind(), format!("{}: {}", e, io_error)))
+            },
+            _ => BenchmarkError::Http(e),
+        }
+    }
+}
+
 impl From<String> for BenchmarkError {
     fn from(s: String) -> Self {
         BenchmarkError::Other(s)
@@ -42,4 +158,30 @@ impl From<&str> for BenchmarkError {
     fn from(s: &str) -> Self {
         BenchmarkError::Other(s.to_string())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_connect_tells_exhausted_ports_from_refusals() {
+        let exhausted = BenchmarkError::from_connect(io::Error::from(io::ErrorKind::AddrNotAvailable));
+        assert!(matches!(exhausted, BenchmarkError::PortsExhausted(_)));
+        assert!(exhausted.is_connect_failure());
+
+        let refused = BenchmarkError::from_connect(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert!(matches!(refused, BenchmarkError::ConnectionRefused));
+        assert!(refused.is_connect_failure());
+    }
+
+    #[test]
+    fn resets_are_told_from_other_io_errors() {
+        for kind in [io::ErrorKind::ConnectionReset, io::ErrorKind::ConnectionAborted, io::ErrorKind::BrokenPipe] {
+            let error = BenchmarkError::from(io::Error::from(kind));
+            assert_eq!(error.kind(), "connection_reset");
+            assert!(error.is_io_failure());
+        }
+        assert_eq!(BenchmarkError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).kind(), "io");
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+use rand::Rng;
+use crate::error::BenchmarkError;
+
+/// `Content-Type` of a `urlencoded` body
+pub const URLENCODED: &str = "application/x-www-form-urlencoded";
+
+/// Splits a `--form` or `--multipart` field into its name and value
+pub fn parse_field(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("invalid form field '{}', expected name=value", s)),
+    }
+}
+
+/// Encodes `fields` as an `application/x-www-form-urlencoded` body: pairs
+/// joined by `&`, spaces as `+` and everything but letters, digits and
+/// `*-._` percent-encoded.
+pub fn urlencoded(fields: &[(String, String)]) -> Vec<u8> {
+    fields.iter()
+        .map(|(name, value)| format!("{}={}", encode_component(name), encode_component(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+        .into_bytes()
+}
+
+fn encode_component(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => out.push(byte as char),
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// A `multipart/form-data` body and the `Content-Type` naming its boundary
+pub struct Multipart {
+    pub body: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Builds a `multipart/form-data` body with one part per field. A value of
+/// `@path` sends the file's contents as a file upload; any other value is
+/// sent as text. The boundary is random and checked not to occur in any
+/// part.
+pub fn multipart<R: Rng>(fields: &[(String, String)], rng: &mut R) -> Result<Multipart, BenchmarkError> {
+    let mut parts = Vec::with_capacity(fields.len());
+    for (name, value) in fields {
+        let part = match value.strip_prefix('@') {
+            Some(path) => {
+                let path = Path::new(path);
+                let contents = fs::read(path).map_err(|e| {
+                    BenchmarkError::Config(format!("Cannot read --multipart file {}: {}", path.display(), e))
+                })?;
+                let filename = path.file_name().map(|f| f.to_string_lossy()).unwrap_or_default();
+                let headers = format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n",
+                    quote(name),
+                    quote(&filename),
+                );
+                (headers, contents)
+            },
+            None => (
+                format!("Content-Disposition: form-data; name=\"{}\"\r\n", quote(name)),
+                value.as_bytes().to_vec(),
+            ),
+        };
+        parts.push(part);
+    }
+
+    let boundary = loop {
+        let boundary = format!("thrustbench-{:016x}", rng.random::<u64>());
+        let needle = boundary.as_bytes();
+        if !parts.iter().any(|(_, contents)| contents.windows(needle.len()).any(|w| w == needle)) {
+            break boundary;
+        }
+    };
+
+    let mut body = Vec::new();
+    for (headers, contents) in &parts {
+        body.extend_from_slice(format!("--{}\r\n{}\r\n", boundary, headers).as_bytes());
+        body.extend_from_slice(contents);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+    Ok(Multipart {
+        body,
+        content_type: format!("multipart/form-data; boundary={}", boundary),
+    })
+}
+
+/// Escapes a name or filename for a quoted `Content-Disposition` parameter,
+/// the way browsers do
+fn quote(s: &str) -> String {
+    s.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn urlencoded_body_is_encoded_exactly() {
+        let body = urlencoded(&fields(&[("name", "Jane Doe"), ("q", "a&b=c/d"), ("safe", "*-._~")]));
+        assert_eq!(body, b"name=Jane+Doe&q=a%26b%3Dc%2Fd&safe=*-._%7E");
+    }
+
+    #[test]
+    fn multipart_body_is_encoded_exactly() {
+        let path = std::env::temp_dir().join(format!("thrustbench-{}-upload.bin", std::process::id()));
+        fs::write(&path, b"\x00\x01data").unwrap();
+        let fields = fields(&[("title", "report"), ("file", &format!("@{}", path.display()))]);
+
+        let multipart = multipart(&fields, &mut StdRng::seed_from_u64(1)).unwrap();
+
+        let boundary = multipart.content_type.strip_prefix("multipart/form-data; boundary=").unwrap();
+        assert!(boundary.starts_with("thrustbench-"));
+        let filename = path.file_name().unwrap().to_string_lossy();
+        let expected = [
+            format!("--{}\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nreport\r\n", boundary).into_bytes(),
+            format!("--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n", boundary, filename).into_bytes(),
+            b"\x00\x01data\r\n".to_vec(),
+            format!("--{}--\r\n", boundary).into_bytes(),
+        ].concat();
+        assert_eq!(multipart.body, expected);
+    }
+
+    #[test]
+    fn multipart_boundary_follows_the_rng_and_avoids_the_contents() {
+        let text = fields(&[("a", "b")]);
+        let first = multipart(&text, &mut StdRng::seed_from_u64(7)).unwrap().content_type;
+        assert_eq!(multipart(&text, &mut StdRng::seed_from_u64(7)).unwrap().content_type, first);
+        assert_ne!(multipart(&text, &mut StdRng::seed_from_u64(8)).unwrap().content_type, first);
+
+        // A part containing the boundary the seed draws first gets the next one
+        let taken = first.strip_prefix("multipart/form-data; boundary=").unwrap();
+        let clashing = multipart(&fields(&[("a", &format!("x{}x", taken))]), &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_ne!(clashing.content_type, first);
+    }
+
+    #[test]
+    fn multipart_names_are_quoted() {
+        let multipart = multipart(&fields(&[("a\"b\r\n", "v")]), &mut StdRng::seed_from_u64(1)).unwrap();
+        let body = String::from_utf8(multipart.body).unwrap();
+        assert!(body.contains("name=\"a%22b%0D%0A\""));
+    }
+}
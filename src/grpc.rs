@@ -0,0 +1,121 @@
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use hyper::{Request, StatusCode, Uri};
+use hyper::client::conn::http2;
+use hyper::header::{HeaderMap, CONTENT_TYPE, TE};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use http_body_util::{BodyExt, Full};
+use tokio::time::timeout;
+use bytes::Bytes;
+use crate::error::BenchmarkError;
+use crate::http;
+use crate::limiter::ConnectLimiter;
+use crate::tls::TlsOptions;
+
+/// Wraps a serialized protobuf message in the gRPC length-prefixed framing:
+/// a 1-byte compression flag (always uncompressed) and a 4-byte big-endian length.
+pub fn frame_message(message: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(5 + message.len());
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
+/// Decodes a hex string (whitespace ignored) into the raw message bytes.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, BenchmarkError> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(BenchmarkError::Parse("Hex payload has an odd number of digits".to_string()));
+    }
+
+    digits.chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair).ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| BenchmarkError::Parse(format!("Invalid hex payload: {}", hex)))
+        })
+        .collect()
+}
+
+/// Issues one unary gRPC call over a new HTTP/2 (h2c) connection.
+///
+/// Returns the `grpc-status` code, the response body (still framed) and the
+/// elapsed time. The status is read from the trailers, or from the headers
+/// for trailers-only responses.
+pub async fn send_grpc(
+    uri: &Uri,
+    headers: &[(String, String)],
+    framed_message: &[u8],
+    connect_limiter: &ConnectLimiter,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+    timeout_duration: Duration,
+) -> Result<(u32, Vec<u8>, Duration), BenchmarkError> {
+    // Waiting for the connection limiter is not part of the latency
+    connect_limiter.acquire().await;
+
+    let start_time = Instant::now();
+
+    // Establish connection
+    let stream = http::connect(uri, &TlsOptions::default(), true, local_addr, connect_timeout).await?;
+    connect_limiter.connected();
+    let io = TokioIo::new(stream);
+
+    let mut request_builder = Request::builder()
+        .method("POST")
+        .uri(uri.clone())
+        .header(CONTENT_TYPE, "application/grpc")
+        .header(TE, "trailers");
+
+    for (name, value) in headers {
+        request_builder = request_builder.header(name, value);
+    }
+
+    let request = request_builder
+        .body(Full::new(Bytes::copy_from_slice(framed_message)))
+        .map_err(|_| BenchmarkError::Parse("Failed to build request".to_string()))?;
+
+    let (mut sender, conn) = http2::handshake(TokioExecutor::new(), io).await?;
+
+    // Spawn connection task
+    tokio::spawn(async move {
+        if let Err(e) = conn.await {
+            tracing::warn!(error = %e, "HTTP/2 connection error");
+        }
+    });
+
+    tracing::debug!("sending unary call");
+    let response = timeout(
+        timeout_duration,
+        sender.send_request(request),
+    ).await
+        .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+
+    if response.status() != StatusCode::OK {
+        return Err(BenchmarkError::ResponseValidation(
+            format!("gRPC call returned HTTP status {}", response.status())
+        ));
+    }
+
+    let header_status = grpc_status(response.headers());
+
+    let body = timeout(
+        timeout_duration,
+        response.collect(),
+    ).await
+        .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+
+    let status = body.trailers()
+        .and_then(grpc_status)
+        .or(header_status)
+        .ok_or_else(|| BenchmarkError::ResponseValidation("Missing grpc-status".to_string()))?;
+
+    tracing::debug!(grpc_status = status, "response received");
+    let elapsed = start_time.elapsed();
+    Ok((status, body.to_bytes().to_vec(), elapsed))
+}
+
+fn grpc_status(headers: &HeaderMap) -> Option<u32> {
+    headers.get("grpc-status")?.to_str().ok()?.parse().ok()
+}
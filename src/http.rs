@@ -1,127 +1,1013 @@
+use std::convert::Infallible;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use hyper::Uri;
-use hyper::client::conn::http1::Builder;
+use hyper::client::conn::http1::{Builder, SendRequest};
 use hyper::client::conn::http2;
 use hyper_util::rt::TokioExecutor;
 use hyper_util::rt::TokioIo;
-use hyper::Request;
-use http_body_util::{BodyExt, Full};
-use hyper::{Method, StatusCode};
-use tokio::net::TcpStream;
+use hyper::{Request, Response};
+use http_body_util::{BodyExt, Full, StreamBody};
+use http_body_util::combinators::BoxBody;
+use hyper::body::Frame;
+use hyper::{HeaderMap, Method, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::time::timeout;
 use bytes::Bytes;
+use regex::Regex;
+use crate::config::{ExpectedLength, StreamResponse};
 use crate::error::BenchmarkError;
+use crate::limiter::ConnectLimiter;
+use crate::tcp;
+use crate::tls::{self, MaybeTlsStream, TlsOptions};
+use crate::trace::Tracer;
 
+/// Size of the chunks a streamed request body is sent in
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Sends one request, on a new connection unless `keep_alive` holds one.
+/// With `stream_body_size` set, a generated body of that many bytes is
+/// streamed in chunks (sent with `Transfer-Encoding: chunked` over HTTP/1.1)
+/// instead of `body`.
+///
+/// `host` overrides the `Host` header and `tls.sni` the TLS server name;
+/// both default to the URI's host, independently of the address connected to.
+///
+/// With `keep_alive`, an HTTP/1.1 connection left open by the previous
+/// request to the same origin is reused, and the connection is kept for the
+/// next one. A reused connection the server closed while it sat idle is
+/// replaced transparently: the request is sent again on a new connection
+/// and counted in `KeepAlive::reconnects`, unless it may have reached the
+/// server and its method isn't idempotent.
+///
+/// With `no_body` the time is taken once the response headers arrive and
+/// the body is not downloaded: the connection is closed, or with
+/// `keep_alive` the body is drained unmeasured so the connection stays usable.
+///
+/// With `stream_response` the body is read chunk by chunk and each chunk's
+/// arrival is timed, up to `max_chunks` if set. A connection whose body was
+/// left unread is closed rather than kept alive.
+///
+/// With `trace`, the request and response bodies are recorded; a streamed
+/// body is recorded as empty.
+///
+/// `Server-Timing` headers are only parsed with `server_timing`.
+#[allow(clippy::too_many_arguments)]
 pub async fn send_request(
     uri: &Uri,
     method: &str,
     headers: &[(String, String)],
+    host: Option<&str>,
+    tls: &TlsOptions,
     body: Option<&[u8]>,
+    stream_body_size: Option<usize>,
+    connect_limiter: &ConnectLimiter,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
     timeout_duration: Duration,
     use_http2: bool,
-) -> Result<(StatusCode, Vec<u8>, Duration), BenchmarkError> {
-    let start_time = Instant::now();
+    mut keep_alive: Option<&mut KeepAlive>,
+    no_body: bool,
+    stream_response: Option<StreamResponse>,
+    server_timing: bool,
+    trace: Option<&Tracer>,
+) -> Result<HttpResponse, BenchmarkError> {
+    let reused = keep_alive.as_deref_mut().and_then(|keep_alive| keep_alive.take(uri));
 
-    let host = uri.host().ok_or_else(|| BenchmarkError::Config("Missing host in URL".to_string()))?;
-    let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("https") { 443 } else { 80 });
-
-    // Establish connection
-    let stream = match timeout(
-        timeout_duration,
-        TcpStream::connect(format!("{}:{}", host, port)),
-    ).await {
-        Ok(Ok(stream)) => stream,
-        Ok(Err(_)) => return Err(BenchmarkError::ConnectionRefused),
-        Err(_) => return Err(BenchmarkError::ConnectionTimeout(timeout_duration)),
-    };
+    // Waiting for the connection limiter is not part of the latency
+    if reused.is_none() {
+        connect_limiter.acquire().await;
+    }
 
-    // Wrap with TokioIo for compatibility
-    let io = TokioIo::new(stream);
+    let start_time = Instant::now();
 
     // Prepare request
     let method = Method::from_bytes(method.as_bytes())
         .map_err(|_| BenchmarkError::Parse(format!("Invalid HTTP method: {}", method)))?;
+    let body_sent = Arc::new(AtomicUsize::new(0));
+    let build_request = || {
+        let mut request_builder = Request::builder()
+            .method(method.clone())
+            .uri(uri.clone());
 
-    let mut request_builder = Request::builder()
-        .method(method)
-        .uri(uri.clone());
+        // HTTP/1.1 requires a Host header; HTTP/2 carries the authority in the URI
+        if !use_http2 && !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("host")) {
+            let authority = uri.authority().map(|a| a.as_str()).unwrap_or_default();
+            request_builder = request_builder.header(hyper::header::HOST, host.unwrap_or(authority));
+        }
 
-    // Add headers
-    for (name, value) in headers {
-        request_builder = request_builder.header(name, value);
-    }
+        // Add headers
+        for (name, value) in headers {
+            request_builder = request_builder.header(name, value);
+        }
 
-    // Add body if present
-    let body_data = body.unwrap_or(&[]);
-    let request = request_builder
-        .body(Full::new(Bytes::from(body_data.to_vec())))
-        .map_err(|_| BenchmarkError::Parse("Failed to build request".to_string()))?;
-
-    // Send request and get response
-    let (status, body_bytes) = if use_http2 {
-        // HTTP/2 connection
-        let (mut sender, conn) = http2::handshake(TokioExecutor::new(), io).await
-            .map_err(BenchmarkError::Http)?;
+        // Add body if present, or stream a generated one
+        let request_body = match stream_body_size {
+            Some(size) => {
+                body_sent.store(0, Ordering::Relaxed);
+                streamed_body(size, body_sent.clone())
+            },
+            None => {
+                let body_data = body.unwrap_or(&[]);
+                body_sent.store(body_data.len(), Ordering::Relaxed);
+                Full::new(Bytes::from(body_data.to_vec())).boxed()
+            }
+        };
+        request_builder
+            .body(request_body)
+            .map_err(|_| BenchmarkError::Parse("Failed to build request".to_string()))
+    };
 
-        // Spawn connection task
-        tokio::spawn(async move {
-            if let Err(e) = conn.await {
-                eprintln!("HTTP/2 connection error: {}", e);
+    // Send request and get the response headers, over the connection
+    // keep-alive left open if there is one
+    let was_reused = reused.is_some();
+    let mut sender = match reused {
+        Some(sender) => sender,
+        None => {
+            let sender = open(uri, tls, use_http2, local_addr, connect_timeout).await?;
+            connect_limiter.connected();
+            if let Some(keep_alive) = keep_alive.as_deref_mut() {
+                keep_alive.opened(uri);
             }
-        });
+            sender
+        }
+    };
 
-        // Send request
-        let response = timeout(
-            timeout_duration,
-            sender.send_request(request),
-        ).await
-            .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+    // Send request
+    tracing::debug!("sending request");
+    let response = match timeout(timeout_duration, sender.send(build_request()?)).await {
+        Ok(Ok(response)) => response,
+        // The server closed a reused connection while it sat idle, e.g. past
+        // its keep-alive timeout. That is no failure of the request, which
+        // is sent again on a new connection; its time still counts from the
+        // first attempt.
+        Ok(Err(e)) if was_reused && is_stale_connection(&e, &method) => {
+            tracing::debug!(error = %e, "keep-alive connection closed by the server, reconnecting");
+            connect_limiter.acquire().await;
+            sender = open(uri, tls, use_http2, local_addr, connect_timeout).await?;
+            connect_limiter.connected();
+            if let Some(keep_alive) = keep_alive.as_deref_mut() {
+                keep_alive.opened(uri);
+                keep_alive.reconnects.fetch_add(1, Ordering::Relaxed);
+            }
+            timeout(timeout_duration, sender.send(build_request()?)).await
+                .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??
+        },
+        Ok(Err(e)) => return Err(e.into()),
+        Err(_) => return Err(BenchmarkError::RequestTimeout(timeout_duration)),
+    };
 
-        let status = response.status();
+    let status = response.status();
+    let cache = classify_cache(response.headers());
+    let server_timing = if server_timing { parse_server_timing(response.headers()) } else { Vec::new() };
+    let header_len = estimated_header_len(&response);
 
+    let mut chunks = Vec::new();
+    let mut body_read = true;
+    let (body_bytes, elapsed) = if no_body {
+        let elapsed = start_time.elapsed();
+        if keep_alive.is_some() {
+            // Drain the body unmeasured so the connection can be reused
+            timeout(timeout_duration, response.collect()).await
+                .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+        }
+        (Vec::new(), elapsed)
+    } else if let Some(stream) = stream_response {
+        let mut body = response.into_body();
+        let mut data = Vec::new();
+        let read = timeout(timeout_duration, async {
+            while let Some(frame) = body.frame().await {
+                let Ok(chunk) = frame?.into_data() else {
+                    continue; // trailers
+                };
+                if chunk.is_empty() {
+                    continue;
+                }
+                chunks.push(start_time.elapsed());
+                data.extend_from_slice(&chunk);
+                if stream.max_chunks.is_some_and(|max| chunks.len() >= max) {
+                    return Ok(false);
+                }
+            }
+            Ok::<_, hyper::Error>(true)
+        }).await;
+        body_read = match read {
+            Ok(read) => read?,
+            // A stream that never ends, such as server-sent events, ends at
+            // the timeout with the chunks it delivered so far
+            Err(_) if stream.max_chunks.is_none() && !chunks.is_empty() => false,
+            Err(_) => return Err(BenchmarkError::RequestTimeout(timeout_duration)),
+        };
+        (data, start_time.elapsed())
+    } else {
         // Get response body
         let body = timeout(
             timeout_duration,
             response.collect(),
         ).await
             .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+        (body.to_bytes().to_vec(), start_time.elapsed())
+    };
+    tracing::debug!(status = status.as_u16(), bytes = body_bytes.len(), "response received");
+    if let Some(trace) = trace {
+        let sent = if stream_body_size.is_some() { &[] } else { body.unwrap_or_default() };
+        trace.record(start_time, elapsed, sent, &body_bytes);
+    }
+
+    // Only a fully read response leaves the connection reusable
+    if let Some(keep_alive) = keep_alive
+        && body_read
+    {
+        keep_alive.put_back(sender);
+    }
+
+    Ok(HttpResponse {
+        status,
+        body: body_bytes,
+        header_len,
+        body_sent: body_sent.load(Ordering::Relaxed),
+        cache,
+        server_timing,
+        elapsed,
+        chunks,
+    })
+}
+
+/// Opens a connection to `uri` and completes the HTTP/1.1 or HTTP/2 handshake
+async fn open(
+    uri: &Uri,
+    tls: &TlsOptions,
+    use_http2: bool,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+) -> Result<Sender, BenchmarkError> {
+    let stream = connect(uri, tls, use_http2, local_addr, connect_timeout).await?;
+    let io = TokioIo::new(stream);
+    if use_http2 {
+        let (sender, conn) = http2::handshake(TokioExecutor::new(), io).await?;
 
-        let bytes = body.to_bytes();
-        (status, bytes.to_vec())
+        // Spawn connection task
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::warn!(error = %e, "HTTP/2 connection error");
+            }
+        });
+        Ok(Sender::Http2(sender))
     } else {
-        // HTTP/1.x connection
-        let (mut sender, conn) = Builder::new()
-            .handshake::<_, Full<Bytes>>(io)
-            .await
-            .map_err(BenchmarkError::Http)?;
+        let (sender, conn) = Builder::new()
+            .handshake::<_, BoxBody<Bytes, Infallible>>(io)
+            .await?;
 
         // Spawn connection task
         tokio::spawn(async move {
             if let Err(e) = conn.await {
-                eprintln!("HTTP/1 connection error: {}", e);
+                tracing::warn!(error = %e, "HTTP/1 connection error");
             }
         });
+        Ok(Sender::Http1(sender))
+    }
+}
 
-        // Send request
-        let response = timeout(
-            timeout_duration,
-            sender.send_request(request),
-        ).await
-            .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+/// Whether a request that failed on a reused connection may be sent again on
+/// a new one because the server had already closed it. A request that never
+/// went out always may; one that may have been written only when `method`
+/// is idempotent, since the server could have acted on it before failing.
+fn is_stale_connection(e: &hyper::Error, method: &Method) -> bool {
+    if e.is_canceled() || e.is_closed() {
+        return true;
+    }
+    let closed_mid_request = e.is_incomplete_message() || std::error::Error::source(e)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| matches!(
+            e.kind(),
+            std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::ConnectionAborted
+        ));
+    closed_mid_request && method.is_idempotent()
+}
 
-        let status = response.status();
+/// One response received by `send_request`.
+pub struct HttpResponse {
+    pub status: StatusCode,
+    /// The response body; empty with `no_body`
+    pub body: Vec<u8>,
+    /// Estimated size of the status line and headers (see `estimated_header_len`)
+    pub header_len: usize,
+    /// Number of request body bytes sent
+    pub body_sent: usize,
+    pub cache: CacheStatus,
+    /// Metrics of the `Server-Timing` headers that carry a duration; empty
+    /// unless asked for
+    pub server_timing: Vec<ServerTiming>,
+    /// Time until the whole response was read, or only its headers with `no_body`
+    pub elapsed: Duration,
+    /// When each body chunk arrived, from the same start as `elapsed`, with
+    /// `stream_response`; empty otherwise
+    pub chunks: Vec<Duration>,
+}
 
-        // Get response body
-        let body = timeout(
-            timeout_duration,
-            response.collect(),
-        ).await
-            .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+/// Size of a response's status line and headers re-encoded as HTTP/1.1 text.
+/// hyper doesn't expose the bytes it parsed, so this is an estimate: the
+/// reason phrase is the canonical one and whitespace is normalized, and
+/// HTTP/2 headers are HPACK-compressed on the wire.
+fn estimated_header_len<B>(response: &Response<B>) -> usize {
+    let status_line = format!("{:?} {}\r\n", response.version(), response.status()).len();
+    let headers: usize = response.headers().iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum();
+    status_line + headers + 2
+}
+
+/// Request side of an HTTP/1.1 or HTTP/2 client connection
+enum Sender {
+    Http1(SendRequest<BoxBody<Bytes, Infallible>>),
+    Http2(http2::SendRequest<BoxBody<Bytes, Infallible>>),
+}
+
+impl Sender {
+    async fn send(&mut self, request: Request<BoxBody<Bytes, Infallible>>) -> Result<Response<hyper::body::Incoming>, hyper::Error> {
+        match self {
+            Sender::Http1(sender) => {
+                sender.ready().await?;
+                sender.send_request(request).await
+            },
+            Sender::Http2(sender) => {
+                sender.ready().await?;
+                sender.send_request(request).await
+            },
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        match self {
+            Sender::Http1(sender) => sender.is_closed(),
+            Sender::Http2(sender) => sender.is_closed(),
+        }
+    }
+}
+
+/// A worker's persistent HTTP/1.1 or HTTP/2 connection under `--keep-alive`.
+/// It is reopened when the target origin changes, when the server closes it,
+/// or after `max_requests` requests (`--keepalive-requests`).
+pub struct KeepAlive {
+    sender: Option<Sender>,
+    origin: String,
+    served: usize,
+    max_requests: Option<usize>,
+    /// Requests resent on a new connection because the server had closed
+    /// the idle one, shared by all workers
+    reconnects: Arc<AtomicUsize>,
+}
+
+impl KeepAlive {
+    pub fn new(max_requests: Option<usize>, reconnects: Arc<AtomicUsize>) -> Self {
+        KeepAlive {
+            sender: None,
+            origin: String::new(),
+            served: 0,
+            max_requests,
+            reconnects,
+        }
+    }
+
+    /// Takes the open connection if it can serve a request to `uri`.
+    /// Otherwise the connection, if any, is dropped, which closes it.
+    fn take(&mut self, uri: &Uri) -> Option<Sender> {
+        let sender = self.sender.take()?;
+        let exhausted = self.max_requests.is_some_and(|max| self.served >= max);
+        if exhausted || sender.is_closed() || self.origin != origin(uri) {
+            tracing::debug!(served = self.served, "closing keep-alive connection");
+            return None;
+        }
+        Some(sender)
+    }
+
+    fn opened(&mut self, uri: &Uri) {
+        self.origin = origin(uri);
+        self.served = 0;
+    }
+
+    fn put_back(&mut self, sender: Sender) {
+        self.served += 1;
+        self.sender = Some(sender);
+    }
+}
+
+/// `scheme://authority` of `uri`
+pub(crate) fn origin(uri: &Uri) -> String {
+    format!(
+        "{}://{}",
+        uri.scheme_str().unwrap_or("http"),
+        uri.authority().map(|a| a.as_str()).unwrap_or_default()
+    )
+}
+
+/// Checks applied to every HTTP response body: a regex (`--expect-body`),
+/// JSON path assertions (`--expect-json`), length bounds (`--expect-length`)
+/// and that it isn't empty (`--require-non-empty`). A failed check turns
+/// the request into a `ResponseValidation` error.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseExpectations {
+    body: Option<Regex>,
+    json: Vec<(String, String)>,
+    length: Option<ExpectedLength>,
+    non_empty: bool,
+}
+
+impl ResponseExpectations {
+    pub fn new(
+        body_pattern: Option<&str>,
+        json: &[(String, String)],
+        length: Option<ExpectedLength>,
+        non_empty: bool,
+    ) -> Result<Self, BenchmarkError> {
+        let body = body_pattern
+            .map(|pattern| Regex::new(pattern)
+                .map_err(|_| BenchmarkError::Parse(format!("Invalid regex pattern: {}", pattern))))
+            .transpose()?;
+
+        Ok(ResponseExpectations { body, json: json.to_vec(), length, non_empty })
+    }
+
+    pub fn check(&self, body: &[u8]) -> Result<(), BenchmarkError> {
+        if self.non_empty && body.is_empty() {
+            return Err(BenchmarkError::ResponseValidation("Response body is empty".to_string()));
+        }
+
+        if let Some(length) = self.length {
+            length.check(body.len())?;
+        }
+
+        if let Some(ref regex) = self.body
+            && !regex.is_match(&String::from_utf8_lossy(body))
+        {
+            return Err(BenchmarkError::ResponseValidation(
+                format!("Expected pattern '{}' not found in response", regex.as_str())
+            ));
+        }
+
+        if self.json.is_empty() {
+            return Ok(());
+        }
+
+        let document: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| BenchmarkError::ResponseValidation(format!("Response is not JSON: {}", e)))?;
+
+        for (path, expected) in &self.json {
+            let actual = document.pointer(&json_pointer(path)).ok_or_else(|| {
+                BenchmarkError::ResponseValidation(format!("JSON path '{}' not found in response", path))
+            })?;
+            if !json_matches(actual, expected) {
+                return Err(BenchmarkError::ResponseValidation(
+                    format!("JSON path '{}' is {}, expected {}", path, actual, expected)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks `response` against `expectations`. A body of at least
+/// `offload_above` bytes is checked on the blocking thread pool instead, so
+/// a slow regex or JSON parse of a large body doesn't stall the other
+/// requests on the worker thread (`--offload-validation-above`).
+pub async fn check_response(
+    expectations: &Arc<ResponseExpectations>,
+    response: HttpResponse,
+    offload_above: Option<usize>,
+) -> Result<HttpResponse, BenchmarkError> {
+    let heavy = expectations.body.is_some() || !expectations.json.is_empty();
+    if !heavy || offload_above.is_none_or(|min| response.body.len() < min) {
+        return expectations.check(&response.body).map(|_| response);
+    }
+
+    let expectations = expectations.clone();
+    match tokio::task::spawn_blocking(move || expectations.check(&response.body).map(|_| response)).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Turns a path such as `data.items.0.id`, `$.data.items.0.id` or
+/// `/data/items/0/id` into a JSON pointer.
+fn json_pointer(path: &str) -> String {
+    if path.starts_with('/') {
+        return path.to_string();
+    }
+    let path = path.strip_prefix('$').unwrap_or(path);
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// The expected value is compared as JSON when it parses as JSON (`42`,
+/// `true`, `null`, `"ok"`) and against string values as plain text otherwise.
+fn json_matches(actual: &serde_json::Value, expected: &str) -> bool {
+    if let Ok(expected_value) = serde_json::from_str::<serde_json::Value>(expected)
+        && *actual == expected_value
+    {
+        return true;
+    }
+    actual.as_str() == Some(expected)
+}
+
+/// Whether a response was served from a cache, according to its headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Unknown,
+}
+
+/// Classifies a response from common cache indicators. `X-Cache`,
+/// `X-Cache-Status` and `CF-Cache-Status` are checked first (values such as
+/// `HIT`, `TCP_HIT`, `Hit from cloudfront`, `STALE` or `MISS`, `EXPIRED`,
+/// `BYPASS`); otherwise a non-zero `Age` counts as a hit.
+pub fn classify_cache(headers: &HeaderMap) -> CacheStatus {
+    const HIT: [&str; 4] = ["HIT", "STALE", "REVALIDATED", "UPDATING"];
+    const MISS: [&str; 4] = ["MISS", "EXPIRED", "BYPASS", "DYNAMIC"];
 
-        let bytes = body.to_bytes();
-        (status, bytes.to_vec())
+    for name in ["x-cache", "x-cache-status", "cf-cache-status"] {
+        let Some(value) = headers.get(name).and_then(|v| v.to_str().ok()) else {
+            continue;
+        };
+        let value = value.to_ascii_uppercase();
+        if HIT.iter().any(|h| value.contains(h)) {
+            return CacheStatus::Hit;
+        }
+        if MISS.iter().any(|m| value.contains(m)) {
+            return CacheStatus::Miss;
+        }
+    }
+
+    match headers.get("age")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    {
+        Some(age) if age > 0 => CacheStatus::Hit,
+        _ => CacheStatus::Unknown,
+    }
+}
+
+/// One metric of a `Server-Timing` header, e.g. `db;dur=12`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerTiming {
+    pub name: String,
+    pub duration: Duration,
+}
+
+/// Parses the metrics of every `Server-Timing` header, such as
+/// `db;dur=12.5, cache;desc="Redis, primary";dur=3`. `dur` is in
+/// milliseconds; metrics without one are skipped.
+pub fn parse_server_timing(headers: &HeaderMap) -> Vec<ServerTiming> {
+    headers.get_all("server-timing")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| split_unquoted(value, ','))
+        .filter_map(|metric| {
+            let mut parts = split_unquoted(metric, ';').into_iter();
+            let name = parts.next()?.trim();
+            let duration = parts.find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("dur")
+                    .then(|| value.trim().trim_matches('"').parse::<f64>().ok())
+                    .flatten()
+            })?;
+            (!name.is_empty() && duration.is_finite() && duration >= 0.0)
+                .then(|| ServerTiming { name: name.to_string(), duration: Duration::from_secs_f64(duration / 1000.0) })
+        })
+        .collect()
+}
+
+/// Splits `s` at `separator`, except inside double-quoted strings
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            },
+            _ => {},
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Opens a connection to the URI's host and port (80, or 443 for https).
+/// For https the TLS handshake is part of establishing the connection and
+/// presents `tls.sni`, or the URI's host when unset.
+pub async fn connect(
+    uri: &Uri,
+    tls: &TlsOptions,
+    http2: bool,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+) -> Result<MaybeTlsStream, BenchmarkError> {
+    let host = uri.host().ok_or_else(|| BenchmarkError::Config("Missing host in URL".to_string()))?;
+    let https = uri.scheme_str() == Some("https");
+    let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+    tracing::debug!(host, port, tls = https, "connecting");
+    let establish = async {
+        let stream = tcp::connect(&format!("{}:{}", host, port), local_addr).await?;
+        if https {
+            tls::handshake(stream, tls.sni.as_deref().unwrap_or(host), http2, tls).await
+        } else {
+            Ok(MaybeTlsStream::Plain(stream))
+        }
     };
 
+    match timeout(connect_timeout, establish).await {
+        Ok(result) => {
+            if result.is_ok() {
+                tracing::debug!("connected");
+            }
+            result
+        },
+        Err(_) => Err(BenchmarkError::ConnectionTimeout(connect_timeout)),
+    }
+}
+
+/// Opens a connection to `uri` and drops it again without sending a request
+/// (`--connect-only`). Returns how long the TCP connect took, with the TLS
+/// handshake for https.
+pub async fn connect_only(
+    uri: &Uri,
+    tls: &TlsOptions,
+    http2: bool,
+    connect_limiter: &ConnectLimiter,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+) -> Result<Duration, BenchmarkError> {
+    // Waiting for the connection limiter is not part of the latency
+    connect_limiter.acquire().await;
+
+    let start_time = Instant::now();
+    let stream = connect(uri, tls, http2, local_addr, connect_timeout).await?;
     let elapsed = start_time.elapsed();
-    Ok((status, body_bytes, elapsed))
-}
\ No newline at end of file
+    connect_limiter.connected();
+    drop(stream);
+    Ok(elapsed)
+}
+
+/// Generates a body of `size` bytes lazily, one chunk at a time, so large
+/// uploads never sit in memory. `sent` counts the bytes hyper has taken:
+/// a chunk counts once hyper asks for the next one or for the end of the
+/// body, so a chunk still pending when the server answers early or the
+/// request fails is left out.
+fn streamed_body(size: usize, sent: Arc<AtomicUsize>) -> BoxBody<Bytes, Infallible> {
+    let chunk = Bytes::from(vec![b'x'; STREAM_CHUNK_SIZE.min(size)]);
+
+    // State: bytes left to generate and the length of the chunk handed out last
+    let frames = futures::stream::unfold((size, 0), move |(remaining, pending)| {
+        let chunk = chunk.clone();
+        let sent = sent.clone();
+        async move {
+            sent.fetch_add(pending, Ordering::Relaxed);
+            if remaining == 0 {
+                return None;
+            }
+            let len = remaining.min(chunk.len());
+            Some((Ok(Frame::data(chunk.slice(..len))), (remaining - len, len)))
+        }
+    });
+
+    StreamBody::new(frames).boxed()
+}
+
+/// A persistent HTTP/1.1 connection that writes several requests before
+/// reading their responses. hyper's client only allows one request in flight
+/// per connection, so pipelining uses this hand-rolled path instead.
+///
+/// HTTP/1.1 requires servers to answer pipelined requests in the order they
+/// were received; responses are matched to requests purely by position.
+pub struct PipelinedConnection {
+    stream: MaybeTlsStream,
+    buffer: Vec<u8>,
+    /// The server announced `Connection: close` or ended a body by closing,
+    /// so nothing more can be sent on this connection
+    closed: bool,
+}
+
+/// How the end of a pipelined response's body is found
+enum BodyLength {
+    Fixed(usize),
+    Chunked,
+    UntilClose,
+}
+
+/// One response read off a pipelined connection.
+pub struct PipelinedResponse {
+    pub status: StatusCode,
+    pub body_len: usize,
+    /// Time from the start of the batch write until this response was fully read
+    pub elapsed: Duration,
+}
+
+/// `uri` with its `host:port` replaced by `authority`, keeping scheme, path
+/// and query.
+pub fn with_authority(uri: &Uri, authority: &str) -> Result<Uri, BenchmarkError> {
+    let mut parts = uri.clone().into_parts();
+    parts.authority = Some(authority.parse()
+        .map_err(|_| BenchmarkError::Config(format!("Invalid host: {}", authority)))?);
+    Uri::from_parts(parts)
+        .map_err(|e| BenchmarkError::Config(format!("Invalid URL for host {}: {}", authority, e)))
+}
+
+/// `uri` with `name=value` appended to its query. `name` and `value` must
+/// not need percent-encoding.
+pub fn with_query_param(uri: &Uri, name: &str, value: &str) -> Uri {
+    let path_and_query = match uri.query() {
+        Some(query) if !query.is_empty() => format!("{}?{}&{}={}", uri.path(), query, name, value),
+        _ => format!("{}?{}={}", uri.path(), name, value),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse()
+        .expect("a valid path and query with a plain parameter appended stays valid"));
+    Uri::from_parts(parts).expect("replacing the path and query keeps the URI valid")
+}
+
+/// Serializes a request in HTTP/1.1 wire format for pipelining.
+pub fn encode_request(
+    uri: &Uri,
+    method: &str,
+    headers: &[(String, String)],
+    body: Option<&[u8]>,
+) -> Result<Vec<u8>, BenchmarkError> {
+    let host = uri.host().ok_or_else(|| BenchmarkError::Config("Missing host in URL".to_string()))?;
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
+    let mut request = format!("{} {} HTTP/1.1\r\n", method, path);
+
+    if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("host")) {
+        match uri.port_u16() {
+            Some(port) => request.push_str(&format!("Host: {}:{}\r\n", host, port)),
+            None => request.push_str(&format!("Host: {}\r\n", host)),
+        }
+    }
+
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    let body = body.unwrap_or(&[]);
+    if !body.is_empty() {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+
+    let mut bytes = request.into_bytes();
+    bytes.extend_from_slice(body);
+    Ok(bytes)
+}
+
+impl PipelinedConnection {
+    pub async fn connect(
+        uri: &Uri,
+        tls: &TlsOptions,
+        connect_limiter: &ConnectLimiter,
+        local_addr: Option<IpAddr>,
+        connect_timeout: Duration,
+    ) -> Result<Self, BenchmarkError> {
+        connect_limiter.acquire().await;
+
+        let stream = connect(uri, tls, false, local_addr, connect_timeout).await?;
+        connect_limiter.connected();
+
+        Ok(PipelinedConnection { stream, buffer: Vec::new(), closed: false })
+    }
+
+    /// Whether the server closed the connection, after which the responses
+    /// to any further requests would never arrive
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Writes `depth` copies of `request` back to back, then reads `depth`
+    /// responses in order. When the server closes the connection after one
+    /// of them, the responses read up to then are returned and the
+    /// connection `is_closed`.
+    pub async fn send_batch(
+        &mut self,
+        request: &[u8],
+        depth: usize,
+        timeout_duration: Duration,
+    ) -> Result<Vec<PipelinedResponse>, BenchmarkError> {
+        let start_time = Instant::now();
+        // Responses to HEAD carry no body, whatever their Content-Length says
+        let head = request.starts_with(b"HEAD ");
+
+        let batch = request.repeat(depth);
+        match timeout(timeout_duration, self.stream.write_all(&batch)).await {
+            Ok(Ok(_)) => {},
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => return Err(BenchmarkError::RequestTimeout(timeout_duration)),
+        }
+
+        let mut responses = Vec::with_capacity(depth);
+        while responses.len() < depth && !self.closed {
+            let (status, body_len) = timeout(timeout_duration, self.read_response(head)).await
+                .map_err(|_| BenchmarkError::RequestTimeout(timeout_duration))??;
+            responses.push(PipelinedResponse {
+                status,
+                body_len,
+                elapsed: start_time.elapsed(),
+            });
+        }
+
+        Ok(responses)
+    }
+
+    /// Reads more bytes from the socket into the buffer, failing on EOF.
+    async fn fill(&mut self) -> Result<(), BenchmarkError> {
+        let mut chunk = [0u8; 8192];
+        let n = self.stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(BenchmarkError::Other("Connection closed mid-response".to_string()));
+        }
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(())
+    }
+
+    /// Reads one complete final response, skipping interim (1xx) ones and
+    /// leaving any following bytes buffered.
+    async fn read_response(&mut self, head: bool) -> Result<(StatusCode, usize), BenchmarkError> {
+        loop {
+            let (status, body) = self.read_head().await?;
+            if status == StatusCode::SWITCHING_PROTOCOLS {
+                return Err(BenchmarkError::Parse("Unexpected 101 Switching Protocols on a pipelined connection".to_string()));
+            }
+            if status.is_informational() {
+                continue;
+            }
+            if head || status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+                return Ok((status, 0));
+            }
+            let body_len = match body {
+                BodyLength::Fixed(len) => {
+                    while self.buffer.len() < len {
+                        self.fill().await?;
+                    }
+                    self.buffer.drain(..len);
+                    len
+                },
+                BodyLength::Chunked => self.read_chunked().await?,
+                BodyLength::UntilClose => self.read_until_close().await?,
+            };
+            return Ok((status, body_len));
+        }
+    }
+
+    /// Parses the status line and headers, noting `Connection: close`
+    async fn read_head(&mut self) -> Result<(StatusCode, BodyLength), BenchmarkError> {
+        loop {
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut response = httparse::Response::new(&mut headers);
+            match response.parse(&self.buffer) {
+                Ok(httparse::Status::Complete(header_len)) => {
+                    let status = StatusCode::from_u16(response.code.unwrap_or(0))
+                        .map_err(|_| BenchmarkError::Parse("Invalid status code".to_string()))?;
+                    let mut content_length = None;
+                    let mut chunked = false;
+                    for header in response.headers.iter() {
+                        if header.name.eq_ignore_ascii_case("content-length") {
+                            content_length = Some(std::str::from_utf8(header.value).ok()
+                                .and_then(|v| v.trim().parse::<usize>().ok())
+                                .ok_or_else(|| BenchmarkError::Parse("Invalid Content-Length".to_string()))?);
+                        } else if header.name.eq_ignore_ascii_case("transfer-encoding") {
+                            chunked = String::from_utf8_lossy(header.value).to_ascii_lowercase().contains("chunked");
+                        } else if header.name.eq_ignore_ascii_case("connection")
+                            && String::from_utf8_lossy(header.value).split(',').any(|v| v.trim().eq_ignore_ascii_case("close"))
+                        {
+                            self.closed = true;
+                        }
+                    }
+                    self.buffer.drain(..header_len);
+                    // Transfer-Encoding takes precedence over Content-Length
+                    let body = match (chunked, content_length) {
+                        (true, _) => BodyLength::Chunked,
+                        (false, Some(len)) => BodyLength::Fixed(len),
+                        (false, None) => BodyLength::UntilClose,
+                    };
+                    return Ok((status, body));
+                },
+                Ok(httparse::Status::Partial) => self.fill().await?,
+                Err(e) => return Err(BenchmarkError::Parse(format!("Invalid HTTP response: {}", e))),
+            }
+        }
+    }
+
+    /// Reads a chunked body: size line, data, CRLF, ... terminated by a
+    /// zero-size chunk
+    async fn read_chunked(&mut self) -> Result<usize, BenchmarkError> {
+        let mut body_len = 0;
+        loop {
+            let line = self.read_line().await?;
+            let size_str = line.split(';').next().unwrap_or("").trim();
+            let size = usize::from_str_radix(size_str, 16)
+                .map_err(|_| BenchmarkError::Parse(format!("Invalid chunk size: {}", size_str)))?;
+            if size == 0 {
+                // Skip optional trailers up to the terminating empty line
+                while !self.read_line().await?.is_empty() {}
+                return Ok(body_len);
+            }
+            while self.buffer.len() < size + 2 {
+                self.fill().await?;
+            }
+            self.buffer.drain(..size + 2);
+            body_len += size;
+        }
+    }
+
+    /// Reads a body delimited by the server closing the connection
+    async fn read_until_close(&mut self) -> Result<usize, BenchmarkError> {
+        let mut chunk = [0u8; 8192];
+        let mut body_len = self.buffer.len();
+        self.buffer.clear();
+        loop {
+            let n = self.stream.read(&mut chunk).await?;
+            if n == 0 {
+                self.closed = true;
+                return Ok(body_len);
+            }
+            body_len += n;
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<String, BenchmarkError> {
+        loop {
+            if let Some(pos) = self.buffer.windows(2).position(|w| w == b"\r\n") {
+                let line = String::from_utf8_lossy(&self.buffer[..pos]).to_string();
+                self.buffer.drain(..pos + 2);
+                return Ok(line);
+            }
+            self.fill().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn streamed_body_counts_a_chunk_once_hyper_moves_past_it() {
+        let size = 2 * STREAM_CHUNK_SIZE + 10;
+        let sent = Arc::new(AtomicUsize::new(0));
+        let mut body = streamed_body(size, sent.clone());
+
+        let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(first.len(), STREAM_CHUNK_SIZE);
+        assert_eq!(sent.load(Ordering::Relaxed), 0);
+
+        body.frame().await.unwrap().unwrap();
+        assert_eq!(sent.load(Ordering::Relaxed), STREAM_CHUNK_SIZE);
+
+        let last = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(last.len(), 10);
+        assert_eq!(sent.load(Ordering::Relaxed), 2 * STREAM_CHUNK_SIZE);
+
+        assert!(body.frame().await.is_none());
+        assert_eq!(sent.load(Ordering::Relaxed), size);
+    }
+
+    #[test]
+    fn header_len_is_the_http1_encoding() {
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("content-length", "5")
+            .body(())
+            .unwrap();
+
+        // "HTTP/1.1 200 OK\r\n" + "content-length: 5\r\n" + "\r\n"
+        assert_eq!(estimated_header_len(&response), 17 + 19 + 2);
+    }
+
+    #[test]
+    fn server_timing_keeps_the_metrics_with_a_duration() {
+        let mut headers = HeaderMap::new();
+        headers.append("server-timing", r#"db;dur=12.5, cache;desc="Redis, primary";DUR=3"#.parse().unwrap());
+        headers.append("server-timing", "miss, app;dur=-1, total;dur=\"20\"".parse().unwrap());
+
+        let timings: Vec<_> = parse_server_timing(&headers).into_iter()
+            .map(|timing| (timing.name, timing.duration))
+            .collect();
+        assert_eq!(timings, vec![
+            ("db".to_string(), Duration::from_micros(12_500)),
+            ("cache".to_string(), Duration::from_millis(3)),
+            ("total".to_string(), Duration::from_millis(20)),
+        ]);
+        assert!(parse_server_timing(&HeaderMap::new()).is_empty());
+    }
+}
@@ -1,7 +1,15 @@
 pub mod http;
 pub mod tcp;
 pub mod uds;
+pub mod protocol;
+pub mod grpc;
+pub mod tls;
+pub mod limiter;
+pub mod trace;
+pub mod stats;
 pub mod config;
 pub mod runner;
 pub mod report;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod form;
+pub mod server;
\ No newline at end of file
@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Caps how many new connections are opened per second across all workers
+/// (`--connect-rate`). Acts as a token bucket holding a single token, so
+/// connections are spaced evenly and idle time never builds up a burst.
+///
+/// It also counts the connections actually opened, so every run has one;
+/// without a rate it never waits.
+pub struct ConnectLimiter {
+    rate: Option<f64>,
+    /// Slot spacing in nanoseconds
+    interval: u64,
+    /// Reference point for the slot and connection offsets below
+    origin: Instant,
+    /// Next free slot, in nanoseconds since `origin`
+    next: AtomicU64,
+    opened: AtomicU64,
+    /// First and last connection, in nanoseconds since `origin`
+    first: AtomicU64,
+    last: AtomicU64,
+}
+
+impl ConnectLimiter {
+    pub fn new(rate: Option<f64>) -> Self {
+        ConnectLimiter {
+            rate,
+            interval: rate.map(|rate| Duration::from_secs_f64(1.0 / rate).as_nanos() as u64).unwrap_or_default(),
+            origin: Instant::now(),
+            next: AtomicU64::new(0),
+            opened: AtomicU64::new(0),
+            first: AtomicU64::new(u64::MAX),
+            last: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits until a new connection may be opened.
+    pub async fn acquire(&self) {
+        if self.rate.is_none() {
+            return;
+        }
+
+        let now = self.elapsed();
+        let interval = self.interval;
+        let previous = self.next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |next| Some(next.max(now) + interval))
+            .unwrap_or_else(|next| next);
+        let slot = self.origin + Duration::from_nanos(previous.max(now));
+        tokio::time::sleep_until(slot.into()).await;
+    }
+
+    /// Records a connection that was established.
+    pub fn connected(&self) {
+        let now = self.elapsed();
+        self.opened.fetch_add(1, Ordering::Relaxed);
+        self.first.fetch_min(now, Ordering::Relaxed);
+        self.last.fetch_max(now, Ordering::Relaxed);
+    }
+
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Number of connections established so far
+    pub fn opened(&self) -> u64 {
+        self.opened.load(Ordering::Relaxed)
+    }
+
+    /// Connections per second between the first and the last connection,
+    /// or 0 until at least two were opened
+    pub fn achieved(&self) -> f64 {
+        let opened = self.opened();
+        let span = self.last.load(Ordering::Relaxed).saturating_sub(self.first.load(Ordering::Relaxed));
+        if opened < 2 || span == 0 {
+            return 0.0;
+        }
+        (opened - 1) as f64 / Duration::from_nanos(span).as_secs_f64()
+    }
+
+    fn elapsed(&self) -> u64 {
+        self.origin.elapsed().as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn concurrent_acquires_are_spaced_by_the_rate() {
+        let start = Instant::now();
+        let limiter = Arc::new(ConnectLimiter::new(Some(100.0)));
+        let handles: Vec<_> = (0..5).map(|_| {
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                limiter.acquire().await;
+                limiter.connected();
+            })
+        }).collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        // Slots at 0, 10, 20, 30 and 40ms
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(limiter.opened(), 5);
+        let achieved = limiter.achieved();
+        assert!(achieved > 50.0 && achieved < 200.0, "achieved {achieved}");
+    }
+
+    #[tokio::test]
+    async fn achieved_ignores_time_after_the_last_connection() {
+        let start = Instant::now();
+        let limiter = ConnectLimiter::new(Some(200.0));
+        for _ in 0..3 {
+            limiter.acquire().await;
+            limiter.connected();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let over_whole_run = 3.0 / start.elapsed().as_secs_f64();
+        assert!(limiter.achieved() > 2.0 * over_whole_run, "achieved {}", limiter.achieved());
+    }
+
+    #[tokio::test]
+    async fn unlimited_never_waits() {
+        let limiter = ConnectLimiter::new(None);
+        let start = Instant::now();
+        for _ in 0..1000 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+        assert_eq!(limiter.achieved(), 0.0);
+    }
+}
@@ -1,14 +1,13 @@
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use thrustbench::{config, error, form, grpc, http, limiter, report, runner, server, tcp, tls, trace};
 
-mod http;
-mod tcp;
-mod uds;
 mod config_manager;
-mod report;
-mod config;
-mod runner;
-mod error;
+mod theme;
 mod tui;
 
 #[derive(Parser)]
@@ -20,52 +19,208 @@ struct Cli {
     #[arg(short, long, help = "Number of concurrent connections")]
     concurrency: Option<usize>,
 
-    #[arg(short, long, help = "Total number of requests")]
+    #[arg(short, long, help = "Total number of requests; 0 runs until --duration elapses")]
     requests: Option<usize>,
 
-    #[arg(short, long, help = "Duration of the test in seconds")]
-    duration: Option<u64>,
+    #[arg(short, long, value_parser = config::parse_duration, help = "Duration of the test, in seconds or e.g. 500ms, 1.5s, 1m30s; the run ends at whichever of --requests and --duration comes first, and 0 sets no time limit")]
+    duration: Option<Duration>,
+
+    #[arg(long, value_name = "DURATION", value_parser = config::parse_duration, help = "Hard cap on the whole command's wall-clock time (in seconds or e.g. 500ms, 1.5s, 2m), every run and phase included: stop there even if requests hang, report what completed and exit with an error")]
+    max_duration: Option<Duration>,
 
     #[arg(short, long, help = "Timeout for each request in milliseconds")]
     timeout: Option<u64>,
 
+    #[arg(long, help = "Timeout for establishing each connection in milliseconds (defaults to --timeout)")]
+    connect_timeout: Option<u64>,
+
     #[arg(long, help = "Keep connections alive")]
     keep_alive: bool,
 
+    #[arg(long, conflicts_with_all = ["keep_alive", "trace_file"], help = "Only open and close connections (TCP, plus TLS for https), sending no requests, and report connection setup time and connections/sec")]
+    connect_only: bool,
+
     #[arg(long, help = "Path to config file")]
     config: Option<PathBuf>,
 
-    #[arg(long, help = "Output format (text, json)")]
+    #[arg(long, help = "Output format (text, json, ndjson, markdown); ndjson prints one compact report per line, one per run or level")]
     output: Option<String>,
 
+    #[arg(long, value_name = "PATH", conflicts_with = "autotune", help = "Write the latency CDF as percentile,latency_ms CSV rows (every 0.1% up to p99, then every 0.01%)")]
+    cdf_output: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH", help = "Directory to write report.json, samples.jsonl and config.json into")]
+    output_dir: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["runs", "autotune", "sweep_concurrency"], help = "Stream one JSON line per completed request to this file while the benchmark runs")]
+    raw_output: Option<PathBuf>,
+
+    #[arg(long, requires = "raw_output", help = "Interleave a progress line with elapsed_ms, rps and p99_us so far into --raw-output every second")]
+    raw_progress: bool,
+
+    #[arg(long, value_name = "FRACTION", default_value_t = 1.0, value_parser = config::parse_sample_rate, requires = "raw_output", help = "Write only this randomly chosen fraction of the requests (e.g. 0.01) into --raw-output; the report still covers all of them")]
+    sample_rate: f64,
+
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["runs", "autotune", "sweep_concurrency"], help = "Record the bytes each request sent and received, with timestamps, in this binary trace file (HTTP records the bodies)")]
+    trace_file: Option<PathBuf>,
+
+    #[arg(long, value_name = "N", default_value_t = 10000, value_parser = clap::value_parser!(u64).range(1..), requires = "trace_file", help = "Stop recording into --trace-file after this many requests")]
+    trace_max_requests: u64,
+
+    #[arg(long, conflicts_with_all = ["output_dir", "cdf_output", "per_worker_stats", "timeout_percentile"], help = "Keep response times as histograms instead of one sample per request, so memory stays flat on long runs; percentiles are accurate to about 2%")]
+    low_memory: bool,
+
+    #[arg(long, default_value = "human", help = "Time unit for the text report (ms, us, human)")]
+    time_unit: report::TimeUnit,
+
+    #[arg(long, help = "End the text report with a single greppable SUMMARY key=value line")]
+    summary_line: bool,
+
+    #[arg(long, help = "Print counts in the text report without thousands separators")]
+    no_thousands_sep: bool,
+
+    #[arg(long, help = "On exit, write a one-line JSON status ({\"ok\",\"total\",\"failed\",\"rps\"}) to stderr for a supervising process, whatever the stdout format")]
+    status_json: bool,
+
+    #[arg(long, help = "Print byte totals in the text report in binary units (KiB, MiB, GiB)")]
+    human_bytes: bool,
+
+    #[arg(long, conflicts_with_all = ["runs", "autotune", "sweep_concurrency", "tui"], help = "Instead of the report, list each distinct error with its count and first message; HTTP 4xx/5xx responses are listed too, with the start of their body")]
+    only_errors: bool,
+
+    #[arg(long, alias = "fail-on-empty-response", conflicts_with = "connect_only", help = "Count responses with an empty body as failed, for endpoints that should always return data (http, tcp, uds)")]
+    require_non_empty: bool,
+
+    #[arg(long, conflicts_with = "history_file", help = "Don't append the run's report to history.jsonl in the config directory")]
+    no_history: bool,
+
+    #[arg(long, value_name = "PATH", help = "Append each run's report to this history file instead")]
+    history_file: Option<PathBuf>,
+
+    #[arg(long, value_name = "N", default_value_t = 1000, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), help = "Keep only the N most recent reports in the history file")]
+    history_max: usize,
+
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["runs", "autotune"], help = "Compare against a report.json from an earlier run and fail if rps or p99 regressed beyond --tolerance")]
+    baseline: Option<PathBuf>,
+
+    #[arg(long, value_name = "PERCENT", default_value = "10%", value_parser = config::parse_tolerance, requires = "baseline", help = "Allowed regression against --baseline")]
+    tolerance: f64,
+
+    #[arg(long, value_name = "LEVEL", help = "Log level or filter for diagnostics on stderr (overrides RUST_LOG)")]
+    log_level: Option<String>,
+
     #[arg(long, help = "Use interactive TUI mode")]
     tui: bool,
+
+    #[arg(long, value_name = "THEME", requires = "tui", help = "TUI colors: dark, light or highcontrast; remembered for later launches")]
+    tui_theme: Option<theme::ThemeName>,
+
+    #[arg(long, group = "pacing", help = "Target request rate across all connections (requests/sec)")]
+    rate: Option<f64>,
+
+    #[arg(long, value_name = "START:END", group = "pacing", value_parser = config::parse_rate_ramp, help = "Ramp the target request rate linearly from START to END requests/sec over --duration (e.g. 100:5000) and report the rate at which errors or latency degraded; runs the full duration unless --requests is given")]
+    rate_ramp: Option<config::RateRamp>,
+
+    #[arg(long, alias = "distribution", requires = "pacing", help = "Distribution of the gaps between --rate send times (constant, poisson, uniform)")]
+    arrival: Option<config::Arrival>,
+
+    #[arg(long, help = "Maximum new connections per second across all connections")]
+    connect_rate: Option<f64>,
+
+    #[arg(long, value_name = "IP", help = "Source IP address to bind client sockets to before connecting (multi-homed hosts)")]
+    local_addr: Option<IpAddr>,
+
+    #[arg(long, requires = "pacing", help = "Report latency from the intended send time (coordinated-omission correction)")]
+    correct_co: bool,
+
+    #[arg(long, help = "Seed for random choices (e.g. URL selection) so runs are reproducible")]
+    seed: Option<u64>,
+
+    #[arg(long, help = "Stop at the first failed request and exit with its error (smoke test)")]
+    fail_fast: bool,
+
+    #[arg(long, value_name = "LATENCY", value_parser = config::parse_latency, help = "Stop the run as soon as the rolling p99 of the last second or so of responses exceeds this (e.g. 500ms), to protect a struggling target")]
+    abort_if_p99_above: Option<Duration>,
+
+    #[arg(long, help = "Report requests and p99 latency for each worker (connection) and warn when load is spread unevenly")]
+    per_worker_stats: bool,
+
+    #[arg(long, value_name = "SECS", help = "Retry connecting to the target for up to SECS seconds before starting, until it accepts connections")]
+    wait_for_ready: Option<u64>,
+
+    #[arg(long, default_value_t = 1, help = "Number of times to repeat the benchmark")]
+    runs: usize,
+
+    #[arg(long, default_value_t = 0, help = "Delay between repeated runs, or --sweep-concurrency levels, in milliseconds")]
+    run_delay: u64,
+
+    #[arg(long, conflicts_with_all = ["runs", "pacing"], help = "Ramp concurrency up until rps stops improving and report the knee")]
+    autotune: bool,
+
+    #[arg(long, value_name = "LIST", value_delimiter = ',', conflicts_with_all = ["runs", "autotune", "baseline", "timeout_percentile", "warmup_until_stable", "output_dir", "cdf_output"], help = "Run the benchmark at each of these concurrency levels (e.g. 1,2,4,8,16) and report how rps and p99 scale")]
+    sweep_concurrency: Vec<usize>,
+
+    #[arg(long, default_value_t = 256, help = "Highest concurrency probed by --autotune")]
+    autotune_max: usize,
+
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..), help = "Duration of each --autotune probe in seconds")]
+    autotune_probe: u64,
+
+    #[arg(long, help = "Stop --autotune once p99 exceeds this many milliseconds")]
+    autotune_max_p99: Option<u64>,
+
+    #[arg(long, value_name = "PERCENTILE", value_parser = config::parse_percentile, conflicts_with = "autotune", help = "Calibrate first, then time out requests slower than this latency percentile of the calibration run")]
+    timeout_percentile: Option<f64>,
+
+    #[arg(long, default_value_t = 3, help = "Duration of the --timeout-percentile calibration run in seconds")]
+    calibration_duration: u64,
+
+    #[arg(long, conflicts_with = "timeout_percentile", help = "Start with --timeout and tighten it toward --adaptive-multiple times the median latency as the run progresses")]
+    adaptive_timeout: bool,
+
+    #[arg(long, value_name = "N", default_value_t = 5.0, requires = "adaptive_timeout", help = "Multiple of the median latency --adaptive-timeout tightens toward")]
+    adaptive_multiple: f64,
+
+    #[arg(long, conflicts_with = "autotune", help = "Warm up until per-second throughput is stable, then measure")]
+    warmup_until_stable: bool,
+
+    #[arg(long, value_name = "PERCENT", default_value_t = 5.0, value_parser = config::parse_warmup_cv, help = "Coefficient of variation of per-second rps (%) below which --warmup-until-stable ends")]
+    warmup_cv: f64,
+
+    #[arg(long, value_name = "SECS", default_value_t = 5, value_parser = clap::value_parser!(u64).range(2..), help = "Seconds of throughput --warmup-until-stable checks for stability")]
+    warmup_window: u64,
+
+    #[arg(long, value_name = "SECS", default_value_t = 60, help = "Longest --warmup-until-stable may run before measuring anyway")]
+    warmup_max: u64,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Benchmark HTTP server")]
-    Http {
-        #[arg(help = "URL to benchmark")]
-        url: String,
+    Http(Box<HttpArgs>),
+    
+    #[command(about = "Benchmark gRPC unary calls over HTTP/2")]
+    Grpc {
+        #[arg(help = "Server URL, e.g. http://localhost:50051")]
+        target: String,
         
-        #[arg(short, long, help = "HTTP method")]
-        method: Option<String>,
+        #[arg(help = "Fully qualified method, e.g. helloworld.Greeter/SayHello")]
+        method: String,
         
-        #[arg(long, help = "Headers in format 'key:value'")]
+        #[arg(long, help = "Metadata headers in format 'key:value'")]
         headers: Option<Vec<String>>,
         
-        #[arg(short, long, help = "Body content for POST/PUT")]
-        body: Option<String>,
+        #[arg(long, help = "Path to a file with the serialized protobuf request message")]
+        data_file: Option<PathBuf>,
         
-        #[arg(long, help = "Path to body file")]
-        body_file: Option<PathBuf>,
+        #[arg(long, conflicts_with = "data_file", help = "Serialized protobuf request message as hex")]
+        hex: Option<String>,
     },
     
     #[command(about = "Benchmark TCP server")]
     Tcp {
-        #[arg(help = "Host:port to benchmark")]
-        address: String,
+        #[arg(required_unless_present = "hosts_file", help = "Host:port to benchmark")]
+        address: Option<String>,
         
         #[arg(short, long, help = "Data to send")]
         data: Option<String>,
@@ -73,8 +228,50 @@ enum Commands {
         #[arg(long, help = "Path to data file")]
         data_file: Option<PathBuf>,
         
-        #[arg(short, long, help = "Expected response pattern (regex)")]
-        expect: Option<String>,
+        #[arg(long, value_name = "DELIM", requires = "data_file", conflicts_with = "data", help = "Split --data-file into payloads separated by DELIM (e.g. '\\n') and send them round-robin")]
+        delimiter: Option<String>,
+        
+        #[arg(short, long, help = "Expected response pattern (regex); repeat to give one per --delimiter payload")]
+        expect: Vec<String>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["expect_length_min", "expect_length_max"], help = "Count responses that aren't exactly SIZE bytes (e.g. 512, 64kb) as failed; reading stops at SIZE bytes")]
+        expect_length: Option<usize>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Count responses shorter than SIZE bytes as failed")]
+        expect_length_min: Option<usize>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Count responses longer than SIZE bytes as failed; reading stops at SIZE bytes")]
+        expect_length_max: Option<usize>,
+        
+        #[arg(long, conflicts_with_all = ["data", "data_file", "expect"], help = "Script of send/expect exchanges; only the last one is measured")]
+        script: Option<PathBuf>,
+        
+        #[arg(long, alias = "repeat-data", value_name = "N", default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), help = "Send the payload N times on each connection, awaiting each response; also reports per-message latency")]
+        repeat: usize,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Expected response size (e.g. 64kb, 4mb); reads the response in fewer, larger reads")]
+        expected_response_size: Option<usize>,
+        
+        #[arg(long, help = "Shut down the write side of the connection after sending, for servers that respond at end of input")]
+        half_close: bool,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Match --expect against responses of at least SIZE bytes (e.g. 256kb) on the blocking thread pool, so it doesn't delay other requests")]
+        offload_validation_above: Option<usize>,
+        
+        #[arg(long, value_name = "VERSION", help = "Send a PROXY protocol header (v1 or v2) first on every connection, as an L4 load balancer would")]
+        proxy_protocol: Option<config::ProxyProtocol>,
+        
+        #[arg(long, value_name = "IP:PORT", requires = "proxy_protocol", help = "Client address the PROXY header claims (default: the connection's own address)")]
+        proxy_source: Option<SocketAddr>,
+        
+        #[arg(long, conflicts_with = "script", help = "Default --data/--expect for a common service: redis-ping, redis-info, memcached-get, memcached-stats, memcached-version, http-get")]
+        preset: Option<String>,
+        
+        #[arg(long, value_name = "PATH", conflicts_with = "address", help = "File of host:port lines to connect to round-robin")]
+        hosts_file: Option<PathBuf>,
+        
+        #[arg(long, requires = "hosts_file", help = "Report requests, failures and latency for each --hosts-file host")]
+        per_host: bool,
     },
     
     #[command(about = "Benchmark Unix Domain Socket server")]
@@ -88,30 +285,387 @@ enum Commands {
         #[arg(long, help = "Path to data file")]
         data_file: Option<PathBuf>,
         
-        #[arg(short, long, help = "Expected response pattern (regex)")]
-        expect: Option<String>,
+        #[arg(long, value_name = "DELIM", requires = "data_file", conflicts_with = "data", help = "Split --data-file into payloads separated by DELIM (e.g. '\\n') and send them round-robin")]
+        delimiter: Option<String>,
+        
+        #[arg(short, long, help = "Expected response pattern (regex); repeat to give one per --delimiter payload")]
+        expect: Vec<String>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["expect_length_min", "expect_length_max"], help = "Count responses that aren't exactly SIZE bytes (e.g. 512, 64kb) as failed; reading stops at SIZE bytes")]
+        expect_length: Option<usize>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Count responses shorter than SIZE bytes as failed")]
+        expect_length_min: Option<usize>,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Count responses longer than SIZE bytes as failed; reading stops at SIZE bytes")]
+        expect_length_max: Option<usize>,
+        
+        #[arg(long, conflicts_with_all = ["data", "data_file", "expect"], help = "Script of send/expect exchanges; only the last one is measured")]
+        script: Option<PathBuf>,
+        
+        #[arg(long, alias = "repeat-data", value_name = "N", default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), help = "Send the payload N times on each connection, awaiting each response; also reports per-message latency")]
+        repeat: usize,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Expected response size (e.g. 64kb, 4mb); reads the response in fewer, larger reads")]
+        expected_response_size: Option<usize>,
+        
+        #[arg(long, help = "Shut down the write side of the connection after sending, for servers that respond at end of input")]
+        half_close: bool,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Match --expect against responses of at least SIZE bytes (e.g. 256kb) on the blocking thread pool, so it doesn't delay other requests")]
+        offload_validation_above: Option<usize>,
+    },
+    
+    #[command(about = "Run a built-in echo server to benchmark against")]
+    Serve {
+        #[arg(long, default_value = "http", help = "Protocol to serve (http, tcp, uds)")]
+        protocol: server::ServeProtocol,
+        
+        #[arg(long, default_value_t = 8080, help = "Port to listen on (http, tcp)")]
+        port: u16,
+        
+        #[arg(long, value_name = "IP", default_value = "127.0.0.1", help = "Address to listen on (http, tcp)")]
+        bind: IpAddr,
+        
+        #[arg(long, value_name = "PATH", help = "Socket path to listen on (uds)")]
+        path: Option<PathBuf>,
+        
+        #[arg(long, value_name = "MS", default_value_t = 0, help = "Delay before every response in milliseconds")]
+        response_delay: u64,
+        
+        #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Answer with this many bytes (e.g. 512, 64kb) instead of echoing the request")]
+        response_size: Option<usize>,
+        
+        #[arg(long, help = "Close tcp/uds connections after the first response, for benchmarks without --expect")]
+        close: bool,
     },
 }
 
+/// Options of the `http` subcommand, boxed in `Commands` since they
+/// outnumber every other subcommand's
+#[derive(Args)]
+struct HttpArgs {
+    #[arg(help = "URL to benchmark")]
+    url: String,
+    
+    #[arg(help = "More URLs to spread requests over; each request picks one at random")]
+    extra_urls: Vec<String>,
+    
+    #[arg(short, long, help = "HTTP method")]
+    method: Option<String>,
+    
+    #[arg(long, help = "Headers in format 'key:value'")]
+    headers: Option<Vec<String>>,
+    
+    #[arg(long, help = "Host header to send instead of the URL's host")]
+    host: Option<String>,
+    
+    #[arg(long, help = "TLS server name (SNI) to present instead of the URL's host")]
+    sni: Option<String>,
+    
+    #[arg(short = 'k', long, help = "Skip TLS certificate verification (flagged in the report)")]
+    insecure: bool,
+    
+    #[arg(long, value_name = "on|off", default_value = "off", action = clap::ArgAction::Set, value_parser = clap::builder::BoolishValueParser::new(), help = "Resume TLS sessions when a worker reconnects, as real clients do; off (the default) makes every handshake a full one")]
+    tls_resumption: bool,
+    
+    #[arg(long, help = "Report when the server's TLS certificate expires and how many days remain")]
+    check_cert: bool,
+    
+    #[arg(long, value_name = "DAYS", requires = "check_cert", help = "With --check-cert, fail the run when the certificate expires in fewer than DAYS days")]
+    cert_min_days: Option<u64>,
+    
+    #[arg(short, long, help = "Body content for POST/PUT")]
+    body: Option<String>,
+    
+    #[arg(long, help = "Path to body file")]
+    body_file: Option<PathBuf>,
+    
+    #[arg(long, value_name = "NAME=VALUE", value_parser = form::parse_field, conflicts_with_all = ["body", "body_file", "stream_body_size", "random_body"], help = "Send an application/x-www-form-urlencoded field, encoded for you (POST unless --method is given); repeatable")]
+    form: Vec<(String, String)>,
+    
+    #[arg(long, value_name = "NAME=VALUE", value_parser = form::parse_field, conflicts_with_all = ["body", "body_file", "stream_body_size", "random_body", "form"], help = "Send a multipart/form-data part; NAME=@PATH uploads a file (POST unless --method is given); repeatable")]
+    multipart: Vec<(String, String)>,
+
+    #[arg(long, value_name = "DEPTH", help = "Pipeline DEPTH requests per persistent HTTP/1.1 connection before reading responses")]
+    pipeline: Option<usize>,
+    
+    #[arg(long, value_name = "BYTES", conflicts_with_all = ["body", "body_file", "pipeline"], help = "Stream a generated request body of this size in chunks")]
+    stream_body_size: Option<usize>,
+    
+    #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["body", "body_file", "stream_body_size"], help = "Send a random body of this size (e.g. 512, 64kb, 1mb), generated per request")]
+    random_body: Option<usize>,
+    
+    #[arg(long, requires = "random_body", help = "Generate the --random-body payload once and reuse it for every request")]
+    fixed_random_body: bool,
+    
+    #[arg(long, value_name = "N", conflicts_with = "pipeline", help = "With --keep-alive, close and reopen each connection after N requests")]
+    keepalive_requests: Option<usize>,
+    
+    #[arg(long, conflicts_with = "pipeline", help = "Measure time to the response headers and don't download the body")]
+    no_body: bool,
+    
+    #[arg(long, conflicts_with_all = ["pipeline", "no_body"], help = "Read the response body chunk by chunk as it streams in (e.g. server-sent events) and report time to first chunk and the intervals between chunks")]
+    stream_response: bool,
+    
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), requires = "stream_response", help = "With --stream-response, stop reading each response after N chunks instead of at its end")]
+    max_chunks: Option<u64>,
+    
+    #[arg(long, value_name = "REGEX", conflicts_with_all = ["pipeline", "no_body"], help = "Count responses whose body doesn't match this pattern (regex) as failed")]
+    expect_body: Option<String>,
+    
+    #[arg(long, value_name = "PATH=VALUE", value_parser = config::parse_json_expectation, conflicts_with_all = ["pipeline", "no_body"], help = "Count responses as failed unless the JSON body has VALUE at PATH (e.g. data.status=ok); repeatable")]
+    expect_json: Vec<(String, String)>,
+    
+    #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["pipeline", "no_body", "expect_length_min", "expect_length_max"], help = "Count responses whose body isn't exactly SIZE bytes (e.g. 512, 64kb) as failed")]
+    expect_length: Option<usize>,
+    
+    #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["pipeline", "no_body"], help = "Count responses whose body is shorter than SIZE bytes as failed")]
+    expect_length_min: Option<usize>,
+    
+    #[arg(long, value_name = "SIZE", value_parser = config::parse_size, conflicts_with_all = ["pipeline", "no_body"], help = "Count responses whose body is longer than SIZE bytes as failed")]
+    expect_length_max: Option<usize>,
+    
+    #[arg(long, value_name = "SIZE", value_parser = config::parse_size, help = "Run --expect-body and --expect-json checks of bodies of at least SIZE bytes (e.g. 256kb) on the blocking thread pool, so they don't delay other requests")]
+    offload_validation_above: Option<usize>,
+    
+    #[arg(long, conflicts_with = "pipeline", help = "Count cache hits/misses from X-Cache, CF-Cache-Status and Age headers")]
+    cache_stats: bool,
+    
+    #[arg(long, conflicts_with = "pipeline", help = "Average the server-side phases (db, cache, render, ...) reported in Server-Timing response headers")]
+    server_timing: bool,
+    
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["extra_urls", "pipeline"], help = "File of host:port lines to send the URL's requests to round-robin, keeping its Host header and SNI")]
+    hosts_file: Option<PathBuf>,
+    
+    #[arg(long, requires = "hosts_file", help = "Report requests, failures and latency for each --hosts-file host")]
+    per_host: bool,
+    
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["extra_urls", "hosts_file", "pipeline"], help = "File of '[WEIGHT] [METHOD] URL [BODY]' lines; each request picks a line by weight and sends its method and body. Paths resolve against the URL")]
+    url_file: Option<PathBuf>,
+    
+    #[arg(long, requires = "url_file", help = "Report requests, failures and latency for each HTTP method of the --url-file")]
+    per_method: bool,
+    
+    #[arg(long, conflicts_with = "pipeline", help = "Send requests over HTTP/2 (ALPN h2 for https, prior knowledge for http)")]
+    http2: bool,
+    
+    #[arg(long, visible_alias = "compare-protocols", conflicts_with_all = ["http2", "pipeline", "hosts_file"], help = "Run the benchmark over HTTP/1.1 and then HTTP/2 and compare the two")]
+    compare_http2: bool,
+    
+    #[arg(long, value_name = "NAME", conflicts_with = "pipeline", help = "Send a unique id in this header with every request (e.g. X-Request-Id) and report the ids of the slowest requests")]
+    request_id_header: Option<String>,
+    
+    #[arg(long, conflicts_with = "pipeline", help = "Append a random _cb query parameter to every request so caches pass it to the origin")]
+    cache_bust: bool,
+    
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["pipeline", "cache_bust"], help = "Like --cache-bust, but send the random value in this header instead")]
+    cache_bust_header: Option<String>,
+    
+    #[arg(long, value_name = "NAME", conflicts_with = "pipeline", help = "Send the request timeout in milliseconds in this header (e.g. X-Request-Deadline) for deadline-aware servers")]
+    deadline_header: Option<String>,
+}
+
+/// How long after the `--max-duration` deadline a command may take to stop
+/// and report before it is abandoned
+const MAX_DURATION_GRACE: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let status_json = cli.status_json && !cli.tui;
+    // Requests and time of every run reported, for `--status-json`
+    let mut totals = None;
+    let result = run(cli, &mut totals).await;
+    if status_json {
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        eprintln!("{}", report::ExitStatus::new(totals, error).to_json());
+    }
+    result
+}
+
+/// Counts `report` towards the `--status-json` totals
+fn record_status(totals: &mut Option<report::RunTotals>, report: &report::BenchmarkReport) {
+    totals.get_or_insert_default().add(report);
+}
+
+/// Runs the command line, adding every run it reports to `totals`, which
+/// keeps them also when the command fails or is abandoned
+async fn run(mut cli: Cli, totals: &mut Option<report::RunTotals>) -> anyhow::Result<()> {
 
     // If TUI mode is selected, start the interactive interface
     if cli.tui {
-        return tui::run_tui().await;
+        return tui::run_tui(cli.tui_theme).await;
     }
 
+    init_logging(cli.log_level.as_deref())?;
+
     // Non-interactive CLI mode requires a command
-    let command = cli.command.ok_or_else(|| {
+    let command = cli.command.take().ok_or_else(|| {
         eprintln!("Error: When not using TUI mode, a command (http, tcp, uds) is required");
         eprintln!("Try running with --help for more information");
         anyhow::anyhow!("No command specified")
     })?;
 
+    if let Some(rate) = cli.rate
+        && rate <= 0.0
+    {
+        anyhow::bail!("--rate must be greater than zero");
+    }
+
+    if cli.rate_ramp.is_some() {
+        if cli.duration.is_some_and(|duration| duration.is_zero()) {
+            anyhow::bail!("--rate-ramp ramps over --duration, so it needs a time limit");
+        }
+        if cli.low_memory {
+            anyhow::bail!("--rate-ramp reports the rate over the run from per-request samples, which --low-memory doesn't keep");
+        }
+        // The ramp is the point of the run; don't let the default request
+        // count cut it short
+        cli.requests.get_or_insert(0);
+    }
+
+    if let Some(rate) = cli.connect_rate
+        && rate <= 0.0
+    {
+        anyhow::bail!("--connect-rate must be greater than zero");
+    }
+
+    if cli.max_duration.is_some_and(|duration| duration.is_zero()) {
+        anyhow::bail!("--max-duration must be greater than zero");
+    }
+
+    // Binding up front turns an address this host doesn't own into one clear
+    // error instead of a failure per request
+    if let Some(ip) = cli.local_addr
+        && let Err(e) = std::net::UdpSocket::bind((ip, 0))
+    {
+        anyhow::bail!("--local-addr {} cannot be bound: {}", ip, e);
+    }
+
+    if cli.runs == 0 {
+        anyhow::bail!("--runs must be at least 1");
+    }
+
+    if cli.autotune_max == 0 {
+        anyhow::bail!("--autotune-max must be at least 1");
+    }
+
+    if cli.sweep_concurrency.contains(&0) {
+        anyhow::bail!("--sweep-concurrency levels must be at least 1");
+    }
+
+    if cli.adaptive_multiple <= 0.0 {
+        anyhow::bail!("--adaptive-multiple must be greater than zero");
+    }
+
+    // One Ctrl-C handler for the whole command. Every runner it starts
+    // watches the same flag, ends early and still reports what completed.
+    let cancel = Arc::new(runner::CancelFlag::default());
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancel.cancel();
+            }
+        }
+    });
+
+    // One deadline for the whole command (`--max-duration`), covering
+    // warmup, calibration, every run and sweep level and the pauses between
+    // them. At the deadline the running benchmark stops and reports what
+    // completed, as on Ctrl-C; a command still going `MAX_DURATION_GRACE`
+    // later is abandoned.
+    let Some(max_duration) = cli.max_duration else {
+        return run_command(&cli, command, &cancel, totals).await;
+    };
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            tokio::time::sleep(max_duration).await;
+            cancel.expire();
+        }
+    });
+    match tokio::time::timeout(max_duration + MAX_DURATION_GRACE, run_command(&cli, command, &cancel, totals)).await {
+        Ok(result) => result,
+        Err(_) => anyhow::bail!("Stopped at --max-duration; the run didn't end within {:?} of the deadline", MAX_DURATION_GRACE),
+    }
+}
+
+/// Runs the subcommand, with `cancel` ending any benchmark it starts early
+async fn run_command(
+    cli: &Cli,
+    command: Commands,
+    cancel: &Arc<runner::CancelFlag>,
+    totals: &mut Option<report::RunTotals>,
+) -> anyhow::Result<()> {
     match command {
-        Commands::Http { url, method, headers, body, body_file } => {
-            let config = config::HttpConfig::new(
+        Commands::Serve { protocol, port, bind, path, response_delay, response_size, close } => {
+            let options = server::ServerOptions {
+                response_delay: Duration::from_millis(response_delay),
+                response_size,
+                close,
+            };
+            serve(protocol, (bind, port).into(), path, options).await?;
+        },
+        Commands::Http(args) => {
+            let HttpArgs { url, extra_urls, method, headers, host, sni, insecure, tls_resumption, check_cert, cert_min_days, body, body_file, form, multipart, pipeline, stream_body_size, random_body, fixed_random_body, keepalive_requests, no_body, stream_response, max_chunks, expect_body, expect_json, expect_length, expect_length_min, expect_length_max, offload_validation_above, cache_stats, server_timing, hosts_file, per_host, url_file, per_method, http2, compare_http2, request_id_header, cache_bust, cache_bust_header, deadline_header } = *args;
+            if pipeline == Some(0) {
+                anyhow::bail!("--pipeline depth must be at least 1");
+            }
+            if pipeline.is_some() && (cli.rate.is_some() || cli.rate_ramp.is_some()) {
+                anyhow::bail!("--pipeline cannot be combined with --rate or --rate-ramp");
+            }
+            if pipeline.is_some() && !extra_urls.is_empty() {
+                anyhow::bail!("--pipeline works with a single URL only");
+            }
+            if keepalive_requests.is_some() && !cli.keep_alive {
+                anyhow::bail!("--keepalive-requests requires --keep-alive");
+            }
+            if keepalive_requests == Some(0) {
+                anyhow::bail!("--keepalive-requests must be at least 1");
+            }
+            if pipeline.is_some() && random_body.is_some() && !fixed_random_body {
+                anyhow::bail!("--pipeline sends the same request every time; use --fixed-random-body");
+            }
+            if let Some(name) = &request_id_header
+                && hyper::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+            {
+                anyhow::bail!("--request-id-header '{}' is not a valid header name", name);
+            }
+            if let Some(name) = &cache_bust_header
+                && hyper::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+            {
+                anyhow::bail!("--cache-bust-header '{}' is not a valid header name", name);
+            }
+            if let Some(name) = &deadline_header
+                && hyper::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+            {
+                anyhow::bail!("--deadline-header '{}' is not a valid header name", name);
+            }
+            if compare_http2 && (cli.runs > 1 || cli.autotune || !cli.sweep_concurrency.is_empty() || cli.baseline.is_some()
+                || cli.timeout_percentile.is_some() || cli.warmup_until_stable
+                || cli.output_dir.is_some() || cli.cdf_output.is_some() || cli.raw_output.is_some() || cli.trace_file.is_some() || cli.only_errors)
+            {
+                anyhow::bail!("--compare-http2 runs the benchmark once per protocol; it can't be combined with --runs, --autotune, --sweep-concurrency, --baseline, --timeout-percentile, --warmup-until-stable, --output-dir, --cdf-output, --raw-output, --trace-file or --only-errors");
+            }
+            if cli.connect_only && (pipeline.is_some() || compare_http2) {
+                anyhow::bail!("--connect-only sends no requests, so it can't be combined with --pipeline or --compare-http2");
+            }
+            if cli.require_non_empty && (pipeline.is_some() || no_body) {
+                anyhow::bail!("--require-non-empty checks the response body, so it can't be combined with --pipeline or --no-body");
+            }
+            if cli.trace_file.is_some() && pipeline.is_some() {
+                anyhow::bail!("Pipelined requests bypass the request path --trace-file records, so it can't be combined with --pipeline");
+            }
+            if cli.low_memory && (per_host || per_method || request_id_header.is_some() || stream_response) {
+                anyhow::bail!("--low-memory keeps no per-request samples, so it can't be combined with --per-host, --per-method, --request-id-header or --stream-response");
+            }
+
+            // Forms are posted unless another method is asked for
+            let method = method.or_else(|| (!form.is_empty() || !multipart.is_empty()).then(|| "POST".to_string()));
+
+            let mut config = config::HttpConfig::new(
                 url,
                 method,
                 headers,
@@ -124,16 +678,274 @@ async fn main() -> anyhow::Result<()> {
                 cli.keep_alive,
             );
 
-            let runner = runner::HttpRunner::new(config);
-            let report = runner.run().await?;
-            report::print_report(&report, cli.output.as_deref());
+            let has_content_type = config.headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("content-type"));
+            if !form.is_empty() {
+                config.body = Some(form::urlencoded(&form));
+                if !has_content_type {
+                    config.headers.push(("Content-Type".to_string(), form::URLENCODED.to_string()));
+                }
+            } else if !multipart.is_empty() {
+                if has_content_type {
+                    anyhow::bail!("--multipart sets the Content-Type header itself, to name the boundary; drop the Content-Type from --headers");
+                }
+                let multipart = form::multipart(&multipart, &mut runner::stream_rng(cli.seed, runner::RngStream::Multipart, 0))?;
+                config.body = Some(multipart.body);
+                config.headers.push(("Content-Type".to_string(), multipart.content_type));
+            }
+
+            config.rate = cli.rate;
+            config.rate_ramp = cli.rate_ramp;
+            config.arrival = cli.arrival.unwrap_or_default();
+            config.correct_co = cli.correct_co;
+            config.connect_rate = cli.connect_rate;
+            config.local_addr = cli.local_addr;
+            config.fail_fast = cli.fail_fast;
+            config.per_worker_stats = cli.per_worker_stats;
+            config.abort_p99_above = cli.abort_if_p99_above;
+            config.low_memory = cli.low_memory;
+            config.only_errors = cli.only_errors;
+            config.require_non_empty = cli.require_non_empty;
+            config.connect_only = cli.connect_only;
+            config.adaptive_timeout = cli.adaptive_timeout.then_some(cli.adaptive_multiple);
+            if let Some(ms) = cli.connect_timeout {
+                config.connect_timeout = Duration::from_millis(ms);
+            }
+            config.extra_urls = extra_urls;
+            config.seed = cli.seed;
+            config.host = host;
+            config.sni = sni;
+            config.insecure = insecure;
+            config.tls_resumption = tls_resumption;
+            config.check_cert = check_cert;
+            config.cert_min_days = cert_min_days;
+            config.pipeline = pipeline;
+            config.stream_body_size = stream_body_size;
+            config.cache_stats = cache_stats;
+            config.server_timing = server_timing;
+            config.random_body = random_body;
+            config.fixed_random_body = fixed_random_body;
+            config.keepalive_requests = keepalive_requests;
+            config.no_body = no_body;
+            config.stream_response = stream_response.then(|| config::StreamResponse {
+                max_chunks: max_chunks.map(|max| max as usize),
+            });
+            config.expect_body = expect_body;
+            config.expect_json = expect_json;
+            config.expect_length = config::ExpectedLength::new(expect_length, expect_length_min, expect_length_max)?;
+            config.offload_validation_above = offload_validation_above;
+            if let Some(path) = hosts_file {
+                config.hosts = config::load_hosts(&path)?;
+                // Each keep-alive worker stays on one host to keep reusing its connection
+                if cli.keep_alive && config.hosts.len() > config.concurrency {
+                    anyhow::bail!(
+                        "--keep-alive pins each worker to one --hosts-file host, so --concurrency must be at least the {} hosts",
+                        config.hosts.len(),
+                    );
+                }
+            }
+            config.per_host = per_host;
+            if let Some(path) = url_file {
+                config.endpoints = config::load_endpoints(&path, &config.url)?;
+            }
+            config.per_method = per_method;
+            config.http2 = http2;
+            config.request_id_header = request_id_header;
+            config.cache_bust = match cache_bust_header {
+                Some(name) => Some(config::CacheBust::Header(name)),
+                None => cache_bust.then_some(config::CacheBust::Query),
+            };
+            config.deadline_header = deadline_header;
+
+            if cli.wait_for_ready.is_some() {
+                let uri: hyper::Uri = config.url.parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid URL {}: {}", config.url, e))?;
+                let mut uris = vec![uri.clone()];
+                for url in config.extra_urls.iter().chain(config.endpoints.iter().map(|e| &e.url)) {
+                    uris.push(url.parse().map_err(|e| anyhow::anyhow!("Invalid URL {}: {}", url, e))?);
+                }
+                if !config.hosts.is_empty() {
+                    uris = config.hosts.iter()
+                        .map(|host| http::with_authority(&uri, host))
+                        .collect::<Result<_, _>>()?;
+                }
+                // Backends from --hosts-file are verified against the URL's host
+                let tls = tls::TlsOptions {
+                    sni: config.sni.clone()
+                        .or_else(|| (!config.hosts.is_empty()).then(|| uri.host().map(str::to_string)).flatten()),
+                    insecure: config.insecure,
+                    ..Default::default()
+                };
+                let (uris, tls) = (&uris, &tls);
+                wait_for_ready(cli, &config.url, config.connect_timeout, || async move {
+                    for uri in uris {
+                        http::connect(uri, tls, false, config.local_addr, config.connect_timeout).await?;
+                    }
+                    Ok(())
+                }).await?;
+            }
+
+            if compare_http2 {
+                compare_http_versions(cli, &config, cancel, totals).await?;
+            } else if cli.autotune {
+                tune(cli, totals, |concurrency, duration| {
+                    let mut probe = config.clone();
+                    probe.concurrency = concurrency;
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let runner = runner::HttpRunner::new(probe).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else if !cli.sweep_concurrency.is_empty() {
+                sweep(cli, &config, totals, |concurrency| {
+                    let mut level = config.clone();
+                    level.concurrency = concurrency;
+                    let runner = runner::HttpRunner::new(level).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else {
+                let preliminaries = prepare(cli, |duration, throughput, keep_samples| {
+                    let mut probe = config.clone();
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let mut runner = runner::HttpRunner::new(probe).with_throughput(throughput).with_cancel(cancel.clone());
+                    if keep_samples {
+                        runner = runner.with_samples();
+                    }
+                    async move { runner.run().await }
+                }).await?;
+                if let Some(cutoff) = preliminaries.timeout_cutoff {
+                    config.strict_timeout = Some(cutoff.timeout);
+                }
+                let mut runner = runner::HttpRunner::new(config.clone()).with_cancel(cancel.clone());
+                if cli.output_dir.is_some() || cli.cdf_output.is_some() {
+                    runner = runner.with_samples();
+                }
+                if let Some(output) = raw_output(cli) {
+                    runner = runner.with_raw_output(output);
+                }
+                if let Some(trace) = trace(cli) {
+                    runner = runner.with_trace(trace);
+                }
+                execute(cli, &config, preliminaries, totals, || runner.run()).await?;
+            }
+        },
+        Commands::Grpc { target, method, headers, data_file, hex } => {
+            if cli.connect_only {
+                anyhow::bail!("--connect-only measures TCP and TLS connection setup; it applies to http and tcp, not grpc");
+            }
+            if cli.require_non_empty {
+                anyhow::bail!("--require-non-empty applies to http, tcp and uds responses, not grpc");
+            }
+            if cli.trace_file.is_some() {
+                anyhow::bail!("--trace-file records HTTP, TCP and UDS requests; gRPC benchmarks can't be traced");
+            }
+            let message = match (data_file, hex) {
+                (Some(path), _) => std::fs::read(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", path, e))?,
+                (None, Some(hex)) => grpc::decode_hex(&hex)?,
+                (None, None) => Vec::new(),
+            };
+
+            let mut config = config::GrpcConfig::new(
+                target,
+                method,
+                headers,
+                message,
+                cli.concurrency,
+                cli.requests,
+                cli.duration,
+                cli.timeout,
+                cli.keep_alive,
+            );
+
+            config.rate = cli.rate;
+            config.rate_ramp = cli.rate_ramp;
+            config.arrival = cli.arrival.unwrap_or_default();
+            config.seed = cli.seed;
+            config.correct_co = cli.correct_co;
+            config.connect_rate = cli.connect_rate;
+            config.local_addr = cli.local_addr;
+            config.fail_fast = cli.fail_fast;
+            config.per_worker_stats = cli.per_worker_stats;
+            config.abort_p99_above = cli.abort_if_p99_above;
+            config.low_memory = cli.low_memory;
+            config.only_errors = cli.only_errors;
+            config.adaptive_timeout = cli.adaptive_timeout.then_some(cli.adaptive_multiple);
+            if let Some(ms) = cli.connect_timeout {
+                config.connect_timeout = Duration::from_millis(ms);
+            }
+
+            if cli.wait_for_ready.is_some() {
+                let uri: hyper::Uri = config.url.parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid URL {}: {}", config.url, e))?;
+                let uri = &uri;
+                wait_for_ready(cli, &config.url, config.connect_timeout, || async move {
+                    http::connect(uri, &tls::TlsOptions::default(), true, config.local_addr, config.connect_timeout).await?;
+                    Ok(())
+                }).await?;
+            }
+
+            if cli.autotune {
+                tune(cli, totals, |concurrency, duration| {
+                    let mut probe = config.clone();
+                    probe.concurrency = concurrency;
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let runner = runner::GrpcRunner::new(probe).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else if !cli.sweep_concurrency.is_empty() {
+                sweep(cli, &config, totals, |concurrency| {
+                    let mut level = config.clone();
+                    level.concurrency = concurrency;
+                    let runner = runner::GrpcRunner::new(level).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else {
+                let preliminaries = prepare(cli, |duration, throughput, keep_samples| {
+                    let mut probe = config.clone();
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let mut runner = runner::GrpcRunner::new(probe).with_throughput(throughput).with_cancel(cancel.clone());
+                    if keep_samples {
+                        runner = runner.with_samples();
+                    }
+                    async move { runner.run().await }
+                }).await?;
+                if let Some(cutoff) = preliminaries.timeout_cutoff {
+                    config.strict_timeout = Some(cutoff.timeout);
+                }
+                let mut runner = runner::GrpcRunner::new(config.clone()).with_cancel(cancel.clone());
+                if cli.output_dir.is_some() || cli.cdf_output.is_some() {
+                    runner = runner.with_samples();
+                }
+                if let Some(output) = raw_output(cli) {
+                    runner = runner.with_raw_output(output);
+                }
+                execute(cli, &config, preliminaries, totals, || runner.run()).await?;
+            }
         },
-        Commands::Tcp { address, data, data_file, expect } => {
-            let config = config::TcpConfig::new(
-                address,
+        Commands::Tcp { address, data, data_file, delimiter, mut expect, expect_length, expect_length_min, expect_length_max, script, repeat, expected_response_size, half_close, offload_validation_above, proxy_protocol, proxy_source, preset, hosts_file, per_host } => {
+            // A preset only fills in what --data/--data-file/--expect leave unset
+            let data = match preset {
+                Some(name) => {
+                    let (preset_data, preset_expect) = config::preset(&name).ok_or_else(|| {
+                        let names: Vec<&str> = config::PRESETS.iter().map(|(name, _, _)| *name).collect();
+                        anyhow::anyhow!("Unknown preset '{}' (expected one of: {})", name, names.join(", "))
+                    })?;
+                    if expect.is_empty() {
+                        expect.push(preset_expect.to_string());
+                    }
+                    data.or_else(|| data_file.is_none().then(|| preset_data.to_string()))
+                },
+                None => data,
+            };
+
+            let mut config = config::TcpConfig::new(
+                address.unwrap_or_default(),
                 data,
-                data_file,
-                expect,
+                data_file.clone(),
+                None,
                 cli.concurrency,
                 cli.requests,
                 cli.duration,
@@ -141,16 +953,132 @@ async fn main() -> anyhow::Result<()> {
                 cli.keep_alive,
             );
 
-            let runner = runner::TcpRunner::new(config);
-            let report = runner.run().await?;
-            report::print_report(&report, cli.output.as_deref());
+            config.rate = cli.rate;
+            config.rate_ramp = cli.rate_ramp;
+            config.arrival = cli.arrival.unwrap_or_default();
+            config.seed = cli.seed;
+            config.correct_co = cli.correct_co;
+            config.connect_rate = cli.connect_rate;
+            config.local_addr = cli.local_addr;
+            config.fail_fast = cli.fail_fast;
+            config.per_worker_stats = cli.per_worker_stats;
+            config.abort_p99_above = cli.abort_if_p99_above;
+            config.low_memory = cli.low_memory;
+            config.only_errors = cli.only_errors;
+            config.require_non_empty = cli.require_non_empty;
+            config.connect_only = cli.connect_only;
+            config.adaptive_timeout = cli.adaptive_timeout.then_some(cli.adaptive_multiple);
+            if let Some(ms) = cli.connect_timeout {
+                config.connect_timeout = Duration::from_millis(ms);
+            }
+            if let Some(path) = hosts_file {
+                config.hosts = config::load_hosts(&path)?;
+            }
+            config.per_host = per_host;
+
+            config.expects = expect;
+            if let (Some(path), Some(delimiter)) = (&data_file, &delimiter) {
+                config.payloads = config::load_payloads(path, delimiter)?;
+            }
+
+            if let Some(path) = script {
+                let mut steps = config::load_script(&path)?;
+                let measured = steps.pop().expect("load_script returns at least one step");
+                config.payloads = vec![measured.send];
+                config.expects = measured.expect.iter().map(|p| p.as_str().to_string()).collect();
+                config.setup = steps;
+            }
+
+            config.repeat = repeat;
+            config.response_size = expected_response_size;
+            config.half_close = half_close;
+            config.offload_validation_above = offload_validation_above;
+            config.expect_length = config::ExpectedLength::new(expect_length, expect_length_min, expect_length_max)?;
+            config.proxy_protocol = proxy_protocol;
+            config.proxy_source = proxy_source;
+            if cli.connect_only && (repeat > 1 || !config.setup.is_empty() || config.proxy_protocol.is_some()) {
+                anyhow::bail!("--connect-only sends nothing, so it can't be combined with --repeat, a multi-step --script or --proxy-protocol");
+            }
+            if repeat > 1 && config.expects.is_empty() {
+                anyhow::bail!("--repeat needs --expect (or a --preset) to tell where each response ends");
+            }
+            if repeat > 1 && half_close {
+                anyhow::bail!("--half-close ends the connection's input after the first send, so it can't be combined with --repeat");
+            }
+            if cli.low_memory && (per_host || repeat > 1) {
+                anyhow::bail!("--low-memory keeps no per-request samples, so it can't be combined with --per-host or --repeat");
+            }
+
+            if cli.wait_for_ready.is_some() {
+                let addresses = if config.hosts.is_empty() {
+                    std::slice::from_ref(&config.address)
+                } else {
+                    config.hosts.as_slice()
+                };
+                let target = runner::TcpRunner::new(config.clone()).target();
+                wait_for_ready(cli, &target, config.connect_timeout, || async move {
+                    for address in addresses {
+                        tcp::connect(address, config.local_addr).await?;
+                    }
+                    Ok(())
+                }).await?;
+            }
+
+            if cli.autotune {
+                tune(cli, totals, |concurrency, duration| {
+                    let mut probe = config.clone();
+                    probe.concurrency = concurrency;
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let runner = runner::TcpRunner::new(probe).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else if !cli.sweep_concurrency.is_empty() {
+                sweep(cli, &config, totals, |concurrency| {
+                    let mut level = config.clone();
+                    level.concurrency = concurrency;
+                    let runner = runner::TcpRunner::new(level).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else {
+                let preliminaries = prepare(cli, |duration, throughput, keep_samples| {
+                    let mut probe = config.clone();
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let mut runner = runner::TcpRunner::new(probe).with_throughput(throughput).with_cancel(cancel.clone());
+                    if keep_samples {
+                        runner = runner.with_samples();
+                    }
+                    async move { runner.run().await }
+                }).await?;
+                if let Some(cutoff) = preliminaries.timeout_cutoff {
+                    config.strict_timeout = Some(cutoff.timeout);
+                }
+                let mut runner = runner::TcpRunner::new(config.clone()).with_cancel(cancel.clone());
+                if cli.output_dir.is_some() || cli.cdf_output.is_some() {
+                    runner = runner.with_samples();
+                }
+                if let Some(output) = raw_output(cli) {
+                    runner = runner.with_raw_output(output);
+                }
+                if let Some(trace) = trace(cli) {
+                    runner = runner.with_trace(trace);
+                }
+                execute(cli, &config, preliminaries, totals, || runner.run()).await?;
+            }
         },
-        Commands::Uds { path, data, data_file, expect } => {
-            let config = config::UdsConfig::new(
+        Commands::Uds { path, data, data_file, delimiter, expect, expect_length, expect_length_min, expect_length_max, script, repeat, expected_response_size, half_close, offload_validation_above } => {
+            if cli.connect_only {
+                anyhow::bail!("--connect-only measures TCP and TLS connection setup; it applies to http and tcp, not uds");
+            }
+            if cli.local_addr.is_some() {
+                anyhow::bail!("--local-addr applies to TCP connections only, not uds");
+            }
+            let mut config = config::UdsConfig::new(
                 path,
                 data,
-                data_file,
-                expect,
+                data_file.clone(),
+                None,
                 cli.concurrency,
                 cli.requests,
                 cli.duration,
@@ -158,11 +1086,528 @@ async fn main() -> anyhow::Result<()> {
                 cli.keep_alive,
             );
 
-            let runner = runner::UdsRunner::new(config);
-            let report = runner.run().await?;
-            report::print_report(&report, cli.output.as_deref());
+            config.rate = cli.rate;
+            config.rate_ramp = cli.rate_ramp;
+            config.arrival = cli.arrival.unwrap_or_default();
+            config.seed = cli.seed;
+            config.correct_co = cli.correct_co;
+            config.connect_rate = cli.connect_rate;
+            config.fail_fast = cli.fail_fast;
+            config.per_worker_stats = cli.per_worker_stats;
+            config.abort_p99_above = cli.abort_if_p99_above;
+            config.low_memory = cli.low_memory;
+            config.only_errors = cli.only_errors;
+            config.require_non_empty = cli.require_non_empty;
+            config.adaptive_timeout = cli.adaptive_timeout.then_some(cli.adaptive_multiple);
+            if let Some(ms) = cli.connect_timeout {
+                config.connect_timeout = Duration::from_millis(ms);
+            }
+
+            config.expects = expect;
+            if let (Some(path), Some(delimiter)) = (&data_file, &delimiter) {
+                config.payloads = config::load_payloads(path, delimiter)?;
+            }
+
+            if let Some(path) = script {
+                let mut steps = config::load_script(&path)?;
+                let measured = steps.pop().expect("load_script returns at least one step");
+                config.payloads = vec![measured.send];
+                config.expects = measured.expect.iter().map(|p| p.as_str().to_string()).collect();
+                config.setup = steps;
+            }
+
+            config.repeat = repeat;
+            config.response_size = expected_response_size;
+            config.half_close = half_close;
+            config.offload_validation_above = offload_validation_above;
+            config.expect_length = config::ExpectedLength::new(expect_length, expect_length_min, expect_length_max)?;
+            if repeat > 1 && config.expects.is_empty() {
+                anyhow::bail!("--repeat needs --expect (or a --preset) to tell where each response ends");
+            }
+            if repeat > 1 && half_close {
+                anyhow::bail!("--half-close ends the connection's input after the first send, so it can't be combined with --repeat");
+            }
+            if cli.low_memory && repeat > 1 {
+                anyhow::bail!("--low-memory keeps no per-request samples, so it can't be combined with --repeat");
+            }
+
+            if cli.wait_for_ready.is_some() {
+                let path = &config.path;
+                wait_for_ready(cli, &path.display().to_string(), config.connect_timeout, || async move {
+                    tokio::net::UnixStream::connect(path).await?;
+                    Ok(())
+                }).await?;
+            }
+
+            if cli.autotune {
+                tune(cli, totals, |concurrency, duration| {
+                    let mut probe = config.clone();
+                    probe.concurrency = concurrency;
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let runner = runner::UdsRunner::new(probe).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else if !cli.sweep_concurrency.is_empty() {
+                sweep(cli, &config, totals, |concurrency| {
+                    let mut level = config.clone();
+                    level.concurrency = concurrency;
+                    let runner = runner::UdsRunner::new(level).with_cancel(cancel.clone());
+                    async move { runner.run().await }
+                }).await?;
+            } else {
+                let preliminaries = prepare(cli, |duration, throughput, keep_samples| {
+                    let mut probe = config.clone();
+                    probe.requests = 0;
+                    probe.duration = duration;
+                    let mut runner = runner::UdsRunner::new(probe).with_throughput(throughput).with_cancel(cancel.clone());
+                    if keep_samples {
+                        runner = runner.with_samples();
+                    }
+                    async move { runner.run().await }
+                }).await?;
+                if let Some(cutoff) = preliminaries.timeout_cutoff {
+                    config.strict_timeout = Some(cutoff.timeout);
+                }
+                let mut runner = runner::UdsRunner::new(config.clone()).with_cancel(cancel.clone());
+                if cli.output_dir.is_some() || cli.cdf_output.is_some() {
+                    runner = runner.with_samples();
+                }
+                if let Some(output) = raw_output(cli) {
+                    runner = runner.with_raw_output(output);
+                }
+                if let Some(trace) = trace(cli) {
+                    runner = runner.with_trace(trace);
+                }
+                execute(cli, &config, preliminaries, totals, || runner.run()).await?;
+            }
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Runs the built-in echo server until Ctrl-C (`serve`)
+async fn serve(
+    protocol: server::ServeProtocol,
+    address: std::net::SocketAddr,
+    path: Option<PathBuf>,
+    options: server::ServerOptions,
+) -> anyhow::Result<()> {
+    let served = match protocol {
+        server::ServeProtocol::Http | server::ServeProtocol::Tcp => {
+            let listener = tokio::net::TcpListener::bind(address).await
+                .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", address, e))?;
+            println!("Serving {} echo on {} (Ctrl-C to stop)", protocol, address);
+            if protocol == server::ServeProtocol::Http {
+                tokio::spawn(server::serve_http(listener, options))
+            } else {
+                tokio::spawn(server::serve_tcp(listener, options))
+            }
+        },
+        server::ServeProtocol::Uds => {
+            let path = path.as_ref().ok_or_else(|| anyhow::anyhow!("--protocol uds needs --path"))?;
+            // A socket left behind by an earlier server would make bind fail
+            if std::fs::symlink_metadata(path).is_ok_and(|m| std::os::unix::fs::FileTypeExt::is_socket(&m.file_type())) {
+                std::fs::remove_file(path)?;
+            }
+            let listener = tokio::net::UnixListener::bind(path)
+                .map_err(|e| anyhow::anyhow!("Failed to listen on {:?}: {}", path, e))?;
+            println!("Serving {} echo on {:?} (Ctrl-C to stop)", protocol, path);
+            tokio::spawn(server::serve_uds(listener, options))
+        },
+    };
+
+    tokio::signal::ctrl_c().await?;
+    served.abort();
+    if protocol == server::ServeProtocol::Uds && let Some(path) = &path {
+        let _ = std::fs::remove_file(path);
+    }
+
+    Ok(())
+}
+
+/// Where `--raw-output` streams to, if anywhere
+fn raw_output(cli: &Cli) -> Option<runner::RawOutput> {
+    cli.raw_output.clone().map(|path| runner::RawOutput { path, progress: cli.raw_progress, sample_rate: cli.sample_rate, seed: cli.seed })
+}
+
+/// Where `--trace-file` records requests to, if anywhere
+fn trace(cli: &Cli) -> Option<trace::TraceOptions> {
+    cli.trace_file.clone().map(|path| trace::TraceOptions { path, max_requests: cli.trace_max_requests as usize })
+}
+
+/// Options of the text report from the command line, with the output's
+/// colors set up for printing it
+fn report_options(cli: &Cli) -> report::ReportOptions {
+    report::configure_color();
+    report::ReportOptions {
+        time_unit: cli.time_unit,
+        summary_line: cli.summary_line,
+        thousands_sep: !cli.no_thousands_sep,
+        human_bytes: cli.human_bytes,
+        only_errors: cli.only_errors,
+    }
+}
+
+/// Runs the benchmark once, or `--runs` times with an aggregate summary, and
+/// prints the result in the requested format. With `--output-dir` the report,
+/// raw samples and `config` are also written there, and `--cdf-output` gets
+/// the latency CDF of all runs. The `preliminaries` are recorded on every
+/// report, and every run is counted in `totals`.
+async fn execute<C, F, Fut>(
+    cli: &Cli,
+    config: &C,
+    preliminaries: Preliminaries,
+    totals: &mut Option<report::RunTotals>,
+    mut run: F,
+) -> anyhow::Result<()>
+where
+    C: serde::Serialize,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<report::BenchmarkReport, error::BenchmarkError>>,
+{
+    let mut run = || {
+        let report = run();
+        async move {
+            report.await.map(|mut report| {
+                report.warmup = preliminaries.warmup;
+                report.timeout_cutoff = preliminaries.timeout_cutoff;
+                report
+            })
+        }
+    };
+
+    let options = report_options(cli);
+
+    if cli.runs > 1 {
+        let delay = Duration::from_millis(cli.run_delay);
+        let aggregate = runner::run_repeated(cli.runs, delay, run).await?;
+        report::print_aggregate_report(&aggregate, config, cli.output.as_deref(), &options);
+        for report in &aggregate.reports {
+            append_history(cli, report, config);
+            record_status(totals, report);
+        }
+        if let Some(report) = aggregate.reports.last() {
+            check_certificate(report)?;
+        }
+        if let Some(dir) = &cli.output_dir {
+            report::write_aggregate_output_dir(dir, &aggregate, config)
+                .map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", dir, e))?;
+        }
+        if let Some(path) = &cli.cdf_output {
+            let samples = aggregate.reports.iter().flat_map(|report| &report.samples);
+            write_cdf(path, &report::sample_latencies(samples, cli.correct_co))?;
+        }
+    } else {
+        let report = run().await?;
+        report::print_report(&report, config, cli.output.as_deref(), &options);
+        append_history(cli, &report, config);
+        record_status(totals, &report);
+        if let Some(dir) = &cli.output_dir {
+            report::write_output_dir(dir, &report, config)
+                .map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", dir, e))?;
+        }
+        if let Some(path) = &cli.cdf_output {
+            write_cdf(path, &report::sample_latencies(&report.samples, cli.correct_co))?;
+        }
+        check_stop_reason(&report)?;
+        check_certificate(&report)?;
+        if let Some(path) = &cli.baseline {
+            check_baseline(cli, path, &report)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a finished run to the report history, unless `--no-history`. The
+/// history is a convenience, so failing to write it only warns.
+fn append_history<C: serde::Serialize>(cli: &Cli, report: &report::BenchmarkReport, config: &C) {
+    if cli.no_history {
+        return;
+    }
+    let path = match &cli.history_file {
+        Some(path) => path.clone(),
+        None => match config_manager::get_history_path() {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Warning: not recording the report in the history: {}", e);
+                return;
+            },
+        },
+    };
+    if let Err(e) = report::append_history(&path, report, config, cli.history_max) {
+        eprintln!("Warning: failed to append the report to {:?}: {}", path, e);
+    }
+}
+
+/// Longest the `--compare-http2` probe waits to connect and for its answer,
+/// so a server that ignores HTTP/2 fails the check quickly
+const HTTP2_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Runs the HTTP benchmark over HTTP/1.1 and then over HTTP/2 and prints both
+/// reports with the change between them (`--compare-http2`). The target must
+/// answer one HTTP/2 request first, so a server without HTTP/2 is reported as
+/// such instead of as an HTTP/2 run of failed requests.
+async fn compare_http_versions(
+    cli: &Cli,
+    config: &config::HttpConfig,
+    cancel: &Arc<runner::CancelFlag>,
+    totals: &mut Option<report::RunTotals>,
+) -> anyhow::Result<()> {
+    let uri: hyper::Uri = config.url.parse()
+        .map_err(|e| anyhow::anyhow!("Invalid URL {}: {}", config.url, e))?;
+    let tls = tls::TlsOptions { sni: config.sni.clone(), insecure: config.insecure, ..Default::default() };
+    // A prior-knowledge request to an HTTP/1.1-only server may get no answer at all
+    http::send_request(
+        &uri,
+        &config.method,
+        &config.headers,
+        config.host.as_deref(),
+        &tls,
+        config.body.as_deref(),
+        None,
+        &limiter::ConnectLimiter::new(None),
+        config.local_addr,
+        config.connect_timeout.min(HTTP2_PROBE_TIMEOUT),
+        config.timeout.min(HTTP2_PROBE_TIMEOUT),
+        true,
+        None,
+        false,
+        None,
+        false,
+        None,
+    ).await
+        .map_err(|e| anyhow::anyhow!("{} does not support HTTP/2, so there is nothing to compare: {}", config.url, e))?;
+
+    let options = report_options(cli);
+
+    // A stopped HTTP/1.1 run leaves nothing to compare against
+    let http1_report = runner::HttpRunner::new(config.clone()).with_cancel(cancel.clone()).run().await?;
+    record_status(totals, &http1_report);
+    if let Err(e) = check_stop_reason(&http1_report) {
+        report::print_report(&http1_report, config, cli.output.as_deref(), &options);
+        return Err(e);
+    }
+
+    let mut http2 = config.clone();
+    http2.http2 = true;
+    let http2_report = runner::HttpRunner::new(http2).with_cancel(cancel.clone()).run().await?;
+    record_status(totals, &http2_report);
+    report::print_http2_comparison(&http1_report, &http2_report, config, cli.output.as_deref(), &options);
+    check_stop_reason(&http2_report)
+}
+
+/// Fails a run that ended early through Ctrl-C or another early stop
+fn check_stop_reason(report: &report::BenchmarkReport) -> anyhow::Result<()> {
+    match report.stop_reason {
+        report::StopReason::Interrupted => anyhow::bail!("Interrupted"),
+        report::StopReason::MaxDuration => anyhow::bail!("Stopped at --max-duration before the run completed"),
+        report::StopReason::LatencySpike => anyhow::bail!("Stopped because p99 latency crossed --abort-if-p99-above"),
+        report::StopReason::RequestsCompleted | report::StopReason::DurationElapsed => Ok(()),
+    }
+}
+
+/// Fails a run whose server certificate expires within `--cert-min-days`
+fn check_certificate(report: &report::BenchmarkReport) -> anyhow::Result<()> {
+    if report::cert_expiring(report) {
+        anyhow::bail!(
+            "The server certificate expires at {} ({} days left), within --cert-min-days {}",
+            report.cert_not_after.as_deref().unwrap_or_default(),
+            report.cert_days_remaining.unwrap_or_default(),
+            report.cert_min_days.unwrap_or_default(),
+        );
+    }
+    if report.cert_min_days.is_some() && report.cert_not_after.is_none() {
+        anyhow::bail!("No server certificate was received, so --cert-min-days couldn't be checked");
+    }
+    Ok(())
+}
+
+/// Writes `--cdf-output` from sorted `latencies`
+fn write_cdf(path: &Path, latencies: &[Duration]) -> anyhow::Result<()> {
+    report::write_cdf(path, latencies)
+        .map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", path, e))
+}
+
+/// Compares `report` against the `--baseline` report and fails when rps or
+/// p99 regressed by more than `--tolerance`. The comparison table is printed
+/// with the text report only, so JSON and Markdown output stay parseable.
+fn check_baseline(cli: &Cli, path: &Path, report: &report::BenchmarkReport) -> anyhow::Result<()> {
+    let baseline = report::load_report(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read baseline {:?}: {}", path, e))?;
+    let deltas = report::compare_reports(&baseline, report);
+
+    if matches!(cli.output.as_deref(), None | Some("text")) {
+        report::print_comparison(&deltas, cli.tolerance);
+    }
+
+    let regressed: Vec<String> = report::regressions(&deltas, cli.tolerance)
+        .iter()
+        .map(|d| format!("{} {:+.1}% ({:.2} -> {:.2})", d.metric, d.change() * 100.0, d.baseline, d.current))
+        .collect();
+    if !regressed.is_empty() {
+        anyhow::bail!(
+            "Regressed beyond {:.1}% of baseline {:?}: {}",
+            cli.tolerance * 100.0,
+            path,
+            regressed.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Pause between `--wait-for-ready` connection attempts
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Retries `probe` until it succeeds, for up to `--wait-for-ready` seconds, so
+/// a benchmark started alongside its server doesn't fail while the server is
+/// still coming up. Each attempt is cut off after `connect_timeout`.
+async fn wait_for_ready<F, Fut>(cli: &Cli, target: &str, connect_timeout: Duration, mut probe: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<(), error::BenchmarkError>>,
+{
+    let Some(secs) = cli.wait_for_ready else {
+        return Ok(());
+    };
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(secs);
+    let mut waiting = false;
+
+    loop {
+        let error = match tokio::time::timeout(connect_timeout, probe()).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => error::BenchmarkError::ConnectionTimeout(connect_timeout).to_string(),
+        };
+        tracing::debug!(%error, "target not ready");
+
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        if remaining.is_zero() {
+            anyhow::bail!("{} was not ready within {}s (--wait-for-ready): {}", target, secs, error);
+        }
+        if !waiting {
+            eprintln!("Waiting up to {}s for {} to accept connections...", secs, target);
+            waiting = true;
+        }
+        tokio::time::sleep(remaining.min(READY_POLL_INTERVAL)).await;
+    }
+}
+
+/// Phases run before the measured benchmark
+#[derive(Debug, Clone, Copy, Default)]
+struct Preliminaries {
+    warmup: Option<report::Warmup>,
+    timeout_cutoff: Option<report::TimeoutCutoff>,
+}
+
+/// Runs `--warmup-until-stable` and then the `--timeout-percentile`
+/// calibration, if requested. `probe` starts a run of the given duration with
+/// the benchmark's settings, reporting its throughput on the channel and
+/// keeping its samples on the report when asked to.
+async fn prepare<F, Fut>(cli: &Cli, mut probe: F) -> anyhow::Result<Preliminaries>
+where
+    F: FnMut(Duration, tokio::sync::mpsc::UnboundedSender<runner::ThroughputPoint>, bool) -> Fut,
+    Fut: std::future::Future<Output = Result<report::BenchmarkReport, error::BenchmarkError>>,
+{
+    let mut preliminaries = Preliminaries::default();
+
+    if cli.warmup_until_stable {
+        let options = runner::WarmupOptions {
+            window: cli.warmup_window as usize,
+            max_cv: cli.warmup_cv / 100.0,
+            max_duration: Duration::from_secs(cli.warmup_max),
+        };
+        preliminaries.warmup = Some(runner::warm_up(&options, |duration, throughput| probe(duration, throughput, false)).await?);
+    }
+
+    if let Some(percentile) = cli.timeout_percentile {
+        let duration = Duration::from_secs(cli.calibration_duration);
+        let cutoff = runner::calibrate_timeout(percentile, duration, |duration| {
+            // Nobody watches the calibration run's throughput
+            let (throughput, _) = tokio::sync::mpsc::unbounded_channel();
+            probe(duration, throughput, true)
+        }).await?;
+        preliminaries.timeout_cutoff = Some(cutoff);
+    }
+
+    Ok(preliminaries)
+}
+
+/// Runs an `--autotune` sweep and prints the sweep table and recommendation.
+async fn tune<F, Fut>(cli: &Cli, totals: &mut Option<report::RunTotals>, mut probe: F) -> anyhow::Result<()>
+where
+    F: FnMut(usize, Duration) -> Fut,
+    Fut: std::future::Future<Output = Result<report::BenchmarkReport, error::BenchmarkError>>,
+{
+    let options = report_options(cli);
+
+    let autotune_options = runner::AutotuneOptions {
+        max_concurrency: cli.autotune_max,
+        probe_duration: Duration::from_secs(cli.autotune_probe),
+        min_gain: 0.1,
+        max_p99: cli.autotune_max_p99.map(Duration::from_millis),
+    };
+
+    // Every probe is a run of its own, counted as it completes
+    let probed = std::cell::Cell::new(*totals);
+    let result = runner::autotune(&autotune_options, |concurrency, duration| {
+        let report = probe(concurrency, duration);
+        let probed = &probed;
+        async move {
+            let report = report.await?;
+            let mut totals = probed.get();
+            record_status(&mut totals, &report);
+            probed.set(totals);
+            Ok(report)
+        }
+    }).await;
+    *totals = probed.get();
+    let result = result?;
+    report::print_autotune_report(&result, cli.output.as_deref(), &options);
+
+    Ok(())
+}
+
+/// Runs the benchmark at every `--sweep-concurrency` level and prints the
+/// scaling table.
+async fn sweep<C, F, Fut>(cli: &Cli, config: &C, totals: &mut Option<report::RunTotals>, run: F) -> anyhow::Result<()>
+where
+    C: serde::Serialize,
+    F: FnMut(usize) -> Fut,
+    Fut: std::future::Future<Output = Result<report::BenchmarkReport, error::BenchmarkError>>,
+{
+    let options = report_options(cli);
+
+    let delay = Duration::from_millis(cli.run_delay);
+    let result = runner::sweep_concurrency(&cli.sweep_concurrency, delay, run).await?;
+    report::print_sweep_report(&result, config, cli.output.as_deref(), &options);
+    for report in &result.reports {
+        record_status(totals, report);
+    }
+
+    Ok(())
+}
+
+/// Sends tracing output to stderr. `--log-level` wins over `RUST_LOG`; with
+/// neither set only errors are shown, so failed requests stay quiet unless
+/// asked for (`--log-level warn` or `debug`).
+fn init_logging(level: Option<&str>) -> anyhow::Result<()> {
+    use std::io::IsTerminal;
+    use tracing_subscriber::EnvFilter;
+
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error")),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .init();
+
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::time::timeout;
+use regex::bytes::Regex;
+use serde::{Serialize, Serializer};
+use crate::config::ExpectedLength;
+use crate::error::BenchmarkError;
+use crate::tcp::write_payload;
+
+/// An open TCP or Unix socket connection handed to a `Protocol`
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// One request of a TCP/UDS benchmark
+#[derive(Debug, Clone, Copy)]
+pub struct Request<'a> {
+    /// Payload to send, if any
+    pub data: Option<&'a [u8]>,
+    /// Pattern the response must match (`--expect`)
+    pub expect: Option<&'a Pattern>,
+    /// Bounds the response's length must fall within (`--expect-length`)
+    pub expect_length: Option<ExpectedLength>,
+    /// Fail an empty response (`--require-non-empty`)
+    pub require_non_empty: bool,
+    pub timeout: Duration,
+    pub buffer_size: usize,
+    /// Expected size of the response (`--expected-response-size`), if known
+    pub response_size: Option<usize>,
+    /// Shut down the write half of the connection once the payload is sent
+    /// (`--half-close`), for servers that respond at end of input
+    pub half_close: bool,
+    /// Match `expect` against responses of at least this many bytes on the
+    /// blocking thread pool (`--offload-validation-above`)
+    pub offload_above: Option<usize>,
+}
+
+/// Response to one `Request`, timed from the start of the exchange
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub data: Vec<u8>,
+    /// When the first response byte arrived, if the protocol tracks it
+    pub first_byte: Option<Duration>,
+    pub elapsed: Duration,
+}
+
+/// What one TCP or UDS request exchanged over its connection
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub response: Response,
+    /// Latency of each exchange of a `--repeat` request; empty otherwise
+    pub messages: Vec<Duration>,
+    /// Bytes the protocol wrote, framing included
+    pub bytes_sent: usize,
+}
+
+/// The request/response exchange the TCP and UDS runners perform on each
+/// connection, after connecting and running any script setup steps.
+///
+/// Implement this for an in-house wire protocol and pass it to
+/// `TcpRunner::with_protocol` or `UdsRunner::with_protocol`.
+#[async_trait]
+pub trait Protocol: Send + Sync {
+    async fn exchange(
+        &self,
+        conn: &mut dyn Connection,
+        request: &Request<'_>,
+    ) -> Result<Response, BenchmarkError>;
+}
+
+/// An expected response pattern (`--expect`), compiled once per run and
+/// matched against the raw response bytes, so binary responses with a
+/// textual marker match too
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, BenchmarkError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| BenchmarkError::Parse(format!("Invalid regex pattern '{}': {}", pattern, e)))?;
+        Ok(Pattern { regex })
+    }
+    
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+    
+    /// Whether the response received so far matches, without copying it
+    pub fn is_match(&self, response: &[u8]) -> bool {
+        self.regex.is_match(response)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Default protocol: writes the payload, then reads until the expected
+/// pattern matches, or until EOF, the timeout or the most bytes
+/// `expect_length` allows when no pattern is expected.
+/// Reads go through a `buffer_size` buffer, or straight into a response
+/// reserved up front when `response_size` is given.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Echo;
+
+#[async_trait]
+impl Protocol for Echo {
+    async fn exchange(
+        &self,
+        conn: &mut dyn Connection,
+        request: &Request<'_>,
+    ) -> Result<Response, BenchmarkError> {
+        let start_time = Instant::now();
+        
+        // Send data if provided
+        if let Some(bytes) = request.data
+            && !bytes.is_empty()
+        {
+            write_payload(conn, bytes, request.timeout).await?;
+            tracing::debug!(bytes = bytes.len(), "request sent");
+        }
+        
+        // Servers that read to EOF only answer once the client stops writing
+        if request.half_close {
+            match timeout(request.timeout, conn.shutdown()).await {
+                Ok(Ok(_)) => tracing::debug!("write half closed"),
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => return Err(BenchmarkError::RequestTimeout(request.timeout)),
+            }
+        }
+        
+        // Read response, noting when its first byte arrives
+        let mut response = Vec::with_capacity(request.response_size.unwrap_or(0));
+        let mut buffer = request.response_size.is_none().then(|| vec![0; request.buffer_size]);
+        let mut first_byte = None;
+        
+        // If we expect a pattern, read until we find it or timeout
+        if let Some(pattern) = request.expect {
+            let deadline = Instant::now() + request.timeout;
+            let mut found = false;
+            
+            while !found {
+                // A server that goes quiet mustn't hold the request past the timeout
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match timeout(remaining, read_chunk(conn, &mut response, buffer.as_deref_mut())).await {
+                    Ok(Ok(0)) => break, // EOF
+                    Ok(Ok(_)) => {
+                        first_byte.get_or_insert_with(|| start_time.elapsed());
+                        // Check if pattern is found
+                        let (matched, checked) = match_pattern(pattern, response, request.offload_above).await;
+                        response = checked;
+                        if matched {
+                            found = true;
+                            break;
+                        }
+                    },
+                    Ok(Err(e)) => return Err(e.into()),
+                    Err(_) => return Err(BenchmarkError::RequestTimeout(request.timeout)),
+                }
+            }
+            
+            if !found {
+                return Err(BenchmarkError::ResponseValidation(
+                    format!("Expected pattern '{}' not found in response", pattern.as_str())
+                ));
+            }
+        } else {
+            // Without a pattern, just read what's available within the timeout
+            match timeout(request.timeout, async {
+                loop {
+                    match read_chunk(conn, &mut response, buffer.as_deref_mut()).await {
+                        Ok(0) => break, // EOF
+                        Ok(_) => {
+                            first_byte.get_or_insert_with(|| start_time.elapsed());
+                            // Nothing more may follow a response that reached its maximum length
+                            if request.expect_length.and_then(|l| l.max).is_some_and(|max| response.len() >= max) {
+                                break;
+                            }
+                        },
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Ok::<(), BenchmarkError>(())
+            }).await {
+                Ok(Ok(_)) => {},
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {}, // Timeout is normal when no pattern is expected
+            }
+        }
+        
+        tracing::debug!(bytes = response.len(), "response received");
+        Ok(Response {
+            data: response,
+            first_byte,
+            elapsed: start_time.elapsed(),
+        })
+    }
+}
+
+/// Matches `pattern` against `response`, on the blocking thread pool when
+/// the response is at least `offload_above` bytes, so a slow match of a
+/// large response doesn't stall the other requests on the worker thread
+async fn match_pattern(pattern: &Pattern, response: Vec<u8>, offload_above: Option<usize>) -> (bool, Vec<u8>) {
+    if offload_above.is_none_or(|min| response.len() < min) {
+        return (pattern.is_match(&response), response);
+    }
+    
+    let pattern = pattern.clone();
+    match tokio::task::spawn_blocking(move || (pattern.is_match(&response), response)).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Appends the next read to `response`, through `buffer` if given or else
+/// straight into the response's spare capacity (growing it when full)
+async fn read_chunk(
+    conn: &mut dyn Connection,
+    response: &mut Vec<u8>,
+    buffer: Option<&mut [u8]>,
+) -> std::io::Result<usize> {
+    match buffer {
+        Some(buffer) => {
+            let n = conn.read(buffer).await?;
+            response.extend_from_slice(&buffer[..n]);
+            Ok(n)
+        },
+        None => conn.read_buf(response).await,
+    }
+}
+
+/// Performs `repeat` exchanges of the same request on one connection, as
+/// `--repeat` does. The responses are concatenated and timed as a whole,
+/// with each exchange's own latency in `messages` when there was more than
+/// one. `bytes_sent` counts what the protocol actually wrote.
+pub(crate) async fn exchange_repeated(
+    protocol: &dyn Protocol,
+    conn: &mut dyn Connection,
+    request: &Request<'_>,
+    repeat: usize,
+) -> Result<Exchange, BenchmarkError> {
+    let mut conn = CountingConnection { conn, written: 0 };
+    if repeat <= 1 {
+        let response = protocol.exchange(&mut conn, request).await?;
+        check_length(request, &response)?;
+        return Ok(Exchange { response, messages: Vec::new(), bytes_sent: conn.written });
+    }
+    
+    let start_time = Instant::now();
+    let mut data = Vec::new();
+    let mut first_byte = None;
+    let mut messages = Vec::with_capacity(repeat);
+    
+    for message in 0..repeat {
+        let offset = start_time.elapsed();
+        let response = protocol.exchange(&mut conn, request).await?;
+        check_length(request, &response)?;
+        tracing::trace!(message, elapsed = ?response.elapsed, "message exchanged");
+        if first_byte.is_none() {
+            first_byte = response.first_byte.map(|t| offset + t);
+        }
+        data.extend_from_slice(&response.data);
+        messages.push(response.elapsed);
+    }
+    
+    Ok(Exchange {
+        response: Response { data, first_byte, elapsed: start_time.elapsed() },
+        messages,
+        bytes_sent: conn.written,
+    })
+}
+
+/// Counts the bytes written through a connection, so `bytes_sent` holds
+/// what went out whatever framing the protocol adds
+struct CountingConnection<'a> {
+    conn: &'a mut dyn Connection,
+    written: usize,
+}
+
+impl AsyncRead for CountingConnection<'_> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.get_mut().conn).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for CountingConnection<'_> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = Pin::new(&mut *this.conn).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = written {
+            this.written += n;
+        }
+        written
+    }
+    
+    fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = Pin::new(&mut *this.conn).poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(n)) = written {
+            this.written += n;
+        }
+        written
+    }
+    
+    fn is_write_vectored(&self) -> bool {
+        self.conn.is_write_vectored()
+    }
+    
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.get_mut().conn).poll_flush(cx)
+    }
+    
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.get_mut().conn).poll_shutdown(cx)
+    }
+}
+
+/// The payload `exchange_repeated` writes for `repeat` exchanges, as
+/// `--trace-file` records it
+pub(crate) fn sent_bytes(request: &Request<'_>, repeat: usize) -> Vec<u8> {
+    request.data.unwrap_or_default().repeat(repeat.max(1))
+}
+
+/// Fails a response outside the request's `expect_length` bounds, or an
+/// empty one with `require_non_empty`, whatever the protocol
+fn check_length(request: &Request<'_>, response: &Response) -> Result<(), BenchmarkError> {
+    if request.require_non_empty && response.data.is_empty() {
+        return Err(BenchmarkError::ResponseValidation("Response is empty".to_string()));
+    }
+    request.expect_length.map_or(Ok(()), |length| length.check(response.data.len()))
+}
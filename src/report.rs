@@ -1,9 +1,16 @@
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 use serde::{Serialize, Deserialize};
 use colored::*;
-use humantime::format_duration;
+use humantime::{format_duration, format_rfc3339_seconds};
+use crate::config::Arrival;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BenchmarkReport {
     pub target: String,
     pub protocol: String,
@@ -22,16 +29,769 @@ pub struct BenchmarkReport {
     pub p99_response_time: Duration,
     pub bytes_sent: u64,
     pub bytes_received: u64,
+    #[serde(default)]
+    pub latency_correction: Option<LatencyCorrection>,
+    #[serde(default)]
+    pub connection_rate: Option<ConnectionRate>,
+    /// How connections were used, e.g. "new connection per request" or
+    /// "persistent keep-alive"
+    #[serde(default)]
+    pub connection_strategy: String,
+    /// Connections established during the run
+    #[serde(default)]
+    pub connections_opened: u64,
+    /// Connections opened per second of a `--connect-only` run
+    #[serde(default)]
+    pub connects_per_second: Option<f64>,
+    /// Keep-alive requests resent on a new connection because the server
+    /// had closed the idle one; absent without keep-alive
+    #[serde(default)]
+    pub stale_reconnects: Option<u64>,
+    /// Whether TLS certificates were verified; `false` for `--insecure`
+    /// runs, absent when no target used TLS
+    #[serde(default)]
+    pub tls_verification: Option<bool>,
+    /// Full and resumed TLS handshakes (`--tls-resumption`); absent when no
+    /// target used TLS
+    #[serde(default)]
+    pub tls_handshakes: Option<TlsHandshakes>,
+    /// When the server certificate expires, in RFC 3339 (`--check-cert`);
+    /// absent without the flag or when no certificate was received
+    #[serde(default)]
+    pub cert_not_after: Option<String>,
+    /// Whole days until `cert_not_after`, negative once it has passed
+    #[serde(default)]
+    pub cert_days_remaining: Option<i64>,
+    /// Fewest days the certificate had to remain valid for the run to pass
+    /// (`--cert-min-days`)
+    #[serde(default)]
+    pub cert_min_days: Option<u64>,
+    #[serde(default)]
+    pub cache_stats: Option<CacheStats>,
+    /// Average time of each `Server-Timing` phase (`--server-timing`)
+    #[serde(default)]
+    pub server_timing: Option<Vec<ServerTimingPhase>>,
+    /// Per-request timeout derived by a `--timeout-percentile` calibration run
+    #[serde(default)]
+    pub timeout_cutoff: Option<TimeoutCutoff>,
+    /// Per-request timeout an `--adaptive-timeout` run ended with
+    #[serde(default)]
+    pub adaptive_timeout: Option<Duration>,
+    /// `--warmup-until-stable` phase run before the measurement
+    #[serde(default)]
+    pub warmup: Option<Warmup>,
+    /// Time to the first response byte of TCP runs, or to the first body
+    /// chunk with `--stream-response`, as opposed to the full response
+    /// times above
+    #[serde(default)]
+    pub ttfb: Option<LatencyPercentiles>,
+    /// Latency of the individual exchanges of a `--repeat` run, whose
+    /// response times above cover all exchanges on a connection
+    #[serde(default)]
+    pub per_message: Option<PerMessage>,
+    /// Response chunks of a `--stream-response` run
+    #[serde(default)]
+    pub streaming: Option<StreamingStats>,
+    /// Statistics for each `--hosts-file` host, in file order (`--per-host`)
+    #[serde(default)]
+    pub per_host: Option<Vec<HostStats>>,
+    /// Statistics for each HTTP method of a `--url-file` mix (`--per-method`)
+    #[serde(default)]
+    pub per_method: Option<Vec<MethodStats>>,
+    /// Requests each worker (one connection at a time) completed, by worker
+    /// index (`--per-worker-stats`)
+    #[serde(default)]
+    pub per_worker_requests: Option<Vec<u64>>,
+    /// p99 latency of each worker's successful requests, by worker index
+    #[serde(default)]
+    pub per_worker_p99: Option<Vec<Duration>>,
+    /// How unevenly the requests spread over the workers
+    #[serde(default)]
+    pub worker_imbalance: Option<WorkerImbalance>,
+    /// The slowest successful requests with their `--request-id-header` ids,
+    /// slowest first
+    #[serde(default)]
+    pub slowest_requests: Option<Vec<SlowRequest>>,
+    /// Failed requests by error kind; absent when nothing failed
+    #[serde(default)]
+    pub errors: Option<ErrorSummary>,
+    /// Each distinct error and HTTP error status with a sample message
+    /// (`--only-errors`)
+    #[serde(default)]
+    pub error_details: Option<Vec<ErrorDetail>>,
+    /// Responses per HTTP status code, ascending; absent for other protocols
+    #[serde(default)]
+    pub status_codes: Option<BTreeMap<u16, u64>>,
+    /// Which limit ended the run
+    #[serde(default)]
+    pub stop_reason: StopReason,
+    /// The rolling p99 that stopped the run under `--abort-if-p99-above`
+    #[serde(default)]
+    pub latency_spike: Option<LatencySpike>,
+    /// Requests actually in flight, against the configured `concurrency`
+    #[serde(default)]
+    pub achieved_concurrency: Option<AchievedConcurrency>,
+    /// Target against achieved rate of a `--rate-ramp` run
+    #[serde(default)]
+    pub rate_ramp: Option<RateRampStats>,
+    /// Every completed request; written to `samples.jsonl` by `--output-dir`
+    #[serde(skip)]
+    pub samples: Vec<SampleRecord>,
+}
+
+/// One completed request, as written to `samples.jsonl`. Times are in microseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleRecord {
+    /// Completion time relative to the start of the run
+    pub offset_us: u64,
+    pub latency_us: u64,
+    /// Latency from the intended send time of a `--rate` paced run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intended_latency_us: Option<u64>,
+    /// Time to the first response byte (TCP)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttfb_us: Option<u64>,
+    /// Value of the `--request-id-header` the request was sent with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// One line of the `--raw-output` stream, tagged with its `type`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RawRecord {
+    /// A completed request
+    Request(SampleRecord),
+    /// Aggregates over the run so far (`--raw-progress`)
+    Progress {
+        elapsed_ms: u64,
+        rps: f64,
+        p99_us: u64,
+    },
+}
+
+/// Failed requests grouped by error kind (see `BenchmarkError::kind`), with
+/// the last few error messages.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorSummary {
+    pub counts: BTreeMap<String, u64>,
+    /// Failures before the connection was established (refused, connect
+    /// timeout, TLS handshake)
+    #[serde(default)]
+    pub connect_failures: u64,
+    /// Failures reading or writing an established connection (reset,
+    /// request timeout, IO and HTTP errors)
+    #[serde(default)]
+    pub io_failures: u64,
+    /// Most recent error messages, newest last
+    pub recent: Vec<String>,
+}
+
+/// One distinct failure of an `--only-errors` run: an error kind, or an HTTP
+/// error status, which otherwise counts as a completed request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    /// Error kind (see `BenchmarkError::kind`), or `http_<status>`
+    pub kind: String,
+    pub count: u64,
+    /// Message of the first occurrence
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Start of the first error response's body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// Responses classified by cache indicators (`--cache-stats`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Responses without any recognizable cache indicator
+    pub unknown: u64,
+}
+
+impl CacheStats {
+    /// Share of hits among the responses that could be classified
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let classified = self.hits + self.misses;
+        (classified > 0).then(|| self.hits as f64 / classified as f64)
+    }
+}
+
+/// One `Server-Timing` phase, averaged over the responses that reported it
+/// (`--server-timing`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTimingPhase {
+    pub name: String,
+    pub responses: usize,
+    pub avg: Duration,
+}
+
+/// Timeout enforced by `--timeout-percentile`: the given latency percentile
+/// of the calibration run. Slower responses count as timeouts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TimeoutCutoff {
+    pub percentile: f64,
+    pub timeout: Duration,
+}
+
+/// Why a run ended. `--requests` and `--duration` both apply, so this tells
+/// whether `total_requests` is the requested count or what fit in the time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Every requested request completed
+    #[default]
+    RequestsCompleted,
+    /// `--duration` ran out first
+    DurationElapsed,
+    /// Ctrl-C
+    Interrupted,
+    /// The `--max-duration` cap cut the run short
+    MaxDuration,
+    /// The rolling p99 crossed `--abort-if-p99-above`
+    LatencySpike,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StopReason::RequestsCompleted => "all requests completed",
+            StopReason::DurationElapsed => "duration elapsed",
+            StopReason::Interrupted => "interrupted",
+            StopReason::MaxDuration => "cut short by --max-duration",
+            StopReason::LatencySpike => "p99 above --abort-if-p99-above",
+        })
+    }
+}
+
+/// A rolling p99 over `--abort-if-p99-above` that stopped a run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencySpike {
+    pub threshold: Duration,
+    pub p99: Duration,
+    /// When the spike was seen, relative to the start of the run
+    pub offset: Duration,
+}
+
+/// Requests in flight over a run. Failed connections, `--rate` pacing and
+/// slow request preparation keep it below the configured concurrency, which
+/// explains a lower requests/sec than expected.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AchievedConcurrency {
+    /// Time-weighted average over the run
+    pub avg: f64,
+    pub max: usize,
+}
+
+/// A `--rate-ramp` run interval by interval, and the target rate at which
+/// it degraded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateRampStats {
+    pub start_rate: f64,
+    pub end_rate: f64,
+    pub points: Vec<RampPoint>,
+    /// Absent when the run kept up with the ramp to the end
+    pub degraded: Option<RampDegradation>,
+}
+
+/// One interval of a `--rate-ramp` run: a second, or a tenth of a run
+/// shorter than ten seconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RampPoint {
+    /// Start of the interval, relative to the start of the run
+    pub offset: Duration,
+    pub target_rate: f64,
+    /// Requests per second that completed successfully within the interval
+    pub achieved_rate: f64,
+    /// Absent when no request completed within the interval
+    pub p99: Option<Duration>,
+}
+
+/// Where a `--rate-ramp` run first fell behind its target or its p99 rose
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RampDegradation {
+    pub offset: Duration,
+    pub target_rate: f64,
+    pub reason: String,
+}
+
+/// How long `--warmup-until-stable` ran, and whether throughput stabilized
+/// or the warmup cap was reached first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Warmup {
+    pub duration: Duration,
+    pub stable: bool,
+}
+
+/// TLS handshakes of a run. With resumption on, reconnecting workers resume
+/// their earlier sessions instead of running full handshakes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TlsHandshakes {
+    /// Whether sessions were offered for resumption
+    pub resumption: bool,
+    pub full: u64,
+    pub resumed: u64,
+}
+
+/// Connection establishment under `--connect-rate`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionRate {
+    pub limit: f64,
+    pub opened: u64,
+    /// Connections opened per second between the first and the last one
+    pub achieved: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub avg: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Individual exchanges of a `--repeat` run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerMessage {
+    /// Exchanges made on each connection
+    pub repeat: usize,
+    /// Exchanges completed by successful requests
+    pub messages: usize,
+    pub latency: LatencyPercentiles,
+}
+
+/// Response bodies read chunk by chunk (`--stream-response`). The time to
+/// the first chunk is the report's `ttfb`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamingStats {
+    /// Chunks each response was cut off at (`--max-chunks`), if any
+    pub max_chunks: Option<usize>,
+    /// Chunks received by successful requests
+    pub chunks: usize,
+    pub chunks_per_response: f64,
+    /// Time between consecutive chunks of a response; absent when no
+    /// response had two
+    pub interval: Option<LatencyPercentiles>,
+}
+
+/// Requests sent to one `--hosts-file` host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostStats {
+    pub host: String,
+    pub requests: usize,
+    pub successful: usize,
+    pub latency: LatencyPercentiles,
+}
+
+/// Requests sent with one HTTP method of a `--url-file` mix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodStats {
+    pub method: String,
+    pub requests: usize,
+    pub successful: usize,
+    pub latency: LatencyPercentiles,
+}
+
+/// How unevenly requests spread over the workers of a run
+/// (`--per-worker-stats`). Uneven load, e.g. from keep-alive connections
+/// landing on backends of different speed, can hide behind the totals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerImbalance {
+    pub mean_requests: f64,
+    /// Largest distance of a worker's request count from the mean, as a
+    /// fraction of the mean
+    pub max_deviation: f64,
+    /// Index of the worker furthest from the mean
+    pub worst_worker: usize,
+    /// Whether `max_deviation` exceeds `WORKER_IMBALANCE_THRESHOLD`
+    pub imbalanced: bool,
+}
+
+/// Deviation from the mean request count past which a run is flagged as
+/// imbalanced: a worker with 50% more or fewer requests than average
+pub const WORKER_IMBALANCE_THRESHOLD: f64 = 0.5;
+
+/// Error kind (see `BenchmarkError::kind`) of connections that failed for
+/// lack of a local port
+const PORTS_EXHAUSTED: &str = "ports_exhausted";
+
+/// Most intervals the text report lists for a `--rate-ramp` run
+const RAMP_ROWS: usize = 20;
+
+impl WorkerImbalance {
+    /// Computes the imbalance of the per-worker request counts; `None`
+    /// without workers
+    pub fn from_requests(requests: &[u64]) -> Option<Self> {
+        if requests.is_empty() {
+            return None;
+        }
+        let mean_requests = requests.iter().sum::<u64>() as f64 / requests.len() as f64;
+        let (worst_worker, max_deviation) = requests.iter()
+            .map(|&n| if mean_requests > 0.0 { (n as f64 - mean_requests).abs() / mean_requests } else { 0.0 })
+            .enumerate()
+            .reduce(|worst, worker| if worker.1 > worst.1 { worker } else { worst })?;
+        Some(WorkerImbalance {
+            mean_requests,
+            max_deviation,
+            worst_worker,
+            imbalanced: max_deviation > WORKER_IMBALANCE_THRESHOLD,
+        })
+    }
+}
+
+/// A slow request, identified by its `--request-id-header` value so it can be
+/// found in the server's logs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowRequest {
+    pub id: String,
+    pub latency: Duration,
+    /// Completion time relative to the start of the run
+    pub offset: Duration,
+}
+
+/// Latency measured against the actual send time versus the intended send
+/// time of a `--rate` paced run. The gap between the two is the stall time a
+/// closed-loop measurement hides (coordinated omission).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyCorrection {
+    pub target_rate: f64,
+    /// Distribution the paced send times followed
+    #[serde(default)]
+    pub arrival: Arrival,
+    /// Whether the headline response times above are the corrected values.
+    pub applied: bool,
+    pub uncorrected: LatencyPercentiles,
+    pub corrected: LatencyPercentiles,
+}
+
+/// Mean and sample standard deviation of one metric across repeated runs.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MetricSummary {
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl MetricSummary {
+    pub fn from_values(values: &[f64]) -> Self {
+        if values.is_empty() {
+            return MetricSummary::default();
+        }
+        
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let stddev = if values.len() > 1 {
+            let variance = values.iter()
+                .map(|v| (v - mean).powi(2))
+                .sum::<f64>() / (values.len() - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        
+        MetricSummary { mean, stddev }
+    }
+}
+
+/// Summary of the same benchmark executed several times (`--runs`).
+/// Latency summaries are in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateReport {
+    pub runs: usize,
+    pub requests_per_second: MetricSummary,
+    pub p50_ms: MetricSummary,
+    pub p90_ms: MetricSummary,
+    pub p95_ms: MetricSummary,
+    pub p99_ms: MetricSummary,
+    pub reports: Vec<BenchmarkReport>,
+}
+
+impl AggregateReport {
+    pub fn from_reports(reports: Vec<BenchmarkReport>) -> Self {
+        let summarize = |metric: fn(&BenchmarkReport) -> f64| {
+            let values: Vec<f64> = reports.iter().map(metric).collect();
+            MetricSummary::from_values(&values)
+        };
+        
+        AggregateReport {
+            runs: reports.len(),
+            requests_per_second: summarize(|r| r.requests_per_second),
+            p50_ms: summarize(|r| r.p50_response_time.as_secs_f64() * 1000.0),
+            p90_ms: summarize(|r| r.p90_response_time.as_secs_f64() * 1000.0),
+            p95_ms: summarize(|r| r.p95_response_time.as_secs_f64() * 1000.0),
+            p99_ms: summarize(|r| r.p99_response_time.as_secs_f64() * 1000.0),
+            reports,
+        }
+    }
+}
+
+/// One probe of an autotune sweep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutotuneStep {
+    pub concurrency: usize,
+    /// Successful requests per second
+    pub requests_per_second: f64,
+    pub p99_response_time: Duration,
+}
+
+/// Result of an `--autotune` sweep: every probe in order plus the
+/// concurrency at which throughput stopped improving (the knee).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutotuneReport {
+    pub steps: Vec<AutotuneStep>,
+    pub recommended_concurrency: usize,
+}
+
+/// One concurrency level of a `--sweep-concurrency` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepStep {
+    pub concurrency: usize,
+    pub requests_per_second: f64,
+    pub p50_response_time: Duration,
+    pub p99_response_time: Duration,
+    pub failed_requests: usize,
+    /// Throughput per connection relative to the first level: 1.0 means
+    /// rps grew in proportion to concurrency, lower values mean it fell behind
+    pub scaling_efficiency: f64,
+}
+
+/// Result of `--sweep-concurrency`: a step per level in the order they ran,
+/// and the full report of each level.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepReport {
+    pub steps: Vec<SweepStep>,
+    pub reports: Vec<BenchmarkReport>,
 }
 
-pub fn print_report(report: &BenchmarkReport, format: Option<&str>) {
+impl SweepReport {
+    pub fn from_reports(reports: Vec<BenchmarkReport>) -> Self {
+        let per_connection = |r: &BenchmarkReport| r.requests_per_second / r.concurrency.max(1) as f64;
+        let base = reports.first().map(per_connection).unwrap_or_default();
+        
+        let steps = reports.iter()
+            .map(|r| SweepStep {
+                concurrency: r.concurrency,
+                requests_per_second: r.requests_per_second,
+                p50_response_time: r.p50_response_time,
+                p99_response_time: r.p99_response_time,
+                failed_requests: r.failed_requests,
+                scaling_efficiency: if base > 0.0 { per_connection(r) / base } else { 0.0 },
+            })
+            .collect();
+        
+        SweepReport { steps, reports }
+    }
+}
+
+/// How durations are rendered in the text report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeUnit {
+    /// `humantime` style, e.g. `1s 200ms`
+    #[default]
+    Human,
+    /// Plain milliseconds with three decimals
+    Millis,
+    /// Plain microseconds with one decimal
+    Micros,
+}
+
+impl FromStr for TimeUnit {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(TimeUnit::Human),
+            "ms" => Ok(TimeUnit::Millis),
+            "us" => Ok(TimeUnit::Micros),
+            other => Err(format!("Unknown time unit '{}' (expected ms, us or human)", other)),
+        }
+    }
+}
+
+/// Presentation settings for the text report.
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    pub time_unit: TimeUnit,
+    /// Print a greppable `SUMMARY key=value ...` line after the report
+    pub summary_line: bool,
+    /// Group the digits of counts with `,` (`--no-thousands-sep` turns it off)
+    pub thousands_sep: bool,
+    /// Print byte totals in binary units, e.g. `1.2 MiB`
+    pub human_bytes: bool,
+    /// Print only the distinct errors instead of the report (`--only-errors`)
+    pub only_errors: bool,
+}
+
+impl ReportOptions {
+    /// Formats a count for the text report
+    fn count(&self, n: u64) -> String {
+        if self.thousands_sep { group_thousands(n) } else { n.to_string() }
+    }
+    
+    /// Formats a byte total for the text report
+    fn bytes(&self, n: u64) -> String {
+        if self.human_bytes { humanize_bytes(n) } else { format!("{} bytes", self.count(n)) }
+    }
+}
+
+/// Inserts a `,` between every group of three digits: `1234567` becomes
+/// `1,234,567`
+pub fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// Renders a byte count in the largest binary unit that keeps the value at
+/// or above one, with one decimal: `512 bytes`, `1.5 KiB`, `1.2 MiB`
+pub fn humanize_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Move up before the value would round to `1024.0`
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Disables ANSI colors when `NO_COLOR` is set or stdout is not a terminal,
+/// so redirected reports don't contain escape codes.
+pub fn configure_color() {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
+
+/// Formats a duration in the requested unit. Plain units carry no suffix;
+/// the unit is added to the label by `time_label`.
+pub fn format_time(duration: Duration, unit: TimeUnit) -> String {
+    match unit {
+        TimeUnit::Human => format_duration(duration).to_string(),
+        TimeUnit::Millis => format!("{:.3}", duration.as_secs_f64() * 1_000.0),
+        TimeUnit::Micros => format!("{:.1}", duration.as_secs_f64() * 1_000_000.0),
+    }
+}
+
+fn unit_suffix(unit: TimeUnit) -> Option<&'static str> {
+    match unit {
+        TimeUnit::Human => None,
+        TimeUnit::Millis => Some("ms"),
+        TimeUnit::Micros => Some("us"),
+    }
+}
+
+/// Appends the unit to a report label when durations are printed as plain numbers.
+pub fn time_label(label: &str, unit: TimeUnit) -> String {
+    match unit_suffix(unit) {
+        Some(suffix) => format!("{} ({}):", label, suffix),
+        None => format!("{}:", label),
+    }
+}
+
+/// Requests and time summed over the runs a process reported
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunTotals {
+    pub total: usize,
+    pub failed: usize,
+    pub time: Duration,
+}
+
+impl RunTotals {
+    pub fn add(&mut self, report: &BenchmarkReport) {
+        self.total += report.total_requests;
+        self.failed += report.failed_requests;
+        self.time += report.total_time;
+    }
+}
+
+/// Outcome written to stderr as one JSON line on exit (`--status-json`),
+/// apart from the report on stdout. The counts cover every run reported and
+/// are null when none was, e.g. after a configuration error; `error` is only
+/// present when the process fails.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitStatus {
+    pub ok: bool,
+    pub total: Option<usize>,
+    pub failed: Option<usize>,
+    pub rps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ExitStatus {
+    pub fn new(totals: Option<RunTotals>, error: Option<String>) -> Self {
+        ExitStatus {
+            ok: error.is_none(),
+            total: totals.map(|t| t.total),
+            failed: totals.map(|t| t.failed),
+            rps: totals.map(|t| if t.time.is_zero() { 0.0 } else { t.total as f64 / t.time.as_secs_f64() }),
+            error,
+        }
+    }
+    
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("exit status serializes")
+    }
+}
+
+/// Prints `report` as text, Markdown, JSON or a single NDJSON line. Only
+/// the JSON outputs embed `config`, the resolved configuration of the run.
+pub fn print_report<C: Serialize>(report: &BenchmarkReport, config: &C, format: Option<&str>, options: &ReportOptions) {
     match format {
-        Some("json") => print_json_report(report),
-        _ => print_text_report(report),
+        Some("json") => print_json_report(report, config),
+        Some("ndjson") => print_json_line(&JsonReport::new(config, report)),
+        _ if options.only_errors => print_error_details(report, options),
+        Some("markdown") => print!("{}", markdown_report(report)),
+        _ => print_text_report(report, options),
+    }
+}
+
+/// `--only-errors`: each distinct error with its count and first message, in
+/// place of the report
+fn print_error_details(report: &BenchmarkReport, options: &ReportOptions) {
+    let details = report.error_details.as_deref().unwrap_or_default();
+    
+    println!();
+    println!("{}", "=".repeat(80).bright_blue());
+    println!("{}", "ERRORS".bright_blue());
+    println!("{}", "=".repeat(80).bright_blue());
+    println!("{} {}", "Target:".bold(), report.target);
+    println!("{} {} of {}", "Failed Requests:".bold(), options.count(report.failed_requests as u64).red(), options.count(report.total_requests as u64));
+    println!();
+    
+    if details.is_empty() {
+        println!("{}", "No errors".green());
+        return;
     }
+    for detail in details {
+        println!("{} {}", format!("{}:", detail.kind).bold(), options.count(detail.count).red());
+        println!("  {} {}", "Message:".bold(), detail.message);
+        if let Some(body) = &detail.body {
+            println!("  {} {}", "Body:".bold(), body.trim_end().replace(['\r', '\n'], " "));
+        }
+    }
+    println!();
 }
 
-fn print_text_report(report: &BenchmarkReport) {
+fn print_text_report(report: &BenchmarkReport, options: &ReportOptions) {
+    let unit = options.time_unit;
+    
     println!();
     println!("{}", "=".repeat(80).bright_blue());
     println!("{}", "BENCHMARK REPORT".bright_blue());
@@ -40,37 +800,1131 @@ fn print_text_report(report: &BenchmarkReport) {
     println!("{} {}", "Target:".bold(), report.target);
     println!("{} {}", "Protocol:".bold(), report.protocol);
     println!("{} {}", "Concurrency:".bold(), report.concurrency);
+    if let Some(achieved) = &report.achieved_concurrency {
+        println!("{} avg {:.2}, max {}", "Achieved Concurrency:".bold(), achieved.avg, achieved.max);
+    }
+    if report.tls_verification == Some(false) {
+        println!("{} {}", "TLS Verification:".bold(), "DISABLED (--insecure)".red().bold());
+    }
+    if let Some(warmup) = &report.warmup {
+        let outcome = if warmup.stable { "until stable" } else { "cap reached, not stable" };
+        println!("{} {} ({})", time_label("Warmup", unit).bold(), format_time(warmup.duration, unit), outcome);
+    }
+    if let Some(cutoff) = &report.timeout_cutoff {
+        println!("{} {} (p{} of calibration run)", time_label("Timeout", unit).bold(), format_time(cutoff.timeout, unit), cutoff.percentile);
+    }
+    if let Some(timeout) = report.adaptive_timeout {
+        println!("{} {} (adaptive, at the end of the run)", time_label("Timeout", unit).bold(), format_time(timeout, unit));
+    }
     println!();
     
     println!("{}", "Request Statistics:".bold().underline());
-    println!("{} {}", "Total Requests:".bold(), report.total_requests);
-    println!("{} {}", "Successful Requests:".bold(), report.successful_requests.to_string().green());
-    println!("{} {}", "Failed Requests:".bold(), report.failed_requests.to_string().red());
+    println!("{} {}", "Total Requests:".bold(), options.count(report.total_requests as u64));
+    println!("{} {}", "Successful Requests:".bold(), options.count(report.successful_requests as u64).green());
+    println!("{} {}", "Failed Requests:".bold(), options.count(report.failed_requests as u64).red());
     println!("{} {}", "Requests/sec:".bold(), format!("{:.2}", report.requests_per_second).bright_green());
+    let stop_reason = match report.stop_reason {
+        StopReason::RequestsCompleted | StopReason::DurationElapsed => report.stop_reason.to_string().normal(),
+        StopReason::Interrupted | StopReason::MaxDuration | StopReason::LatencySpike => report.stop_reason.to_string().yellow(),
+    };
+    println!("{} {}", "Stopped:".bold(), stop_reason);
+    if let Some(spike) = &report.latency_spike {
+        println!(
+            "{} p99 {} above {} after {}",
+            "Latency Spike:".bold(),
+            format_time(spike.p99, unit),
+            format_time(spike.threshold, unit),
+            format_time(spike.offset, unit),
+        );
+    }
     println!();
     
     println!("{}", "Timing Statistics:".bold().underline());
-    println!("{} {}", "Total Time:".bold(), format_duration(report.total_time));
-    println!("{} {}", "Average Response Time:".bold(), format_duration(report.avg_response_time));
-    println!("{} {}", "Minimum Response Time:".bold(), format_duration(report.min_response_time));
-    println!("{} {}", "Maximum Response Time:".bold(), format_duration(report.max_response_time));
-    println!("{} {}", "p50 Response Time:".bold(), format_duration(report.p50_response_time));
-    println!("{} {}", "p90 Response Time:".bold(), format_duration(report.p90_response_time));
-    println!("{} {}", "p95 Response Time:".bold(), format_duration(report.p95_response_time));
-    println!("{} {}", "p99 Response Time:".bold(), format_duration(report.p99_response_time));
+    let timings = [
+        ("Total Time", report.total_time),
+        ("Average Response Time", report.avg_response_time),
+        ("Minimum Response Time", report.min_response_time),
+        ("Maximum Response Time", report.max_response_time),
+        ("p50 Response Time", report.p50_response_time),
+        ("p90 Response Time", report.p90_response_time),
+        ("p95 Response Time", report.p95_response_time),
+        ("p99 Response Time", report.p99_response_time),
+    ];
+    for (label, duration) in timings {
+        println!("{} {}", time_label(label, unit).bold(), format_time(duration, unit));
+    }
     println!();
     
+    if let Some(ttfb) = &report.ttfb {
+        println!("{}", "Time to First Byte:".bold().underline());
+        let timings = [
+            ("Average TTFB", ttfb.avg),
+            ("p50 TTFB", ttfb.p50),
+            ("p90 TTFB", ttfb.p90),
+            ("p95 TTFB", ttfb.p95),
+            ("p99 TTFB", ttfb.p99),
+            ("Maximum TTFB", ttfb.max),
+        ];
+        for (label, duration) in timings {
+            println!("{} {}", time_label(label, unit).bold(), format_time(duration, unit));
+        }
+        println!();
+    }
+    
+    if let Some(streaming) = &report.streaming {
+        println!("{}", "Streaming Response:".bold().underline());
+        println!("{} {}", "Chunks:".bold(), options.count(streaming.chunks as u64));
+        println!("{} {:.2}", "Chunks per Response:".bold(), streaming.chunks_per_response);
+        if let Some(max) = streaming.max_chunks {
+            println!("{} {}", "Max Chunks:".bold(), max);
+        }
+        if let Some(interval) = &streaming.interval {
+            let timings = [
+                ("Average Chunk Interval", interval.avg),
+                ("p50 Chunk Interval", interval.p50),
+                ("p90 Chunk Interval", interval.p90),
+                ("p95 Chunk Interval", interval.p95),
+                ("p99 Chunk Interval", interval.p99),
+                ("Maximum Chunk Interval", interval.max),
+            ];
+            for (label, duration) in timings {
+                println!("{} {}", time_label(label, unit).bold(), format_time(duration, unit));
+            }
+        }
+        println!();
+    }
+    
+    if let Some(per_message) = &report.per_message {
+        println!(
+            "{}",
+            format!("Per-Message Latency ({} messages per connection):", per_message.repeat).bold().underline()
+        );
+        println!("{} {}", "Messages:".bold(), options.count(per_message.messages as u64));
+        let latency = &per_message.latency;
+        let timings = [
+            ("Average Message Time", latency.avg),
+            ("p50 Message Time", latency.p50),
+            ("p90 Message Time", latency.p90),
+            ("p95 Message Time", latency.p95),
+            ("p99 Message Time", latency.p99),
+            ("Maximum Message Time", latency.max),
+        ];
+        for (label, duration) in timings {
+            println!("{} {}", time_label(label, unit).bold(), format_time(duration, unit));
+        }
+        println!();
+    }
+    
+    if let Some(hosts) = &report.per_host {
+        println!("{}", "Per-Host Statistics:".bold().underline());
+        let width = hosts.iter().map(|h| h.host.len()).max().unwrap_or(0).max(4);
+        println!("{:<width$} {:>10} {:>8} {:>20} {:>20}", "Host", "Requests", "Failed", "p50", "p99");
+        for host in hosts {
+            let failed = format!("{:>8}", options.count((host.requests - host.successful) as u64));
+            println!(
+                "{:<width$} {:>10} {} {:>20} {:>20}",
+                host.host,
+                options.count(host.requests as u64),
+                if host.requests > host.successful { failed.red() } else { failed.normal() },
+                format_time(host.latency.p50, unit),
+                format_time(host.latency.p99, unit),
+            );
+        }
+        println!();
+    }
+    
+    if let Some(methods) = &report.per_method {
+        println!("{}", "Per-Method Statistics:".bold().underline());
+        println!("{:<8} {:>10} {:>8} {:>20} {:>20}", "Method", "Requests", "Failed", "p50", "p99");
+        for method in methods {
+            let failed = format!("{:>8}", options.count((method.requests - method.successful) as u64));
+            println!(
+                "{:<8} {:>10} {} {:>20} {:>20}",
+                method.method,
+                options.count(method.requests as u64),
+                if method.requests > method.successful { failed.red() } else { failed.normal() },
+                format_time(method.latency.p50, unit),
+                format_time(method.latency.p99, unit),
+            );
+        }
+        println!();
+    }
+    
+    if let (Some(requests), Some(p99)) = (&report.per_worker_requests, &report.per_worker_p99) {
+        println!("{}", "Per-Worker Statistics:".bold().underline());
+        println!("{:<8} {:>10} {:>20}", "Worker", "Requests", "p99");
+        for (worker, (requests, p99)) in requests.iter().zip(p99).enumerate() {
+            println!("{:<8} {:>10} {:>20}", worker, options.count(*requests), format_time(*p99, unit));
+        }
+        if let Some(imbalance) = &report.worker_imbalance {
+            println!(
+                "{} {:.1}% (worker {}, mean {:.1} requests)",
+                "Max Deviation:".bold(),
+                imbalance.max_deviation * 100.0,
+                imbalance.worst_worker,
+                imbalance.mean_requests,
+            );
+            if imbalance.imbalanced {
+                println!("{}", imbalance_warning(imbalance, requests).yellow());
+            }
+        }
+        println!();
+    }
+    
+    if let Some(slowest) = report.slowest_requests.as_ref().filter(|s| !s.is_empty()) {
+        println!("{}", "Slowest Requests:".bold().underline());
+        let width = slowest.iter().map(|r| r.id.len()).max().unwrap_or(0).max(10);
+        println!("{:<width$} {:>20} {:>20}", "Request ID", "Latency", "Completed At");
+        for request in slowest {
+            println!(
+                "{:<width$} {:>20} {:>20}",
+                request.id,
+                format_time(request.latency, unit),
+                format_time(request.offset, unit),
+            );
+        }
+        println!();
+    }
+    
     println!("{}", "Transfer Statistics:".bold().underline());
-    println!("{} {} bytes", "Total Data Sent:".bold(), report.bytes_sent);
-    println!("{} {} bytes", "Total Data Received:".bold(), report.bytes_received);
+    println!("{} {}", "Total Data Sent:".bold(), options.bytes(report.bytes_sent));
+    println!("{} {}", "Total Data Received:".bold(), options.bytes(report.bytes_received));
+    println!();
+    
+    if let Some(status_codes) = report.status_codes.as_ref().filter(|codes| !codes.is_empty()) {
+        println!("{}", "Status Codes:".bold().underline());
+        for (&code, count) in status_codes {
+            let label = format!("{}:", code);
+            let label = match code {
+                200..=299 => label.green(),
+                300..=399 => label.yellow(),
+                _ => label.red(),
+            };
+            println!("{} {}", label.bold(), options.count(*count));
+        }
+        println!();
+    }
+    
+    if let Some(errors) = &report.errors {
+        println!("{}", "Errors:".bold().underline());
+        for (kind, count) in &errors.counts {
+            println!("{} {}", format!("{}:", kind).bold(), options.count(*count).red());
+        }
+        println!("{} {}", "Connect Failures:".bold(), options.count(errors.connect_failures));
+        println!("{} {}", "I/O Failures:".bold(), options.count(errors.io_failures));
+        if let Some(&count) = errors.counts.get(PORTS_EXHAUSTED) {
+            println!("{}", ports_exhausted_warning(count).yellow());
+        }
+        println!();
+    }
+    
+    if let Some(cache) = &report.cache_stats {
+        println!("{}", "Cache Statistics:".bold().underline());
+        println!("{} {}", "Cache Hits:".bold(), options.count(cache.hits).green());
+        println!("{} {}", "Cache Misses:".bold(), options.count(cache.misses).yellow());
+        println!("{} {}", "Unclassified:".bold(), options.count(cache.unknown));
+        if let Some(ratio) = cache.hit_ratio() {
+            println!("{} {:.1}%", "Hit Ratio:".bold(), ratio * 100.0);
+        }
+        println!();
+    }
+    
+    if let Some(phases) = &report.server_timing {
+        println!("{}", "Server Timing:".bold().underline());
+        if phases.is_empty() {
+            println!("No response carried a Server-Timing header with durations");
+        } else {
+            println!("{:<20} {:>10} {:>20}", "Phase", "Responses", "Avg");
+            for phase in phases {
+                println!("{:<20} {:>10} {:>20}", phase.name, options.count(phase.responses as u64), format_time(phase.avg, unit));
+            }
+        }
+        println!();
+    }
+    
+    println!("{}", "Connection Statistics:".bold().underline());
+    println!("{} {}", "Strategy:".bold(), report.connection_strategy);
+    println!("{} {}", "Connections Opened:".bold(), options.count(report.connections_opened));
+    if let Some(reconnects) = report.stale_reconnects {
+        println!("{} {}", "Idle Reconnects:".bold(), options.count(reconnects));
+    }
+    if let Some(tls) = &report.tls_handshakes {
+        let resumption = if tls.resumption { "" } else { " (resumption off)" };
+        println!("{} {} full, {} resumed{}", "TLS Handshakes:".bold(), options.count(tls.full), options.count(tls.resumed), resumption);
+    }
+    if let (Some(not_after), Some(days)) = (&report.cert_not_after, report.cert_days_remaining) {
+        let line = format!("{} ({})", not_after, cert_days_label(days, report.cert_min_days));
+        let line = if days < 0 || cert_expiring(report) { line.red().to_string() } else { line };
+        println!("{} {}", "Certificate Expires:".bold(), line);
+    }
+    if let Some(connections) = &report.connection_rate {
+        println!("{} {:.2} (limit {:.2})", "Connections/sec:".bold(), connections.achieved, connections.limit);
+    } else if let Some(rate) = report.connects_per_second {
+        println!("{} {}", "Connections/sec:".bold(), format!("{:.2}", rate).bright_green());
+    }
     println!();
     
+    if let Some(correction) = &report.latency_correction {
+        println!("{}", "Coordinated Omission:".bold().underline());
+        println!("{} {:.2} req/s ({} arrivals)", "Target Rate:".bold(), correction.target_rate, correction.arrival);
+        println!("{} {}", "Correction Applied:".bold(), if correction.applied { "yes" } else { "no" });
+        println!("{:<10} {:>16} {:>16}", unit_suffix(unit).map(|s| format!("({})", s)).unwrap_or_default(), "Uncorrected", "Corrected");
+        let rows = [
+            ("avg", correction.uncorrected.avg, correction.corrected.avg),
+            ("p50", correction.uncorrected.p50, correction.corrected.p50),
+            ("p90", correction.uncorrected.p90, correction.corrected.p90),
+            ("p95", correction.uncorrected.p95, correction.corrected.p95),
+            ("p99", correction.uncorrected.p99, correction.corrected.p99),
+            ("max", correction.uncorrected.max, correction.corrected.max),
+        ];
+        for (label, uncorrected, corrected) in rows {
+            println!(
+                "{:<10} {:>16} {:>16}",
+                label.bold(),
+                format_time(uncorrected, unit),
+                format_time(corrected, unit),
+            );
+        }
+        println!();
+    }
+    
+    if let Some(ramp) = &report.rate_ramp {
+        println!("{}", "Rate Ramp:".bold().underline());
+        println!("{} {:.2} -> {:.2} req/s", "Target Rate:".bold(), ramp.start_rate, ramp.end_rate);
+        match &ramp.degraded {
+            Some(degraded) => println!(
+                "{} {}",
+                "Degraded At:".bold(),
+                format!("{:.2} req/s after {} ({})", degraded.target_rate, format_duration(degraded.offset), degraded.reason).red(),
+            ),
+            None => println!("{} {}", "Degraded At:".bold(), "kept up to the end".bright_green()),
+        }
+        println!("{:<10} {:>12} {:>12} {:>20}", "Offset (s)", "Target", "Achieved", "p99");
+        // Long runs show an evenly spaced selection; JSON has every interval
+        let step = ramp.points.len().div_ceil(RAMP_ROWS).max(1);
+        for point in ramp.points.iter().step_by(step) {
+            println!(
+                "{:<10.2} {:>12.2} {:>12.2} {:>20}",
+                point.offset.as_secs_f64(),
+                point.target_rate,
+                point.achieved_rate,
+                point.p99.map(|p99| format_time(p99, unit)).unwrap_or_else(|| "-".to_string()),
+            );
+        }
+        println!();
+    }
+    
     println!("{}", "=".repeat(80).bright_blue());
+    
+    if options.summary_line {
+        println!("{}", summary_line(report));
+    }
 }
 
-fn print_json_report(report: &BenchmarkReport) {
-    match serde_json::to_string_pretty(report) {
+/// One-line summary of stable `key=value` pairs for scripts to grep, e.g.
+/// `SUMMARY target=http://localhost/ protocol=HTTP requests=100 errors=0 rps=1234.56 p50=1.200ms p90=3.100ms p99=12.300ms`.
+/// A target with spaces or quotes is double-quoted, with `\` and `"` escaped.
+pub fn summary_line(report: &BenchmarkReport) -> String {
+    let ms = |d: Duration| format!("{:.3}ms", d.as_secs_f64() * 1_000.0);
+    format!(
+        "SUMMARY target={} protocol={} requests={} errors={} rps={:.2} p50={} p90={} p99={}",
+        summary_value(&report.target),
+        report.protocol.replace(' ', "_"),
+        report.total_requests,
+        report.failed_requests,
+        report.requests_per_second,
+        ms(report.p50_response_time),
+        ms(report.p90_response_time),
+        ms(report.p99_response_time),
+    )
+}
+
+/// `value` as one whitespace-free token of the summary line
+fn summary_value(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Whether the server certificate expires within `--cert-min-days`
+pub fn cert_expiring(report: &BenchmarkReport) -> bool {
+    report.cert_days_remaining.zip(report.cert_min_days).is_some_and(|(days, min)| days < min as i64)
+}
+
+/// "N days left", or "expired N days ago", noting the `--cert-min-days`
+/// threshold when there is one
+fn cert_days_label(days: i64, min_days: Option<u64>) -> String {
+    let label = if days < 0 {
+        format!("expired {} days ago", -days)
+    } else {
+        format!("{} days left", days)
+    };
+    match min_days {
+        Some(min) => format!("{}, minimum {}", label, min),
+        None => label,
+    }
+}
+
+/// GitHub-flavored Markdown rendering of a report for PR comments: a heading
+/// with the target and a `| Metric | Value |` table, times in milliseconds.
+pub fn markdown_report(report: &BenchmarkReport) -> String {
+    let ms = |d: Duration| format!("{:.3} ms", d.as_secs_f64() * 1_000.0);
+    
+    let mut rows = vec![
+        ("Total requests".to_string(), report.total_requests.to_string()),
+        ("Successful requests".to_string(), report.successful_requests.to_string()),
+        ("Failed requests".to_string(), report.failed_requests.to_string()),
+        ("Requests/sec".to_string(), format!("{:.2}", report.requests_per_second)),
+        ("Stopped".to_string(), report.stop_reason.to_string()),
+        ("Total time".to_string(), ms(report.total_time)),
+        ("Average response time".to_string(), ms(report.avg_response_time)),
+        ("Minimum response time".to_string(), ms(report.min_response_time)),
+        ("Maximum response time".to_string(), ms(report.max_response_time)),
+        ("p50 response time".to_string(), ms(report.p50_response_time)),
+        ("p90 response time".to_string(), ms(report.p90_response_time)),
+        ("p95 response time".to_string(), ms(report.p95_response_time)),
+        ("p99 response time".to_string(), ms(report.p99_response_time)),
+        ("Data sent".to_string(), format!("{} bytes", report.bytes_sent)),
+        ("Data received".to_string(), format!("{} bytes", report.bytes_received)),
+        ("Connections opened".to_string(), report.connections_opened.to_string()),
+    ];
+    if let Some(rate) = report.connects_per_second {
+        rows.push(("Connections/sec".to_string(), format!("{:.2}", rate)));
+    }
+    if let Some(achieved) = &report.achieved_concurrency {
+        rows.push((
+            "Achieved concurrency".to_string(),
+            format!("avg {:.2}, max {} of {}", achieved.avg, achieved.max, report.concurrency),
+        ));
+    }
+    if let Some(spike) = &report.latency_spike {
+        rows.push((
+            "Latency spike".to_string(),
+            format!("p99 {} above {} after {}", ms(spike.p99), ms(spike.threshold), ms(spike.offset)),
+        ));
+    }
+    if let Some(ramp) = &report.rate_ramp {
+        rows.push(("Rate ramp".to_string(), format!("{:.2} -> {:.2} req/s", ramp.start_rate, ramp.end_rate)));
+        rows.push((
+            "Degraded at".to_string(),
+            match &ramp.degraded {
+                Some(degraded) => format!("{:.2} req/s after {} ({})", degraded.target_rate, format_duration(degraded.offset), degraded.reason),
+                None => "kept up to the end".to_string(),
+            },
+        ));
+    }
+    if let Some(reconnects) = report.stale_reconnects {
+        rows.push(("Idle reconnects".to_string(), reconnects.to_string()));
+    }
+    if let Some(tls) = &report.tls_handshakes {
+        let resumption = if tls.resumption { "" } else { " (resumption off)" };
+        rows.push(("TLS handshakes".to_string(), format!("{} full, {} resumed{}", tls.full, tls.resumed, resumption)));
+    }
+    if let (Some(not_after), Some(days)) = (&report.cert_not_after, report.cert_days_remaining) {
+        rows.push(("Certificate expires".to_string(), format!("{} ({})", not_after, cert_days_label(days, report.cert_min_days))));
+    }
+    if let Some(timeout) = report.adaptive_timeout {
+        rows.push(("Final adaptive timeout".to_string(), ms(timeout)));
+    }
+    if let Some(streaming) = &report.streaming {
+        rows.push(("Chunks per response".to_string(), format!("{:.2}", streaming.chunks_per_response)));
+        if let Some(ttfb) = &report.ttfb {
+            rows.push(("p50 time to first chunk".to_string(), ms(ttfb.p50)));
+            rows.push(("p99 time to first chunk".to_string(), ms(ttfb.p99)));
+        }
+        if let Some(interval) = &streaming.interval {
+            rows.push(("p50 chunk interval".to_string(), ms(interval.p50)));
+            rows.push(("p99 chunk interval".to_string(), ms(interval.p99)));
+        }
+    }
+    if let Some(per_message) = &report.per_message {
+        rows.push(("Messages per connection".to_string(), per_message.repeat.to_string()));
+        rows.push(("p50 message time".to_string(), ms(per_message.latency.p50)));
+        rows.push(("p99 message time".to_string(), ms(per_message.latency.p99)));
+    }
+    if let Some(hosts) = &report.per_host {
+        for host in hosts {
+            rows.push((
+                format!("Host `{}`", host.host),
+                format!("{} requests, {} failed, p99 {}", host.requests, host.requests - host.successful, ms(host.latency.p99)),
+            ));
+        }
+    }
+    if let Some(phases) = &report.server_timing {
+        for phase in phases {
+            rows.push((format!("Server timing `{}`", phase.name), format!("avg {} over {} responses", ms(phase.avg), phase.responses)));
+        }
+    }
+    if let Some(methods) = &report.per_method {
+        for method in methods {
+            rows.push((
+                format!("Method `{}`", method.method),
+                format!("{} requests, {} failed, p99 {}", method.requests, method.requests - method.successful, ms(method.latency.p99)),
+            ));
+        }
+    }
+    if let Some(requests) = &report.per_worker_requests {
+        let (min, max) = (requests.iter().min().unwrap_or(&0), requests.iter().max().unwrap_or(&0));
+        rows.push(("Requests per worker (min / max)".to_string(), format!("{} / {}", min, max)));
+    }
+    if let Some(imbalance) = &report.worker_imbalance {
+        let deviation = format!("{:.1}% (worker {})", imbalance.max_deviation * 100.0, imbalance.worst_worker);
+        let warning = if imbalance.imbalanced { " **imbalanced**" } else { "" };
+        rows.push(("Worker max deviation".to_string(), format!("{}{}", deviation, warning)));
+    }
+    if let Some(slowest) = &report.slowest_requests {
+        for request in slowest {
+            rows.push((format!("Slow request `{}`", request.id), ms(request.latency)));
+        }
+    }
+    if let Some(status_codes) = &report.status_codes {
+        for (code, count) in status_codes {
+            rows.push((format!("HTTP {}", code), count.to_string()));
+        }
+    }
+    if let Some(errors) = &report.errors {
+        for (kind, count) in &errors.counts {
+            rows.push((format!("Errors: `{}`", kind), count.to_string()));
+        }
+        rows.push(("Connect failures".to_string(), errors.connect_failures.to_string()));
+        rows.push(("I/O failures".to_string(), errors.io_failures.to_string()));
+        if let Some(&count) = errors.counts.get(PORTS_EXHAUSTED) {
+            rows.push(("Warning".to_string(), ports_exhausted_warning(count)));
+        }
+    }
+    
+    let mut out = String::new();
+    out.push_str(&format!("### Benchmark: `{}`\n\n", report.target.replace('`', "'")));
+    out.push_str(&format!("**Protocol:** {} | **Concurrency:** {}", report.protocol, report.concurrency));
+    if !report.connection_strategy.is_empty() {
+        out.push_str(&format!(" | **Connections:** {}", report.connection_strategy));
+    }
+    out.push_str("\n\n");
+    out.push_str("| Metric | Value |\n");
+    out.push_str("| --- | ---: |\n");
+    for (metric, value) in rows {
+        out.push_str(&format!("| {} | {} |\n", metric, value.replace('|', "\\|")));
+    }
+    out
+}
+
+/// Change of one metric between a baseline report and the current run.
+/// Latencies are in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// Whether an increase is an improvement (throughput) or a regression
+    /// (latency)
+    pub higher_is_better: bool,
+    /// Whether `--baseline` fails the run when this metric regresses
+    pub gated: bool,
+}
+
+impl MetricDelta {
+    /// Relative change from the baseline, e.g. `0.05` for 5% higher
+    pub fn change(&self) -> f64 {
+        if self.baseline == 0.0 {
+            return 0.0;
+        }
+        (self.current - self.baseline) / self.baseline
+    }
+    
+    /// Relative change in the bad direction; positive means worse
+    pub fn regression(&self) -> f64 {
+        if self.higher_is_better { -self.change() } else { self.change() }
+    }
+}
+
+/// Compares the headline metrics of `current` against `baseline`. Requests/sec
+/// and p99 are gated; the other percentiles are informational.
+pub fn compare_reports(baseline: &BenchmarkReport, current: &BenchmarkReport) -> Vec<MetricDelta> {
+    let ms = |d: Duration| d.as_secs_f64() * 1_000.0;
+    let delta = |metric: &str, baseline: f64, current: f64, higher_is_better: bool, gated: bool| MetricDelta {
+        metric: metric.to_string(),
+        baseline,
+        current,
+        higher_is_better,
+        gated,
+    };
+    
+    vec![
+        delta("rps", baseline.requests_per_second, current.requests_per_second, true, true),
+        delta("p50", ms(baseline.p50_response_time), ms(current.p50_response_time), false, false),
+        delta("p90", ms(baseline.p90_response_time), ms(current.p90_response_time), false, false),
+        delta("p95", ms(baseline.p95_response_time), ms(current.p95_response_time), false, false),
+        delta("p99", ms(baseline.p99_response_time), ms(current.p99_response_time), false, true),
+    ]
+}
+
+/// Gated metrics that regressed by more than `tolerance` (a fraction)
+pub fn regressions(deltas: &[MetricDelta], tolerance: f64) -> Vec<&MetricDelta> {
+    deltas.iter()
+        .filter(|d| d.gated && d.regression() > tolerance)
+        .collect()
+}
+
+/// Prints the `--baseline` comparison table, marking regressions beyond
+/// `tolerance`.
+pub fn print_comparison(deltas: &[MetricDelta], tolerance: f64) {
+    let title = format!("Baseline Comparison (tolerance {:.1}%):", tolerance * 100.0);
+    print_delta_table(&title, ("Baseline", "Current"), deltas, Some(tolerance));
+}
+
+/// Table of `deltas` with improvements in green and, given a `tolerance`,
+/// gated regressions beyond it in red
+fn print_delta_table(title: &str, columns: (&str, &str), deltas: &[MetricDelta], tolerance: Option<f64>) {
+    println!("{}", title.bold().underline());
+    println!("{:<8} {:>14} {:>14} {:>10}", "", columns.0, columns.1, "Change");
+    for delta in deltas {
+        let change = format!("{:+.1}%", delta.change() * 100.0);
+        let change = if tolerance.is_some_and(|tolerance| delta.gated && delta.regression() > tolerance) {
+            format!("{:>10}", change).red().bold().to_string()
+        } else if delta.regression() < 0.0 {
+            format!("{:>10}", change).green().to_string()
+        } else {
+            format!("{:>10}", change)
+        };
+        let unit = if delta.metric == "rps" { "" } else { " ms" };
+        println!(
+            "{:<8} {:>14} {:>14} {}",
+            delta.metric,
+            format!("{:.2}{}", delta.baseline, unit),
+            format!("{:.2}{}", delta.current, unit),
+            change,
+        );
+    }
+    println!();
+}
+
+/// Prints the reports of a `--compare-http2` run, HTTP/1.1 first, followed
+/// by the change from HTTP/1.1 to HTTP/2. JSON output is one document with
+/// both reports and the comparison; `config` is that of the HTTP/1.1 run.
+pub fn print_http2_comparison<C: Serialize>(
+    http1: &BenchmarkReport,
+    http2: &BenchmarkReport,
+    config: &C,
+    format: Option<&str>,
+    options: &ReportOptions,
+) {
+    let deltas = compare_reports(http1, http2);
+    match format {
+        Some("json") => {
+            let comparison = serde_json::json!({
+                "http1": http1,
+                "http2": http2,
+                "comparison": deltas,
+            });
+            match serde_json::to_string_pretty(&JsonReport::new(config, comparison)) {
+                Ok(json) => println!("{}", json),
+                Err(_) => eprintln!("Error serializing report to JSON"),
+            }
+        },
+        Some("ndjson") => {
+            print_json_line(&JsonReport::new(config, http1));
+            print_json_line(&JsonReport::new(config, http2));
+            print_json_line(&JsonReport::new(config, serde_json::json!({ "comparison": deltas })));
+        },
+        Some("markdown") => {
+            print!("{}", markdown_report(http1));
+            println!();
+            print!("{}", markdown_report(http2));
+            println!();
+            println!("### HTTP/1.1 vs HTTP/2\n");
+            println!("| Metric | HTTP/1.1 | HTTP/2 | Change |");
+            println!("| --- | ---: | ---: | ---: |");
+            for delta in &deltas {
+                println!("| {} | {:.2} | {:.2} | {:+.1}% |", delta.metric, delta.baseline, delta.current, delta.change() * 100.0);
+            }
+        },
+        _ => {
+            print_text_report(http1, options);
+            print_text_report(http2, options);
+            print_delta_table("HTTP/1.1 vs HTTP/2:", ("HTTP/1.1", "HTTP/2"), &deltas, None);
+        },
+    }
+}
+
+/// Reads a `BenchmarkReport` written by `--output json` or `--output-dir`
+pub fn load_report(path: &Path) -> io::Result<BenchmarkReport> {
+    let contents = fs::read_to_string(path)?;
+    let value: serde_json::Value = serde_json::from_str(&contents)?;
+    // Either `--output json` output or a bare `report.json` from `--output-dir`
+    let report = match value {
+        serde_json::Value::Object(mut fields) if fields.contains_key("config") && fields.contains_key("report") => {
+            fields.remove("report").unwrap_or_default()
+        },
+        value => value,
+    };
+    serde_json::from_value(report).map_err(io::Error::from)
+}
+
+/// `--output json` document: a report together with the resolved config that
+/// produced it and when it was printed (RFC 3339, UTC), so archived results
+/// describe themselves
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonReport<C, R = BenchmarkReport> {
+    pub config: C,
+    pub report: R,
+    pub timestamp: String,
+}
+
+impl<C, R> JsonReport<C, R> {
+    pub fn new(config: C, report: R) -> Self {
+        JsonReport {
+            config,
+            report,
+            timestamp: format_rfc3339_seconds(SystemTime::now()).to_string(),
+        }
+    }
+}
+
+fn print_json_report<C: Serialize>(report: &BenchmarkReport, config: &C) {
+    match serde_json::to_string_pretty(&JsonReport::new(config, report)) {
         Ok(json) => println!("{}", json),
         Err(_) => eprintln!("Error serializing report to JSON"),
     }
-}
\ No newline at end of file
+}
+
+/// `--output ndjson`: prints `value` as one line of compact JSON, so runs
+/// that produce several reports can be streamed and ingested line by line
+fn print_json_line<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => println!("{}", json),
+        Err(_) => eprintln!("Error serializing report to JSON"),
+    }
+}
+
+pub fn print_aggregate_report<C: Serialize>(aggregate: &AggregateReport, config: &C, format: Option<&str>, options: &ReportOptions) {
+    match format {
+        Some("json") => match serde_json::to_string_pretty(&JsonReport::new(config, aggregate)) {
+            Ok(json) => println!("{}", json),
+            Err(_) => eprintln!("Error serializing report to JSON"),
+        },
+        Some("ndjson") => {
+            for report in &aggregate.reports {
+                print_json_line(&JsonReport::new(config, report));
+            }
+        },
+        Some("markdown") => {
+            for report in &aggregate.reports {
+                println!("{}", markdown_report(report));
+            }
+            print!("{}", markdown_aggregate_report(aggregate));
+        },
+        _ => {
+            for (i, report) in aggregate.reports.iter().enumerate() {
+                println!();
+                println!("{}", format!("Run {}/{}", i + 1, aggregate.runs).bold());
+                print_text_report(report, options);
+            }
+            print_aggregate_text_report(aggregate);
+        }
+    }
+}
+
+fn print_aggregate_text_report(aggregate: &AggregateReport) {
+    println!();
+    println!("{}", "=".repeat(80).bright_blue());
+    println!("{}", format!("AGGREGATE OF {} RUNS", aggregate.runs).bright_blue());
+    println!("{}", "=".repeat(80).bright_blue());
+    
+    println!("{:<16} {:>14} {:>14}", "", "Mean", "Stddev");
+    let rows = [
+        ("Requests/sec", aggregate.requests_per_second),
+        ("p50 (ms)", aggregate.p50_ms),
+        ("p90 (ms)", aggregate.p90_ms),
+        ("p95 (ms)", aggregate.p95_ms),
+        ("p99 (ms)", aggregate.p99_ms),
+    ];
+    for (label, summary) in rows {
+        println!("{:<16} {:>14.2} {:>14.2}", label.bold(), summary.mean, summary.stddev);
+    }
+    println!();
+    
+    println!("{}", "=".repeat(80).bright_blue());
+}
+
+/// Markdown table of the mean and standard deviation across `--runs`.
+fn markdown_aggregate_report(aggregate: &AggregateReport) -> String {
+    let mut out = format!("### Aggregate of {} runs\n\n", aggregate.runs);
+    out.push_str("| Metric | Mean | Std Dev |\n");
+    out.push_str("| --- | ---: | ---: |\n");
+    let rows = [
+        ("Requests/sec", aggregate.requests_per_second),
+        ("p50 (ms)", aggregate.p50_ms),
+        ("p90 (ms)", aggregate.p90_ms),
+        ("p95 (ms)", aggregate.p95_ms),
+        ("p99 (ms)", aggregate.p99_ms),
+    ];
+    for (metric, summary) in rows {
+        out.push_str(&format!("| {} | {:.2} | {:.2} |\n", metric, summary.mean, summary.stddev));
+    }
+    out
+}
+
+pub fn print_autotune_report(report: &AutotuneReport, format: Option<&str>, options: &ReportOptions) {
+    match format {
+        Some("json") => match serde_json::to_string_pretty(report) {
+            Ok(json) => println!("{}", json),
+            Err(_) => eprintln!("Error serializing report to JSON"),
+        },
+        Some("ndjson") => print_json_line(report),
+        _ => print_autotune_text_report(report, options),
+    }
+}
+
+fn print_autotune_text_report(report: &AutotuneReport, options: &ReportOptions) {
+    println!();
+    println!("{}", "=".repeat(80).bright_blue());
+    println!("{}", "AUTOTUNE SWEEP".bright_blue());
+    println!("{}", "=".repeat(80).bright_blue());
+    
+    println!("{:<14} {:>16} {:>20}", "Concurrency", "Requests/sec", "p99");
+    for step in &report.steps {
+        let line = format!(
+            "{:<14} {:>16.2} {:>20}",
+            step.concurrency,
+            step.requests_per_second,
+            format_time(step.p99_response_time, options.time_unit),
+        );
+        if step.concurrency == report.recommended_concurrency {
+            println!("{}", line.green().bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+    println!();
+    
+    println!("{} {}", "Recommended Concurrency:".bold(), report.recommended_concurrency.to_string().green());
+    println!("{}", "=".repeat(80).bright_blue());
+}
+
+pub fn print_sweep_report<C: Serialize>(sweep: &SweepReport, config: &C, format: Option<&str>, options: &ReportOptions) {
+    match format {
+        Some("json") => match serde_json::to_string_pretty(&JsonReport::new(config, sweep)) {
+            Ok(json) => println!("{}", json),
+            Err(_) => eprintln!("Error serializing report to JSON"),
+        },
+        Some("ndjson") => {
+            for report in &sweep.reports {
+                print_json_line(&JsonReport::new(config, report));
+            }
+        },
+        Some("markdown") => {
+            println!("### Concurrency Sweep\n");
+            println!("| Concurrency | Requests/sec | p50 (ms) | p99 (ms) | Failed | Efficiency |");
+            println!("| ---: | ---: | ---: | ---: | ---: | ---: |");
+            for step in &sweep.steps {
+                println!(
+                    "| {} | {:.2} | {:.2} | {:.2} | {} | {:.0}% |",
+                    step.concurrency,
+                    step.requests_per_second,
+                    step.p50_response_time.as_secs_f64() * 1000.0,
+                    step.p99_response_time.as_secs_f64() * 1000.0,
+                    step.failed_requests,
+                    step.scaling_efficiency * 100.0,
+                );
+            }
+        },
+        _ => print_sweep_text_report(sweep, options),
+    }
+}
+
+fn print_sweep_text_report(sweep: &SweepReport, options: &ReportOptions) {
+    println!();
+    println!("{}", "=".repeat(80).bright_blue());
+    println!("{}", "CONCURRENCY SWEEP".bright_blue());
+    println!("{}", "=".repeat(80).bright_blue());
+    
+    println!("{:<14} {:>14} {:>16} {:>16} {:>8} {:>10}", "Concurrency", "Requests/sec", "p50", "p99", "Failed", "Efficiency");
+    for step in &sweep.steps {
+        let efficiency = format!("{:.0}%", step.scaling_efficiency * 100.0);
+        println!(
+            "{:<14} {:>14.2} {:>16} {:>16} {:>8} {:>10}",
+            step.concurrency,
+            step.requests_per_second,
+            format_time(step.p50_response_time, options.time_unit),
+            format_time(step.p99_response_time, options.time_unit),
+            step.failed_requests,
+            efficiency,
+        );
+    }
+    println!();
+    if let Some(first) = sweep.steps.first() {
+        println!("Efficiency: requests/sec per connection relative to concurrency {}", first.concurrency);
+    }
+    println!("{}", "=".repeat(80).bright_blue());
+}
+
+/// Warning for a run whose load spread unevenly over its workers
+fn imbalance_warning(imbalance: &WorkerImbalance, requests: &[u64]) -> String {
+    let worst = requests.get(imbalance.worst_worker).copied().unwrap_or_default();
+    let direction = if worst as f64 > imbalance.mean_requests { "more" } else { "fewer" };
+    format!(
+        "Warning: worker {} handled {:.0}% {} requests than the average; load is unevenly spread over the connections",
+        imbalance.worst_worker,
+        imbalance.max_deviation * 100.0,
+        direction,
+    )
+}
+
+/// Warning for a run whose client ran out of local ports, so its failures
+/// aren't blamed on the server
+fn ports_exhausted_warning(count: u64) -> String {
+    format!(
+        "Warning: {} connections failed because this machine ran out of local ports; use --keep-alive, lower the concurrency or widen the ephemeral port range (net.ipv4.ip_local_port_range on Linux)",
+        count,
+    )
+}
+
+/// Writes `report.json`, `samples.jsonl` and `config.json` into `dir`,
+/// creating it if needed.
+pub fn write_output_dir<C: Serialize>(dir: &Path, report: &BenchmarkReport, config: &C) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    write_json(&dir.join("report.json"), report)?;
+    write_json(&dir.join("config.json"), config)?;
+    
+    let mut samples = BufWriter::new(File::create(dir.join("samples.jsonl"))?);
+    for sample in &report.samples {
+        serde_json::to_writer(&mut samples, sample)?;
+        writeln!(samples)?;
+    }
+    samples.flush()
+}
+
+/// Like `write_output_dir` for `--runs`: the report is the aggregate and each
+/// sample line carries the 1-based run it belongs to.
+pub fn write_aggregate_output_dir<C: Serialize>(dir: &Path, aggregate: &AggregateReport, config: &C) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    write_json(&dir.join("report.json"), aggregate)?;
+    write_json(&dir.join("config.json"), config)?;
+    
+    let mut samples = BufWriter::new(File::create(dir.join("samples.jsonl"))?);
+    for (i, report) in aggregate.reports.iter().enumerate() {
+        for sample in &report.samples {
+            let mut line = serde_json::to_value(sample)?;
+            line["run"] = serde_json::json!(i + 1);
+            serde_json::to_writer(&mut samples, &line)?;
+            writeln!(samples)?;
+        }
+    }
+    samples.flush()
+}
+
+/// Writes `--cdf-output`: a `percentile,latency_ms` CSV of the cumulative
+/// distribution of `latencies`, which must be sorted in ascending order.
+pub fn write_cdf(path: &Path, latencies: &[Duration]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "percentile,latency_ms")?;
+    for (percent, latency) in crate::stats::cdf(latencies) {
+        writeln!(file, "{:.2},{:.3}", percent, latency.as_secs_f64() * 1_000.0)?;
+    }
+    file.flush()
+}
+
+/// Sorted latencies of `samples`, from the intended send time when
+/// `correct_co` is set, like the report's headline percentiles
+pub fn sample_latencies<'a>(samples: impl IntoIterator<Item = &'a SampleRecord>, correct_co: bool) -> Vec<Duration> {
+    let mut latencies: Vec<Duration> = samples.into_iter()
+        .map(|s| {
+            let micros = match s.intended_latency_us {
+                Some(intended) if correct_co => intended,
+                _ => s.latency_us,
+            };
+            Duration::from_micros(micros)
+        })
+        .collect();
+    crate::stats::sort_latencies(&mut latencies);
+    latencies
+}
+
+/// Adds a report to the history file at `path`, one compact `--output json`
+/// document per line, oldest first. The line is appended; only once the
+/// file holds more than `max_entries` reports is it rewritten without the
+/// oldest ones.
+pub fn append_history<C: Serialize>(path: &Path, report: &BenchmarkReport, config: &C, max_entries: usize) -> io::Result<()> {
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    let entry = serde_json::to_string(&JsonReport::new(config, report))?;
+    
+    let mut file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+    // Don't run on from a last line an interrupted write left unterminated
+    if file.metadata()?.len() > 0 {
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            writeln!(file)?;
+        }
+    }
+    writeln!(file, "{}", entry)?;
+    drop(file);
+    
+    if count_lines(path)? <= max_entries {
+        return Ok(());
+    }
+    let existing = fs::read_to_string(path)?;
+    let entries: Vec<&str> = existing.lines().filter(|line| !line.trim().is_empty()).collect();
+    let kept = &entries[entries.len().saturating_sub(max_entries)..];
+    // Written aside and renamed over, so a failed rewrite loses no history
+    let rewritten = path.with_extension("jsonl.tmp");
+    let mut file = BufWriter::new(File::create(&rewritten)?);
+    for line in kept {
+        writeln!(file, "{}", line)?;
+    }
+    file.flush()?;
+    drop(file);
+    fs::rename(&rewritten, path)
+}
+
+/// Lines in the file at `path`, counted a buffer at a time rather than by
+/// reading it into memory
+fn count_lines(path: &Path) -> io::Result<usize> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut lines = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(lines);
+        }
+        lines += buf.iter().filter(|&&b| b == b'\n').count();
+        let len = buf.len();
+        reader.consume(len);
+    }
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut file, value)?;
+    writeln!(file)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn summary_line_quotes_targets_with_spaces() {
+        let report = BenchmarkReport {
+            target: "/tmp/my socket".to_string(),
+            protocol: "UDS".to_string(),
+            ..Default::default()
+        };
+        let line = summary_line(&report);
+        
+        assert!(line.starts_with("SUMMARY target=\"/tmp/my socket\" protocol=UDS requests=0 "), "{line}");
+    }
+    
+    #[test]
+    fn exhausted_ports_add_a_client_side_warning() {
+        let mut report = BenchmarkReport {
+            errors: Some(ErrorSummary {
+                counts: BTreeMap::from([("connection_refused".to_string(), 2)]),
+                connect_failures: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(!markdown_report(&report).contains("ran out of local ports"));
+        
+        report.errors.as_mut().unwrap().counts.insert(PORTS_EXHAUSTED.to_string(), 7);
+        let markdown = markdown_report(&report);
+        
+        assert!(markdown.contains("| Errors: `ports_exhausted` | 7 |"), "{markdown}");
+        assert!(markdown.contains(&format!("| Warning | {} |", ports_exhausted_warning(7))), "{markdown}");
+        assert!(ports_exhausted_warning(7).starts_with("Warning: 7 connections failed"));
+    }
+    
+    #[test]
+    fn summary_values_stay_single_tokens() {
+        assert_eq!(summary_value("http://localhost/"), "http://localhost/");
+        assert_eq!(summary_value(""), "\"\"");
+        assert_eq!(summary_value("a \"b\"\\"), "\"a \\\"b\\\"\\\\\"");
+    }
+    
+    #[test]
+    fn worker_imbalance_flags_a_worker_far_from_the_mean() {
+        let even = WorkerImbalance::from_requests(&[100, 110, 90, 100]).unwrap();
+        assert_eq!(even.mean_requests, 100.0);
+        assert!((even.max_deviation - 0.1).abs() < 1e-9);
+        assert!(!even.imbalanced);
+        
+        let skewed = WorkerImbalance::from_requests(&[100, 100, 10, 190]).unwrap();
+        assert_eq!(skewed.worst_worker, 2);
+        assert!((skewed.max_deviation - 0.9).abs() < 1e-9);
+        assert!(skewed.imbalanced);
+        
+        assert!(!WorkerImbalance::from_requests(&[0, 0]).unwrap().imbalanced);
+        assert!(WorkerImbalance::from_requests(&[]).is_none());
+    }
+    
+    #[test]
+    fn counts_are_grouped_unless_turned_off() {
+        let options = ReportOptions { thousands_sep: true, ..Default::default() };
+        assert_eq!(options.count(1_234_567), "1,234,567");
+        assert_eq!(options.bytes(1_234_567), "1,234,567 bytes");
+        
+        let options = ReportOptions { thousands_sep: false, ..Default::default() };
+        assert_eq!(options.count(1_234_567), "1234567");
+    }
+    
+    #[test]
+    fn group_thousands_splits_at_every_third_digit() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1_000), "1,000");
+        assert_eq!(group_thousands(123_456), "123,456");
+        assert_eq!(group_thousands(999_999), "999,999");
+        assert_eq!(group_thousands(1_000_000), "1,000,000");
+        assert_eq!(group_thousands(u64::MAX), "18,446,744,073,709,551,615");
+    }
+    
+    #[test]
+    fn humanize_bytes_moves_up_a_unit_at_each_boundary() {
+        const KIB: u64 = 1024;
+        assert_eq!(humanize_bytes(0), "0 bytes");
+        assert_eq!(humanize_bytes(KIB - 1), "1023 bytes");
+        assert_eq!(humanize_bytes(KIB), "1.0 KiB");
+        assert_eq!(humanize_bytes(KIB + KIB / 2), "1.5 KiB");
+        // Just under a MiB would round to `1024.0 KiB`
+        assert_eq!(humanize_bytes(KIB * KIB - 1), "1.0 MiB");
+        assert_eq!(humanize_bytes(KIB * KIB), "1.0 MiB");
+        assert_eq!(humanize_bytes(1023 * KIB * KIB), "1023.0 MiB");
+        assert_eq!(humanize_bytes(KIB * KIB * KIB), "1.0 GiB");
+        assert_eq!(humanize_bytes(KIB.pow(4)), "1.0 TiB");
+        assert_eq!(humanize_bytes(KIB.pow(5)), "1024.0 TiB");
+    }
+    
+    #[test]
+    fn history_keeps_only_the_most_recent_reports() {
+        let path = std::env::temp_dir().join(format!("thrustbench-history-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        
+        for total in 1..=8 {
+            let report = BenchmarkReport { total_requests: total, ..Default::default() };
+            append_history(&path, &report, &(), 5).unwrap();
+        }
+        
+        let totals: Vec<u64> = fs::read_to_string(&path).unwrap().lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["report"]["total_requests"].as_u64().unwrap())
+            .collect();
+        assert_eq!(totals, [4, 5, 6, 7, 8]);
+        fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn cert_days_label_counts_down_and_notes_the_minimum() {
+        assert_eq!(cert_days_label(30, None), "30 days left");
+        assert_eq!(cert_days_label(0, None), "0 days left");
+        assert_eq!(cert_days_label(-3, None), "expired 3 days ago");
+        assert_eq!(cert_days_label(5, Some(14)), "5 days left, minimum 14");
+    }
+}
@@ -1,49 +1,1632 @@
-use std::time::{Duration, Instant};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time::sleep;
 use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use hyper::Uri;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use hyper::{StatusCode, Uri};
 use indicatif::{ProgressBar, ProgressStyle};
+use colored::Colorize;
+use tracing::Instrument;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
-use crate::config::{BenchmarkConfig, HttpConfig, TcpConfig, UdsConfig};
-use crate::report::BenchmarkReport;
+use crate::config::{self, Arrival, BenchmarkConfig, CacheBust, Endpoint, GrpcConfig, HttpConfig, RateRamp, StreamResponse, TcpConfig, UdsConfig};
+use crate::report::{AchievedConcurrency, AggregateReport, AutotuneReport, AutotuneStep, BenchmarkReport, CacheStats, ConnectionRate, ErrorDetail, ErrorSummary, HostStats, LatencyCorrection, LatencyPercentiles, LatencySpike, MethodStats, MetricSummary, PerMessage, RampDegradation, RampPoint, RateRampStats, RawRecord, SampleRecord, ServerTimingPhase, SlowRequest, StopReason, StreamingStats, SweepReport, TimeoutCutoff, TlsHandshakes, Warmup, WorkerImbalance};
 use crate::error::BenchmarkError;
-use crate::http;
+use crate::grpc;
+use crate::http::{self, CacheStatus, ServerTiming};
+use crate::limiter::ConnectLimiter;
+use crate::protocol::{self, Protocol};
+use crate::tls::TlsOptions;
+use crate::stats::{self, Statistics};
 use crate::tcp;
+use crate::trace::{TraceOptions, Tracer};
 use crate::uds;
 
 const BUFFER_SIZE: usize = 8192;
+/// How often the live percentiles next to the progress bar are refreshed
+const LIVE_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often an `--adaptive-timeout` moves toward its target
+const ADAPTIVE_TIMEOUT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often paused workers check whether the run was resumed
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Length of each of the two windows `--abort-if-p99-above` rolls over
+const SPIKE_WINDOW: Duration = Duration::from_secs(1);
+/// Connection strategy of every runner except HTTP with `--keep-alive`
+const NEW_CONNECTION_PER_REQUEST: &str = "new connection per request";
+/// Connection strategy of a `--connect-only` run
+const CONNECT_ONLY: &str = "connect only, no requests sent";
+/// Error messages kept on the report, newest last
+const RECENT_ERRORS: usize = 10;
+/// Bytes of an HTTP error response body kept with `--only-errors`
+const ERROR_BODY_SNIPPET: usize = 200;
+
+/// Number of slowest requests listed with their `--request-id-header` ids
+const SLOWEST_REQUESTS: usize = 10;
+/// Stands in for the end of a run without a time limit (`--duration 0`)
+const NO_TIME_LIMIT: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+/// How long before the stop time an open-ended `--stream-response` stream is
+/// cut off, so its sample is sent before the workers are cancelled
+const STREAM_END_MARGIN: Duration = Duration::from_millis(20);
+/// Query parameter carrying the random value of `--cache-bust`
+const CACHE_BUST_PARAM: &str = "_cb";
+
+/// A completed request as seen by the collector.
+struct Sample {
+    /// When the request completed, relative to the start of the run.
+    offset: Duration,
+    /// Latency measured from the moment the request was actually sent.
+    latency: Duration,
+    /// Latency measured from the moment the request should have been sent
+    /// according to `--rate`, if the run is paced.
+    intended_latency: Option<Duration>,
+    /// Time to the first response byte (TCP), from the same start as `latency`.
+    ttfb: Option<Duration>,
+    /// Latency of each exchange of a `--repeat` request; empty otherwise.
+    messages: Vec<Duration>,
+    /// Arrival of each response chunk of a `--stream-response` request, from
+    /// the same start as `latency`; empty otherwise.
+    chunks: Vec<Duration>,
+    /// Index of the target the request went to, e.g. its `--hosts-file` host.
+    target: usize,
+    /// Index of the worker (connection) that sent the request.
+    worker: usize,
+    /// Value of the `--request-id-header` the request carried.
+    request_id: Option<String>,
+}
+
+impl Sample {
+    /// The latency the report headlines: corrected when `correct_co` is set.
+    fn headline(&self, correct_co: bool) -> Duration {
+        match self.intended_latency {
+            Some(intended) if correct_co => intended,
+            _ => self.latency,
+        }
+    }
+    
+    /// The sample as written to `samples.jsonl` and `--raw-output`
+    fn record(&self) -> SampleRecord {
+        SampleRecord {
+            offset_us: self.offset.as_micros() as u64,
+            latency_us: self.latency.as_micros() as u64,
+            intended_latency_us: self.intended_latency.map(|d| d.as_micros() as u64),
+            ttfb_us: self.ttfb.map(|d| d.as_micros() as u64),
+            request_id: self.request_id.clone(),
+        }
+    }
+}
+
+/// Failed requests by error kind and the most recent error messages,
+/// shared by the workers of a run.
+#[derive(Default)]
+struct ErrorTally {
+    /// Failures per `BenchmarkError::KINDS` entry
+    counts: [AtomicU64; BenchmarkError::KINDS.len()],
+    connect_failures: AtomicU64,
+    io_failures: AtomicU64,
+    /// The last `RECENT_ERRORS` messages, newest last
+    recent: std::sync::Mutex<VecDeque<String>>,
+    /// Each distinct error with its first message, with `--only-errors`
+    details: Option<std::sync::Mutex<BTreeMap<String, ErrorDetail>>>,
+}
+
+impl ErrorTally {
+    fn new(details: bool) -> Self {
+        ErrorTally {
+            details: details.then(Default::default),
+            ..Default::default()
+        }
+    }
+    
+    /// Failures of one `BenchmarkError::kind` so far
+    fn count(&self, kind: &str) -> u64 {
+        BenchmarkError::KINDS.iter()
+            .position(|k| *k == kind)
+            .map_or(0, |index| self.counts[index].load(Ordering::Relaxed))
+    }
+    
+    /// Failed requests so far, of every kind
+    fn failures(&self) -> u64 {
+        self.counts.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+    
+    /// Counts `error` against `requests` failed requests (a whole batch when pipelining).
+    fn record(&self, error: &BenchmarkError, requests: usize) {
+        if let Some(ref details) = self.details {
+            let mut details = details.lock().unwrap_or_else(|e| e.into_inner());
+            details.entry(error.kind().to_string())
+                .or_insert_with(|| ErrorDetail {
+                    kind: error.kind().to_string(),
+                    count: 0,
+                    message: error.to_string(),
+                    status: None,
+                    body: None,
+                })
+                .count += requests as u64;
+        }
+        
+        let kind = BenchmarkError::KINDS.iter()
+            .position(|kind| *kind == error.kind())
+            .expect("every error kind is listed in KINDS");
+        self.counts[kind].fetch_add(requests as u64, Ordering::Relaxed);
+        if error.is_connect_failure() {
+            self.connect_failures.fetch_add(requests as u64, Ordering::Relaxed);
+        } else if error.is_io_failure() {
+            self.io_failures.fetch_add(requests as u64, Ordering::Relaxed);
+        }
+        
+        let message = error.to_string();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == RECENT_ERRORS {
+            recent.pop_front();
+        }
+        recent.push_back(message);
+    }
+    
+    fn summary(&self) -> Option<ErrorSummary> {
+        let counts: BTreeMap<String, u64> = BenchmarkError::KINDS.iter()
+            .zip(&self.counts)
+            .filter_map(|(kind, count)| {
+                let count = count.load(Ordering::Relaxed);
+                (count > 0).then(|| (kind.to_string(), count))
+            })
+            .collect();
+        if counts.is_empty() {
+            return None;
+        }
+        
+        Some(ErrorSummary {
+            counts,
+            connect_failures: self.connect_failures.load(Ordering::Relaxed),
+            io_failures: self.io_failures.load(Ordering::Relaxed),
+            recent: self.recent.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect(),
+        })
+    }
+    
+    /// Records an HTTP error status (4xx or 5xx), keeping the start of the
+    /// first such response's body. These responses still count as completed
+    /// requests; only `--only-errors` lists them.
+    fn record_status(&self, status: StatusCode, body: &[u8]) {
+        let Some(ref details) = self.details else { return };
+        if !status.is_client_error() && !status.is_server_error() {
+            return;
+        }
+        let mut details = details.lock().unwrap_or_else(|e| e.into_inner());
+        details.entry(format!("http_{}", status.as_u16()))
+            .or_insert_with(|| ErrorDetail {
+                kind: format!("http_{}", status.as_u16()),
+                count: 0,
+                message: status.to_string(),
+                status: Some(status.as_u16()),
+                body: (!body.is_empty())
+                    .then(|| String::from_utf8_lossy(&body[..body.len().min(ERROR_BODY_SNIPPET)]).into_owned()),
+            })
+            .count += 1;
+    }
+    
+    /// The distinct errors, most frequent first; `None` without `--only-errors`
+    fn details(&self) -> Option<Vec<ErrorDetail>> {
+        let details = self.details.as_ref()?.lock().unwrap_or_else(|e| e.into_inner());
+        let mut details: Vec<_> = details.values().cloned().collect();
+        details.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+        Some(details)
+    }
+}
+
+/// Lowest status code `StatusCode` accepts; the highest is 999
+const MIN_STATUS: u16 = 100;
+
+/// Responses per HTTP status code, counted lock-free by the workers.
+struct StatusCounts {
+    /// One counter per valid status code, from `MIN_STATUS`
+    counts: Vec<AtomicUsize>,
+}
+
+impl StatusCounts {
+    fn new() -> Self {
+        StatusCounts { counts: (MIN_STATUS..1000).map(|_| AtomicUsize::new(0)).collect() }
+    }
+    
+    fn record(&self, status: StatusCode) {
+        self.counts[(status.as_u16() - MIN_STATUS) as usize].fetch_add(1, Ordering::Relaxed);
+    }
+    
+    fn summary(&self) -> BTreeMap<u16, u64> {
+        (MIN_STATUS..).zip(&self.counts)
+            .filter_map(|(code, count)| {
+                let count = count.load(Ordering::Relaxed);
+                (count > 0).then_some((code, count as u64))
+            })
+            .collect()
+    }
+}
+
+/// Holds the first failure of a `--fail-fast` run; workers stop once it is set.
+#[derive(Default)]
+struct FailFast {
+    tripped: AtomicBool,
+    error: std::sync::Mutex<Option<BenchmarkError>>,
+}
+
+impl FailFast {
+    /// Keeps `error` if it is the run's first failure.
+    fn trip(&self, error: BenchmarkError) {
+        if !self.tripped.swap(true, Ordering::Relaxed) {
+            *self.error.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+        }
+    }
+    
+    fn is_tripped(&self) -> bool {
+        self.tripped.load(Ordering::Relaxed)
+    }
+    
+    fn take_error(&self) -> Option<BenchmarkError> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+/// Ends a run from outside, on Ctrl-C or at the `--max-duration` deadline.
+/// `main` sets it for the whole command and passes the same flag to every
+/// runner it starts, so a run stops at the next collector check and still
+/// reports what completed.
+#[derive(Default)]
+pub struct CancelFlag {
+    cancelled: AtomicBool,
+    expired: AtomicBool,
+}
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    
+    /// Cancels the run because the command's deadline passed, which its
+    /// report gives as the stop reason
+    pub fn expire(&self) {
+        self.expired.store(true, Ordering::Relaxed);
+        self.cancel();
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    
+    pub fn is_expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+/// Pauses a run from outside, e.g. the TUI's `p` key. While paused, workers
+/// start no new requests but keep their connections, and the paused time is
+/// left out of the run's duration and requests/sec.
+pub struct PauseFlag {
+    base: Instant,
+    /// Nanoseconds from `base` to the start of the current pause, or
+    /// `NOT_PAUSED`
+    paused_at: AtomicU64,
+    /// Nanoseconds spent in pauses that have ended
+    paused_total: AtomicU64,
+}
+
+impl PauseFlag {
+    const NOT_PAUSED: u64 = u64::MAX;
+    
+    pub fn new() -> Self {
+        PauseFlag {
+            base: Instant::now(),
+            paused_at: AtomicU64::new(Self::NOT_PAUSED),
+            paused_total: AtomicU64::new(0),
+        }
+    }
+    
+    /// Pauses a running run or resumes a paused one, returning whether it is
+    /// now paused
+    pub fn toggle(&self) -> bool {
+        let now = self.base.elapsed().as_nanos() as u64;
+        match self.paused_at.swap(Self::NOT_PAUSED, Ordering::SeqCst) {
+            Self::NOT_PAUSED => {
+                self.paused_at.store(now, Ordering::SeqCst);
+                true
+            },
+            since => {
+                self.paused_total.fetch_add(now.saturating_sub(since), Ordering::SeqCst);
+                false
+            },
+        }
+    }
+    
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.load(Ordering::Relaxed) != Self::NOT_PAUSED
+    }
+    
+    /// Time spent paused so far, including a pause in progress
+    pub fn paused_time(&self) -> Duration {
+        let current = match self.paused_at.load(Ordering::SeqCst) {
+            Self::NOT_PAUSED => 0,
+            since => (self.base.elapsed().as_nanos() as u64).saturating_sub(since),
+        };
+        Duration::from_nanos(self.paused_total.load(Ordering::SeqCst) + current)
+    }
+    
+    /// Returns once the run isn't paused
+    async fn wait(&self) {
+        while self.is_paused() {
+            sleep(PAUSE_POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Default for PauseFlag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time `pause` has held the run so far
+fn paused_time(pause: Option<&PauseFlag>) -> Duration {
+    pause.map_or(Duration::ZERO, PauseFlag::paused_time)
+}
+
+/// Whether a run has reached `stop_time`, pushed back by the time it spent
+/// paused
+fn past_stop_time(stop_time: Instant, pause: Option<&PauseFlag>) -> bool {
+    Instant::now().checked_sub(paused_time(pause)).is_some_and(|now| now >= stop_time)
+}
+
+/// Time left before a run reaches `stop_time`, pushed back by the time it
+/// spent paused
+fn time_left(stop_time: Instant, pause: Option<&PauseFlag>) -> Duration {
+    (stop_time + paused_time(pause)).saturating_duration_since(Instant::now())
+}
+
+/// Round-robin over the `--hosts-file` hosts, counting each host's
+/// completed requests for `--per-host`
+struct HostRotation {
+    next: AtomicUsize,
+    completed: Vec<AtomicUsize>,
+}
+
+impl HostRotation {
+    fn new(hosts: usize) -> Self {
+        HostRotation {
+            next: AtomicUsize::new(0),
+            completed: (0..hosts).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+    
+    /// Index of the host the next request goes to
+    fn next(&self) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % self.completed.len()
+    }
+    
+    /// Host a `--keep-alive` worker sends all its requests to, so its one
+    /// connection is reused; workers are spread over the hosts in turn
+    fn pinned(&self, worker: usize) -> usize {
+        worker % self.completed.len()
+    }
+    
+    fn complete(&self, host: usize) {
+        self.completed[host].fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// Requests, successes and latency of every host, from the run's samples
+    fn stats(&self, hosts: &[String], samples: &[Sample], correct_co: bool) -> Vec<HostStats> {
+        hosts.iter()
+            .enumerate()
+            .map(|(i, host)| {
+                let mut latencies: Vec<Duration> = samples.iter()
+                    .filter(|s| s.target == i)
+                    .map(|s| s.headline(correct_co))
+                    .collect();
+                stats::sort_latencies(&mut latencies);
+                HostStats {
+                    host: host.clone(),
+                    requests: self.completed[i].load(Ordering::Relaxed),
+                    successful: latencies.len(),
+                    latency: stats::latency_percentiles(&latencies),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Weighted choice over the `--url-file` endpoints, counting each
+/// endpoint's completed requests for `--per-method`
+struct EndpointMix {
+    endpoints: Vec<Endpoint>,
+    /// Running total of the weights; the last is the sum of all of them
+    cumulative: Vec<u64>,
+    completed: Vec<AtomicUsize>,
+}
+
+impl EndpointMix {
+    fn new(endpoints: &[Endpoint]) -> Self {
+        EndpointMix {
+            endpoints: endpoints.to_vec(),
+            cumulative: endpoints.iter()
+                .scan(0, |total, endpoint| {
+                    *total += endpoint.weight as u64;
+                    Some(*total)
+                })
+                .collect(),
+            completed: endpoints.iter().map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+    
+    /// Index of the endpoint the next request goes to, in proportion to the
+    /// weights
+    fn pick(&self, rng: &mut StdRng) -> usize {
+        let n = rng.random_range(0..self.cumulative[self.cumulative.len() - 1]);
+        self.cumulative.partition_point(|&total| total <= n)
+    }
+    
+    fn complete(&self, endpoint: usize) {
+        self.completed[endpoint].fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// Requests, successes and latency of every method, in the order the
+    /// file first uses them; lines without one count under `default_method`
+    fn method_stats(&self, default_method: &str, samples: &[Sample], correct_co: bool) -> Vec<MethodStats> {
+        let method = |i: usize| self.endpoints[i].method.as_deref().unwrap_or(default_method);
+        let mut methods: Vec<&str> = Vec::new();
+        for i in 0..self.endpoints.len() {
+            if !methods.contains(&method(i)) {
+                methods.push(method(i));
+            }
+        }
+        
+        methods.into_iter()
+            .map(|name| {
+                let mut latencies: Vec<Duration> = samples.iter()
+                    .filter(|s| method(s.target) == name)
+                    .map(|s| s.headline(correct_co))
+                    .collect();
+                stats::sort_latencies(&mut latencies);
+                MethodStats {
+                    method: name.to_string(),
+                    requests: (0..self.endpoints.len())
+                        .filter(|&i| method(i) == name)
+                        .map(|i| self.completed[i].load(Ordering::Relaxed))
+                        .sum(),
+                    successful: latencies.len(),
+                    latency: stats::latency_percentiles(&latencies),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Requests in flight across all workers, for the concurrency a run actually
+/// achieved: connect failures and gaps between requests keep it below the
+/// configured number of connections
+struct InFlight {
+    /// When the run started; in-flight time is counted from here
+    epoch: Instant,
+    current: AtomicUsize,
+    peak: AtomicUsize,
+    /// Sum of the time every finished request spent in flight
+    busy_nanos: AtomicU64,
+    /// Sum of the start times of the requests still in flight
+    started_nanos: AtomicU64,
+}
+
+impl InFlight {
+    fn new() -> Self {
+        InFlight {
+            epoch: Instant::now(),
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            busy_nanos: AtomicU64::new(0),
+            started_nanos: AtomicU64::new(0),
+        }
+    }
+    
+    /// Counts `n` requests in flight until the returned guard is dropped
+    fn enter(&self, n: usize) -> InFlightGuard<'_> {
+        let start = self.epoch.elapsed().as_nanos() as u64;
+        self.started_nanos.fetch_add(start * n as u64, Ordering::Relaxed);
+        let current = self.current.fetch_add(n, Ordering::Relaxed) + n;
+        self.peak.fetch_max(current, Ordering::Relaxed);
+        InFlightGuard { in_flight: self, n, start }
+    }
+    
+    /// Average in-flight requests over `total_time` (the total time spent in
+    /// flight over the run's duration), and the most at any moment. Requests
+    /// still hung at the end count as in flight until now.
+    fn stats(&self, total_time: Duration) -> AchievedConcurrency {
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        let outstanding = (self.current.load(Ordering::Relaxed) as u64 * now)
+            .saturating_sub(self.started_nanos.load(Ordering::Relaxed));
+        let busy = Duration::from_nanos(self.busy_nanos.load(Ordering::Relaxed) + outstanding);
+        AchievedConcurrency {
+            avg: if total_time.is_zero() { 0.0 } else { busy.as_secs_f64() / total_time.as_secs_f64() },
+            max: self.peak.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct InFlightGuard<'a> {
+    in_flight: &'a InFlight,
+    n: usize,
+    /// Nanoseconds since the `InFlight` epoch
+    start: u64,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        let n = self.n as u64;
+        let busy = (self.in_flight.epoch.elapsed().as_nanos() as u64).saturating_sub(self.start) * n;
+        self.in_flight.busy_nanos.fetch_add(busy, Ordering::Relaxed);
+        self.in_flight.current.fetch_sub(self.n, Ordering::Relaxed);
+        self.in_flight.started_nanos.fetch_sub(self.start * n, Ordering::Relaxed);
+    }
+}
+
+/// Name, total duration and number of responses of each `Server-Timing`
+/// phase, in the order the phases first appeared
+type ServerTimingPhases = Vec<(String, Duration, usize)>;
+
+/// Time every `Server-Timing` phase took over a run (`--server-timing`).
+/// Each worker records into its own phases, so workers never wait on each
+/// other; they are merged once the run is over.
+struct ServerTimingTally {
+    workers: Vec<std::sync::Mutex<ServerTimingPhases>>,
+}
+
+impl ServerTimingTally {
+    fn new(workers: usize) -> Self {
+        ServerTimingTally { workers: (0..workers).map(|_| Default::default()).collect() }
+    }
+    
+    fn record(&self, worker: usize, timings: &[ServerTiming]) {
+        if timings.is_empty() {
+            return;
+        }
+        let mut phases = self.workers[worker].lock().unwrap_or_else(|e| e.into_inner());
+        for timing in timings {
+            match phases.iter_mut().find(|(name, ..)| *name == timing.name) {
+                Some((_, total, count)) => {
+                    *total += timing.duration;
+                    *count += 1;
+                },
+                None => phases.push((timing.name.clone(), timing.duration, 1)),
+            }
+        }
+    }
+    
+    fn stats(&self) -> Vec<ServerTimingPhase> {
+        let mut phases: ServerTimingPhases = Vec::new();
+        for worker in &self.workers {
+            for (name, total, count) in worker.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+                match phases.iter_mut().find(|(n, ..)| n == name) {
+                    Some((_, t, c)) => {
+                        *t += *total;
+                        *c += count;
+                    },
+                    None => phases.push((name.clone(), *total, *count)),
+                }
+            }
+        }
+        phases.iter()
+            .map(|(name, total, count)| ServerTimingPhase {
+                name: name.clone(),
+                responses: *count,
+                avg: total.div_f64(*count as f64),
+            })
+            .collect()
+    }
+}
+
+/// Requests completed by each worker of a run (`--per-worker-stats`)
+struct WorkerTally {
+    completed: Vec<AtomicUsize>,
+}
+
+impl WorkerTally {
+    fn new(workers: usize) -> Self {
+        WorkerTally { completed: (0..workers).map(|_| AtomicUsize::new(0)).collect() }
+    }
+    
+    fn complete(&self, worker: usize, requests: usize) {
+        self.completed[worker].fetch_add(requests, Ordering::Relaxed);
+    }
+    
+    /// Requests and p99 latency of every worker, by worker index, from the
+    /// run's samples
+    fn stats(&self, samples: &[Sample], correct_co: bool) -> (Vec<u64>, Vec<Duration>) {
+        let mut latencies = vec![Vec::new(); self.completed.len()];
+        for sample in samples {
+            latencies[sample.worker].push(sample.headline(correct_co));
+        }
+        let p99 = latencies.iter_mut()
+            .map(|latencies| {
+                stats::sort_latencies(latencies);
+                stats::percentile(latencies, 0.99)
+            })
+            .collect();
+        let requests = self.completed.iter().map(|c| c.load(Ordering::Relaxed) as u64).collect();
+        (requests, p99)
+    }
+}
+
+/// Unique ids for the `--request-id-header`, shared by the workers of a run:
+/// a random prefix per run, fixed by `--seed`, followed by a sequence number
+struct RequestIds {
+    header: String,
+    prefix: u32,
+    next: AtomicU64,
+}
+
+impl RequestIds {
+    fn new(header: String, seed: Option<u64>) -> Self {
+        let prefix = stream_rng(seed, RngStream::RequestIds, 0).random();
+        RequestIds { header, prefix, next: AtomicU64::new(0) }
+    }
+    
+    fn next(&self) -> String {
+        format!("{:08x}-{}", self.prefix, self.next.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A `--rate-ramp` run is reported in intervals of a second, or shorter
+/// ones for runs of under `RAMP_MIN_POINTS` seconds
+const RAMP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fewest intervals a `--rate-ramp` run is split into
+const RAMP_MIN_POINTS: u32 = 10;
+
+/// An interval of a `--rate-ramp` run degraded when it completed fewer than
+/// this share of its target rate successfully, e.g. because requests failed
+const RAMP_SHORTFALL: f64 = 0.9;
+
+/// ... or when its p99 rose past this multiple of the first interval's
+const RAMP_P99_FACTOR: f64 = 2.0;
+
+/// Target against achieved rate for each whole interval of a `--rate-ramp`
+/// run (see `RAMP_INTERVAL`), and the target rate at which the run
+/// degraded. It takes two degraded intervals in a row, so a single noisy
+/// one isn't mistaken for the limit. Requests still finishing after
+/// `duration` are past the ramp, so they fall in no interval.
+fn rate_ramp_stats(ramp: RateRamp, duration: Duration, total_time: Duration, samples: &[Sample], correct_co: bool) -> RateRampStats {
+    let ramped = total_time.min(duration);
+    let interval = RAMP_INTERVAL.min(ramped / RAMP_MIN_POINTS);
+    let count = if interval.is_zero() { 0 } else { (ramped.as_nanos() / interval.as_nanos()) as usize };
+    let mut intervals = vec![Vec::new(); count];
+    for sample in samples {
+        let index = (sample.offset.as_nanos() / interval.as_nanos().max(1)) as usize;
+        if let Some(latencies) = intervals.get_mut(index) {
+            latencies.push(sample.headline(correct_co));
+        }
+    }
+    let points: Vec<RampPoint> = intervals.into_iter()
+        .enumerate()
+        .map(|(index, mut latencies)| {
+            stats::sort_latencies(&mut latencies);
+            let offset = interval * index as u32;
+            RampPoint {
+                offset,
+                target_rate: ramp.rate_at(offset + interval / 2, duration),
+                achieved_rate: latencies.len() as f64 / interval.as_secs_f64(),
+                p99: (!latencies.is_empty()).then(|| stats::percentile(&latencies, 0.99)),
+            }
+        })
+        .collect();
+    
+    let baseline = points.iter().find_map(|point| point.p99);
+    let degradation = |point: &RampPoint| {
+        if point.achieved_rate < point.target_rate * RAMP_SHORTFALL {
+            Some(format!("throughput below {:.0}% of target", RAMP_SHORTFALL * 100.0))
+        } else if let (Some(p99), Some(baseline)) = (point.p99, baseline)
+            && p99 > baseline.mul_f64(RAMP_P99_FACTOR)
+        {
+            Some(format!("p99 above {}x the first interval's", RAMP_P99_FACTOR))
+        } else {
+            None
+        }
+    };
+    let degraded = points.windows(2).find_map(|pair| {
+        degradation(&pair[0])
+            .filter(|_| degradation(&pair[1]).is_some())
+            .map(|reason| RampDegradation { offset: pair[0].offset, target_rate: pair[0].target_rate, reason })
+    });
+    
+    RateRampStats { start_rate: ramp.start, end_rate: ramp.end, points, degraded }
+}
+
+/// The rate a run is paced at: `--rate`, or where `--rate-ramp` starts
+fn pacing_rate(rate: Option<f64>, ramp: Option<RateRamp>) -> Option<f64> {
+    rate.or(ramp.map(|ramp| ramp.start))
+}
+
+/// The `SLOWEST_REQUESTS` slowest samples that carry a request id, slowest first
+fn slowest_requests(samples: &[Sample], correct_co: bool) -> Vec<SlowRequest> {
+    let mut slowest: Vec<&Sample> = samples.iter().filter(|s| s.request_id.is_some()).collect();
+    // Only the slowest few need ordering, not the whole run
+    let slowest_first = |s: &&Sample| std::cmp::Reverse(s.headline(correct_co));
+    if slowest.len() > SLOWEST_REQUESTS {
+        slowest.select_nth_unstable_by_key(SLOWEST_REQUESTS, slowest_first);
+        slowest.truncate(SLOWEST_REQUESTS);
+    }
+    slowest.sort_by_key(slowest_first);
+    slowest.iter()
+        .filter_map(|s| Some(SlowRequest {
+            id: s.request_id.clone()?,
+            latency: s.headline(correct_co),
+            offset: s.offset,
+        }))
+        .collect()
+}
+
+/// When a run that started at `start` runs out of time. A zero `duration`
+/// sets no time limit, leaving the run to its request count.
+fn stop_time(start: Instant, duration: Duration) -> Instant {
+    if duration.is_zero() {
+        start + NO_TIME_LIMIT
+    } else {
+        start + duration
+    }
+}
+
+/// Which limit ended a run once its collector loop has exited
+fn stop_reason(
+    interrupted: bool,
+    cancel: Option<&CancelFlag>,
+    latency_spike: bool,
+    requests: usize,
+    completed: usize,
+) -> StopReason {
+    if interrupted && cancel.is_some_and(CancelFlag::is_expired) {
+        StopReason::MaxDuration
+    } else if interrupted {
+        StopReason::Interrupted
+    } else if latency_spike {
+        StopReason::LatencySpike
+    } else if requests > 0 && completed >= requests {
+        StopReason::RequestsCompleted
+    } else {
+        StopReason::DurationElapsed
+    }
+}
+
+/// Fails a probe, or one of several `--runs`, that was cut short by Ctrl-C
+/// or another early stop, so the surrounding sequence stops with it.
+fn completed_or_stopped(report: BenchmarkReport) -> Result<BenchmarkReport, BenchmarkError> {
+    match report.stop_reason {
+        StopReason::Interrupted => Err(BenchmarkError::Stopped("Interrupted".to_string())),
+        StopReason::MaxDuration => Err(BenchmarkError::Stopped("Stopped at --max-duration".to_string())),
+        StopReason::LatencySpike => Err(BenchmarkError::Stopped("Stopped by --abort-if-p99-above".to_string())),
+        StopReason::RequestsCompleted | StopReason::DurationElapsed => Ok(report),
+    }
+}
+
+/// Streaming latency histogram behind the live p50/p99 shown next to the
+/// progress bar. Values are bucketed in microseconds: exactly below 128us,
+/// then 64 buckets per power of two (under 2% relative error).
+#[derive(Default)]
+struct LiveHistogram {
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl LiveHistogram {
+    const SUB_BUCKETS: u64 = 64;
+    
+    fn new() -> Self {
+        LiveHistogram { counts: Vec::new(), total: 0 }
+    }
+    
+    fn bucket(micros: u64) -> usize {
+        if micros < 2 * Self::SUB_BUCKETS {
+            return micros as usize;
+        }
+        let exp = 63 - micros.leading_zeros() as u64; // >= 7
+        let shift = exp - 6;
+        let mantissa = micros >> shift; // in 64..128
+        (2 * Self::SUB_BUCKETS + (exp - 7) * Self::SUB_BUCKETS + (mantissa - Self::SUB_BUCKETS)) as usize
+    }
+    
+    /// Lower bound of the bucket, in microseconds
+    fn bucket_value(index: usize) -> u64 {
+        let index = index as u64;
+        if index < 2 * Self::SUB_BUCKETS {
+            return index;
+        }
+        let offset = index - 2 * Self::SUB_BUCKETS;
+        let exp = offset / Self::SUB_BUCKETS + 7;
+        let mantissa = offset % Self::SUB_BUCKETS + Self::SUB_BUCKETS;
+        mantissa << (exp - 6)
+    }
+    
+    fn record(&mut self, latency: Duration) {
+        self.record_n(latency, 1);
+    }
+    
+    /// Records `n` values of `latency` at once
+    fn record_n(&mut self, latency: Duration, n: u64) {
+        if n == 0 {
+            return;
+        }
+        let index = Self::bucket(latency.as_micros().min(u64::MAX as u128) as u64);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += n;
+        self.total += n;
+    }
+    
+    fn quantile(&self, q: f64) -> Duration {
+        Self::quantile_of(&[self], q)
+    }
+    
+    /// Quantile of the values of several histograms together
+    fn quantile_of(histograms: &[&LiveHistogram], q: f64) -> Duration {
+        let total: u64 = histograms.iter().map(|h| h.total).sum();
+        if total == 0 {
+            return Duration::ZERO;
+        }
+        let target = ((q * total as f64).ceil() as u64).max(1);
+        let buckets = histograms.iter().map(|h| h.counts.len()).max().unwrap_or(0);
+        let mut seen = 0;
+        for index in 0..buckets {
+            seen += histograms.iter().filter_map(|h| h.counts.get(index)).sum::<u64>();
+            if seen >= target {
+                return Duration::from_micros(Self::bucket_value(index));
+            }
+        }
+        Duration::ZERO
+    }
+    
+    fn summary(&self) -> String {
+        format!(
+            "p50 {:.2}ms p99 {:.2}ms",
+            self.quantile(0.5).as_secs_f64() * 1000.0,
+            self.quantile(0.99).as_secs_f64() * 1000.0,
+        )
+    }
+}
+
+/// Response times summarized as they arrive: a histogram for the
+/// percentiles, with the average, minimum and maximum kept exactly
+#[derive(Default)]
+struct LatencySummary {
+    histogram: LiveHistogram,
+    sum: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl LatencySummary {
+    fn record(&mut self, latency: Duration) {
+        if self.histogram.total == 0 || latency < self.min {
+            self.min = latency;
+        }
+        self.max = self.max.max(latency);
+        self.sum += latency;
+        self.histogram.record(latency);
+    }
+    
+    /// Like `stats::latency_percentiles`, with the percentiles read from the
+    /// histogram and kept within the exact minimum and maximum
+    fn percentiles(&self) -> LatencyPercentiles {
+        let quantile = |q| self.histogram.quantile(q).clamp(self.min, self.max);
+        LatencyPercentiles {
+            avg: if self.histogram.total == 0 {
+                Duration::ZERO
+            } else {
+                self.sum.div_f64(self.histogram.total as f64)
+            },
+            p50: quantile(0.5),
+            p90: quantile(0.9),
+            p95: quantile(0.95),
+            p99: quantile(0.99),
+            max: self.max,
+        }
+    }
+}
+
+/// `--low-memory`: what the report needs from the samples, summarized as
+/// they arrive instead of kept, so memory stays flat however long the run.
+struct SampleSummary {
+    latency: LatencySummary,
+    /// Latencies from the intended send times, for paced runs
+    corrected: Option<LatencySummary>,
+    ttfb: LatencySummary,
+}
+
+impl SampleSummary {
+    fn new(paced: bool) -> Self {
+        SampleSummary {
+            latency: LatencySummary::default(),
+            corrected: paced.then(LatencySummary::default),
+            ttfb: LatencySummary::default(),
+        }
+    }
+    
+    fn record(&mut self, sample: &Sample) {
+        self.latency.record(sample.latency);
+        if let Some(ref mut corrected) = self.corrected {
+            corrected.record(sample.intended_latency.unwrap_or(sample.latency));
+        }
+        if let Some(ttfb) = sample.ttfb {
+            self.ttfb.record(ttfb);
+        }
+    }
+    
+    /// What `summarize_samples` and `Statistics::from_samples` give for the
+    /// samples themselves
+    fn statistics(
+        &self,
+        rate: Option<f64>,
+        arrival: Arrival,
+        correct_co: bool,
+        total_time: Duration,
+        total: usize,
+        successful: usize,
+    ) -> (Statistics, Option<LatencyCorrection>) {
+        let headline = match self.corrected {
+            Some(ref corrected) if correct_co => corrected,
+            _ => &self.latency,
+        };
+        let stats = Statistics::from_summary(headline.percentiles(), headline.min, total_time, total, successful);
+        
+        let correction = rate.zip(self.corrected.as_ref()).map(|(target_rate, corrected)| LatencyCorrection {
+            target_rate,
+            arrival,
+            applied: correct_co,
+            uncorrected: self.latency.percentiles(),
+            corrected: corrected.percentiles(),
+        });
+        (stats, correction)
+    }
+    
+    fn ttfb(&self) -> Option<LatencyPercentiles> {
+        (self.ttfb.histogram.total > 0).then(|| self.ttfb.percentiles())
+    }
+}
+
+/// `--abort-if-p99-above`: watches the p99 of the requests of the current
+/// and the previous `SPIKE_WINDOW`, so a spike counts for at most two windows
+/// and the run's early history doesn't dilute it. Failed requests count as
+/// taking the whole timeout, so a server that stops answering trips it too.
+struct SpikeBreaker {
+    threshold: Duration,
+    timeout: Duration,
+    errors: Arc<ErrorTally>,
+    /// Failures already counted in the windows
+    failures: u64,
+    current: LiveHistogram,
+    previous: LiveHistogram,
+    window_start: Instant,
+}
+
+impl SpikeBreaker {
+    /// Requests needed in the windows before their p99 is trusted
+    const MIN_SAMPLES: u64 = 20;
+    
+    fn new(threshold: Duration, timeout: Duration, errors: Arc<ErrorTally>) -> Self {
+        SpikeBreaker {
+            threshold,
+            timeout,
+            errors,
+            failures: 0,
+            current: LiveHistogram::new(),
+            previous: LiveHistogram::new(),
+            window_start: Instant::now(),
+        }
+    }
+    
+    /// Records a response in the current window
+    fn record(&mut self, latency: Duration) {
+        self.current.record(latency);
+    }
+    
+    /// Called by the collector on every pass, with or without new responses:
+    /// moves on to a new window once `SPIKE_WINDOW` has passed, adds the
+    /// failures since the last call and returns the windows' p99 if it is
+    /// now above the threshold
+    fn check(&mut self) -> Option<Duration> {
+        if self.window_start.elapsed() >= SPIKE_WINDOW {
+            self.previous = std::mem::replace(&mut self.current, LiveHistogram::new());
+            self.window_start = Instant::now();
+        }
+        let failures = self.errors.failures();
+        self.current.record_n(self.timeout, failures - self.failures);
+        self.failures = failures;
+        
+        if self.current.total + self.previous.total < Self::MIN_SAMPLES {
+            return None;
+        }
+        let p99 = LiveHistogram::quantile_of(&[&self.current, &self.previous], 0.99);
+        (p99 > self.threshold).then_some(p99)
+    }
+}
+
+/// `--adaptive-timeout`: the per-request timeout shared by the workers. It
+/// starts at the configured timeout and, once enough requests have ended,
+/// moves a step toward `multiple` times the live median every
+/// `ADAPTIVE_TIMEOUT_INTERVAL`, never above the configured timeout. Timed-out
+/// requests count towards the median as the slowest requests, so a timeout
+/// that cuts off many of them widens again instead of shrinking further.
+struct AdaptiveTimeout {
+    nanos: AtomicU64,
+    ceiling: Duration,
+    multiple: f64,
+    errors: Arc<ErrorTally>,
+}
+
+impl AdaptiveTimeout {
+    /// Requests needed before the median is trusted
+    const MIN_SAMPLES: u64 = 20;
+    /// Share of the distance to the target covered per step
+    const STEP: f64 = 0.25;
+    
+    fn new(ceiling: Duration, multiple: f64, errors: Arc<ErrorTally>) -> Self {
+        AdaptiveTimeout {
+            nanos: AtomicU64::new(ceiling.as_nanos() as u64),
+            ceiling,
+            multiple,
+            errors,
+        }
+    }
+    
+    fn current(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+    
+    /// Called by the collector every `ADAPTIVE_TIMEOUT_INTERVAL` with the
+    /// responses so far; returns whether there were enough to take a step
+    fn update(&self, live: &LiveHistogram) -> bool {
+        let timeouts = self.errors.count("request_timeout");
+        let ended = live.total + timeouts;
+        if ended < Self::MIN_SAMPLES {
+            return false;
+        }
+        
+        // Timed-out requests took at least the timeout, longer than any response
+        let median_rank = ended as f64 * 0.5;
+        let median = if median_rank > live.total as f64 {
+            self.current()
+        } else {
+            live.quantile(median_rank / live.total as f64)
+        };
+        let target = median.mul_f64(self.multiple).min(self.ceiling);
+        let current = self.current().as_secs_f64();
+        let next = current + (target.as_secs_f64() - current) * Self::STEP;
+        self.nanos.store(Duration::from_secs_f64(next).as_nanos() as u64, Ordering::Relaxed);
+        true
+    }
+}
+
+/// Request timeout and strict timeout of the next request. An
+/// `--adaptive-timeout` replaces the request timeout and also applies to the
+/// whole response, alongside any `--timeout-percentile` cutoff: whichever
+/// is shorter classifies it.
+fn request_timeouts(
+    adaptive: Option<&AdaptiveTimeout>,
+    timeout: Duration,
+    strict_timeout: Option<Duration>,
+) -> (Duration, Option<Duration>) {
+    match adaptive.map(AdaptiveTimeout::current) {
+        Some(current) => (current, Some(strict_timeout.map_or(current, |strict| strict.min(current)))),
+        None => (timeout, strict_timeout),
+    }
+}
+
+/// Requests/sec over one interval, at seconds since the start of the run
+pub type ThroughputPoint = (f64, f64);
+
+/// Turns the completed-request count into `ThroughputPoint`s for a live
+/// listener such as the TUI graph, one per `LIVE_UPDATE_INTERVAL`.
+struct ThroughputFeed {
+    tx: mpsc::UnboundedSender<ThroughputPoint>,
+    start: Instant,
+    last: Instant,
+    last_completed: usize,
+}
+
+impl ThroughputFeed {
+    fn new(tx: mpsc::UnboundedSender<ThroughputPoint>, start: Instant) -> Self {
+        ThroughputFeed { tx, start, last: start, last_completed: 0 }
+    }
+    
+    fn tick(&mut self, completed: usize) {
+        if self.last.elapsed() >= LIVE_UPDATE_INTERVAL {
+            self.send(completed);
+        }
+    }
+    
+    /// Sends the rate over the final, partial interval unless it is too
+    /// short to give a meaningful rate
+    fn finish(mut self, completed: usize) {
+        let first = self.last == self.start;
+        if completed > self.last_completed && (first || self.last.elapsed() >= LIVE_UPDATE_INTERVAL / 10) {
+            self.send(completed);
+        }
+    }
+    
+    fn send(&mut self, completed: usize) {
+        let interval = self.last.elapsed().as_secs_f64();
+        let rate = (completed - self.last_completed) as f64 / interval;
+        let _ = self.tx.send((self.start.elapsed().as_secs_f64(), rate));
+        self.last = Instant::now();
+        self.last_completed = completed;
+    }
+}
+
+/// `--raw-output`: the file completed requests are streamed to, whether
+/// `progress` records are interleaved (`--raw-progress`), and the fraction of
+/// requests written (`--sample-rate`), drawn from the `--seed` stream when
+/// one is given
+#[derive(Clone)]
+pub struct RawOutput {
+    pub path: PathBuf,
+    pub progress: bool,
+    pub sample_rate: f64,
+    pub seed: Option<u64>,
+}
+
+/// Writes the `--raw-output` stream while a run is in progress: a `request`
+/// line per completed request and, with `progress`, a `progress` line with
+/// the aggregates so far once per `LIVE_UPDATE_INTERVAL`. The file is
+/// flushed at the same interval so readers see the lines live. The lines
+/// are written by a task of their own, so the collector never waits on the
+/// disk, and a failed write is logged rather than ending the run.
+struct RawStream {
+    tx: mpsc::UnboundedSender<RawWrite>,
+    writer: tokio::task::JoinHandle<()>,
+    start: Instant,
+    progress: bool,
+    sample_rate: f64,
+    /// Picks the requests written when `sample_rate` is below 1
+    rng: StdRng,
+    last_flush: Instant,
+    last_progress_ms: Option<u64>,
+}
+
+/// What the collector hands the `--raw-output` writer task
+enum RawWrite {
+    Line(Vec<u8>),
+    Flush,
+}
+
+impl RawStream {
+    fn create(output: &RawOutput, start: Instant) -> Result<Self, BenchmarkError> {
+        let file = std::fs::File::create(&output.path).map_err(|e| {
+            BenchmarkError::Config(format!("Cannot write --raw-output {}: {}", output.path.display(), e))
+        })?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        Ok(RawStream {
+            tx,
+            writer: tokio::spawn(write_raw_output(tokio::fs::File::from_std(file), output.path.clone(), rx)),
+            start,
+            progress: output.progress,
+            sample_rate: output.sample_rate,
+            rng: stream_rng(output.seed, RngStream::Sampling, 0),
+            last_flush: start,
+            last_progress_ms: None,
+        })
+    }
+    
+    /// Writes the request, or with a `sample_rate` below 1 that share of the
+    /// requests chosen at random
+    fn request(&mut self, sample: &Sample) {
+        if self.sample_rate < 1.0 && !self.rng.random_bool(self.sample_rate) {
+            return;
+        }
+        self.write(&RawRecord::Request(sample.record()));
+    }
+    
+    fn tick(&mut self, live: &LiveHistogram) {
+        if self.last_flush.elapsed() < LIVE_UPDATE_INTERVAL {
+            return;
+        }
+        self.last_flush = Instant::now();
+        if self.progress {
+            self.write_progress(live);
+        }
+        let _ = self.tx.send(RawWrite::Flush);
+    }
+    
+    /// Writes the final progress record and waits for the writer to flush
+    /// the stream
+    async fn finish(mut self, live: &LiveHistogram) {
+        if self.progress {
+            self.write_progress(live);
+        }
+        drop(self.tx);
+        let _ = self.writer.await;
+    }
+    
+    /// Successful requests/sec and p99 over the run so far, both from the
+    /// successful requests; skipped when no time has passed since the
+    /// previous one, so progress timestamps always increase
+    fn write_progress(&mut self, live: &LiveHistogram) {
+        let elapsed = self.start.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        if self.last_progress_ms.is_some_and(|last| elapsed_ms <= last) {
+            return;
+        }
+        self.last_progress_ms = Some(elapsed_ms);
+        self.write(&RawRecord::Progress {
+            elapsed_ms,
+            rps: live.total as f64 / elapsed.as_secs_f64(),
+            p99_us: live.quantile(0.99).as_micros() as u64,
+        });
+    }
+    
+    fn write(&mut self, record: &RawRecord) {
+        let mut line = serde_json::to_vec(record).expect("raw records serialize");
+        line.push(b'\n');
+        let _ = self.tx.send(RawWrite::Line(line));
+    }
+}
+
+/// The `--raw-output` writer task: writes the lines it is sent, flushing on
+/// request and at the end. After a failed write it warns once and drops the
+/// rest, so the run still finishes with its report.
+async fn write_raw_output(file: tokio::fs::File, path: PathBuf, mut rx: mpsc::UnboundedReceiver<RawWrite>) {
+    use tokio::io::AsyncWriteExt;
+    
+    let mut file = tokio::io::BufWriter::new(file);
+    let mut failed = false;
+    while let Some(write) = rx.recv().await {
+        if failed {
+            continue;
+        }
+        let result = match write {
+            RawWrite::Line(line) => file.write_all(&line).await,
+            RawWrite::Flush => file.flush().await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(path = %path.display(), error = %e, "failed to write --raw-output, dropping the rest of the stream");
+            failed = true;
+        }
+    }
+    if !failed && let Err(e) = file.flush().await {
+        tracing::warn!(path = %path.display(), error = %e, "failed to write --raw-output");
+    }
+}
+
+/// The progress bar of a run, with the live percentiles as its message:
+/// counting up to `requests`, or the completed requests of a duration-only run
+fn progress_bar(requests: usize) -> ProgressBar {
+    if requests == 0 {
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("[{elapsed_precise}] {spinner:.cyan} {pos} requests {msg}")
+                .unwrap()
+        );
+        return bar;
+    }
+    
+    let bar = ProgressBar::new(requests as u64);
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {percent}% ({eta}) {msg}")
+            .unwrap()
+            .progress_chars("##-")
+    );
+    bar
+}
+
+/// The collector loop every runner runs while its workers send samples
+struct Collector<'a> {
+    start_time: Instant,
+    stop_time: Instant,
+    pause: Option<&'a PauseFlag>,
+    correct_co: bool,
+    /// Summarizes the samples instead of keeping them (`--low-memory`)
+    summary: Option<SampleSummary>,
+    spike_breaker: Option<SpikeBreaker>,
+    adaptive_timeout: Option<&'a AdaptiveTimeout>,
+    fail_fast: Option<&'a FailFast>,
+    cancel: Option<&'a CancelFlag>,
+    progress: &'a ProgressBar,
+    throughput: Option<mpsc::UnboundedSender<ThroughputPoint>>,
+    raw_output: Option<&'a RawOutput>,
+    completed: &'a AtomicUsize,
+}
+
+/// What a `Collector` gathered from a run
+struct Collected {
+    samples: Vec<Sample>,
+    summary: Option<SampleSummary>,
+    interrupted: bool,
+    latency_spike: Option<LatencySpike>,
+}
+
+impl Collector<'_> {
+    /// Collects samples as they arrive, so the live percentiles stay current,
+    /// until the workers finish, the stop time passes, `--fail-fast` trips,
+    /// the cancel flag (Ctrl-C) is set or `--abort-if-p99-above` ends the
+    /// run. The remaining workers are then cancelled and the samples they
+    /// already sent are collected.
+    async fn run(mut self, set: &mut JoinSet<()>, rx: &mut mpsc::Receiver<Sample>) -> Result<Collected, BenchmarkError> {
+        let mut samples = Vec::new();
+        let mut live = LiveHistogram::new();
+        let mut last_update = Instant::now();
+        let mut adaptive_updated = Instant::now();
+        let mut feed = self.throughput.take().map(|tx| ThroughputFeed::new(tx, self.start_time));
+        let mut raw = self.raw_output.map(|output| RawStream::create(output, self.start_time)).transpose()?;
+        let mut interrupted = false;
+        let mut latency_spike = None;
+        while !past_stop_time(self.stop_time, self.pause) && !set.is_empty()
+            && !self.fail_fast.is_some_and(|f| f.is_tripped()) && !interrupted && latency_spike.is_none()
+        {
+            tokio::select! {
+                _ = sleep(Duration::from_millis(100)) => {
+                    // Just a timeout to check if we've reached the stop time
+                }
+                _ = set.join_next() => {
+                    // A worker has completed
+                }
+                Some(sample) = rx.recv() => {
+                    live.record(sample.headline(self.correct_co));
+                    if let Some(ref mut breaker) = self.spike_breaker {
+                        breaker.record(sample.headline(self.correct_co));
+                    }
+                    if let Some(ref mut raw) = raw {
+                        raw.request(&sample);
+                    }
+                    if let Some(ref mut summary) = self.summary {
+                        summary.record(&sample);
+                    } else {
+                        samples.push(sample);
+                    }
+                }
+            }
+            
+            // Also without new responses, so failures and idle gaps count
+            if let Some(ref mut breaker) = self.spike_breaker
+                && let Some(p99) = breaker.check()
+            {
+                latency_spike = Some(LatencySpike { threshold: breaker.threshold, p99, offset: self.start_time.elapsed() });
+            }
+            
+            if last_update.elapsed() >= LIVE_UPDATE_INTERVAL {
+                self.progress.set_message(live.summary());
+                last_update = Instant::now();
+            }
+            
+            // Also without new responses, as timed-out requests send none
+            if let Some(adaptive) = self.adaptive_timeout
+                && adaptive_updated.elapsed() >= ADAPTIVE_TIMEOUT_INTERVAL
+                && adaptive.update(&live)
+            {
+                adaptive_updated = Instant::now();
+            }
+            
+            if let Some(ref mut feed) = feed {
+                feed.tick(self.completed.load(Ordering::Relaxed));
+            }
+            
+            if let Some(ref mut raw) = raw {
+                raw.tick(&live);
+            }
+            
+            if self.cancel.is_some_and(CancelFlag::is_cancelled) {
+                interrupted = true;
+            }
+        }
+        
+        // Cancel any remaining tasks
+        set.abort_all();
+        
+        if let Some(feed) = feed {
+            feed.finish(self.completed.load(Ordering::Relaxed));
+        }
+        
+        // Collect the remaining response samples
+        while let Some(sample) = rx.recv().await {
+            if let Some(ref mut raw) = raw {
+                live.record(sample.headline(self.correct_co));
+                raw.request(&sample);
+            }
+            if let Some(ref mut summary) = self.summary {
+                summary.record(&sample);
+            } else {
+                samples.push(sample);
+            }
+        }
+        
+        if let Some(raw) = raw {
+            raw.finish(&live).await;
+        }
+        
+        self.progress.finish_and_clear();
+        
+        Ok(Collected { samples, summary: self.summary, interrupted, latency_spike })
+    }
+}
+
+/// Hands out send slots so that all workers together follow the configured
+/// request rate, with gaps between slots drawn from the arrival distribution.
+struct Pacer {
+    start: Instant,
+    rate: f64,
+    arrival: Arrival,
+    /// `--rate-ramp` the rate follows instead, and the duration it ramps over
+    ramp: Option<(RateRamp, Duration)>,
+    /// Slots handed out so far, which place a constant arrival's next slot
+    slots: AtomicU64,
+    /// Offset of the next slot from `start` in seconds, and the RNG drawing
+    /// the gaps, for the random arrivals
+    next_slot: std::sync::Mutex<(f64, StdRng)>,
+    /// Pauses of the run, which push every later slot back by their length
+    pause: Option<Arc<PauseFlag>>,
+}
+
+impl Pacer {
+    fn new(start: Instant, rate: f64, arrival: Arrival, seed: Option<u64>) -> Self {
+        Pacer {
+            start,
+            rate,
+            arrival,
+            ramp: None,
+            slots: AtomicU64::new(0),
+            next_slot: std::sync::Mutex::new((0.0, stream_rng(seed, RngStream::Pacer, 0))),
+            pause: None,
+        }
+    }
+    
+    /// Leaves the time `pause` holds the run out of the schedule, so slots
+    /// missed while paused aren't sent in a burst on resume
+    fn with_pause(mut self, pause: Option<Arc<PauseFlag>>) -> Self {
+        self.pause = pause;
+        self
+    }
+    
+    /// Ramps the rate along `ramp` over `duration` instead of holding it
+    fn with_ramp(mut self, ramp: Option<RateRamp>, duration: Duration) -> Self {
+        self.ramp = ramp.map(|ramp| (ramp, duration));
+        self
+    }
+    
+    /// Target rate for a slot `offset` seconds into the run
+    fn rate_at(&self, offset: f64) -> f64 {
+        match self.ramp {
+            Some((ramp, duration)) => ramp.rate_at(Duration::from_secs_f64(offset), duration),
+            None => self.rate,
+        }
+    }
+    
+    /// Offset of the next slot from `start`
+    fn next_offset(&self) -> Duration {
+        if self.arrival == Arrival::Constant {
+            let slot = self.slots.fetch_add(1, Ordering::Relaxed) as f64;
+            return match self.ramp {
+                Some((ramp, duration)) => ramp.time_of(slot, duration),
+                None => Duration::from_secs_f64(slot / self.rate),
+            };
+        }
+        
+        let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        let (offset, rng) = &mut *next_slot;
+        let slot = *offset;
+        *offset += arrival_gap(self.arrival, self.rate_at(slot), rng);
+        Duration::from_secs_f64(slot)
+    }
+    
+    /// Waits for the next free slot and returns the time the request was
+    /// intended to go out.
+    async fn wait(&self) -> Instant {
+        let offset = self.next_offset();
+        loop {
+            let paused = paused_time(self.pause.as_deref());
+            let intended = self.start + offset + paused;
+            tokio::time::sleep_until(intended.into()).await;
+            // A pause that started while waiting moves the slot again
+            match self.pause {
+                Some(ref pause) if pause.is_paused() || pause.paused_time() != paused => pause.wait().await,
+                _ => return intended,
+            }
+        }
+    }
+}
+
+/// Seconds between two paced send times. Every distribution has a mean gap
+/// of `1 / rate`, so they differ only in how bursty the arrivals are.
+fn arrival_gap(arrival: Arrival, rate: f64, rng: &mut StdRng) -> f64 {
+    match arrival {
+        Arrival::Constant => 1.0 / rate,
+        // Inverse transform of the exponential distribution; `1 - u` is in
+        // (0, 1] so the logarithm stays finite
+        Arrival::Poisson => -(1.0 - rng.random::<f64>()).ln() / rate,
+        Arrival::Uniform => rng.random::<f64>() * 2.0 / rate,
+    }
+}
 
 pub struct HttpRunner {
     config: HttpConfig,
+    throughput: Option<mpsc::UnboundedSender<ThroughputPoint>>,
+    progress: Option<Arc<AtomicUsize>>,
+    pause: Option<Arc<PauseFlag>>,
+    cancel: Option<Arc<CancelFlag>>,
+    raw_output: Option<RawOutput>,
+    trace: Option<TraceOptions>,
+    keep_samples: bool,
 }
 
 impl HttpRunner {
     pub fn new(config: HttpConfig) -> Self {
-        HttpRunner { config }
+        HttpRunner { config, throughput: None, progress: None, pause: None, cancel: None, raw_output: None, trace: None, keep_samples: false }
+    }
+    
+    /// Sends requests/sec once per second while the benchmark runs
+    pub fn with_throughput(mut self, tx: mpsc::UnboundedSender<ThroughputPoint>) -> Self {
+        self.throughput = Some(tx);
+        self
+    }
+    
+    /// Streams completed requests to `output` while the benchmark runs
+    pub fn with_raw_output(mut self, output: RawOutput) -> Self {
+        self.raw_output = Some(output);
+        self
+    }
+    
+    /// Keeps every completed request on the report's `samples`
+    pub fn with_samples(mut self) -> Self {
+        self.keep_samples = true;
+        self
+    }
+    
+    /// Records the bytes of each request in a `--trace-file`
+    pub fn with_trace(mut self, trace: TraceOptions) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    /// Counts completed requests in `completed` while the benchmark runs,
+    /// for a caller showing progress
+    pub fn with_progress(mut self, completed: Arc<AtomicUsize>) -> Self {
+        self.progress = Some(completed);
+        self
+    }
+    
+    /// Lets the caller pause and resume the run through `pause`
+    pub fn with_pause(mut self, pause: Arc<PauseFlag>) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+    
+    /// Ends the run early, with the report so far, once `cancel` is set
+    pub fn with_cancel(mut self, cancel: Arc<CancelFlag>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    
+    /// How connections are used, as recorded in the report
+    fn connection_strategy(&self) -> String {
+        match (self.config.pipeline, self.config.keepalive_requests) {
+            _ if self.config.connect_only => CONNECT_ONLY.to_string(),
+            (Some(depth), _) => format!("persistent, pipelining {} requests", depth),
+            _ if !self.config.is_keep_alive() => NEW_CONNECTION_PER_REQUEST.to_string(),
+            (None, Some(max)) => format!("persistent keep-alive, reconnecting every {} requests", max),
+            (None, None) => "persistent keep-alive".to_string(),
+        }
     }
     
     pub async fn run(&self) -> Result<BenchmarkReport, BenchmarkError> {
+        config::check_limits(self.config.requests, self.config.duration)?;
         let uri: Uri = self.config.url.parse()
             .map_err(|_| BenchmarkError::Config(format!("Invalid URL: {}", self.config.url)))?;
         
-        println!("Starting HTTP benchmark for {} with {} connections...", self.config.url, self.config.concurrency);
-        
-        // Create progress bar
-        let progress = if self.config.requests > 0 {
-            let bar = ProgressBar::new(self.config.requests as u64);
-            bar.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {percent}% ({eta})")
-                    .unwrap()
-                    .progress_chars("##-")
-            );
-            Some(bar)
-        } else {
-            None
-        };
+        eprintln!("Starting HTTP benchmark for {} with {} connections...", self.config.url, self.config.concurrency);
+        if self.config.insecure {
+            eprintln!("{}", "WARNING: TLS certificate verification is disabled (--insecure); \
+                these results do not reflect a verified production connection".red().bold());
+        }
+        
+        let progress = progress_bar(self.config.requests);
         
         let concurrency = self.config.concurrency;
         let requests_per_worker = if self.config.requests > 0 {
@@ -52,185 +1635,708 @@ impl HttpRunner {
             usize::MAX // run forever until duration is reached
         };
         
+        let tracer = self.trace.as_ref().map(Tracer::create).transpose()?.map(Arc::new);
         let start_time = Instant::now();
-        let stop_time = start_time + self.config.duration;
+        let stop_time = stop_time(start_time, self.config.duration);
+        let rate = pacing_rate(self.config.rate, self.config.rate_ramp);
+        let pacer = rate.map(|rate| Arc::new(
+            Pacer::new(start_time, rate, self.config.arrival, self.config.seed)
+                .with_ramp(self.config.rate_ramp, self.config.duration)
+                .with_pause(self.pause.clone())
+        ));
+        let connect_limiter = Arc::new(ConnectLimiter::new(self.config.connect_rate));
         
         // Shared counters for all workers
-        let completed_requests = Arc::new(AtomicUsize::new(0));
+        let completed_requests = self.progress.clone().unwrap_or_default();
         let successful_requests = Arc::new(AtomicUsize::new(0));
         let bytes_sent = Arc::new(AtomicUsize::new(0));
         let bytes_received = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(ErrorTally::new(self.config.only_errors));
+        let adaptive_timeout = self.config.adaptive_timeout
+            .map(|multiple| Arc::new(AdaptiveTimeout::new(self.config.timeout, multiple, errors.clone())));
+        let fail_fast = self.config.fail_fast.then(|| Arc::new(FailFast::default()));
+        let in_flight = Arc::new(InFlight::new());
+        let worker_tally = self.config.per_worker_stats.then(|| Arc::new(WorkerTally::new(concurrency)));
+        // Response counts indexed by `CacheStatus`
+        let cache_counts = Arc::new([AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)]);
+        let server_timing = self.config.server_timing.then(|| Arc::new(ServerTimingTally::new(concurrency)));
+        let stale_reconnects = Arc::new(AtomicUsize::new(0));
+        let status_counts = Arc::new(StatusCounts::new());
+        
+        // A fixed random body is generated once and sent like a regular body
+        let (body, random_body) = match self.config.random_body {
+            Some(size) if self.config.fixed_random_body => {
+                (Some(config::random_payload(&mut stream_rng(self.config.seed, RngStream::FixedBody, 0), size)), None)
+            },
+            random_body => (self.config.body.clone(), random_body),
+        };
+        
+        // Pipelined workers send pre-encoded HTTP/1.1 requests
+        let pipelined_request = match self.config.pipeline {
+            Some(_) => {
+                let mut headers = self.config.headers.clone();
+                if let Some(ref host) = self.config.host {
+                    headers.push(("Host".to_string(), host.clone()));
+                }
+                Some(Arc::new(http::encode_request(
+                    &uri,
+                    &self.config.method,
+                    &headers,
+                    body.as_deref(),
+                )?))
+            },
+            None => None,
+        };
+        
+        // `--hosts-file` requests keep naming the URL's host in `Host` and SNI
+        let hosts_file = !self.config.hosts.is_empty();
+        let host_header = self.config.host.clone()
+            .or_else(|| hosts_file.then(|| uri.authority().map(|a| a.to_string())).flatten());
+        let tls = TlsOptions {
+            sni: self.config.sni.clone()
+                .or_else(|| hosts_file.then(|| uri.host().map(str::to_string)).flatten()),
+            insecure: self.config.insecure,
+            ..Default::default()
+        };
+        
+        let expectations = Arc::new(http::ResponseExpectations::new(
+            self.config.expect_body.as_deref(),
+            &self.config.expect_json,
+            self.config.expect_length,
+            self.config.require_non_empty,
+        )?);
+        
+        // Every target URL; each request picks one with the worker's RNG, or
+        // the next `--hosts-file` host round-robin
+        let mut targets = vec![uri.clone()];
+        for url in &self.config.extra_urls {
+            targets.push(url.parse()
+                .map_err(|_| BenchmarkError::Config(format!("Invalid URL: {}", url)))?);
+        }
+        if hosts_file {
+            targets = self.config.hosts.iter()
+                .map(|host| http::with_authority(&uri, host))
+                .collect::<Result<_, _>>()?;
+        }
+        // `--url-file` endpoints are picked by weight instead
+        if !self.config.endpoints.is_empty() {
+            targets = self.config.endpoints.iter()
+                .map(|endpoint| endpoint.url.parse()
+                    .map_err(|_| BenchmarkError::Config(format!("Invalid URL: {}", endpoint.url))))
+                .collect::<Result<_, _>>()?;
+        }
+        let targets = Arc::new(targets);
+        let rotation = hosts_file.then(|| Arc::new(HostRotation::new(targets.len())));
+        let mix = (!self.config.endpoints.is_empty()).then(|| Arc::new(EndpointMix::new(&self.config.endpoints)));
+        let request_ids = self.config.request_id_header.clone().map(|header| Arc::new(RequestIds::new(header, self.config.seed)));
         
         // Channel for response times
-        let (tx, mut rx) = mpsc::channel::<Duration>(10000);
+        let (tx, mut rx) = mpsc::channel::<Sample>(10000);
         
         // Spawn worker tasks
         let mut set = JoinSet::new();
         
-        for _ in 0..concurrency {
+        for worker in 0..concurrency {
             let uri = uri.clone();
+            let targets = targets.clone();
+            let rotation = rotation.clone();
+            let mix = mix.clone();
+            let request_ids = request_ids.clone();
+            let mut cache_bust = self.config.cache_bust.clone()
+                .map(|kind| (kind, stream_rng(self.config.seed, RngStream::CacheBust, worker)));
+            let deadline_header = self.config.deadline_header.clone();
+            let mut rng = worker_rng(self.config.seed, worker);
+            let pipeline = self.config.pipeline.zip(pipelined_request.clone());
             let method = self.config.method.clone();
-            let headers = self.config.headers.clone();
-            let body = self.config.body.clone();
+            // The configured headers, followed by this request's own ones
+            let mut request_headers = self.config.headers.clone();
+            let configured_headers = request_headers.len();
+            let host = host_header.clone();
+            // Each worker resumes its own sessions, like an independent client
+            let tls = if self.config.tls_resumption {
+                tls.clone().with_session_cache()
+            } else {
+                tls.clone()
+            };
+            let expectations = expectations.clone();
+            let body = body.clone();
+            let stream_body_size = self.config.stream_body_size;
+            let no_body = self.config.no_body;
+            let offload_validation_above = self.config.offload_validation_above;
+            let stream_response = self.config.stream_response;
+            let connect_only = self.config.connect_only;
+            let http2 = self.config.http2;
             let timeout_duration = self.config.timeout;
-            let _keep_alive = self.config.is_keep_alive();
+            let connect_timeout = self.config.connect_timeout;
+            let local_addr = self.config.local_addr;
+            let strict_timeout = self.config.strict_timeout;
+            let mut keep_alive = self.config.is_keep_alive()
+                .then(|| http::KeepAlive::new(self.config.keepalive_requests, stale_reconnects.clone()));
             let completed_clone = completed_requests.clone();
             let successful_clone = successful_requests.clone();
             let bytes_sent_clone = bytes_sent.clone();
             let bytes_received_clone = bytes_received.clone();
+            let errors_clone = errors.clone();
+            let fail_fast_clone = fail_fast.clone();
+            let in_flight_clone = in_flight.clone();
+            let worker_tally_clone = worker_tally.clone();
+            let cache_counts_clone = cache_counts.clone();
+            let server_timing_clone = server_timing.clone();
+            let status_counts_clone = status_counts.clone();
             let tx_clone = tx.clone();
             let progress_clone = progress.clone();
+            let pause_clone = self.pause.clone();
+            let tracer_clone = tracer.clone();
+            let pacer_clone = pacer.clone();
+            let connect_limiter_clone = connect_limiter.clone();
+            let adaptive_timeout_clone = adaptive_timeout.clone();
             
             set.spawn(async move {
-                let _conn_reuse: Option<()> = None;
+                if let Some((depth, request)) = pipeline {
+                    let mut conn: Option<http::PipelinedConnection> = None;
+                    let mut sent = 0;
+                    
+                    while sent < requests_per_worker {
+                        if let Some(ref pause) = pause_clone {
+                            pause.wait().await;
+                        }
+                        
+                        if past_stop_time(stop_time, pause_clone.as_deref()) || fail_fast_clone.as_ref().is_some_and(|f| f.is_tripped()) {
+                            break;
+                        }
+                        
+                        let batch = depth.min(requests_per_worker - sent);
+                        let in_flight = in_flight_clone.enter(batch);
+                        
+                        let (timeout_duration, strict_timeout) = request_timeouts(adaptive_timeout_clone.as_deref(), timeout_duration, strict_timeout);
+                        
+                        // (Re)connect if the previous batch broke the connection
+                        if conn.is_none() {
+                            conn = match http::PipelinedConnection::connect(&uri, &tls, &connect_limiter_clone, local_addr, connect_timeout).await {
+                                Ok(c) => Some(c),
+                                Err(e) => {
+                                    tracing::warn!(kind = e.kind(), error = %e, "pipelined connect failed");
+                                    errors_clone.record(&e, batch);
+                                    if let Some(ref fail_fast) = fail_fast_clone {
+                                        fail_fast.trip(e);
+                                    }
+                                    None
+                                }
+                            };
+                        }
+                        
+                        if let Some(ref mut c) = conn {
+                            let sent_batch = c.send_batch(&request, batch, timeout_duration).await;
+                            drop(in_flight);
+                            match sent_batch {
+                                Ok(responses) => {
+                                    bytes_sent_clone.fetch_add(request.len() * batch, Ordering::Relaxed);
+                                    
+                                    // A server closing the connection leaves the rest of the batch unanswered
+                                    let unanswered = batch - responses.len();
+                                    if unanswered > 0 {
+                                        let e = BenchmarkError::Other(format!(
+                                            "Server closed the pipelined connection with {} requests unanswered",
+                                            unanswered,
+                                        ));
+                                        tracing::warn!(kind = e.kind(), error = %e, "pipelined batch cut short");
+                                        errors_clone.record(&e, unanswered);
+                                        if let Some(ref fail_fast) = fail_fast_clone {
+                                            fail_fast.trip(e);
+                                        }
+                                    }
+                                    
+                                    for response in responses {
+                                        bytes_received_clone.fetch_add(response.body_len, Ordering::Relaxed);
+                                        if let Err(e) = within_timeout(response.elapsed, strict_timeout) {
+                                            tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                                            errors_clone.record(&e, 1);
+                                            if let Some(ref fail_fast) = fail_fast_clone {
+                                                fail_fast.trip(e);
+                                            }
+                                            continue;
+                                        }
+                                        successful_clone.fetch_add(1, Ordering::Relaxed);
+                                        status_counts_clone.record(response.status);
+                                        errors_clone.record_status(response.status, &[]);
+                                        let sample = Sample {
+                                            offset: start_time.elapsed(),
+                                            latency: response.elapsed,
+                                            intended_latency: None,
+                                            ttfb: None,
+                                            messages: Vec::new(),
+                                            chunks: Vec::new(),
+                                            target: 0,
+                                            worker,
+                                            request_id: None,
+                                        };
+                                        let _ = tx_clone.send(sample).await;
+                                    }
+                                    if c.is_closed() {
+                                        conn = None;
+                                    }
+                                },
+                                Err(e) => {
+                                    tracing::warn!(kind = e.kind(), error = %e, batch, "pipelined batch failed");
+                                    errors_clone.record(&e, batch);
+                                    if let Some(ref fail_fast) = fail_fast_clone {
+                                        fail_fast.trip(e);
+                                    }
+                                    // Responses can't be matched to requests after an error
+                                    conn = None;
+                                }
+                            }
+                        }
+                        
+                        sent += batch;
+                        completed_clone.fetch_add(batch, Ordering::Relaxed);
+                        if let Some(ref tally) = worker_tally_clone {
+                            tally.complete(worker, batch);
+                        }
+                        
+                        progress_clone.inc(batch as u64);
+                    }
+                    return;
+                }
                 
-                for _ in 0..requests_per_worker {
-                    if Instant::now() >= stop_time {
+                for request in 0..requests_per_worker {
+                    // Start nothing new while the run is paused
+                    if let Some(ref pause) = pause_clone {
+                        pause.wait().await;
+                    }
+                    
+                    let intended = match pacer_clone {
+                        Some(ref pacer) => Some(pacer.wait().await),
+                        None => None,
+                    };
+                    
+                    if past_stop_time(stop_time, pause_clone.as_deref()) || fail_fast_clone.as_ref().is_some_and(|f| f.is_tripped()) {
                         break;
                     }
                     
-                    // TODO: Handle connection reuse when keep_alive is true
+                    let in_flight = in_flight_clone.enter(1);
+                    
+                    let (mut timeout_duration, strict_timeout) = request_timeouts(adaptive_timeout_clone.as_deref(), timeout_duration, strict_timeout);
+                    // A stream without `--max-chunks` also ends at the stop time
+                    if stream_response.is_some_and(|stream| stream.max_chunks.is_none()) {
+                        let left = time_left(stop_time, pause_clone.as_deref()).saturating_sub(STREAM_END_MARGIN);
+                        if left.is_zero() {
+                            break;
+                        }
+                        timeout_duration = timeout_duration.min(left);
+                    }
+                    
+                    let target_index = match (&rotation, &mix) {
+                        (Some(rotation), _) if keep_alive.is_some() => rotation.pinned(worker),
+                        (Some(rotation), _) => rotation.next(),
+                        (None, Some(mix)) => mix.pick(&mut rng),
+                        (None, None) => rng.random_range(0..targets.len()),
+                    };
+                    let target = &targets[target_index];
+                    let random = random_body.map(|size| config::random_payload(&mut rng, size));
+                    // A `--url-file` line may bring its own method and body
+                    let endpoint = mix.as_ref().map(|mix| &mix.endpoints[target_index]);
+                    let request_method = endpoint.and_then(|e| e.method.as_deref()).unwrap_or(&method);
+                    let request_body = endpoint.and_then(|e| e.body.as_deref())
+                        .map(str::as_bytes)
+                        .or(random.as_deref())
+                        .or(body.as_deref());
+                    
+                    let request_id = request_ids.as_ref().map(|ids| ids.next());
+                    request_headers.truncate(configured_headers);
+                    if let (Some(ids), Some(id)) = (&request_ids, &request_id) {
+                        request_headers.push((ids.header.clone(), id.clone()));
+                    }
+                    // The timeout starts when the request is sent, so all of it remains
+                    if let Some(ref name) = deadline_header {
+                        request_headers.push((name.clone(), timeout_duration.as_millis().to_string()));
+                    }
+                    
+                    // A fresh random value per request, so no cache can answer it
+                    let mut target = Cow::Borrowed(target);
+                    if let Some((ref kind, ref mut cache_bust_rng)) = cache_bust {
+                        let value = format!("{:016x}", cache_bust_rng.random::<u64>());
+                        match kind {
+                            CacheBust::Query => {
+                                target = Cow::Owned(http::with_query_param(&target, CACHE_BUST_PARAM, &value));
+                            },
+                            CacheBust::Header(name) => request_headers.push((name.clone(), value)),
+                        }
+                    }
+                    
+                    // `--connect-only` times the connection and sends nothing
+                    if connect_only {
+                        let connected = http::connect_only(&target, &tls, http2, &connect_limiter_clone, local_addr, connect_timeout).await;
+                        drop(in_flight);
+                        match connected {
+                            Ok(elapsed) => {
+                                successful_clone.fetch_add(1, Ordering::Relaxed);
+                                let sample = Sample {
+                                    offset: start_time.elapsed(),
+                                    latency: elapsed,
+                                    intended_latency: intended.map(|t| t.elapsed()),
+                                    ttfb: None,
+                                    messages: Vec::new(),
+                                    chunks: Vec::new(),
+                                    target: target_index,
+                                    worker,
+                                    request_id,
+                                };
+                                let _ = tx_clone.send(sample).await;
+                            },
+                            Err(e) => {
+                                tracing::warn!(kind = e.kind(), error = %e, "connect failed");
+                                errors_clone.record(&e, 1);
+                                if let Some(ref fail_fast) = fail_fast_clone {
+                                    fail_fast.trip(e);
+                                }
+                            }
+                        }
+                        completed_clone.fetch_add(1, Ordering::Relaxed);
+                        if let Some(ref tally) = worker_tally_clone {
+                            tally.complete(worker, 1);
+                        }
+                        if let Some(ref rotation) = rotation {
+                            rotation.complete(target_index);
+                        }
+                        if let Some(ref mix) = mix {
+                            mix.complete(target_index);
+                        }
+                        
+                        progress_clone.inc(1);
+                        continue;
+                    }
                     
                     // Send request
-                    match http::send_request(
-                        &uri,
-                        &method,
-                        &headers,
-                        body.as_deref(),
+                    let response = http::send_request(
+                        &target,
+                        request_method,
+                        &request_headers,
+                        host.as_deref(),
+                        &tls,
+                        request_body,
+                        stream_body_size,
+                        &connect_limiter_clone,
+                        local_addr,
+                        connect_timeout,
                         timeout_duration,
-                        false, // use HTTP/1.1
-                    ).await {
-                        Ok((_status, body, elapsed)) => {
+                        http2,
+                        keep_alive.as_mut(),
+                        no_body,
+                        stream_response,
+                        server_timing_clone.is_some(),
+                        tracer_clone.as_deref(),
+                    ).instrument(tracing::debug_span!("request", n = request)).await;
+                    let response = match response {
+                        Ok(response) => http::check_response(&expectations, response, offload_validation_above).await,
+                        Err(e) => Err(e),
+                    };
+                    // Waiting for the collector isn't time in flight
+                    drop(in_flight);
+                    match response.and_then(|response| within_timeout(response.elapsed, strict_timeout).map(|_| response)) {
+                        Ok(response) => {
                             successful_clone.fetch_add(1, Ordering::Relaxed);
-                            bytes_received_clone.fetch_add(body.len(), Ordering::Relaxed);
+                            cache_counts_clone[response.cache as usize].fetch_add(1, Ordering::Relaxed);
+                            if let Some(ref server_timing) = server_timing_clone {
+                                server_timing.record(worker, &response.server_timing);
+                            }
+                            status_counts_clone.record(response.status);
+                            errors_clone.record_status(response.status, &response.body);
+                            // Without the body only the headers were received
+                            let received = if no_body { response.header_len } else { response.body.len() };
+                            bytes_received_clone.fetch_add(received, Ordering::Relaxed);
                             
-                            if let Some(body_size) = body.len().checked_add(
-                                headers.iter().fold(0, |acc, (k, v)| acc + k.len() + v.len())
+                            if let Some(request_size) = response.body_sent.checked_add(
+                                request_headers.iter().fold(0, |acc, (k, v)| acc + k.len() + v.len())
                             ) {
-                                bytes_sent_clone.fetch_add(body_size, Ordering::Relaxed);
+                                bytes_sent_clone.fetch_add(request_size, Ordering::Relaxed);
                             }
                             
-                            let _ = tx_clone.send(elapsed).await;
+                            let sample = Sample {
+                                offset: start_time.elapsed(),
+                                latency: response.elapsed,
+                                intended_latency: intended.map(|t| t.elapsed()),
+                                ttfb: response.chunks.first().copied(),
+                                messages: Vec::new(),
+                                chunks: response.chunks,
+                                target: target_index,
+                                worker,
+                                request_id,
+                            };
+                            let _ = tx_clone.send(sample).await;
                         },
-                        Err(_) => {
-                            // Error handling is already done in the http module
+                        Err(e) => {
+                            tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                            errors_clone.record(&e, 1);
+                            if let Some(ref fail_fast) = fail_fast_clone {
+                                fail_fast.trip(e);
+                            }
                         }
                     }
                     
                     completed_clone.fetch_add(1, Ordering::Relaxed);
-                    
-                    if let Some(ref bar) = progress_clone {
-                        bar.inc(1);
+                    if let Some(ref tally) = worker_tally_clone {
+                        tally.complete(worker, 1);
                     }
+                    if let Some(ref rotation) = rotation {
+                        rotation.complete(target_index);
+                    }
+                    if let Some(ref mix) = mix {
+                        mix.complete(target_index);
+                    }
+                    
+                    progress_clone.inc(1);
                 }
-            });
+            }.instrument(tracing::debug_span!("worker", id = worker)));
         }
         
         // Drop the original sender so the channel can close when all workers are done
         drop(tx);
         
-        // Wait for all workers to complete or timeout
-        while (Instant::now() < stop_time) && (!set.is_empty()) {
-            tokio::select! {
-                _ = sleep(Duration::from_millis(100)) => {
-                    // Just a timeout to check if we've reached the stop time
-                }
-                _ = set.join_next() => {
-                    // A worker has completed
-                }
-            }
-        }
-        
-        // Cancel any remaining tasks
-        set.abort_all();
-        
-        // Collect all response times
-        let mut response_times = Vec::new();
-        while let Some(time) = rx.recv().await {
-            response_times.push(time);
+        let Collected { samples, summary, interrupted, latency_spike } = Collector {
+            start_time,
+            stop_time,
+            pause: self.pause.as_deref(),
+            cancel: self.cancel.as_deref(),
+            correct_co: self.config.correct_co,
+            summary: self.config.low_memory.then(|| SampleSummary::new(rate.is_some())),
+            spike_breaker: self.config.abort_p99_above
+                .map(|threshold| SpikeBreaker::new(threshold, self.config.timeout, errors.clone())),
+            adaptive_timeout: adaptive_timeout.as_deref(),
+            fail_fast: fail_fast.as_deref(),
+            progress: &progress,
+            throughput: self.throughput.clone(),
+            raw_output: self.raw_output.as_ref(),
+            completed: &completed_requests,
+        }.run(&mut set, &mut rx).await?;
+        if let Some(tracer) = tracer {
+            tracer.finish().await?;
         }
         
-        if let Some(bar) = progress {
-            bar.finish_and_clear();
+        if let Some(error) = fail_fast.and_then(|f| f.take_error()) {
+            return Err(error);
         }
         
-        // Sort response times for percentiles
-        response_times.sort();
+        let stop_reason = stop_reason(
+            interrupted,
+            self.cancel.as_deref(),
+            latency_spike.is_some(),
+            self.config.requests,
+            completed_requests.load(Ordering::Relaxed),
+        );
         
-        // Calculate statistics
-        let total_time = start_time.elapsed();
-        let total_requests = completed_requests.load(Ordering::Relaxed);
-        let successful = successful_requests.load(Ordering::Relaxed);
-        let failed = total_requests.saturating_sub(successful);
+        let cert_not_after = tls.handshakes.cert_not_after().filter(|_| self.config.check_cert);
         
-        let avg_time = if response_times.is_empty() {
-            Duration::from_secs(0)
-        } else {
-            response_times.iter().fold(Duration::from_secs(0), |acc, &x| acc + x) 
-                / response_times.len() as u32
+        let records = if self.keep_samples { sample_records(&samples) } else { Vec::new() };
+        let ttfb = match summary {
+            Some(ref summary) => summary.ttfb(),
+            None => {
+                let mut ttfbs: Vec<Duration> = samples.iter().filter_map(|s| s.ttfb).collect();
+                stats::sort_latencies(&mut ttfbs);
+                (!ttfbs.is_empty()).then(|| stats::latency_percentiles(&ttfbs))
+            },
         };
+        let streaming = self.config.stream_response.map(|stream| streaming_stats(&samples, stream));
+        let per_host = rotation.as_ref()
+            .filter(|_| self.config.per_host)
+            .map(|rotation| rotation.stats(&self.config.hosts, &samples, self.config.correct_co));
+        let per_method = mix.as_ref()
+            .filter(|_| self.config.per_method)
+            .map(|mix| mix.method_stats(&self.config.method, &samples, self.config.correct_co));
+        let slowest_requests = request_ids.is_some()
+            .then(|| slowest_requests(&samples, self.config.correct_co));
         
-        let min_time = response_times.first().cloned().unwrap_or_else(|| Duration::from_secs(0));
-        let max_time = response_times.last().cloned().unwrap_or_else(|| Duration::from_secs(0));
-        
-        let p50 = percentile(&response_times, 0.5);
-        let p90 = percentile(&response_times, 0.9);
-        let p95 = percentile(&response_times, 0.95);
-        let p99 = percentile(&response_times, 0.99);
+        let (per_worker_requests, per_worker_p99) = worker_tally.as_ref()
+            .map(|tally| tally.stats(&samples, self.config.correct_co))
+            .unzip();
+        let worker_imbalance = per_worker_requests.as_deref().and_then(WorkerImbalance::from_requests);
         
-        let requests_per_second = if total_time.as_secs_f64() > 0.0 {
-            total_requests as f64 / total_time.as_secs_f64()
-        } else {
-            0.0
+        // Calculate statistics from the headline response times, sorted for
+        // percentiles, or from their summary under `--low-memory`
+        // Time spent paused counts towards neither the duration nor the rate
+        let total_time = start_time.elapsed().saturating_sub(paused_time(self.pause.as_deref()));
+        let completed = completed_requests.load(Ordering::Relaxed);
+        let successful = successful_requests.load(Ordering::Relaxed);
+        let achieved_concurrency = in_flight.stats(total_time);
+        let rate_ramp = self.config.rate_ramp
+            .map(|ramp| rate_ramp_stats(ramp, self.config.duration, total_time, &samples, self.config.correct_co));
+        let (stats, latency_correction) = match summary {
+            Some(summary) => summary.statistics(
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ),
+            None => aggregate_samples(
+                samples,
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ).await,
         };
         
         Ok(BenchmarkReport {
             target: self.config.url.clone(),
-            protocol: "HTTP".to_string(),
+            protocol: if self.config.http2 { "HTTP/2" } else { "HTTP" }.to_string(),
             concurrency: self.config.concurrency,
-            total_requests,
-            successful_requests: successful,
-            failed_requests: failed,
-            total_time,
-            requests_per_second,
-            avg_response_time: avg_time,
-            min_response_time: min_time,
-            max_response_time: max_time,
-            p50_response_time: p50,
-            p90_response_time: p90,
-            p95_response_time: p95,
-            p99_response_time: p99,
+            total_requests: stats.total_requests,
+            successful_requests: stats.successful_requests,
+            failed_requests: stats.failed_requests,
+            total_time: stats.total_time,
+            requests_per_second: stats.requests_per_second,
+            avg_response_time: stats.avg,
+            min_response_time: stats.min,
+            max_response_time: stats.max,
+            p50_response_time: stats.p50,
+            p90_response_time: stats.p90,
+            p95_response_time: stats.p95,
+            p99_response_time: stats.p99,
             bytes_sent: bytes_sent.load(Ordering::Relaxed) as u64,
             bytes_received: bytes_received.load(Ordering::Relaxed) as u64,
+            latency_correction,
+            connection_rate: connection_rate(&connect_limiter),
+            connection_strategy: self.connection_strategy(),
+            connections_opened: connect_limiter.opened(),
+            connects_per_second: self.config.connect_only.then(|| per_second(connect_limiter.opened(), total_time)),
+            stale_reconnects: self.config.is_keep_alive()
+                .then(|| stale_reconnects.load(Ordering::Relaxed) as u64),
+            tls_verification: targets.iter()
+                .any(|t| t.scheme_str() == Some("https"))
+                .then_some(!self.config.insecure),
+            tls_handshakes: targets.iter()
+                .any(|t| t.scheme_str() == Some("https"))
+                .then(|| TlsHandshakes {
+                    resumption: self.config.tls_resumption,
+                    full: tls.handshakes.full(),
+                    resumed: tls.handshakes.resumed(),
+                }),
+            cert_not_after: cert_not_after.map(humantime::format_rfc3339_seconds).map(|t| t.to_string()),
+            cert_days_remaining: cert_not_after.map(days_until),
+            cert_min_days: self.config.check_cert.then_some(self.config.cert_min_days).flatten(),
+            stop_reason,
+            latency_spike,
+            rate_ramp,
+            achieved_concurrency: Some(achieved_concurrency),
+            samples: records,
+            cache_stats: self.config.cache_stats.then(|| CacheStats {
+                hits: cache_counts[CacheStatus::Hit as usize].load(Ordering::Relaxed) as u64,
+                misses: cache_counts[CacheStatus::Miss as usize].load(Ordering::Relaxed) as u64,
+                unknown: cache_counts[CacheStatus::Unknown as usize].load(Ordering::Relaxed) as u64,
+            }),
+            server_timing: server_timing.as_ref().map(|server_timing| server_timing.stats()),
+            timeout_cutoff: None,
+            adaptive_timeout: adaptive_timeout.as_ref().map(|adaptive| adaptive.current()),
+            warmup: None,
+            ttfb,
+            per_message: None,
+            streaming,
+            per_host,
+            per_method,
+            per_worker_requests,
+            per_worker_p99,
+            worker_imbalance,
+            slowest_requests,
+            errors: errors.summary(),
+            error_details: errors.details(),
+            status_codes: Some(status_counts.summary()),
         })
     }
 }
 
 pub struct TcpRunner {
     config: TcpConfig,
+    throughput: Option<mpsc::UnboundedSender<ThroughputPoint>>,
+    progress: Option<Arc<AtomicUsize>>,
+    pause: Option<Arc<PauseFlag>>,
+    cancel: Option<Arc<CancelFlag>>,
+    protocol: Arc<dyn Protocol>,
+    raw_output: Option<RawOutput>,
+    trace: Option<TraceOptions>,
+    keep_samples: bool,
 }
 
 impl TcpRunner {
     pub fn new(config: TcpConfig) -> Self {
-        TcpRunner { config }
+        TcpRunner { config, throughput: None, progress: None, pause: None, cancel: None, raw_output: None, trace: None, keep_samples: false, protocol: Arc::new(protocol::Echo) }
+    }
+    
+    /// The address, or the `--hosts-file` hosts, named in the report
+    pub fn target(&self) -> String {
+        match self.config.hosts.as_slice() {
+            [] => self.config.address.clone(),
+            [host] => host.clone(),
+            [first, rest @ ..] => format!("{} and {} more", first, rest.len()),
+        }
+    }
+    
+    /// Sends requests/sec once per second while the benchmark runs
+    pub fn with_throughput(mut self, tx: mpsc::UnboundedSender<ThroughputPoint>) -> Self {
+        self.throughput = Some(tx);
+        self
+    }
+    
+    /// Streams completed requests to `output` while the benchmark runs
+    pub fn with_raw_output(mut self, output: RawOutput) -> Self {
+        self.raw_output = Some(output);
+        self
+    }
+    
+    /// Keeps every completed request on the report's `samples`
+    pub fn with_samples(mut self) -> Self {
+        self.keep_samples = true;
+        self
+    }
+    
+    /// Records the bytes of each request in a `--trace-file`
+    pub fn with_trace(mut self, trace: TraceOptions) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    /// Counts completed requests in `completed` while the benchmark runs,
+    /// for a caller showing progress
+    pub fn with_progress(mut self, completed: Arc<AtomicUsize>) -> Self {
+        self.progress = Some(completed);
+        self
+    }
+    
+    /// Lets the caller pause and resume the run through `pause`
+    pub fn with_pause(mut self, pause: Arc<PauseFlag>) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+    
+    /// Ends the run early, with the report so far, once `cancel` is set
+    pub fn with_cancel(mut self, cancel: Arc<CancelFlag>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    
+    /// Replaces the default `protocol::Echo` exchange with a custom protocol
+    pub fn with_protocol(mut self, protocol: Box<dyn Protocol>) -> Self {
+        self.protocol = Arc::from(protocol);
+        self
     }
     
     pub async fn run(&self) -> Result<BenchmarkReport, BenchmarkError> {
-        println!("Starting TCP benchmark for {} with {} connections...", self.config.address, self.config.concurrency);
-        
-        // Create progress bar
-        let progress = if self.config.requests > 0 {
-            let bar = ProgressBar::new(self.config.requests as u64);
-            bar.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {percent}% ({eta})")
-                    .unwrap()
-                    .progress_chars("##-")
-            );
-            Some(bar)
-        } else {
-            None
-        };
+        config::check_limits(self.config.requests, self.config.duration)?;
+        // Compiled once and shared by every worker
+        let expects = Arc::new(config::compile_expects(&self.config.payloads, &self.config.expects)?);
+        // A family mismatch would fail every request, so it fails the run up front
+        if let Some(source) = self.config.proxy_source {
+            let addresses = if self.config.hosts.is_empty() { std::slice::from_ref(&self.config.address) } else { &self.config.hosts };
+            for address in addresses {
+                tcp::check_proxy_source(address, source, self.config.local_addr).await?;
+            }
+        }
+        eprintln!("Starting TCP benchmark for {} with {} connections...", self.target(), self.config.concurrency);
+        
+        let progress = progress_bar(self.config.requests);
         
         let concurrency = self.config.concurrency;
         let requests_per_worker = if self.config.requests > 0 {
@@ -239,177 +2345,693 @@ impl TcpRunner {
             usize::MAX // run forever until duration is reached
         };
         
+        let tracer = self.trace.as_ref().map(Tracer::create).transpose()?.map(Arc::new);
         let start_time = Instant::now();
-        let stop_time = start_time + self.config.duration;
+        let stop_time = stop_time(start_time, self.config.duration);
+        let rate = pacing_rate(self.config.rate, self.config.rate_ramp);
+        let pacer = rate.map(|rate| Arc::new(
+            Pacer::new(start_time, rate, self.config.arrival, self.config.seed)
+                .with_ramp(self.config.rate_ramp, self.config.duration)
+                .with_pause(self.pause.clone())
+        ));
+        let connect_limiter = Arc::new(ConnectLimiter::new(self.config.connect_rate));
         
         // Shared counters for all workers
-        let completed_requests = Arc::new(AtomicUsize::new(0));
+        let completed_requests = self.progress.clone().unwrap_or_default();
         let successful_requests = Arc::new(AtomicUsize::new(0));
         let bytes_sent = Arc::new(AtomicUsize::new(0));
         let bytes_received = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(ErrorTally::new(self.config.only_errors));
+        let adaptive_timeout = self.config.adaptive_timeout
+            .map(|multiple| Arc::new(AdaptiveTimeout::new(self.config.timeout, multiple, errors.clone())));
+        let fail_fast = self.config.fail_fast.then(|| Arc::new(FailFast::default()));
+        let in_flight = Arc::new(InFlight::new());
+        let worker_tally = self.config.per_worker_stats.then(|| Arc::new(WorkerTally::new(concurrency)));
+        
+        // Requests go to `address`, or round-robin to the `--hosts-file` hosts
+        let targets = if self.config.hosts.is_empty() {
+            Arc::new(vec![self.config.address.clone()])
+        } else {
+            Arc::new(self.config.hosts.clone())
+        };
+        let rotation = (!self.config.hosts.is_empty()).then(|| Arc::new(HostRotation::new(targets.len())));
         
         // Channel for response times
-        let (tx, mut rx) = mpsc::channel::<Duration>(10000);
+        let (tx, mut rx) = mpsc::channel::<Sample>(10000);
         
         // Spawn worker tasks
         let mut set = JoinSet::new();
         
-        for _ in 0..concurrency {
-            let address = self.config.address.clone();
-            let data = self.config.data.clone();
-            let expect = self.config.expect.clone();
+        for worker in 0..concurrency {
+            let targets = targets.clone();
+            let rotation = rotation.clone();
+            let payloads = self.config.payloads.clone();
+            let expects = expects.clone();
+            let setup = self.config.setup.clone();
             let timeout_duration = self.config.timeout;
+            let connect_timeout = self.config.connect_timeout;
+            let local_addr = self.config.local_addr;
+            let repeat = self.config.repeat;
+            let connect_only = self.config.connect_only;
+            let response_size = self.config.response_size;
+            let half_close = self.config.half_close;
+            let offload_above = self.config.offload_validation_above;
+            let require_non_empty = self.config.require_non_empty;
+            let expect_length = self.config.expect_length;
+            let proxy_protocol = self.config.proxy_protocol;
+            let proxy_source = self.config.proxy_source;
+            let strict_timeout = self.config.strict_timeout;
             let completed_clone = completed_requests.clone();
             let successful_clone = successful_requests.clone();
             let bytes_sent_clone = bytes_sent.clone();
             let bytes_received_clone = bytes_received.clone();
+            let errors_clone = errors.clone();
+            let fail_fast_clone = fail_fast.clone();
+            let in_flight_clone = in_flight.clone();
+            let worker_tally_clone = worker_tally.clone();
             let tx_clone = tx.clone();
             let progress_clone = progress.clone();
+            let pause_clone = self.pause.clone();
+            let tracer_clone = tracer.clone();
+            let pacer_clone = pacer.clone();
+            let connect_limiter_clone = connect_limiter.clone();
+            let adaptive_timeout_clone = adaptive_timeout.clone();
+            let protocol_clone = self.protocol.clone();
             
             set.spawn(async move {
-                for _ in 0..requests_per_worker {
-                    if Instant::now() >= stop_time {
+                for request in 0..requests_per_worker {
+                    // Start nothing new while the run is paused
+                    if let Some(ref pause) = pause_clone {
+                        pause.wait().await;
+                    }
+                    
+                    let intended = match pacer_clone {
+                        Some(ref pacer) => Some(pacer.wait().await),
+                        None => None,
+                    };
+                    
+                    if past_stop_time(stop_time, pause_clone.as_deref()) || fail_fast_clone.as_ref().is_some_and(|f| f.is_tripped()) {
                         break;
                     }
                     
-                    // Send TCP request
-                    match tcp::send_tcp(
-                        &address,
-                        data.as_deref(),
-                        expect.as_deref(),
-                        timeout_duration,
-                        BUFFER_SIZE,
-                    ).await {
-                        Ok((response, elapsed)) => {
+                    let in_flight = in_flight_clone.enter(1);
+                    
+                    let (timeout_duration, strict_timeout) = request_timeouts(adaptive_timeout_clone.as_deref(), timeout_duration, strict_timeout);
+                    
+                    // Workers start at different payloads so all of them are in flight
+                    let (data, expect) = config::payload_for(&payloads, &expects, worker + request);
+                    
+                    let target = rotation.as_ref().map_or(0, |rotation| rotation.next());
+                    
+                    // Send TCP request, or with `--connect-only` only connect
+                    let exchanged = if connect_only {
+                        tcp::connect_only(&targets[target], &connect_limiter_clone, local_addr, connect_timeout).await
+                            .map(|elapsed| protocol::Exchange {
+                                response: protocol::Response { data: Vec::new(), first_byte: None, elapsed },
+                                messages: Vec::new(),
+                                bytes_sent: 0,
+                            })
+                    } else {
+                        tcp::send_tcp(
+                            &targets[target],
+                            &setup,
+                            protocol_clone.as_ref(),
+                            protocol::Request {
+                                data,
+                                expect,
+                                expect_length,
+                                require_non_empty,
+                                timeout: timeout_duration,
+                                buffer_size: BUFFER_SIZE,
+                                response_size,
+                                half_close,
+                                offload_above,
+                            },
+                            &connect_limiter_clone,
+                            local_addr,
+                            connect_timeout,
+                            repeat,
+                            proxy_protocol,
+                            proxy_source,
+                            tracer_clone.as_deref(),
+                        ).instrument(tracing::debug_span!("request", n = request)).await
+                    };
+                    drop(in_flight);
+                    match exchanged.and_then(|exchanged| within_timeout(exchanged.response.elapsed, strict_timeout).map(|_| exchanged)) {
+                        Ok(protocol::Exchange { response, messages, bytes_sent }) => {
                             successful_clone.fetch_add(1, Ordering::Relaxed);
-                            bytes_received_clone.fetch_add(response.len(), Ordering::Relaxed);
-                            
-                            if let Some(ref d) = data {
-                                bytes_sent_clone.fetch_add(d.len(), Ordering::Relaxed);
-                            }
+                            bytes_received_clone.fetch_add(response.data.len(), Ordering::Relaxed);
+                            bytes_sent_clone.fetch_add(bytes_sent, Ordering::Relaxed);
                             
-                            let _ = tx_clone.send(elapsed).await;
+                            let sample = Sample {
+                                offset: start_time.elapsed(),
+                                latency: response.elapsed,
+                                intended_latency: intended.map(|t| t.elapsed()),
+                                ttfb: response.first_byte,
+                                messages,
+                                chunks: Vec::new(),
+                                target,
+                                worker,
+                                request_id: None,
+                            };
+                            let _ = tx_clone.send(sample).await;
                         },
-                        Err(_) => {
-                            // Error handling is already done in the tcp module
+                        Err(e) => {
+                            tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                            errors_clone.record(&e, 1);
+                            if let Some(ref fail_fast) = fail_fast_clone {
+                                fail_fast.trip(e);
+                            }
                         }
                     }
                     
                     completed_clone.fetch_add(1, Ordering::Relaxed);
-                    
-                    if let Some(ref bar) = progress_clone {
-                        bar.inc(1);
+                    if let Some(ref tally) = worker_tally_clone {
+                        tally.complete(worker, 1);
+                    }
+                    if let Some(ref rotation) = rotation {
+                        rotation.complete(target);
                     }
+                    
+                    progress_clone.inc(1);
                 }
-            });
+            }.instrument(tracing::debug_span!("worker", id = worker)));
         }
         
         // Drop the original sender so the channel can close when all workers are done
         drop(tx);
         
-        // Wait for all workers to complete or timeout
-        while (Instant::now() < stop_time) && (!set.is_empty()) {
-            tokio::select! {
-                _ = sleep(Duration::from_millis(100)) => {
-                    // Just a timeout to check if we've reached the stop time
-                }
-                _ = set.join_next() => {
-                    // A worker has completed
-                }
-            }
+        let Collected { samples, summary, interrupted, latency_spike } = Collector {
+            start_time,
+            stop_time,
+            pause: self.pause.as_deref(),
+            cancel: self.cancel.as_deref(),
+            correct_co: self.config.correct_co,
+            summary: self.config.low_memory.then(|| SampleSummary::new(rate.is_some())),
+            spike_breaker: self.config.abort_p99_above
+                .map(|threshold| SpikeBreaker::new(threshold, self.config.timeout, errors.clone())),
+            adaptive_timeout: adaptive_timeout.as_deref(),
+            fail_fast: fail_fast.as_deref(),
+            progress: &progress,
+            throughput: self.throughput.clone(),
+            raw_output: self.raw_output.as_ref(),
+            completed: &completed_requests,
+        }.run(&mut set, &mut rx).await?;
+        if let Some(tracer) = tracer {
+            tracer.finish().await?;
         }
         
-        // Cancel any remaining tasks
-        set.abort_all();
-        
-        // Collect all response times
-        let mut response_times = Vec::new();
-        while let Some(time) = rx.recv().await {
-            response_times.push(time);
+        if let Some(error) = fail_fast.and_then(|f| f.take_error()) {
+            return Err(error);
         }
         
-        if let Some(bar) = progress {
-            bar.finish_and_clear();
-        }
+        let stop_reason = stop_reason(
+            interrupted,
+            self.cancel.as_deref(),
+            latency_spike.is_some(),
+            self.config.requests,
+            completed_requests.load(Ordering::Relaxed),
+        );
         
-        // Sort response times for percentiles
-        response_times.sort();
+        let records = if self.keep_samples { sample_records(&samples) } else { Vec::new() };
+        let per_message = per_message(&samples, self.config.repeat);
+        let per_host = rotation.as_ref()
+            .filter(|_| self.config.per_host)
+            .map(|rotation| rotation.stats(&self.config.hosts, &samples, self.config.correct_co));
         
-        // Calculate statistics
-        let total_time = start_time.elapsed();
-        let total_requests = completed_requests.load(Ordering::Relaxed);
+        let ttfb = match summary {
+            Some(ref summary) => summary.ttfb(),
+            None => {
+                let mut ttfbs: Vec<Duration> = samples.iter().filter_map(|s| s.ttfb).collect();
+                stats::sort_latencies(&mut ttfbs);
+                (!ttfbs.is_empty()).then(|| stats::latency_percentiles(&ttfbs))
+            },
+        };
+        
+        let (per_worker_requests, per_worker_p99) = worker_tally.as_ref()
+            .map(|tally| tally.stats(&samples, self.config.correct_co))
+            .unzip();
+        let worker_imbalance = per_worker_requests.as_deref().and_then(WorkerImbalance::from_requests);
+        
+        // Calculate statistics from the headline response times, sorted for
+        // percentiles, or from their summary under `--low-memory`
+        // Time spent paused counts towards neither the duration nor the rate
+        let total_time = start_time.elapsed().saturating_sub(paused_time(self.pause.as_deref()));
+        let completed = completed_requests.load(Ordering::Relaxed);
         let successful = successful_requests.load(Ordering::Relaxed);
-        let failed = total_requests.saturating_sub(successful);
+        let achieved_concurrency = in_flight.stats(total_time);
+        let rate_ramp = self.config.rate_ramp
+            .map(|ramp| rate_ramp_stats(ramp, self.config.duration, total_time, &samples, self.config.correct_co));
+        let (stats, latency_correction) = match summary {
+            Some(summary) => summary.statistics(
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ),
+            None => aggregate_samples(
+                samples,
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ).await,
+        };
+        
+        Ok(BenchmarkReport {
+            target: self.target(),
+            protocol: "TCP".to_string(),
+            concurrency: self.config.concurrency,
+            total_requests: stats.total_requests,
+            successful_requests: stats.successful_requests,
+            failed_requests: stats.failed_requests,
+            total_time: stats.total_time,
+            requests_per_second: stats.requests_per_second,
+            avg_response_time: stats.avg,
+            min_response_time: stats.min,
+            max_response_time: stats.max,
+            p50_response_time: stats.p50,
+            p90_response_time: stats.p90,
+            p95_response_time: stats.p95,
+            p99_response_time: stats.p99,
+            bytes_sent: bytes_sent.load(Ordering::Relaxed) as u64,
+            bytes_received: bytes_received.load(Ordering::Relaxed) as u64,
+            latency_correction,
+            connection_rate: connection_rate(&connect_limiter),
+            connection_strategy: if self.config.connect_only { CONNECT_ONLY } else { NEW_CONNECTION_PER_REQUEST }.to_string(),
+            connections_opened: connect_limiter.opened(),
+            connects_per_second: self.config.connect_only.then(|| per_second(connect_limiter.opened(), total_time)),
+            stale_reconnects: None,
+            tls_verification: None,
+            tls_handshakes: None,
+            cert_not_after: None,
+            cert_days_remaining: None,
+            cert_min_days: None,
+            stop_reason,
+            latency_spike,
+            rate_ramp,
+            achieved_concurrency: Some(achieved_concurrency),
+            samples: records,
+            cache_stats: None,
+            server_timing: None,
+            timeout_cutoff: None,
+            adaptive_timeout: adaptive_timeout.as_ref().map(|adaptive| adaptive.current()),
+            warmup: None,
+            ttfb,
+            per_message,
+            streaming: None,
+            per_host,
+            per_method: None,
+            per_worker_requests,
+            per_worker_p99,
+            worker_imbalance,
+            slowest_requests: None,
+            errors: errors.summary(),
+            error_details: errors.details(),
+            status_codes: None,
+        })
+    }
+}
+
+pub struct GrpcRunner {
+    config: GrpcConfig,
+    throughput: Option<mpsc::UnboundedSender<ThroughputPoint>>,
+    raw_output: Option<RawOutput>,
+    cancel: Option<Arc<CancelFlag>>,
+    keep_samples: bool,
+}
+
+impl GrpcRunner {
+    pub fn new(config: GrpcConfig) -> Self {
+        GrpcRunner { config, throughput: None, raw_output: None, cancel: None, keep_samples: false }
+    }
+    
+    /// Ends the run early, with the report so far, once `cancel` is set
+    pub fn with_cancel(mut self, cancel: Arc<CancelFlag>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    
+    /// Sends requests/sec once per second while the benchmark runs
+    pub fn with_throughput(mut self, tx: mpsc::UnboundedSender<ThroughputPoint>) -> Self {
+        self.throughput = Some(tx);
+        self
+    }
+    
+    /// Streams completed requests to `output` while the benchmark runs
+    pub fn with_raw_output(mut self, output: RawOutput) -> Self {
+        self.raw_output = Some(output);
+        self
+    }
+    
+    /// Keeps every completed request on the report's `samples`
+    pub fn with_samples(mut self) -> Self {
+        self.keep_samples = true;
+        self
+    }
+    
+    pub async fn run(&self) -> Result<BenchmarkReport, BenchmarkError> {
+        config::check_limits(self.config.requests, self.config.duration)?;
+        eprintln!("Starting gRPC benchmark for {} with {} connections...", self.config.url, self.config.concurrency);
+        
+        let progress = progress_bar(self.config.requests);
         
-        let avg_time = if response_times.is_empty() {
-            Duration::from_secs(0)
+        let concurrency = self.config.concurrency;
+        let requests_per_worker = if self.config.requests > 0 {
+            self.config.requests.div_ceil(concurrency) // ceiling division
         } else {
-            response_times.iter().fold(Duration::from_secs(0), |acc, &x| acc + x) 
-                / response_times.len() as u32
+            usize::MAX // run forever until duration is reached
         };
         
-        let min_time = response_times.first().cloned().unwrap_or_else(|| Duration::from_secs(0));
-        let max_time = response_times.last().cloned().unwrap_or_else(|| Duration::from_secs(0));
+        let start_time = Instant::now();
+        let stop_time = stop_time(start_time, self.config.duration);
+        let rate = pacing_rate(self.config.rate, self.config.rate_ramp);
+        let pacer = rate.map(|rate| Arc::new(
+            Pacer::new(start_time, rate, self.config.arrival, self.config.seed)
+                .with_ramp(self.config.rate_ramp, self.config.duration)
+        ));
+        let connect_limiter = Arc::new(ConnectLimiter::new(self.config.connect_rate));
+        
+        // Shared counters for all workers
+        let completed_requests = Arc::new(AtomicUsize::new(0));
+        let successful_requests = Arc::new(AtomicUsize::new(0));
+        let bytes_sent = Arc::new(AtomicUsize::new(0));
+        let bytes_received = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(ErrorTally::new(self.config.only_errors));
+        let adaptive_timeout = self.config.adaptive_timeout
+            .map(|multiple| Arc::new(AdaptiveTimeout::new(self.config.timeout, multiple, errors.clone())));
+        let fail_fast = self.config.fail_fast.then(|| Arc::new(FailFast::default()));
+        let in_flight = Arc::new(InFlight::new());
+        let worker_tally = self.config.per_worker_stats.then(|| Arc::new(WorkerTally::new(concurrency)));
+        
+        // Channel for response times
+        let (tx, mut rx) = mpsc::channel::<Sample>(10000);
         
-        let p50 = percentile(&response_times, 0.5);
-        let p90 = percentile(&response_times, 0.9);
-        let p95 = percentile(&response_times, 0.95);
-        let p99 = percentile(&response_times, 0.99);
+        // Spawn worker tasks
+        let mut set = JoinSet::new();
         
-        let requests_per_second = if total_time.as_secs_f64() > 0.0 {
-            total_requests as f64 / total_time.as_secs_f64()
-        } else {
-            0.0
+        let uri: Uri = self.config.url.parse()
+            .map_err(|_| BenchmarkError::Config(format!("Invalid URL: {}", self.config.url)))?;
+        let message = Arc::new(grpc::frame_message(&self.config.message));
+        
+        for worker in 0..concurrency {
+            let uri = uri.clone();
+            let headers = self.config.headers.clone();
+            let message = message.clone();
+            let timeout_duration = self.config.timeout;
+            let connect_timeout = self.config.connect_timeout;
+            let local_addr = self.config.local_addr;
+            let strict_timeout = self.config.strict_timeout;
+            let completed_clone = completed_requests.clone();
+            let successful_clone = successful_requests.clone();
+            let bytes_sent_clone = bytes_sent.clone();
+            let bytes_received_clone = bytes_received.clone();
+            let errors_clone = errors.clone();
+            let fail_fast_clone = fail_fast.clone();
+            let in_flight_clone = in_flight.clone();
+            let worker_tally_clone = worker_tally.clone();
+            let tx_clone = tx.clone();
+            let progress_clone = progress.clone();
+            let pacer_clone = pacer.clone();
+            let connect_limiter_clone = connect_limiter.clone();
+            let adaptive_timeout_clone = adaptive_timeout.clone();
+            
+            set.spawn(async move {
+                for request in 0..requests_per_worker {
+                    let intended = match pacer_clone {
+                        Some(ref pacer) => Some(pacer.wait().await),
+                        None => None,
+                    };
+                    
+                    if Instant::now() >= stop_time || fail_fast_clone.as_ref().is_some_and(|f| f.is_tripped()) {
+                        break;
+                    }
+                    
+                    let in_flight = in_flight_clone.enter(1);
+                    
+                    let (timeout_duration, strict_timeout) = request_timeouts(adaptive_timeout_clone.as_deref(), timeout_duration, strict_timeout);
+                    
+                    // Send unary call
+                    let called = grpc::send_grpc(
+                        &uri,
+                        &headers,
+                        &message,
+                        &connect_limiter_clone,
+                        local_addr,
+                        connect_timeout,
+                        timeout_duration,
+                    ).instrument(tracing::debug_span!("request", n = request)).await
+                        .and_then(|(status, response, elapsed)| within_timeout(elapsed, strict_timeout).map(|_| (status, response, elapsed)));
+                    drop(in_flight);
+                    match called {
+                        Ok((status, response, elapsed)) => {
+                            bytes_received_clone.fetch_add(response.len(), Ordering::Relaxed);
+                            bytes_sent_clone.fetch_add(message.len(), Ordering::Relaxed);
+                            
+                            // Only grpc-status 0 (OK) counts as a success; failed
+                            // calls stay out of the latency statistics
+                            if status != 0 {
+                                let e = BenchmarkError::ResponseValidation(format!("grpc-status {}", status));
+                                tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                                errors_clone.record(&e, 1);
+                                if let Some(ref fail_fast) = fail_fast_clone {
+                                    fail_fast.trip(e);
+                                }
+                            } else {
+                                successful_clone.fetch_add(1, Ordering::Relaxed);
+                                let sample = Sample {
+                                    offset: start_time.elapsed(),
+                                    latency: elapsed,
+                                    intended_latency: intended.map(|t| t.elapsed()),
+                                    ttfb: None,
+                                    messages: Vec::new(),
+                                    chunks: Vec::new(),
+                                    target: 0,
+                                    worker,
+                                    request_id: None,
+                                };
+                                let _ = tx_clone.send(sample).await;
+                            }
+                        },
+                        Err(e) => {
+                            tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                            errors_clone.record(&e, 1);
+                            if let Some(ref fail_fast) = fail_fast_clone {
+                                fail_fast.trip(e);
+                            }
+                        }
+                    }
+                    
+                    completed_clone.fetch_add(1, Ordering::Relaxed);
+                    if let Some(ref tally) = worker_tally_clone {
+                        tally.complete(worker, 1);
+                    }
+                    
+                    progress_clone.inc(1);
+                }
+            }.instrument(tracing::debug_span!("worker", id = worker)));
+        }
+        
+        // Drop the original sender so the channel can close when all workers are done
+        drop(tx);
+        
+        let Collected { samples, summary, interrupted, latency_spike } = Collector {
+            start_time,
+            stop_time,
+            pause: None,
+            cancel: self.cancel.as_deref(),
+            correct_co: self.config.correct_co,
+            summary: self.config.low_memory.then(|| SampleSummary::new(rate.is_some())),
+            spike_breaker: self.config.abort_p99_above
+                .map(|threshold| SpikeBreaker::new(threshold, self.config.timeout, errors.clone())),
+            adaptive_timeout: adaptive_timeout.as_deref(),
+            fail_fast: fail_fast.as_deref(),
+            progress: &progress,
+            throughput: self.throughput.clone(),
+            raw_output: self.raw_output.as_ref(),
+            completed: &completed_requests,
+        }.run(&mut set, &mut rx).await?;
+        
+        if let Some(error) = fail_fast.and_then(|f| f.take_error()) {
+            return Err(error);
+        }
+        
+        let stop_reason = stop_reason(
+            interrupted,
+            self.cancel.as_deref(),
+            latency_spike.is_some(),
+            self.config.requests,
+            completed_requests.load(Ordering::Relaxed),
+        );
+        
+        let records = if self.keep_samples { sample_records(&samples) } else { Vec::new() };
+        
+        let (per_worker_requests, per_worker_p99) = worker_tally.as_ref()
+            .map(|tally| tally.stats(&samples, self.config.correct_co))
+            .unzip();
+        let worker_imbalance = per_worker_requests.as_deref().and_then(WorkerImbalance::from_requests);
+        
+        // Calculate statistics from the headline response times, sorted for
+        // percentiles, or from their summary under `--low-memory`
+        let total_time = start_time.elapsed();
+        let completed = completed_requests.load(Ordering::Relaxed);
+        let successful = successful_requests.load(Ordering::Relaxed);
+        let achieved_concurrency = in_flight.stats(total_time);
+        let rate_ramp = self.config.rate_ramp
+            .map(|ramp| rate_ramp_stats(ramp, self.config.duration, total_time, &samples, self.config.correct_co));
+        let (stats, latency_correction) = match summary {
+            Some(summary) => summary.statistics(
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ),
+            None => aggregate_samples(
+                samples,
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ).await,
         };
         
         Ok(BenchmarkReport {
-            target: self.config.address.clone(),
-            protocol: "TCP".to_string(),
+            target: self.config.url.clone(),
+            protocol: "gRPC".to_string(),
             concurrency: self.config.concurrency,
-            total_requests,
-            successful_requests: successful,
-            failed_requests: failed,
-            total_time,
-            requests_per_second,
-            avg_response_time: avg_time,
-            min_response_time: min_time,
-            max_response_time: max_time,
-            p50_response_time: p50,
-            p90_response_time: p90,
-            p95_response_time: p95,
-            p99_response_time: p99,
+            total_requests: stats.total_requests,
+            successful_requests: stats.successful_requests,
+            failed_requests: stats.failed_requests,
+            total_time: stats.total_time,
+            requests_per_second: stats.requests_per_second,
+            avg_response_time: stats.avg,
+            min_response_time: stats.min,
+            max_response_time: stats.max,
+            p50_response_time: stats.p50,
+            p90_response_time: stats.p90,
+            p95_response_time: stats.p95,
+            p99_response_time: stats.p99,
             bytes_sent: bytes_sent.load(Ordering::Relaxed) as u64,
             bytes_received: bytes_received.load(Ordering::Relaxed) as u64,
+            latency_correction,
+            connection_rate: connection_rate(&connect_limiter),
+            connection_strategy: NEW_CONNECTION_PER_REQUEST.to_string(),
+            connections_opened: connect_limiter.opened(),
+            connects_per_second: None,
+            stale_reconnects: None,
+            tls_verification: None,
+            tls_handshakes: None,
+            cert_not_after: None,
+            cert_days_remaining: None,
+            cert_min_days: None,
+            stop_reason,
+            latency_spike,
+            rate_ramp,
+            achieved_concurrency: Some(achieved_concurrency),
+            samples: records,
+            cache_stats: None,
+            server_timing: None,
+            timeout_cutoff: None,
+            adaptive_timeout: adaptive_timeout.as_ref().map(|adaptive| adaptive.current()),
+            warmup: None,
+            ttfb: None,
+            per_message: None,
+            streaming: None,
+            per_host: None,
+            per_method: None,
+            per_worker_requests,
+            per_worker_p99,
+            worker_imbalance,
+            slowest_requests: None,
+            errors: errors.summary(),
+            error_details: errors.details(),
+            status_codes: None,
         })
     }
 }
 
 pub struct UdsRunner {
     config: UdsConfig,
+    throughput: Option<mpsc::UnboundedSender<ThroughputPoint>>,
+    progress: Option<Arc<AtomicUsize>>,
+    pause: Option<Arc<PauseFlag>>,
+    cancel: Option<Arc<CancelFlag>>,
+    protocol: Arc<dyn Protocol>,
+    raw_output: Option<RawOutput>,
+    trace: Option<TraceOptions>,
+    keep_samples: bool,
 }
 
 impl UdsRunner {
     pub fn new(config: UdsConfig) -> Self {
-        UdsRunner { config }
+        UdsRunner { config, throughput: None, progress: None, pause: None, cancel: None, raw_output: None, trace: None, keep_samples: false, protocol: Arc::new(protocol::Echo) }
+    }
+    
+    /// Sends requests/sec once per second while the benchmark runs
+    pub fn with_throughput(mut self, tx: mpsc::UnboundedSender<ThroughputPoint>) -> Self {
+        self.throughput = Some(tx);
+        self
+    }
+    
+    /// Streams completed requests to `output` while the benchmark runs
+    pub fn with_raw_output(mut self, output: RawOutput) -> Self {
+        self.raw_output = Some(output);
+        self
+    }
+    
+    /// Keeps every completed request on the report's `samples`
+    pub fn with_samples(mut self) -> Self {
+        self.keep_samples = true;
+        self
+    }
+    
+    /// Records the bytes of each request in a `--trace-file`
+    pub fn with_trace(mut self, trace: TraceOptions) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    /// Counts completed requests in `completed` while the benchmark runs,
+    /// for a caller showing progress
+    pub fn with_progress(mut self, completed: Arc<AtomicUsize>) -> Self {
+        self.progress = Some(completed);
+        self
+    }
+    
+    /// Lets the caller pause and resume the run through `pause`
+    pub fn with_pause(mut self, pause: Arc<PauseFlag>) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+    
+    /// Ends the run early, with the report so far, once `cancel` is set
+    pub fn with_cancel(mut self, cancel: Arc<CancelFlag>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+    
+    /// Replaces the default `protocol::Echo` exchange with a custom protocol
+    pub fn with_protocol(mut self, protocol: Box<dyn Protocol>) -> Self {
+        self.protocol = Arc::from(protocol);
+        self
     }
     
     pub async fn run(&self) -> Result<BenchmarkReport, BenchmarkError> {
-        println!("Starting Unix Domain Socket benchmark for {:?} with {} connections...", 
+        config::check_limits(self.config.requests, self.config.duration)?;
+        // Compiled once and shared by every worker
+        let expects = Arc::new(config::compile_expects(&self.config.payloads, &self.config.expects)?);
+        eprintln!("Starting Unix Domain Socket benchmark for {:?} with {} connections...", 
                  self.config.path, self.config.concurrency);
         
-        // Create progress bar
-        let progress = if self.config.requests > 0 {
-            let bar = ProgressBar::new(self.config.requests as u64);
-            bar.set_style(
-                ProgressStyle::default_bar()
-                    .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {percent}% ({eta})")
-                    .unwrap()
-                    .progress_chars("##-")
-            );
-            Some(bar)
-        } else {
-            None
-        };
+        let progress = progress_bar(self.config.requests);
         
         let concurrency = self.config.concurrency;
         let requests_per_worker = if self.config.requests > 0 {
@@ -418,157 +3040,1143 @@ impl UdsRunner {
             usize::MAX // run forever until duration is reached
         };
         
+        let tracer = self.trace.as_ref().map(Tracer::create).transpose()?.map(Arc::new);
         let start_time = Instant::now();
-        let stop_time = start_time + self.config.duration;
+        let stop_time = stop_time(start_time, self.config.duration);
+        let rate = pacing_rate(self.config.rate, self.config.rate_ramp);
+        let pacer = rate.map(|rate| Arc::new(
+            Pacer::new(start_time, rate, self.config.arrival, self.config.seed)
+                .with_ramp(self.config.rate_ramp, self.config.duration)
+                .with_pause(self.pause.clone())
+        ));
+        let connect_limiter = Arc::new(ConnectLimiter::new(self.config.connect_rate));
         
         // Shared counters for all workers
-        let completed_requests = Arc::new(AtomicUsize::new(0));
+        let completed_requests = self.progress.clone().unwrap_or_default();
         let successful_requests = Arc::new(AtomicUsize::new(0));
         let bytes_sent = Arc::new(AtomicUsize::new(0));
         let bytes_received = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(ErrorTally::new(self.config.only_errors));
+        let adaptive_timeout = self.config.adaptive_timeout
+            .map(|multiple| Arc::new(AdaptiveTimeout::new(self.config.timeout, multiple, errors.clone())));
+        let fail_fast = self.config.fail_fast.then(|| Arc::new(FailFast::default()));
+        let in_flight = Arc::new(InFlight::new());
+        let worker_tally = self.config.per_worker_stats.then(|| Arc::new(WorkerTally::new(concurrency)));
         
         // Channel for response times
-        let (tx, mut rx) = mpsc::channel::<Duration>(10000);
+        let (tx, mut rx) = mpsc::channel::<Sample>(10000);
         
         // Spawn worker tasks
         let mut set = JoinSet::new();
         
-        for _ in 0..concurrency {
+        for worker in 0..concurrency {
             let path = self.config.path.clone();
-            let data = self.config.data.clone();
-            let expect = self.config.expect.clone();
+            let payloads = self.config.payloads.clone();
+            let expects = expects.clone();
+            let setup = self.config.setup.clone();
             let timeout_duration = self.config.timeout;
+            let connect_timeout = self.config.connect_timeout;
+            let repeat = self.config.repeat;
+            let response_size = self.config.response_size;
+            let half_close = self.config.half_close;
+            let offload_above = self.config.offload_validation_above;
+            let require_non_empty = self.config.require_non_empty;
+            let expect_length = self.config.expect_length;
+            let strict_timeout = self.config.strict_timeout;
             let completed_clone = completed_requests.clone();
             let successful_clone = successful_requests.clone();
             let bytes_sent_clone = bytes_sent.clone();
             let bytes_received_clone = bytes_received.clone();
+            let errors_clone = errors.clone();
+            let fail_fast_clone = fail_fast.clone();
+            let in_flight_clone = in_flight.clone();
+            let worker_tally_clone = worker_tally.clone();
             let tx_clone = tx.clone();
             let progress_clone = progress.clone();
+            let pause_clone = self.pause.clone();
+            let tracer_clone = tracer.clone();
+            let pacer_clone = pacer.clone();
+            let connect_limiter_clone = connect_limiter.clone();
+            let adaptive_timeout_clone = adaptive_timeout.clone();
+            let protocol_clone = self.protocol.clone();
             
             set.spawn(async move {
-                for _ in 0..requests_per_worker {
-                    if Instant::now() >= stop_time {
+                for request in 0..requests_per_worker {
+                    // Start nothing new while the run is paused
+                    if let Some(ref pause) = pause_clone {
+                        pause.wait().await;
+                    }
+                    
+                    let intended = match pacer_clone {
+                        Some(ref pacer) => Some(pacer.wait().await),
+                        None => None,
+                    };
+                    
+                    if past_stop_time(stop_time, pause_clone.as_deref()) || fail_fast_clone.as_ref().is_some_and(|f| f.is_tripped()) {
                         break;
                     }
                     
+                    let in_flight = in_flight_clone.enter(1);
+                    
+                    let (timeout_duration, strict_timeout) = request_timeouts(adaptive_timeout_clone.as_deref(), timeout_duration, strict_timeout);
+                    
+                    // Workers start at different payloads so all of them are in flight
+                    let (data, expect) = config::payload_for(&payloads, &expects, worker + request);
+                    
                     // Send UDS request
-                    match uds::send_uds(
+                    let exchanged = uds::send_uds(
                         &path,
-                        data.as_deref(),
-                        expect.as_deref(),
-                        timeout_duration,
-                        BUFFER_SIZE,
-                    ).await {
-                        Ok((response, elapsed)) => {
+                        &setup,
+                        protocol_clone.as_ref(),
+                        protocol::Request {
+                            data,
+                            expect,
+                            expect_length,
+                            require_non_empty,
+                            timeout: timeout_duration,
+                            buffer_size: BUFFER_SIZE,
+                            response_size,
+                            half_close,
+                            offload_above,
+                        },
+                        &connect_limiter_clone,
+                        connect_timeout,
+                        repeat,
+                        tracer_clone.as_deref(),
+                    ).instrument(tracing::debug_span!("request", n = request)).await;
+                    drop(in_flight);
+                    match exchanged.and_then(|exchanged| within_timeout(exchanged.response.elapsed, strict_timeout).map(|_| exchanged)) {
+                        Ok(protocol::Exchange { response, messages, bytes_sent }) => {
                             successful_clone.fetch_add(1, Ordering::Relaxed);
-                            bytes_received_clone.fetch_add(response.len(), Ordering::Relaxed);
+                            bytes_received_clone.fetch_add(response.data.len(), Ordering::Relaxed);
+                            bytes_sent_clone.fetch_add(bytes_sent, Ordering::Relaxed);
                             
-                            if let Some(ref d) = data {
-                                bytes_sent_clone.fetch_add(d.len(), Ordering::Relaxed);
-                            }
-                            
-                            let _ = tx_clone.send(elapsed).await;
+                            let sample = Sample {
+                                offset: start_time.elapsed(),
+                                latency: response.elapsed,
+                                intended_latency: intended.map(|t| t.elapsed()),
+                                ttfb: None,
+                                messages,
+                                chunks: Vec::new(),
+                                target: 0,
+                                worker,
+                                request_id: None,
+                            };
+                            let _ = tx_clone.send(sample).await;
                         },
-                        Err(_) => {
-                            // Error handling is already done in the uds module
+                        Err(e) => {
+                            tracing::warn!(kind = e.kind(), error = %e, "request failed");
+                            errors_clone.record(&e, 1);
+                            if let Some(ref fail_fast) = fail_fast_clone {
+                                fail_fast.trip(e);
+                            }
                         }
                     }
                     
                     completed_clone.fetch_add(1, Ordering::Relaxed);
-                    
-                    if let Some(ref bar) = progress_clone {
-                        bar.inc(1);
+                    if let Some(ref tally) = worker_tally_clone {
+                        tally.complete(worker, 1);
                     }
+                    
+                    progress_clone.inc(1);
                 }
-            });
+            }.instrument(tracing::debug_span!("worker", id = worker)));
         }
         
         // Drop the original sender so the channel can close when all workers are done
         drop(tx);
         
-        // Wait for all workers to complete or timeout
-        while (Instant::now() < stop_time) && (!set.is_empty()) {
-            tokio::select! {
-                _ = sleep(Duration::from_millis(100)) => {
-                    // Just a timeout to check if we've reached the stop time
-                }
-                _ = set.join_next() => {
-                    // A worker has completed
-                }
-            }
-        }
-        
-        // Cancel any remaining tasks
-        set.abort_all();
-        
-        // Collect all response times
-        let mut response_times = Vec::new();
-        while let Some(time) = rx.recv().await {
-            response_times.push(time);
+        let Collected { samples, summary, interrupted, latency_spike } = Collector {
+            start_time,
+            stop_time,
+            pause: self.pause.as_deref(),
+            cancel: self.cancel.as_deref(),
+            correct_co: self.config.correct_co,
+            summary: self.config.low_memory.then(|| SampleSummary::new(rate.is_some())),
+            spike_breaker: self.config.abort_p99_above
+                .map(|threshold| SpikeBreaker::new(threshold, self.config.timeout, errors.clone())),
+            adaptive_timeout: adaptive_timeout.as_deref(),
+            fail_fast: fail_fast.as_deref(),
+            progress: &progress,
+            throughput: self.throughput.clone(),
+            raw_output: self.raw_output.as_ref(),
+            completed: &completed_requests,
+        }.run(&mut set, &mut rx).await?;
+        if let Some(tracer) = tracer {
+            tracer.finish().await?;
         }
         
-        if let Some(bar) = progress {
-            bar.finish_and_clear();
+        if let Some(error) = fail_fast.and_then(|f| f.take_error()) {
+            return Err(error);
         }
         
-        // Sort response times for percentiles
-        response_times.sort();
-        
-        // Calculate statistics
-        let total_time = start_time.elapsed();
-        let total_requests = completed_requests.load(Ordering::Relaxed);
-        let successful = successful_requests.load(Ordering::Relaxed);
-        let failed = total_requests.saturating_sub(successful);
-        
-        let avg_time = if response_times.is_empty() {
-            Duration::from_secs(0)
-        } else {
-            response_times.iter().fold(Duration::from_secs(0), |acc, &x| acc + x) 
-                / response_times.len() as u32
-        };
+        let stop_reason = stop_reason(
+            interrupted,
+            self.cancel.as_deref(),
+            latency_spike.is_some(),
+            self.config.requests,
+            completed_requests.load(Ordering::Relaxed),
+        );
         
-        let min_time = response_times.first().cloned().unwrap_or_else(|| Duration::from_secs(0));
-        let max_time = response_times.last().cloned().unwrap_or_else(|| Duration::from_secs(0));
+        let records = if self.keep_samples { sample_records(&samples) } else { Vec::new() };
+        let per_message = per_message(&samples, self.config.repeat);
         
-        let p50 = percentile(&response_times, 0.5);
-        let p90 = percentile(&response_times, 0.9);
-        let p95 = percentile(&response_times, 0.95);
-        let p99 = percentile(&response_times, 0.99);
+        let (per_worker_requests, per_worker_p99) = worker_tally.as_ref()
+            .map(|tally| tally.stats(&samples, self.config.correct_co))
+            .unzip();
+        let worker_imbalance = per_worker_requests.as_deref().and_then(WorkerImbalance::from_requests);
         
-        let requests_per_second = if total_time.as_secs_f64() > 0.0 {
-            total_requests as f64 / total_time.as_secs_f64()
-        } else {
-            0.0
+        // Calculate statistics from the headline response times, sorted for
+        // percentiles, or from their summary under `--low-memory`
+        // Time spent paused counts towards neither the duration nor the rate
+        let total_time = start_time.elapsed().saturating_sub(paused_time(self.pause.as_deref()));
+        let completed = completed_requests.load(Ordering::Relaxed);
+        let successful = successful_requests.load(Ordering::Relaxed);
+        let achieved_concurrency = in_flight.stats(total_time);
+        let rate_ramp = self.config.rate_ramp
+            .map(|ramp| rate_ramp_stats(ramp, self.config.duration, total_time, &samples, self.config.correct_co));
+        let (stats, latency_correction) = match summary {
+            Some(summary) => summary.statistics(
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ),
+            None => aggregate_samples(
+                samples,
+                rate,
+                self.config.arrival,
+                self.config.correct_co,
+                total_time,
+                completed,
+                successful,
+            ).await,
         };
         
         Ok(BenchmarkReport {
             target: self.config.path.to_string_lossy().to_string(),
             protocol: "Unix Domain Socket".to_string(),
             concurrency: self.config.concurrency,
-            total_requests,
-            successful_requests: successful,
-            failed_requests: failed,
-            total_time,
-            requests_per_second,
-            avg_response_time: avg_time,
-            min_response_time: min_time,
-            max_response_time: max_time,
-            p50_response_time: p50,
-            p90_response_time: p90,
-            p95_response_time: p95,
-            p99_response_time: p99,
+            total_requests: stats.total_requests,
+            successful_requests: stats.successful_requests,
+            failed_requests: stats.failed_requests,
+            total_time: stats.total_time,
+            requests_per_second: stats.requests_per_second,
+            avg_response_time: stats.avg,
+            min_response_time: stats.min,
+            max_response_time: stats.max,
+            p50_response_time: stats.p50,
+            p90_response_time: stats.p90,
+            p95_response_time: stats.p95,
+            p99_response_time: stats.p99,
             bytes_sent: bytes_sent.load(Ordering::Relaxed) as u64,
             bytes_received: bytes_received.load(Ordering::Relaxed) as u64,
+            latency_correction,
+            connection_rate: connection_rate(&connect_limiter),
+            connection_strategy: NEW_CONNECTION_PER_REQUEST.to_string(),
+            connections_opened: connect_limiter.opened(),
+            connects_per_second: None,
+            stale_reconnects: None,
+            tls_verification: None,
+            tls_handshakes: None,
+            cert_not_after: None,
+            cert_days_remaining: None,
+            cert_min_days: None,
+            stop_reason,
+            latency_spike,
+            rate_ramp,
+            achieved_concurrency: Some(achieved_concurrency),
+            samples: records,
+            cache_stats: None,
+            server_timing: None,
+            timeout_cutoff: None,
+            adaptive_timeout: adaptive_timeout.as_ref().map(|adaptive| adaptive.current()),
+            warmup: None,
+            ttfb: None,
+            per_message,
+            streaming: None,
+            per_host: None,
+            per_method: None,
+            per_worker_requests,
+            per_worker_p99,
+            worker_imbalance,
+            slowest_requests: None,
+            errors: errors.summary(),
+            error_details: errors.details(),
+            status_codes: None,
         })
     }
 }
 
-fn percentile(durations: &[Duration], percentile: f64) -> Duration {
-    if durations.is_empty() {
-        return Duration::from_secs(0);
+/// Executes a benchmark `runs` times, pausing `delay` between runs, and
+/// aggregates the individual reports.
+pub async fn run_repeated<F, Fut>(
+    runs: usize,
+    delay: Duration,
+    mut run: F,
+) -> Result<AggregateReport, BenchmarkError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<BenchmarkReport, BenchmarkError>>,
+{
+    let mut reports = Vec::with_capacity(runs);
+    
+    for i in 0..runs {
+        if i > 0 && !delay.is_zero() {
+            sleep(delay).await;
+        }
+        
+        eprintln!("Run {}/{}", i + 1, runs);
+        reports.push(run().await.and_then(completed_or_stopped)?);
+    }
+    
+    Ok(AggregateReport::from_reports(reports))
+}
+
+/// Runs the benchmark once at each concurrency level, in the order given, as
+/// `--sweep-concurrency` does, pausing `delay` between levels.
+pub async fn sweep_concurrency<F, Fut>(
+    levels: &[usize],
+    delay: Duration,
+    mut run: F,
+) -> Result<SweepReport, BenchmarkError>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = Result<BenchmarkReport, BenchmarkError>>,
+{
+    let mut reports = Vec::with_capacity(levels.len());
+    
+    for (i, &concurrency) in levels.iter().enumerate() {
+        if i > 0 && !delay.is_zero() {
+            sleep(delay).await;
+        }
+        
+        eprintln!("Concurrency {} ({}/{})", concurrency, i + 1, levels.len());
+        reports.push(run(concurrency).await.and_then(completed_or_stopped)?);
+    }
+    
+    Ok(SweepReport::from_reports(reports))
+}
+
+/// Limits of an `--autotune` sweep.
+#[derive(Debug, Clone)]
+pub struct AutotuneOptions {
+    pub max_concurrency: usize,
+    pub probe_duration: Duration,
+    /// Smallest relative rps gain over the best level so far that still counts as an improvement
+    pub min_gain: f64,
+    /// Stop once p99 exceeds this, regardless of throughput
+    pub max_p99: Option<Duration>,
+}
+
+/// Probes doubling concurrency levels (1, 2, 4, ... up to the maximum) for a
+/// short duration each and stops once rps improves by less than `min_gain`
+/// over the best level so far, or p99 crosses `max_p99`. The recommended
+/// concurrency is the last level that still improved throughput. Only
+/// successful requests count towards rps, so a level that merely fails
+/// faster is no improvement.
+pub async fn autotune<F, Fut>(
+    options: &AutotuneOptions,
+    mut probe: F,
+) -> Result<AutotuneReport, BenchmarkError>
+where
+    F: FnMut(usize, Duration) -> Fut,
+    Fut: Future<Output = Result<BenchmarkReport, BenchmarkError>>,
+{
+    let mut steps = Vec::new();
+    let mut best: Option<(usize, f64)> = None;
+    let mut concurrency = 1;
+    
+    loop {
+        eprintln!("Probing concurrency {} for {:?}", concurrency, options.probe_duration);
+        let report = probe(concurrency, options.probe_duration).await.and_then(completed_or_stopped)?;
+        let rps = per_second(report.successful_requests as u64, report.total_time);
+        steps.push(AutotuneStep {
+            concurrency,
+            requests_per_second: rps,
+            p99_response_time: report.p99_response_time,
+        });
+        
+        if let Some(max_p99) = options.max_p99
+            && report.p99_response_time > max_p99
+        {
+            break;
+        }
+        
+        let improved = match best {
+            Some((_, best_rps)) => rps >= best_rps * (1.0 + options.min_gain),
+            None => true,
+        };
+        if !improved {
+            break;
+        }
+        best = Some((concurrency, rps));
+        
+        if concurrency >= options.max_concurrency {
+            break;
+        }
+        concurrency = (concurrency * 2).min(options.max_concurrency);
+    }
+    
+    Ok(AutotuneReport {
+        steps,
+        recommended_concurrency: best.map(|(c, _)| c).unwrap_or(1),
+    })
+}
+
+/// Limits of a `--warmup-until-stable` phase.
+#[derive(Debug, Clone)]
+pub struct WarmupOptions {
+    /// Number of per-second throughput samples the variation is computed over
+    pub window: usize,
+    /// Coefficient of variation (stddev / mean) of those samples below which
+    /// throughput counts as stable
+    pub max_cv: f64,
+    /// Warmup ends here even if throughput never stabilized
+    pub max_duration: Duration,
+}
+
+/// Runs `warmup`, a benchmark of the given duration reporting its per-second
+/// throughput on the channel, until the coefficient of variation of the last
+/// `window` samples falls below `max_cv` or `max_duration` passes. The
+/// warmup run is then dropped, cancelling its workers, and its results are
+/// discarded.
+pub async fn warm_up<F, Fut>(options: &WarmupOptions, warmup: F) -> Result<Warmup, BenchmarkError>
+where
+    F: FnOnce(Duration, mpsc::UnboundedSender<ThroughputPoint>) -> Fut,
+    Fut: Future<Output = Result<BenchmarkReport, BenchmarkError>>,
+{
+    eprintln!(
+        "Warming up until throughput varies by less than {:.1}% over {}s (at most {:?})",
+        options.max_cv * 100.0,
+        options.window,
+        options.max_duration,
+    );
+    
+    let start = Instant::now();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let run = warmup(options.max_duration, tx);
+    tokio::pin!(run);
+    
+    let mut rates = Vec::new();
+    let stable = loop {
+        tokio::select! {
+            result = &mut run => {
+                result.and_then(completed_or_stopped)?;
+                break false;
+            }
+            Some((_, rate)) = rx.recv() => {
+                rates.push(rate);
+                if rates.len() >= options.window {
+                    let window = MetricSummary::from_values(&rates[rates.len() - options.window..]);
+                    if window.mean > 0.0 && window.stddev / window.mean < options.max_cv {
+                        break true;
+                    }
+                }
+            }
+        }
+    };
+    
+    let warmup = Warmup { duration: start.elapsed(), stable };
+    if stable {
+        eprintln!("Throughput stable after {:?}", warmup.duration);
+    } else {
+        eprintln!("Throughput not stable after {:?}; measuring anyway", warmup.duration);
+    }
+    
+    Ok(warmup)
+}
+
+/// Runs a `--timeout-percentile` calibration probe for `duration` and
+/// returns the given latency percentile of its successful responses as the
+/// timeout for the measured run.
+pub async fn calibrate_timeout<F, Fut>(
+    percentile: f64,
+    duration: Duration,
+    probe: F,
+) -> Result<TimeoutCutoff, BenchmarkError>
+where
+    F: FnOnce(Duration) -> Fut,
+    Fut: Future<Output = Result<BenchmarkReport, BenchmarkError>>,
+{
+    eprintln!("Calibrating p{} timeout for {:?}", percentile, duration);
+    let report = probe(duration).await.and_then(completed_or_stopped)?;
+    
+    let mut latencies: Vec<Duration> = report.samples.iter()
+        .map(|s| Duration::from_micros(s.latency_us))
+        .collect();
+    if latencies.is_empty() {
+        return Err(BenchmarkError::Config("calibration run had no successful responses".to_string()));
+    }
+    stats::sort_latencies(&mut latencies);
+    
+    // A zero timeout would fail every request
+    let timeout = stats::percentile(&latencies, percentile / 100.0).max(Duration::from_micros(1));
+    eprintln!("Timeout cutoff set to {:?}", timeout);
+    
+    Ok(TimeoutCutoff { percentile, timeout })
+}
+
+/// With a strict timeout, a response that completed but took longer than
+/// the timeout in total (connect included) still counts as a timeout.
+fn within_timeout(elapsed: Duration, strict_timeout: Option<Duration>) -> Result<(), BenchmarkError> {
+    match strict_timeout {
+        Some(timeout) if elapsed > timeout => Err(BenchmarkError::RequestTimeout(timeout)),
+        _ => Ok(()),
+    }
+}
+
+/// Percentiles of the individual exchanges of a `--repeat` run
+fn per_message(samples: &[Sample], repeat: usize) -> Option<PerMessage> {
+    if repeat <= 1 {
+        return None;
+    }
+    
+    let mut latencies: Vec<Duration> = samples.iter().flat_map(|s| s.messages.iter().copied()).collect();
+    stats::sort_latencies(&mut latencies);
+    Some(PerMessage {
+        repeat,
+        messages: latencies.len(),
+        latency: stats::latency_percentiles(&latencies),
+    })
+}
+
+/// Chunks per response and the intervals between chunks of a
+/// `--stream-response` run
+fn streaming_stats(samples: &[Sample], stream: StreamResponse) -> StreamingStats {
+    let chunks: usize = samples.iter().map(|s| s.chunks.len()).sum();
+    let mut intervals: Vec<Duration> = samples.iter()
+        .flat_map(|s| s.chunks.windows(2).map(|pair| pair[1] - pair[0]))
+        .collect();
+    stats::sort_latencies(&mut intervals);
+    StreamingStats {
+        max_chunks: stream.max_chunks,
+        chunks,
+        chunks_per_response: if samples.is_empty() { 0.0 } else { chunks as f64 / samples.len() as f64 },
+        interval: (!intervals.is_empty()).then(|| stats::latency_percentiles(&intervals)),
+    }
+}
+
+/// Raw per-request records kept on the report for `--output-dir`
+fn sample_records(samples: &[Sample]) -> Vec<SampleRecord> {
+    samples.iter().map(Sample::record).collect()
+}
+
+/// Independent random streams of a run. With `--seed` each one has its own
+/// fixed sequence, so drawing more from one never shifts another.
+#[derive(Clone, Copy)]
+pub enum RngStream {
+    /// A worker's choices of target, method and body; indexed by worker
+    Worker,
+    /// Gaps between `--arrival poisson` or `uniform` send slots
+    Pacer,
+    /// The `--fixed-random-body` payload
+    FixedBody,
+    /// The run's `--request-id-header` prefix
+    RequestIds,
+    /// `--cache-bust` values; indexed by worker
+    CacheBust,
+    /// The `--multipart` boundary
+    Multipart,
+    /// The requests `--sample-rate` writes to `--raw-output`
+    Sampling,
+}
+
+/// RNG for one stream (and index within it, such as the worker). With
+/// `--seed` the seed, stream and index are mixed by `splitmix64`, so the same
+/// seed and config reproduce every stream; without it the RNG is seeded from
+/// the OS.
+pub fn stream_rng(seed: Option<u64>, stream: RngStream, index: usize) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(splitmix64(splitmix64(splitmix64(seed) ^ stream as u64) ^ index as u64)),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// RNG for one worker's choices
+fn worker_rng(seed: Option<u64>, worker: usize) -> StdRng {
+    stream_rng(seed, RngStream::Worker, worker)
+}
+
+/// SplitMix64 finalizer, so nearby seeds and worker indexes land on
+/// unrelated streams (`--seed 1` worker 0 is not `--seed 0` worker 1)
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Whole days from now until `time`, negative once it has passed
+fn days_until(time: SystemTime) -> i64 {
+    const DAY: u64 = 86_400;
+    match time.duration_since(SystemTime::now()) {
+        Ok(left) => (left.as_secs() / DAY) as i64,
+        Err(past) => -(past.duration().as_secs().div_ceil(DAY) as i64),
+    }
+}
+
+/// Achieved connection rate of a run limited by `--connect-rate`
+fn connection_rate(limiter: &ConnectLimiter) -> Option<ConnectionRate> {
+    Some(ConnectionRate {
+        limit: limiter.rate()?,
+        opened: limiter.opened(),
+        achieved: limiter.achieved(),
+    })
+}
+
+/// `count` spread over `total_time`, or 0 for a run that took no time
+fn per_second(count: u64, total_time: Duration) -> f64 {
+    if total_time.as_secs_f64() > 0.0 {
+        count as f64 / total_time.as_secs_f64()
+    } else {
+        0.0
+    }
+}
+
+/// `summarize_samples` and the statistics of the headline response times, on
+/// a blocking thread: sorting millions of samples would otherwise hold up
+/// the runtime's other tasks, such as a `--runs` neighbour or the TUI.
+async fn aggregate_samples(
+    samples: Vec<Sample>,
+    rate: Option<f64>,
+    arrival: Arrival,
+    correct_co: bool,
+    total_time: Duration,
+    completed: usize,
+    successful: usize,
+) -> (Statistics, Option<LatencyCorrection>) {
+    let aggregate = move || {
+        let (response_times, latency_correction) = summarize_samples(samples, rate, arrival, correct_co);
+        (Statistics::from_samples(&response_times, total_time, completed, successful), latency_correction)
+    };
+    match tokio::task::spawn_blocking(aggregate).await {
+        Ok(aggregated) => aggregated,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Splits the collected samples into the sorted headline response times and,
+/// for paced runs, the uncorrected/corrected latency breakdown. The headline
+/// times are the corrected ones only when `correct_co` is set.
+fn summarize_samples(
+    samples: Vec<Sample>,
+    rate: Option<f64>,
+    arrival: Arrival,
+    correct_co: bool,
+) -> (Vec<Duration>, Option<LatencyCorrection>) {
+    let mut uncorrected: Vec<Duration> = samples.iter().map(|s| s.latency).collect();
+    stats::sort_latencies(&mut uncorrected);
+    
+    let Some(target_rate) = rate else {
+        return (uncorrected, None);
+    };
+    
+    let mut corrected: Vec<Duration> = samples.iter()
+        .map(|s| s.intended_latency.unwrap_or(s.latency))
+        .collect();
+    stats::sort_latencies(&mut corrected);
+    
+    let correction = LatencyCorrection {
+        target_rate,
+        arrival,
+        applied: correct_co,
+        uncorrected: stats::latency_percentiles(&uncorrected),
+        corrected: stats::latency_percentiles(&corrected),
+    };
+    
+    let headline = if correct_co { corrected } else { uncorrected };
+    (headline, Some(correction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// A sample completing at `offset` with the given latencies
+    fn sample(offset_ms: u64, latency_ms: u64, intended_ms: Option<u64>) -> Sample {
+        Sample {
+            offset: Duration::from_millis(offset_ms),
+            latency: Duration::from_millis(latency_ms),
+            intended_latency: intended_ms.map(Duration::from_millis),
+            ttfb: None,
+            messages: Vec::new(),
+            chunks: Vec::new(),
+            target: 0,
+            worker: 0,
+            request_id: None,
+        }
+    }
+    
+    #[test]
+    fn rate_ramp_stats_splits_a_short_run_into_intervals() {
+        let ramp = RateRamp { start: 100.0, end: 300.0 };
+        // 500ms: ten 50ms intervals, with 5 requests in each, then 20 in each
+        let samples: Vec<Sample> = (0..500u64)
+            .filter(|ms| if *ms < 250 { ms % 10 == 0 } else { ms % 10 < 4 })
+            .map(|ms| sample(ms, if ms < 400 { 1 } else { 10 }, None))
+            .collect();
+        
+        let stats = rate_ramp_stats(ramp, Duration::from_millis(500), Duration::from_millis(500), &samples, false);
+        
+        assert_eq!(stats.points.len(), 10);
+        assert_eq!(stats.points[1].offset, Duration::from_millis(50));
+        assert!((stats.points[0].target_rate - 110.0).abs() < 1e-9);
+        assert!((stats.points[9].target_rate - 290.0).abs() < 1e-9);
+        assert!((stats.points[0].achieved_rate - 100.0).abs() < 1e-9);
+        assert!((stats.points[9].achieved_rate - 400.0).abs() < 1e-9);
+        // 100 req/s falls short of the second interval's target of 130
+        let degraded = stats.degraded.unwrap();
+        assert_eq!(degraded.offset, Duration::from_millis(50));
+        assert!(degraded.reason.starts_with("throughput"));
+        
+        // Requests finishing past the ramp neither stretch nor add an interval
+        let late = [sample(520, 1, None), sample(540, 1, None)];
+        let stats = rate_ramp_stats(ramp, Duration::from_millis(500), Duration::from_millis(560), &late, false);
+        assert_eq!(stats.points.len(), 10);
+        assert_eq!(stats.points[9].offset, Duration::from_millis(450));
+        assert!(stats.points.iter().all(|point| point.achieved_rate == 0.0));
+        
+        // Whole seconds for longer runs
+        let stats = rate_ramp_stats(ramp, Duration::from_secs(12), Duration::from_millis(12_500), &[], false);
+        assert_eq!(stats.points.len(), 12);
+        assert_eq!(stats.points[11].offset, Duration::from_secs(11));
+        assert!(rate_ramp_stats(ramp, Duration::ZERO, Duration::ZERO, &[], false).points.is_empty());
+    }
+    
+    #[test]
+    fn summarize_samples_headlines_corrected_latency_only_when_asked() {
+        let samples = || vec![sample(10, 5, Some(5)), sample(20, 10, Some(40)), sample(30, 1, Some(2))];
+        
+        let (headline, correction) = summarize_samples(samples(), Some(100.0), Arrival::Constant, false);
+        assert_eq!(headline, [1, 5, 10].map(Duration::from_millis));
+        let correction = correction.unwrap();
+        assert!(!correction.applied);
+        assert_eq!(correction.uncorrected.max, Duration::from_millis(10));
+        assert_eq!(correction.corrected.max, Duration::from_millis(40));
+        
+        let (headline, correction) = summarize_samples(samples(), Some(100.0), Arrival::Constant, true);
+        assert_eq!(headline, [2, 5, 40].map(Duration::from_millis));
+        assert!(correction.unwrap().applied);
+    }
+    
+    #[test]
+    fn summarize_samples_without_rate_has_no_correction() {
+        let (headline, correction) = summarize_samples(vec![sample(10, 3, None)], None, Arrival::Constant, true);
+        assert_eq!(headline, [Duration::from_millis(3)]);
+        assert!(correction.is_none());
+    }
+    
+    /// A one-second report with `successful` of `total` requests
+    fn report(total: usize, successful: usize, p99_ms: u64) -> BenchmarkReport {
+        BenchmarkReport {
+            total_requests: total,
+            successful_requests: successful,
+            failed_requests: total - successful,
+            total_time: Duration::from_secs(1),
+            requests_per_second: total as f64,
+            p99_response_time: Duration::from_millis(p99_ms),
+            ..Default::default()
+        }
+    }
+    
+    #[tokio::test]
+    async fn autotune_ranks_levels_by_successful_requests() {
+        let options = AutotuneOptions {
+            max_concurrency: 16,
+            probe_duration: Duration::from_millis(1),
+            min_gain: 0.1,
+            max_p99: None,
+        };
+        // Level 4 answers the most requests, but half of them fail
+        let result = autotune(&options, |concurrency, _| async move {
+            Ok(match concurrency {
+                1 => report(100, 100, 5),
+                2 => report(200, 200, 5),
+                _ => report(400, 200, 5),
+            })
+        }).await.unwrap();
+        
+        assert_eq!(result.recommended_concurrency, 2);
+        assert_eq!(result.steps.len(), 3);
+        assert_eq!(result.steps[2].requests_per_second, 200.0);
+    }
+    
+    #[tokio::test]
+    async fn autotune_stops_once_p99_is_too_high() {
+        let options = AutotuneOptions {
+            max_concurrency: 16,
+            probe_duration: Duration::from_millis(1),
+            min_gain: 0.1,
+            max_p99: Some(Duration::from_millis(50)),
+        };
+        let result = autotune(&options, |concurrency, _| async move {
+            Ok(report(100 * concurrency, 100 * concurrency, 20 * concurrency as u64))
+        }).await.unwrap();
+        
+        // p99 is 80ms at concurrency 4
+        assert_eq!(result.steps.len(), 3);
+        assert_eq!(result.recommended_concurrency, 2);
+    }
+    
+    #[test]
+    fn duration_only_runs_get_a_spinner_for_live_percentiles() {
+        assert_eq!(progress_bar(0).length(), None);
+        assert_eq!(progress_bar(10).length(), Some(10));
+    }
+    
+    #[test]
+    fn worker_rngs_do_not_overlap_across_adjacent_seeds() {
+        let draws = |seed, worker| -> Vec<u64> {
+            let mut rng = worker_rng(Some(seed), worker);
+            (0..4).map(|_| rng.random()).collect()
+        };
+        
+        assert_eq!(draws(7, 3), draws(7, 3));
+        assert_ne!(draws(1, 0), draws(0, 1));
+        assert_ne!(draws(7, 0), draws(7, 1));
+    }
+    
+    #[test]
+    fn error_tally_counts_kinds_and_keeps_the_latest_messages() {
+        let tally = ErrorTally::new(false);
+        assert!(tally.summary().is_none());
+        
+        tally.record(&BenchmarkError::ConnectionRefused, 1);
+        for i in 0..12 {
+            tally.record(&BenchmarkError::Other(i.to_string()), 2);
+        }
+        let summary = tally.summary().unwrap();
+        
+        assert_eq!(summary.counts["connection_refused"], 1);
+        assert_eq!(summary.counts["other"], 24);
+        assert_eq!(summary.connect_failures, 1);
+        assert_eq!(summary.recent.len(), RECENT_ERRORS);
+        assert_eq!(summary.recent.first().unwrap(), "Unexpected error: 2");
+        assert_eq!(summary.recent.last().unwrap(), "Unexpected error: 11");
+    }
+    
+    #[test]
+    fn error_tally_details_keep_each_distinct_error_with_its_first_message() {
+        assert!(ErrorTally::new(false).details().is_none());
+        let tally = ErrorTally::new(true);
+        
+        tally.record(&BenchmarkError::Other("first".to_string()), 1);
+        tally.record(&BenchmarkError::Other("second".to_string()), 2);
+        tally.record(&BenchmarkError::ConnectionRefused, 1);
+        tally.record_status(StatusCode::OK, b"fine");
+        tally.record_status(StatusCode::SERVICE_UNAVAILABLE, &[b'x'; ERROR_BODY_SNIPPET + 10]);
+        tally.record_status(StatusCode::SERVICE_UNAVAILABLE, b"other body");
+        let details = tally.details().unwrap();
+        
+        let kinds: Vec<_> = details.iter().map(|d| (d.kind.as_str(), d.count)).collect();
+        assert_eq!(kinds, [("other", 3), ("http_503", 2), ("connection_refused", 1)]);
+        assert_eq!(details[0].message, "Unexpected error: first");
+        assert_eq!(details[1].status, Some(503));
+        assert_eq!(details[1].body.as_deref(), Some("x".repeat(ERROR_BODY_SNIPPET).as_str()));
+        assert_eq!(details[2].body, None);
+        // Error statuses count as completed requests, not failures
+        assert_eq!(tally.failures(), 4);
+    }
+    
+    #[test]
+    fn status_counts_cover_the_valid_range() {
+        let counts = StatusCounts::new();
+        for code in [100, 200, 200, 404, 999] {
+            counts.record(StatusCode::from_u16(code).unwrap());
+        }
+        
+        assert_eq!(counts.summary(), BTreeMap::from([(100, 1), (200, 2), (404, 1), (999, 1)]));
+    }
+    
+    #[test]
+    fn pacer_spaces_constant_slots_by_the_rate() {
+        let pacer = Pacer::new(Instant::now(), 100.0, Arrival::Constant, None);
+        let offsets: Vec<Duration> = (0..4).map(|_| pacer.next_offset()).collect();
+        assert_eq!(offsets, [0, 10, 20, 30].map(Duration::from_millis));
+        
+        let ramp = RateRamp { start: 100.0, end: 300.0 };
+        let pacer = Pacer::new(Instant::now(), 100.0, Arrival::Constant, None)
+            .with_ramp(Some(ramp), Duration::from_secs(10));
+        for _ in 0..2000 {
+            pacer.next_offset();
+        }
+        assert_eq!(pacer.next_offset(), Duration::from_secs(10));
+    }
+    
+    #[test]
+    fn poisson_pacer_keeps_the_mean_rate_with_spread_out_gaps() {
+        // Mean and variance of the gaps between 5000 slots at 100 req/s
+        let gaps = |arrival| {
+            let pacer = Pacer::new(Instant::now(), 100.0, arrival, Some(7));
+            let offsets: Vec<f64> = (0..5000).map(|_| pacer.next_offset().as_secs_f64()).collect();
+            let gaps: Vec<f64> = offsets.windows(2).map(|pair| pair[1] - pair[0]).collect();
+            let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+            let variance = gaps.iter().map(|gap| (gap - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+            (mean, variance)
+        };
+        
+        let (constant_mean, constant_variance) = gaps(Arrival::Constant);
+        let (poisson_mean, poisson_variance) = gaps(Arrival::Poisson);
+        assert!((constant_mean - 0.01).abs() < 1e-9);
+        assert!((poisson_mean - 0.01).abs() < 0.0005, "{}", poisson_mean);
+        assert!(constant_variance < 1e-12, "{}", constant_variance);
+        // Exponential gaps have a variance of the mean squared
+        assert!((poisson_variance / 0.0001 - 1.0).abs() < 0.1, "{}", poisson_variance);
+    }
+    
+    #[test]
+    fn seeded_pacer_draws_its_own_stream() {
+        let gaps = |seed| {
+            let pacer = Pacer::new(Instant::now(), 100.0, Arrival::Poisson, Some(seed));
+            (0..4).map(|_| pacer.next_offset()).collect::<Vec<_>>()
+        };
+        assert_eq!(gaps(7), gaps(7));
+        assert_ne!(gaps(7), gaps(8));
+        
+        // Not the gaps worker 0 would draw from the same seed
+        let mut worker = worker_rng(Some(7), 0);
+        let mut offset = 0.0;
+        let worker_offsets: Vec<Duration> = (0..4).map(|_| {
+            let slot = offset;
+            offset += arrival_gap(Arrival::Poisson, 100.0, &mut worker);
+            Duration::from_secs_f64(slot)
+        }).collect();
+        assert_ne!(gaps(7), worker_offsets);
+    }
+    
+    #[test]
+    fn request_ids_are_distinct_and_their_prefix_follows_the_seed() {
+        let ids = RequestIds::new("X-Request-Id".to_string(), Some(7));
+        let first = ids.next();
+        let second = ids.next();
+        assert_ne!(first, second);
+        assert_eq!(first.split_once('-').unwrap().0, second.split_once('-').unwrap().0);
+        
+        assert_eq!(RequestIds::new("X-Request-Id".to_string(), Some(7)).next(), first);
+        assert_ne!(RequestIds::new("X-Request-Id".to_string(), Some(8)).next(), first);
+    }
+    
+    #[test]
+    fn live_percentiles_converge_to_the_report() {
+        // A skewed spread from 200us to 10ms, in random order
+        let mut rng = StdRng::seed_from_u64(7);
+        let latencies: Vec<Duration> = (0..20_000)
+            .map(|_| Duration::from_micros((200.0 * (1.0 + 49.0 * rng.random::<f64>().powi(4))) as u64))
+            .collect();
+        let close = |live: Duration, exact: Duration| {
+            (live.as_secs_f64() / exact.as_secs_f64() - 1.0).abs() < 0.02
+        };
+        
+        let mut live = LiveHistogram::new();
+        for (i, latency) in latencies.iter().enumerate() {
+            live.record(*latency);
+            let seen = i + 1;
+            // At every point the live values are those a report of the
+            // requests so far would give, ending at the final report's
+            if seen % 5000 == 0 {
+                let mut sorted = latencies[..seen].to_vec();
+                stats::sort_latencies(&mut sorted);
+                let report = stats::latency_percentiles(&sorted);
+                assert!(close(live.quantile(0.5), report.p50), "p50 {:?} against {:?} after {}", live.quantile(0.5), report.p50, seen);
+                assert!(close(live.quantile(0.99), report.p99), "p99 {:?} against {:?} after {}", live.quantile(0.99), report.p99, seen);
+            }
+        }
+    }
+    
+    #[test]
+    fn adaptive_timeout_counts_timed_out_requests_as_the_slowest() {
+        let mut live = LiveHistogram::new();
+        for _ in 0..20 {
+            live.record(Duration::from_millis(10));
+        }
+        
+        // With only fast responses it steps toward 3x their median
+        let errors = Arc::new(ErrorTally::default());
+        let adaptive = AdaptiveTimeout::new(Duration::from_secs(1), 3.0, errors.clone());
+        assert!(adaptive.update(&live));
+        assert!(adaptive.current() < Duration::from_millis(800));
+        
+        // Once most requests time out, the median is the timeout itself
+        errors.record(&BenchmarkError::RequestTimeout(adaptive.current()), 30);
+        let narrowed = adaptive.current();
+        assert!(adaptive.update(&live));
+        assert!(adaptive.current() > narrowed);
+        assert!(adaptive.current() <= Duration::from_secs(1));
+    }
+    
+    #[test]
+    fn adaptive_timeout_waits_for_enough_requests() {
+        let mut live = LiveHistogram::new();
+        live.record(Duration::from_millis(10));
+        let adaptive = AdaptiveTimeout::new(Duration::from_secs(1), 3.0, Arc::default());
+        assert!(!adaptive.update(&live));
+        assert_eq!(adaptive.current(), Duration::from_secs(1));
+    }
+    
+    #[test]
+    fn adaptive_timeout_keeps_a_shorter_strict_timeout() {
+        let adaptive = AdaptiveTimeout::new(Duration::from_secs(1), 3.0, Arc::default());
+        let timeout = Duration::from_secs(5);
+        
+        assert_eq!(request_timeouts(None, timeout, Some(Duration::from_millis(200))), (timeout, Some(Duration::from_millis(200))));
+        assert_eq!(request_timeouts(Some(&adaptive), timeout, None), (Duration::from_secs(1), Some(Duration::from_secs(1))));
+        assert_eq!(
+            request_timeouts(Some(&adaptive), timeout, Some(Duration::from_millis(200))),
+            (Duration::from_secs(1), Some(Duration::from_millis(200))),
+        );
+    }
+    
+    #[test]
+    fn spike_breaker_counts_failures_as_timeouts() {
+        let errors = Arc::new(ErrorTally::default());
+        let mut breaker = SpikeBreaker::new(Duration::from_millis(100), Duration::from_secs(2), errors.clone());
+        for _ in 0..30 {
+            breaker.record(Duration::from_millis(5));
+        }
+        assert_eq!(breaker.check(), None);
+        
+        // Requests that never got a response still trip it
+        errors.record(&BenchmarkError::RequestTimeout(Duration::from_secs(2)), 5);
+        let p99 = breaker.check().unwrap();
+        assert!(p99 > Duration::from_secs(1), "{:?}", p99);
+    }
+    
+    #[test]
+    fn spike_breaker_windows_move_on_without_responses() {
+        let mut breaker = SpikeBreaker::new(Duration::from_millis(100), Duration::from_secs(2), Arc::default());
+        for _ in 0..30 {
+            breaker.record(Duration::from_millis(500));
+        }
+        assert!(breaker.check().is_some());
+        
+        // Two idle windows later the slow responses no longer count
+        for _ in 0..2 {
+            breaker.window_start -= SPIKE_WINDOW;
+            breaker.check();
+        }
+        for _ in 0..30 {
+            breaker.record(Duration::from_millis(5));
+        }
+        assert_eq!(breaker.check(), None);
+    }
+    
+    #[test]
+    fn latency_summary_stays_close_to_the_exact_percentiles() {
+        let mut latencies: Vec<Duration> = (1..=1000).map(|i| Duration::from_micros(i * 37)).collect();
+        let mut summary = LatencySummary::default();
+        for latency in latencies.iter().rev() {
+            summary.record(*latency);
+        }
+        latencies.sort();
+        let exact = stats::latency_percentiles(&latencies);
+        let summarized = summary.percentiles();
+        
+        // Average and maximum are kept exactly, the percentiles within a bucket
+        assert_eq!(summarized.avg, exact.avg);
+        assert_eq!(summarized.max, exact.max);
+        assert_eq!(summary.min, latencies[0]);
+        for (summarized, exact) in [(summarized.p50, exact.p50), (summarized.p90, exact.p90), (summarized.p99, exact.p99)] {
+            let error = (summarized.as_secs_f64() - exact.as_secs_f64()).abs() / exact.as_secs_f64();
+            assert!(error < 0.02, "{:?} vs {:?}", summarized, exact);
+        }
+    }
+    
+    #[test]
+    fn pause_flag_counts_only_the_time_spent_paused() {
+        let pause = PauseFlag::new();
+        assert!(!pause.is_paused());
+        assert_eq!(pause.paused_time(), Duration::ZERO);
+        
+        assert!(pause.toggle());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(pause.is_paused());
+        assert!(pause.paused_time() >= Duration::from_millis(30));
+        
+        assert!(!pause.toggle());
+        let paused = pause.paused_time();
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(pause.paused_time(), paused);
     }
     
-    let index = ((durations.len() as f64) * percentile).floor() as usize;
-    let index = index.min(durations.len() - 1);
-    durations[index]
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn paused_run_sends_nothing_until_resumed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        // Counts the requests that reach the server
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(AtomicUsize::new(0));
+        tokio::spawn({
+            let received = received.clone();
+            async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let received = received.clone();
+                    tokio::spawn(async move {
+                        let mut request = [0; 5];
+                        while stream.read_exact(&mut request).await.is_ok() {
+                            received.fetch_add(1, Ordering::Relaxed);
+                            if stream.write_all(&request).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                }
+            }
+        });
+        let config = TcpConfig::new(
+            addr.to_string(), Some("hello".to_string()), None, Some("hello".to_string()),
+            Some(2), Some(6), Some(Duration::from_secs(10)), Some(2000), true,
+        );
+        let pause = Arc::new(PauseFlag::new());
+        pause.toggle();
+        
+        let runner = TcpRunner::new(config).with_pause(pause.clone());
+        let run = tokio::spawn(async move { runner.run().await });
+        sleep(PAUSE_POLL_INTERVAL * 3).await;
+        assert_eq!(received.load(Ordering::Relaxed), 0);
+        
+        pause.toggle();
+        let report = run.await.unwrap().unwrap();
+        assert_eq!(report.successful_requests, 6);
+        assert_eq!(received.load(Ordering::Relaxed), 6);
+    }
+    
+    #[test]
+    fn server_timing_tally_merges_the_phases_of_every_worker() {
+        let timing = |name: &str, ms| ServerTiming { name: name.to_string(), duration: Duration::from_millis(ms) };
+        let tally = ServerTimingTally::new(2);
+        tally.record(1, &[timing("db", 10), timing("cache", 2)]);
+        tally.record(0, &[timing("db", 20)]);
+        tally.record(0, &[timing("render", 5)]);
+        tally.record(1, &[]);
+        
+        let phases: Vec<_> = tally.stats().into_iter()
+            .map(|phase| (phase.name, phase.responses, phase.avg))
+            .collect();
+        assert_eq!(phases, vec![
+            ("db".to_string(), 2, Duration::from_millis(15)),
+            ("render".to_string(), 1, Duration::from_millis(5)),
+            ("cache".to_string(), 1, Duration::from_millis(2)),
+        ]);
+    }
+    
+    #[test]
+    fn in_flight_counts_requests_still_outstanding() {
+        let in_flight = InFlight::new();
+        drop(in_flight.enter(1));
+        let hung = in_flight.enter(2);
+        std::thread::sleep(Duration::from_millis(100));
+        
+        // Two requests in flight for the whole 100ms, though neither finished
+        let stats = in_flight.stats(Duration::from_millis(100));
+        assert!((1.8..=2.5).contains(&stats.avg), "{}", stats.avg);
+        assert_eq!(stats.max, 2);
+        
+        drop(hung);
+        let stats = in_flight.stats(Duration::from_millis(100));
+        assert!((1.8..=2.5).contains(&stats.avg), "{}", stats.avg);
+    }
+}
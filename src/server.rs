@@ -0,0 +1,174 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::time::sleep;
+
+const BUFFER_SIZE: usize = 8192;
+
+/// Pause after a failed accept, so running out of file descriptors under
+/// load doesn't turn the accept loop into a busy loop
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Protocols the built-in echo server speaks (`serve --protocol`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServeProtocol {
+    #[default]
+    Http,
+    Tcp,
+    Uds,
+}
+
+impl FromStr for ServeProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(ServeProtocol::Http),
+            "tcp" => Ok(ServeProtocol::Tcp),
+            "uds" => Ok(ServeProtocol::Uds),
+            other => Err(format!("Unknown protocol '{}' (expected http, tcp or uds)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ServeProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ServeProtocol::Http => "HTTP",
+            ServeProtocol::Tcp => "TCP",
+            ServeProtocol::Uds => "Unix Domain Socket",
+        })
+    }
+}
+
+/// How the echo server answers
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Pause before every response, to inject latency
+    pub response_delay: Duration,
+    /// Answer with this many bytes instead of echoing the request
+    pub response_size: Option<usize>,
+    /// Close TCP and Unix socket connections after the first response, for
+    /// clients that read until the connection closes
+    pub close: bool,
+}
+
+impl ServerOptions {
+    fn payload(&self) -> Option<Bytes> {
+        self.response_size.map(|size| Bytes::from(vec![b'x'; size]))
+    }
+}
+
+/// Serves HTTP/1.1 with keep-alive, answering every request with its own
+/// body, or the `response_size` payload. Runs until the task is dropped.
+pub async fn serve_http(listener: TcpListener, options: ServerOptions) {
+    let payload = options.payload();
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // Running out of file descriptors under load shouldn't stop the server
+                accept_failed(e).await;
+                continue;
+            }
+        };
+
+        let payload = payload.clone();
+        let delay = options.response_delay;
+        tokio::spawn(async move {
+            let service = service_fn(move |request: Request<Incoming>| {
+                let payload = payload.clone();
+                async move {
+                    // A request body that breaks off is answered with an empty one
+                    let body = request.into_body().collect().await
+                        .map(|body| body.to_bytes())
+                        .unwrap_or_default();
+                    if !delay.is_zero() {
+                        sleep(delay).await;
+                    }
+                    Ok::<_, Infallible>(Response::new(Full::new(payload.unwrap_or(body))))
+                }
+            });
+            if let Err(e) = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                tracing::debug!(error = %e, "HTTP connection closed");
+            }
+        });
+    }
+}
+
+/// Serves TCP, echoing every read back, or answering it with the
+/// `response_size` payload, until the client closes the connection (or
+/// after one response with `close`)
+pub async fn serve_tcp(listener: TcpListener, options: ServerOptions) {
+    let payload = options.payload();
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(echo(stream, payload.clone(), options.response_delay, options.close));
+            },
+            Err(e) => accept_failed(e).await,
+        }
+    }
+}
+
+/// Like `serve_tcp` for a Unix domain socket
+pub async fn serve_uds(listener: UnixListener, options: ServerOptions) {
+    let payload = options.payload();
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(echo(stream, payload.clone(), options.response_delay, options.close));
+            },
+            Err(e) => accept_failed(e).await,
+        }
+    }
+}
+
+/// Logs a failed accept and backs off before the next one
+async fn accept_failed(e: std::io::Error) {
+    tracing::warn!(error = %e, "accept failed");
+    sleep(ACCEPT_BACKOFF).await;
+}
+
+async fn echo<S>(mut stream: S, payload: Option<Bytes>, delay: Duration, close: bool)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    loop {
+        let n = match stream.read(&mut buffer).await {
+            Ok(0) => return,
+            Ok(n) => n,
+            Err(e) => {
+                tracing::debug!(error = %e, "connection closed");
+                return;
+            }
+        };
+        if !delay.is_zero() {
+            sleep(delay).await;
+        }
+        let response = payload.as_deref().unwrap_or(&buffer[..n]);
+        if let Err(e) = stream.write_all(response).await {
+            tracing::debug!(error = %e, "connection closed");
+            return;
+        }
+        if close {
+            let _ = stream.shutdown().await;
+            return;
+        }
+    }
+}
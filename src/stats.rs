@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use rayon::slice::ParallelSliceMut;
+
+use crate::report::LatencyPercentiles;
+
+/// Summary statistics of a finished run, covering every computed field of
+/// `BenchmarkReport`.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    pub total_requests: usize,
+    pub successful_requests: usize,
+    pub failed_requests: usize,
+    pub total_time: Duration,
+    pub requests_per_second: f64,
+    pub avg: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+
+impl Statistics {
+    /// Computes the statistics from response times sorted in ascending order.
+    pub fn from_samples(
+        sorted: &[Duration],
+        total_time: Duration,
+        total: usize,
+        successful: usize,
+    ) -> Self {
+        Self::from_summary(
+            latency_percentiles(sorted),
+            sorted.first().cloned().unwrap_or_default(),
+            total_time,
+            total,
+            successful,
+        )
+    }
+
+    /// Like `from_samples` for response times summarized elsewhere, e.g. in
+    /// a histogram
+    pub fn from_summary(
+        latency: LatencyPercentiles,
+        min: Duration,
+        total_time: Duration,
+        total: usize,
+        successful: usize,
+    ) -> Self {
+        let requests_per_second = if total_time.as_secs_f64() > 0.0 {
+            total as f64 / total_time.as_secs_f64()
+        } else {
+            0.0
+        };
+
+        Statistics {
+            total_requests: total,
+            successful_requests: successful,
+            failed_requests: total.saturating_sub(successful),
+            total_time,
+            requests_per_second,
+            avg: latency.avg,
+            min,
+            max: latency.max,
+            p50: latency.p50,
+            p90: latency.p90,
+            p95: latency.p95,
+            p99: latency.p99,
+        }
+    }
+}
+
+/// Average, percentiles and max of response times sorted in ascending order.
+pub fn latency_percentiles(sorted: &[Duration]) -> LatencyPercentiles {
+    LatencyPercentiles {
+        avg: average(sorted),
+        p50: percentile(sorted, 0.5),
+        p90: percentile(sorted, 0.9),
+        p95: percentile(sorted, 0.95),
+        p99: percentile(sorted, 0.99),
+        max: sorted.last().cloned().unwrap_or_default(),
+    }
+}
+
+/// Sorts response times in ascending order for the functions above. Runs
+/// of millions of samples are sorted on all cores, so the report doesn't
+/// keep a finished run waiting.
+pub fn sort_latencies(latencies: &mut [Duration]) {
+    // The parallel sort only costs extra on a single core
+    if rayon::current_num_threads() > 1 {
+        latencies.par_sort_unstable();
+    } else {
+        latencies.sort_unstable();
+    }
+}
+
+pub fn average(durations: &[Duration]) -> Duration {
+    if durations.is_empty() {
+        return Duration::from_secs(0);
+    }
+
+    durations.iter().sum::<Duration>() / durations.len() as u32
+}
+
+/// Nearest-rank percentile of response times sorted in ascending order.
+pub fn percentile(sorted: &[Duration], percentile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::from_secs(0);
+    }
+
+    let index = ((sorted.len() as f64) * percentile).floor() as usize;
+    let index = index.min(sorted.len() - 1);
+    sorted[index]
+}
+
+/// Points of the cumulative latency distribution as `(percentile, latency)`
+/// pairs, percentiles in percent: every 0.1% up to p99, then every 0.01% up
+/// to p100. The first point is the minimum and the last the maximum.
+pub fn cdf(sorted: &[Duration]) -> Vec<(f64, Duration)> {
+    if sorted.is_empty() {
+        return Vec::new();
+    }
+
+    // Counted in hundredths of a percent so the steps don't drift
+    let body = (0..9_900).step_by(10);
+    let tail = 9_900..=10_000;
+    body.chain(tail)
+        .map(|hundredths| {
+            let percent = hundredths as f64 / 100.0;
+            (percent, percentile(sorted, percent / 100.0))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cdf_rises_from_the_minimum_to_the_maximum() {
+        let mut latencies: Vec<Duration> = (0..10_000u64)
+            .map(|i| Duration::from_micros(100 + (i * 7919) % 10_000))
+            .collect();
+        sort_latencies(&mut latencies);
+
+        let points = cdf(&latencies);
+
+        assert_eq!(points.first(), Some(&(0.0, latencies[0])));
+        assert_eq!(points.last(), Some(&(100.0, *latencies.last().unwrap())));
+        assert!(points.windows(2).all(|pair| pair[0].0 < pair[1].0 && pair[0].1 <= pair[1].1));
+        // 0.1% steps up to p99, then 0.01% steps in the tail
+        assert_eq!(points.len(), 990 + 101);
+    }
+
+    #[test]
+    fn aggregation_of_shuffled_samples_is_exact() {
+        // 1..=100_000µs in a fixed shuffled order; 7919 is prime, so the
+        // stride visits every residue once
+        let mut latencies: Vec<Duration> = (0..100_000u64)
+            .map(|i| Duration::from_micros(1 + (i * 7919) % 100_000))
+            .collect();
+        sort_latencies(&mut latencies);
+
+        assert!(latencies.iter().enumerate().all(|(i, &latency)| latency == Duration::from_micros(i as u64 + 1)));
+
+        let stats = Statistics::from_samples(&latencies, Duration::from_secs(10), 100_000, 100_000);
+        assert_eq!(stats.min, Duration::from_micros(1));
+        assert_eq!(stats.max, Duration::from_micros(100_000));
+        assert_eq!(stats.avg, Duration::from_nanos(50_000_500));
+        assert_eq!(stats.p50, Duration::from_micros(50_001));
+        assert_eq!(stats.p90, Duration::from_micros(90_001));
+        assert_eq!(stats.p95, Duration::from_micros(95_001));
+        assert_eq!(stats.p99, Duration::from_micros(99_001));
+        assert_eq!(stats.requests_per_second, 10_000.0);
+    }
+}
@@ -1,92 +1,328 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
-use tokio::net::TcpStream;
+use tokio::net::{TcpSocket, TcpStream};
 use tokio::time::timeout;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::config::{ProxyProtocol, ScriptStep};
 use crate::error::BenchmarkError;
+use crate::limiter::ConnectLimiter;
+use crate::protocol::{self, Exchange, Protocol, Request, Response};
+use crate::trace::Tracer;
 
+/// First 12 bytes of every PROXY protocol v2 header
+const PROXY_V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+#[allow(clippy::too_many_arguments)]
 pub async fn send_tcp(
     address: &str,
-    data: Option<&[u8]>,
-    expect_pattern: Option<&str>,
-    timeout_duration: Duration,
-    buffer_size: usize,
-) -> Result<(Vec<u8>, Duration), BenchmarkError> {
-    let start_time = Instant::now();
+    setup: &[ScriptStep],
+    protocol: &dyn Protocol,
+    request: Request<'_>,
+    connect_limiter: &ConnectLimiter,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+    repeat: usize,
+    proxy_protocol: Option<ProxyProtocol>,
+    proxy_source: Option<SocketAddr>,
+    trace: Option<&Tracer>,
+) -> Result<Exchange, BenchmarkError> {
+    // Waiting for the connection limiter is not part of the latency
+    connect_limiter.acquire().await;
+    
+    let mut start_time = Instant::now();
     
     // Establish connection
     let mut stream = match timeout(
-        timeout_duration,
-        TcpStream::connect(address),
+        connect_timeout,
+        connect(address, local_addr),
     ).await {
-        Ok(Ok(stream)) => stream,
-        Ok(Err(_)) => return Err(BenchmarkError::ConnectionRefused),
-        Err(_) => return Err(BenchmarkError::ConnectionTimeout(timeout_duration)),
+        Ok(Ok(stream)) => {
+            tracing::debug!("connected");
+            connect_limiter.connected();
+            stream
+        },
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Err(BenchmarkError::ConnectionTimeout(connect_timeout)),
     };
     
-    // Send data if provided
-    if let Some(bytes) = data {
-        if !bytes.is_empty() {
-            match timeout(timeout_duration, stream.write_all(bytes)).await {
-                Ok(Ok(_)) => {},
-                Ok(Err(e)) => return Err(BenchmarkError::Io(e)),
-                Err(_) => return Err(BenchmarkError::RequestTimeout(timeout_duration)),
-            }
+    // Announce the client the way an L4 load balancer would, before anything else
+    if let Some(version) = proxy_protocol {
+        let source = match proxy_source {
+            Some(source) => source,
+            None => stream.local_addr()?,
+        };
+        let header = proxy_header(version, source, stream.peer_addr()?)?;
+        write_payload(&mut stream, &header, request.timeout).await?;
+    }
+    
+    // Run the setup exchanges; only the final exchange is measured
+    if !setup.is_empty() {
+        run_setup(&mut stream, setup, request.timeout, request.buffer_size).await?;
+        start_time = Instant::now();
+    }
+    
+    // Connecting counts towards the latency unless setup steps ran first
+    let exchange_start = start_time.elapsed();
+    let Exchange { response, messages, bytes_sent } = protocol::exchange_repeated(protocol, &mut stream, &request, repeat).await?;
+    if let Some(trace) = trace {
+        trace.record(start_time, exchange_start + response.elapsed, &protocol::sent_bytes(&request, repeat), &response.data);
+    }
+    Ok(Exchange {
+        response: Response {
+            data: response.data,
+            first_byte: response.first_byte.map(|t| exchange_start + t),
+            elapsed: exchange_start + response.elapsed,
+        },
+        messages,
+        bytes_sent,
+    })
+}
+
+/// Opens a connection to `address` and drops it again without sending
+/// anything (`--connect-only`), returning how long connecting took
+pub async fn connect_only(
+    address: &str,
+    connect_limiter: &ConnectLimiter,
+    local_addr: Option<IpAddr>,
+    connect_timeout: Duration,
+) -> Result<Duration, BenchmarkError> {
+    // Waiting for the connection limiter is not part of the latency
+    connect_limiter.acquire().await;
+    
+    let start_time = Instant::now();
+    match timeout(connect_timeout, connect(address, local_addr)).await {
+        Ok(Ok(_stream)) => {
+            let elapsed = start_time.elapsed();
+            connect_limiter.connected();
+            Ok(elapsed)
+        },
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(BenchmarkError::ConnectionTimeout(connect_timeout)),
+    }
+}
+
+/// Connects to `address` (`host:port`). With `local_addr` the socket is
+/// bound to that source IP first and only peer addresses of the same family
+/// are tried. A failed bind is a configuration error; a failed connect is
+/// `ConnectionRefused`, or `PortsExhausted` when no local port is left.
+pub async fn connect(address: &str, local_addr: Option<IpAddr>) -> Result<TcpStream, BenchmarkError> {
+    let Some(local_addr) = local_addr else {
+        return TcpStream::connect(address).await.map_err(BenchmarkError::from_connect);
+    };
+    
+    let peers = tokio::net::lookup_host(address).await
+        .map_err(|_| BenchmarkError::ConnectionRefused)?
+        .filter(|peer| peer.is_ipv4() == local_addr.is_ipv4());
+    
+    let mut last_error = None;
+    for peer in peers {
+        let socket = match peer {
+            SocketAddr::V4(_) => TcpSocket::new_v4(),
+            SocketAddr::V6(_) => TcpSocket::new_v6(),
+        }?;
+        // Binding to port 0 fails with `AddrInUse` once every port is taken
+        socket.bind(SocketAddr::new(local_addr, 0)).map_err(|e| match e.kind() {
+            io::ErrorKind::AddrInUse => BenchmarkError::PortsExhausted(e),
+            _ => BenchmarkError::Config(format!("Cannot bind to local address {}: {}", local_addr, e)),
+        })?;
+        match socket.connect(peer).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
         }
     }
     
-    // Read response
-    let mut response = Vec::new();
+    match last_error {
+        Some(e) => Err(BenchmarkError::from_connect(e)),
+        None => Err(BenchmarkError::Io(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!("{} has no address of the same family as local address {}", address, local_addr),
+        ))),
+    }
+}
+
+/// Checks before a run that a PROXY header claiming `source` can be sent to
+/// `address`: the header can't mix address families, so `address` must
+/// resolve to an address of `source`'s family, as must `local_addr` if set.
+pub async fn check_proxy_source(address: &str, source: SocketAddr, local_addr: Option<IpAddr>) -> Result<(), BenchmarkError> {
+    if let Some(local_addr) = local_addr
+        && local_addr.is_ipv4() != source.is_ipv4()
+    {
+        return Err(BenchmarkError::Config(format!(
+            "--proxy-source {} and --local-addr {} are not of the same address family",
+            source,
+            local_addr,
+        )));
+    }
+    let mut peers = tokio::net::lookup_host(address).await
+        .map_err(|e| BenchmarkError::Config(format!("Cannot resolve {}: {}", address, e)))?;
+    if !peers.any(|peer| peer.is_ipv4() == source.is_ipv4()) {
+        return Err(BenchmarkError::Config(format!(
+            "--proxy-source {} is an {} address, but {} has none of that family",
+            source,
+            if source.is_ipv4() { "IPv4" } else { "IPv6" },
+            address,
+        )));
+    }
+    Ok(())
+}
+
+/// The PROXY protocol header announcing a TCP connection from `source` to
+/// `destination`, which must be of the same address family.
+pub(crate) fn proxy_header(
+    version: ProxyProtocol,
+    source: SocketAddr,
+    destination: SocketAddr,
+) -> Result<Vec<u8>, BenchmarkError> {
+    let (v1_family, v2_family, mut addresses) = match (source.ip(), destination.ip()) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => ("TCP4", 0x11, [src.octets().as_slice(), &dst.octets()].concat()),
+        (IpAddr::V6(src), IpAddr::V6(dst)) => ("TCP6", 0x21, [src.octets().as_slice(), &dst.octets()].concat()),
+        _ => return Err(BenchmarkError::Config(format!(
+            "PROXY source {} and destination {} are not of the same address family",
+            source,
+            destination,
+        ))),
+    };
+    
+    match version {
+        ProxyProtocol::V1 => Ok(format!(
+            "PROXY {} {} {} {} {}\r\n",
+            v1_family,
+            source.ip(),
+            destination.ip(),
+            source.port(),
+            destination.port(),
+        ).into_bytes()),
+        ProxyProtocol::V2 => {
+            addresses.extend_from_slice(&source.port().to_be_bytes());
+            addresses.extend_from_slice(&destination.port().to_be_bytes());
+            
+            let mut header = PROXY_V2_SIGNATURE.to_vec();
+            header.push(0x21); // version 2, PROXY command
+            header.push(v2_family); // AF_INET or AF_INET6, over STREAM
+            header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+            header.extend_from_slice(&addresses);
+            Ok(header)
+        },
+    }
+}
+
+/// Writes all of `bytes` within `timeout_duration`, however slowly the peer
+/// reads them.
+pub(crate) async fn write_payload<S>(
+    stream: &mut S,
+    bytes: &[u8],
+    timeout_duration: Duration,
+) -> Result<(), BenchmarkError>
+where
+    S: AsyncWrite + Unpin + ?Sized,
+{
+    match timeout(timeout_duration, stream.write_all(bytes)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(BenchmarkError::RequestTimeout(timeout_duration)),
+    }
+}
+
+/// Runs the setup steps of a script on an open connection, waiting for each
+/// step's expected pattern before moving on to the next one.
+pub(crate) async fn run_setup<S>(
+    stream: &mut S,
+    setup: &[ScriptStep],
+    timeout_duration: Duration,
+    buffer_size: usize,
+) -> Result<(), BenchmarkError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     let mut buffer = vec![0; buffer_size];
     
-    // If we expect a pattern, read until we find it or timeout
-    if let Some(pattern) = expect_pattern {
-        let regex = Regex::new(pattern)
-            .map_err(|_| BenchmarkError::Parse(format!("Invalid regex pattern: {}", pattern)))?;
-        
-        let deadline = Instant::now() + timeout_duration;
-        let mut found = false;
+    for step in setup {
+        write_payload(stream, &step.send, timeout_duration).await?;
         
-        while Instant::now() < deadline && !found {
-            match stream.read(&mut buffer).await {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    response.extend_from_slice(&buffer[..n]);
-                    // Check if pattern is found
-                    if let Ok(text) = String::from_utf8(response.clone()) {
-                        if regex.is_match(&text) {
-                            found = true;
-                            break;
-                        }
-                    }
-                },
-                Err(e) => return Err(BenchmarkError::Io(e)),
-            }
-        }
-        
-        if !found {
-            return Err(BenchmarkError::ResponseValidation(
-                format!("Expected pattern '{}' not found in response", pattern)
-            ));
-        }
-    } else {
-        // Without a pattern, just read what's available within the timeout
-        match timeout(timeout_duration, async {
+        let Some(ref pattern) = step.expect else {
+            continue;
+        };
+        let mut response = Vec::new();
+        let found = timeout(timeout_duration, async {
             loop {
                 match stream.read(&mut buffer).await {
-                    Ok(0) => break, // EOF
-                    Ok(n) => response.extend_from_slice(&buffer[..n]),
-                    Err(e) => return Err(BenchmarkError::Io(e)),
+                    Ok(0) => return Ok(false), // EOF
+                    Ok(n) => {
+                        response.extend_from_slice(&buffer[..n]);
+                        if pattern.is_match(&response) {
+                            return Ok(true);
+                        }
+                    },
+                    Err(e) => return Err(e.into()),
                 }
             }
-            Ok::<(), BenchmarkError>(())
-        }).await {
-            Ok(Ok(_)) => {},
+        }).await;
+        
+        match found {
+            Ok(Ok(true)) => {},
+            Ok(Ok(false)) | Err(_) => {
+                return Err(BenchmarkError::ResponseValidation(
+                    format!("Setup step expected pattern '{}' not found in response", pattern.as_str())
+                ));
+            },
             Ok(Err(e)) => return Err(e),
-            Err(_) => {}, // Timeout is normal when no pattern is expected
         }
     }
     
-    let elapsed = start_time.elapsed();
-    Ok((response, elapsed))
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAYLOAD_SIZE: usize = 4 * 1024 * 1024;
+
+    #[tokio::test]
+    async fn write_payload_sends_a_multi_megabyte_payload() {
+        let (mut client, mut server) = tokio::io::duplex(64 * 1024);
+        let reader = tokio::spawn(async move {
+            let mut received = Vec::new();
+            server.read_to_end(&mut received).await.unwrap();
+            received
+        });
+
+        let payload: Vec<u8> = (0..PAYLOAD_SIZE).map(|i| i as u8).collect();
+        write_payload(&mut client, &payload, Duration::from_secs(5)).await.unwrap();
+        drop(client);
+
+        assert_eq!(reader.await.unwrap(), payload);
+    }
+
+    #[tokio::test]
+    async fn write_payload_has_one_deadline_for_the_whole_write() {
+        let (mut client, mut server) = tokio::io::duplex(64 * 1024);
+        // Each 64 KiB read is quick, but all of them take over a second
+        tokio::spawn(async move {
+            let mut buffer = vec![0; 64 * 1024];
+            while server.read(&mut buffer).await.is_ok_and(|n| n > 0) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+
+        let payload = vec![0; PAYLOAD_SIZE];
+        let result = write_payload(&mut client, &payload, Duration::from_millis(200)).await;
+        assert!(matches!(result, Err(BenchmarkError::RequestTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn check_proxy_source_needs_a_target_of_the_same_family() {
+        let v4: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::7]:4000".parse().unwrap();
+
+        assert!(check_proxy_source("127.0.0.1:80", v4, None).await.is_ok());
+        assert!(check_proxy_source("[::1]:80", v6, None).await.is_ok());
+        assert!(matches!(check_proxy_source("127.0.0.1:80", v6, None).await, Err(BenchmarkError::Config(_))));
+        assert!(matches!(check_proxy_source("[::1]:80", v4, None).await, Err(BenchmarkError::Config(_))));
+        assert!(matches!(
+            check_proxy_source("127.0.0.1:80", v4, Some("::1".parse().unwrap())).await,
+            Err(BenchmarkError::Config(_))
+        ));
+    }
+}
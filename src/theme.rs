@@ -0,0 +1,145 @@
+use std::fmt;
+use std::str::FromStr;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Built-in TUI color schemes, chosen with `--tui-theme`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    /// Light text on the terminal's own (dark) background
+    #[default]
+    Dark,
+    /// Dark text for terminals with a light background
+    Light,
+    /// Bright colors on black, with focus shown in reverse video
+    HighContrast,
+}
+
+impl FromStr for ThemeName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(ThemeName::Dark),
+            "light" => Ok(ThemeName::Light),
+            "highcontrast" => Ok(ThemeName::HighContrast),
+            other => Err(format!("Unknown theme '{}' (expected dark, light or highcontrast)", other)),
+        }
+    }
+}
+
+impl fmt::Display for ThemeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ThemeName::Dark => "dark",
+            ThemeName::Light => "light",
+            ThemeName::HighContrast => "highcontrast",
+        })
+    }
+}
+
+/// Named colors the TUI draws with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Labels and regular text
+    pub text: Color,
+    /// Values, selected tabs and focused fields
+    pub highlight: Color,
+    /// Hints and secondary text
+    pub muted: Color,
+    /// The throughput chart
+    pub accent: Color,
+    pub success: Color,
+    pub error: Color,
+    /// Background of the whole screen; `Reset` keeps the terminal's own
+    pub background: Color,
+    /// Added to `highlight` for focused fields and selected items
+    pub focus: Modifier,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Theme {
+                text: Color::White,
+                highlight: Color::Yellow,
+                muted: Color::Gray,
+                accent: Color::Cyan,
+                success: Color::Green,
+                error: Color::Red,
+                background: Color::Reset,
+                focus: Modifier::BOLD,
+            },
+            ThemeName::Light => Theme {
+                text: Color::Black,
+                highlight: Color::Blue,
+                muted: Color::DarkGray,
+                accent: Color::Magenta,
+                success: Color::Green,
+                error: Color::Red,
+                background: Color::Reset,
+                focus: Modifier::BOLD,
+            },
+            ThemeName::HighContrast => Theme {
+                text: Color::White,
+                highlight: Color::LightYellow,
+                muted: Color::White,
+                accent: Color::LightCyan,
+                success: Color::LightGreen,
+                error: Color::LightRed,
+                background: Color::Black,
+                focus: Modifier::BOLD | Modifier::REVERSED,
+            },
+        }
+    }
+
+    /// Style of a focused field or selected item
+    pub fn focused(&self) -> Style {
+        Style::default().fg(self.highlight).add_modifier(self.focus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [ThemeName; 3] = [ThemeName::Dark, ThemeName::Light, ThemeName::HighContrast];
+
+    #[test]
+    fn every_theme_keeps_its_colors_apart() {
+        for name in ALL {
+            let theme = Theme::new(name);
+
+            assert_ne!(theme.text, theme.background, "{}", name);
+            assert_ne!(theme.highlight, theme.text, "{}", name);
+            assert_ne!(theme.highlight, theme.background, "{}", name);
+            assert_ne!(theme.success, theme.error, "{}", name);
+            assert_ne!(theme.error, theme.background, "{}", name);
+        }
+    }
+
+    #[test]
+    fn every_theme_makes_the_selection_stand_out() {
+        for name in ALL {
+            let theme = Theme::new(name);
+            let focused = theme.focused();
+
+            assert_eq!(focused.fg, Some(theme.highlight), "{}", name);
+            assert!(!focused.add_modifier.is_empty(), "{}", name);
+            assert_ne!(focused, Style::default().fg(theme.text), "{}", name);
+        }
+        assert!(Theme::new(ThemeName::HighContrast).focused().add_modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn themes_differ_and_round_trip_through_their_names() {
+        for (i, name) in ALL.iter().enumerate() {
+            assert_eq!(name.to_string().parse::<ThemeName>(), Ok(*name));
+            for other in &ALL[i + 1..] {
+                assert_ne!(Theme::new(*name), Theme::new(*other), "{} and {}", name, other);
+            }
+        }
+        assert!("solarized".parse::<ThemeName>().is_err());
+    }
+}
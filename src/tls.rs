@@ -0,0 +1,368 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rustls::{ClientConfig, DigitallySignedStruct, HandshakeKind, SignatureScheme};
+use rustls::client::{ClientSessionMemoryCache, Resumption};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use crate::error::BenchmarkError;
+
+/// Sessions a worker's cache keeps for resumption, enough for a handful of
+/// `--url`/`--hosts-file` targets
+const SESSION_CACHE_SIZE: usize = 32;
+
+/// A connection that is either plain TCP or TLS over TCP, so the HTTP code
+/// can treat `http` and `https` targets alike.
+pub enum MaybeTlsStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+/// TLS settings for `https` targets.
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// Server name (SNI) to present; defaults to the URL's host
+    pub sni: Option<String>,
+    /// Skip certificate verification (`--insecure`)
+    pub insecure: bool,
+    /// Sessions to resume on later connections (`--tls-resumption on`);
+    /// without a cache every handshake is a full one
+    pub sessions: Option<Arc<SessionCache>>,
+    /// Full and resumed handshakes, shared by all clones of the options
+    pub handshakes: Arc<HandshakeCounts>,
+}
+
+impl TlsOptions {
+    /// The same options with a new, empty session cache, so each worker
+    /// resumes its own sessions like an independent client
+    pub fn with_session_cache(mut self) -> Self {
+        self.sessions = Some(Arc::new(SessionCache {
+            configs: [const { OnceLock::new() }; 2],
+            store: Arc::new(ClientSessionMemoryCache::new(SESSION_CACHE_SIZE)),
+        }));
+        self
+    }
+}
+
+/// A worker's resumable sessions, with the client configuration that stores
+/// them built once per ALPN list rather than on every handshake
+#[derive(Debug)]
+pub struct SessionCache {
+    configs: [OnceLock<Arc<ClientConfig>>; 2],
+    store: Arc<ClientSessionMemoryCache>,
+}
+
+/// Handshakes completed with the options they were counted on
+#[derive(Debug, Default)]
+pub struct HandshakeCounts {
+    full: AtomicU64,
+    resumed: AtomicU64,
+    /// Expiry of the first server certificate these handshakes saw
+    cert_not_after: OnceLock<SystemTime>,
+}
+
+impl HandshakeCounts {
+    pub fn full(&self) -> u64 {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    pub fn resumed(&self) -> u64 {
+        self.resumed.load(Ordering::Relaxed)
+    }
+
+    /// When the server's certificate expires (`--check-cert`), if a
+    /// handshake has received one yet
+    pub fn cert_not_after(&self) -> Option<SystemTime> {
+        self.cert_not_after.get().copied()
+    }
+}
+
+/// Client configuration trusting the bundled webpki roots, or accepting any
+/// certificate when `insecure` is set. The ALPN list offers `h2` when
+/// `http2` is set and `http/1.1` otherwise. Sessions are not resumed; see
+/// `connector`.
+fn client_config(http2: bool, insecure: bool) -> Arc<ClientConfig> {
+    static CONFIGS: [OnceLock<Arc<ClientConfig>>; 4] = [const { OnceLock::new() }; 4];
+
+    CONFIGS[http2 as usize + 2 * insecure as usize].get_or_init(|| {
+        let roots = rustls::RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let mut config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        if insecure {
+            let provider = config.crypto_provider().clone();
+            config.dangerous().set_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)));
+        }
+        config.alpn_protocols = if http2 {
+            vec![b"h2".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+        config.resumption = Resumption::disabled();
+        Arc::new(config)
+    }).clone()
+}
+
+/// Connector for `options`: the shared client configuration, resuming
+/// sessions from the options' cache when there is one
+fn connector(http2: bool, options: &TlsOptions) -> TlsConnector {
+    let Some(sessions) = &options.sessions else {
+        return TlsConnector::from(client_config(http2, options.insecure));
+    };
+    let config = sessions.configs[http2 as usize].get_or_init(|| {
+        let mut config = (*client_config(http2, options.insecure)).clone();
+        config.resumption = Resumption::store(sessions.store.clone());
+        Arc::new(config)
+    });
+    TlsConnector::from(config.clone())
+}
+
+/// Certificate verifier for `--insecure`: any certificate is accepted, but
+/// handshake signatures are still checked so the connection itself works
+/// like a verified one.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Runs the TLS handshake on `stream`, presenting `server_name` as SNI and
+/// verifying the certificate against it unless `options.insecure` is set.
+/// The handshake is counted as full or resumed in `options.handshakes`.
+pub async fn handshake(
+    stream: TcpStream,
+    server_name: &str,
+    http2: bool,
+    options: &TlsOptions,
+) -> Result<MaybeTlsStream, BenchmarkError> {
+    let name = ServerName::try_from(server_name.to_string())
+        .map_err(|_| BenchmarkError::Config(format!("Invalid TLS server name: {}", server_name)))?;
+
+    let stream = connector(http2, options)
+        .connect(name, stream)
+        .await
+        .map_err(handshake_error)?;
+
+    let counter = match stream.get_ref().1.handshake_kind() {
+        Some(HandshakeKind::Resumed) => &options.handshakes.resumed,
+        _ => &options.handshakes.full,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+
+    // The certificate is the same on every connection, so it's parsed once
+    if options.handshakes.cert_not_after.get().is_none()
+        && let Some(not_after) = stream.get_ref().1.peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| cert_not_after(cert))
+    {
+        let _ = options.handshakes.cert_not_after.set(not_after);
+    }
+
+    // Speaking HTTP/2 to a server that didn't agree to it only yields
+    // protocol errors, so fail the handshake instead
+    if http2 && stream.get_ref().1.alpn_protocol() != Some(b"h2".as_slice()) {
+        return Err(BenchmarkError::TlsHandshake("server did not negotiate HTTP/2 (ALPN h2)".to_string()));
+    }
+
+    Ok(MaybeTlsStream::Tls(Box::new(stream)))
+}
+
+/// The end of an X.509 certificate's validity period (`notAfter`), read
+/// straight from its DER encoding: the fifth field of the TBSCertificate, or
+/// the fourth when the optional version is absent.
+fn cert_not_after(cert: &[u8]) -> Option<SystemTime> {
+    let (_, cert, _) = der_element(cert, 0x30)?;
+    let (_, mut tbs, _) = der_element(cert, 0x30)?;
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_element(tbs, 0xa0)?.2;
+    }
+    for tag in [0x02, 0x30, 0x30] {
+        tbs = der_element(tbs, tag)?.2; // serial number, signature algorithm, issuer
+    }
+    let (_, validity, _) = der_element(tbs, 0x30)?;
+    let (_, _, validity) = der_element(validity, *validity.first()?)?; // notBefore
+    let (tag, not_after, _) = der_element(validity, *validity.first()?)?;
+    asn1_time(tag, not_after)
+}
+
+/// Splits the DER element at the start of `input`, which must have `tag`,
+/// into its tag, contents and the input after it
+fn der_element(input: &[u8], tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    let (&found, rest) = input.split_first()?;
+    if found != tag {
+        return None;
+    }
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let octets = (first & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets].iter().fold(0, |len, &b| (len << 8) | b as usize);
+        (len, &rest[octets..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// An ASN.1 UTCTime (`YYMMDDHHMMSSZ`, tag 0x17) or GeneralizedTime
+/// (`YYYYMMDDHHMMSSZ`, tag 0x18), the two forms certificates use
+fn asn1_time(tag: u8, value: &[u8]) -> Option<SystemTime> {
+    let value = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        // RFC 5280: two-digit years from 50 on are in the 1900s
+        0x17 => {
+            let year: i64 = value.get(..2)?.parse().ok()?;
+            (if year >= 50 { 1900 + year } else { 2000 + year }, &value[2..])
+        },
+        0x18 => (value.get(..4)?.parse().ok()?, &value[4..]),
+        _ => return None,
+    };
+    if rest.len() != 10 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |i: usize| rest[i..i + 2].parse::<i64>().unwrap();
+    let (month, day, hour, minute, second) = (field(0), field(2), field(4), field(6), field(8));
+
+    // Days from 1970-01-01 to the date, counting in 400-year eras that
+    // start on March 1st so leap days fall at the end of a year
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// Tells a rejected server certificate (expired, self-signed, wrong name)
+/// apart from other handshake failures
+fn handshake_error(e: io::Error) -> BenchmarkError {
+    match e.get_ref().and_then(|inner| inner.downcast_ref::<rustls::Error>()) {
+        Some(rustls::Error::InvalidCertificate(reason)) => {
+            BenchmarkError::CertificateVerification(format!("{:?}", reason))
+        },
+        Some(inner) => BenchmarkError::TlsHandshake(inner.to_string()),
+        None => BenchmarkError::TlsHandshake(e.to_string()),
+    }
+}
+
+impl AsyncRead for MaybeTlsStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            MaybeTlsStream::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for MaybeTlsStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            MaybeTlsStream::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            MaybeTlsStream::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            MaybeTlsStream::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn cert_not_after_reads_the_end_of_validity() {
+        // Self-signed, valid from 2025-01-01 (UTCTime) to 2099-12-31 (GeneralizedTime)
+        let cert = include_bytes!("../tests/data/localhost.crt.der");
+        assert_eq!(cert_not_after(cert), at(4_102_444_799));
+
+        assert_eq!(cert_not_after(&cert[..cert.len() / 2]), None);
+        assert_eq!(cert_not_after(b"not a certificate"), None);
+    }
+
+    #[test]
+    fn der_element_splits_short_and_long_lengths() {
+        assert_eq!(der_element(&[0x02, 0x01, 0x05, 0xff], 0x02), Some((0x02, &[0x05][..], &[0xff][..])));
+        let long = [&[0x30, 0x81, 0x80][..], &[0u8; 0x80]].concat();
+        assert_eq!(der_element(&long, 0x30).map(|(_, contents, rest)| (contents.len(), rest.len())), Some((0x80, 0)));
+
+        assert_eq!(der_element(&[0x02, 0x01, 0x05], 0x30), None); // other tag
+        assert_eq!(der_element(&[0x02, 0x03, 0x05], 0x02), None); // truncated
+        assert_eq!(der_element(&[0x30, 0x80, 0x00, 0x00], 0x30), None); // indefinite length
+    }
+
+    #[test]
+    fn asn1_time_reads_both_forms() {
+        assert_eq!(asn1_time(0x17, b"250101000000Z"), at(1_735_689_600));
+        assert_eq!(asn1_time(0x18, b"20240229120000Z"), at(1_709_208_000));
+        // Two-digit years below 50 are in the 2000s, from 50 in the 1900s
+        assert_eq!(asn1_time(0x17, b"491231235959Z"), at(2_524_607_999));
+        assert_eq!(asn1_time(0x17, b"700101000001Z"), at(1));
+        assert_eq!(asn1_time(0x17, b"500101000000Z"), None);
+
+        assert_eq!(asn1_time(0x17, b"250101000000"), None);
+        assert_eq!(asn1_time(0x17, b"2501010000Z"), None);
+        assert_eq!(asn1_time(0x18, b"2025o101000000Z"), None);
+        assert_eq!(asn1_time(0x04, b"250101000000Z"), None);
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::{mpsc, oneshot};
+use crate::error::BenchmarkError;
+
+/// First bytes of every trace file, ending in the format version
+pub const TRACE_MAGIC: &[u8; 8] = b"TBTRACE1";
+
+/// `--trace-file`: where the bytes of each request are recorded, and for how
+/// many requests at most (`--trace-max-requests`)
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    pub path: PathBuf,
+    pub max_requests: usize,
+}
+
+/// Records what each request sent and received, for inspecting a protocol
+/// issue after the run. The file starts with `TRACE_MAGIC`, followed by one
+/// record per completed request, all integers little-endian:
+///
+/// | field        | type         |                                          |
+/// |--------------|--------------|------------------------------------------|
+/// | start_ns     | u64          | when the request started, from run start |
+/// | elapsed_ns   | u64          | how long it took                         |
+/// | sent_len     | u32          |                                          |
+/// | sent         | sent_len     | bytes written                            |
+/// | received_len | u32          |                                          |
+/// | received     | received_len | bytes read                               |
+///
+/// For TCP and UDS these are the payload and response of the measured
+/// exchange (all of them with `--repeat`); for HTTP, the request and response
+/// bodies. Requests that fail are not recorded, and recording stops after
+/// `max_requests`. The records are written by a task of their own, so the
+/// workers never wait on the disk.
+pub struct Tracer {
+    path: PathBuf,
+    tx: mpsc::UnboundedSender<TraceWrite>,
+    start: Instant,
+    max_requests: usize,
+    recorded: AtomicUsize,
+}
+
+/// What the workers hand the writer task
+enum TraceWrite {
+    Record(Vec<u8>),
+    /// Flush the file and answer with the first error any write hit
+    Finish(oneshot::Sender<io::Result<()>>),
+}
+
+impl Tracer {
+    pub fn create(options: &TraceOptions) -> Result<Self, BenchmarkError> {
+        let mut file = File::create(&options.path).map_err(|e| trace_error(&options.path, e))?;
+        file.write_all(TRACE_MAGIC).map_err(|e| trace_error(&options.path, e))?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_trace(tokio::fs::File::from_std(file), rx));
+        Ok(Tracer {
+            path: options.path.clone(),
+            tx,
+            start: Instant::now(),
+            max_requests: options.max_requests,
+            recorded: AtomicUsize::new(0),
+        })
+    }
+
+    /// Appends a request that started at `started` and took `elapsed`,
+    /// unless `max_requests` were recorded already
+    pub fn record(&self, started: Instant, elapsed: Duration, sent: &[u8], received: &[u8]) {
+        if self.recorded.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < self.max_requests).then_some(n + 1)).is_err() {
+            return;
+        }
+
+        let mut record = Vec::with_capacity(24 + sent.len() + received.len());
+        record.extend_from_slice(&(started.saturating_duration_since(self.start).as_nanos() as u64).to_le_bytes());
+        record.extend_from_slice(&(elapsed.as_nanos() as u64).to_le_bytes());
+        for bytes in [sent, received] {
+            record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            record.extend_from_slice(bytes);
+        }
+
+        let _ = self.tx.send(TraceWrite::Record(record));
+    }
+
+    /// Waits for the records so far to be written and flushed, failing with
+    /// the first error any of them hit
+    pub async fn finish(&self) -> Result<(), BenchmarkError> {
+        let (done, result) = oneshot::channel();
+        let _ = self.tx.send(TraceWrite::Finish(done));
+        match result.await {
+            Ok(result) => result.map_err(|e| trace_error(&self.path, e)),
+            Err(_) => Err(trace_error(&self.path, io::Error::other("trace writer stopped"))),
+        }
+    }
+}
+
+/// The writer task behind a `Tracer`. After a failed write the remaining
+/// records are dropped, and the error is reported by `Tracer::finish`.
+async fn write_trace(file: tokio::fs::File, mut rx: mpsc::UnboundedReceiver<TraceWrite>) {
+    let mut file = BufWriter::new(file);
+    let mut error = None;
+    while let Some(write) = rx.recv().await {
+        match write {
+            TraceWrite::Record(record) => {
+                if error.is_none()
+                    && let Err(e) = file.write_all(&record).await
+                {
+                    error = Some(e);
+                }
+            },
+            TraceWrite::Finish(done) => {
+                let result = match error.take() {
+                    Some(e) => Err(e),
+                    None => file.flush().await,
+                };
+                let _ = done.send(result);
+                return;
+            },
+        }
+    }
+    // A run that failed before finishing still leaves its records behind
+    let _ = file.flush().await;
+}
+
+fn trace_error(path: &Path, e: io::Error) -> BenchmarkError {
+    BenchmarkError::Config(format!("Cannot write --trace-file {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits the next `n` bytes off `trace`
+    fn take(trace: &mut &[u8], n: usize) -> Vec<u8> {
+        let (head, rest) = trace.split_at(n);
+        *trace = rest;
+        head.to_vec()
+    }
+
+    /// Splits a trace file into its records' fields
+    fn parse(mut trace: &[u8]) -> Vec<(u64, u64, Vec<u8>, Vec<u8>)> {
+        assert_eq!(take(&mut trace, 8), TRACE_MAGIC);
+        let mut records = Vec::new();
+        while !trace.is_empty() {
+            let start = u64::from_le_bytes(take(&mut trace, 8).try_into().unwrap());
+            let elapsed = u64::from_le_bytes(take(&mut trace, 8).try_into().unwrap());
+            let sent_len = u32::from_le_bytes(take(&mut trace, 4).try_into().unwrap()) as usize;
+            let sent = take(&mut trace, sent_len);
+            let received_len = u32::from_le_bytes(take(&mut trace, 4).try_into().unwrap()) as usize;
+            records.push((start, elapsed, sent, take(&mut trace, received_len)));
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn records_are_framed_after_the_magic_up_to_the_limit() {
+        let path = std::env::temp_dir().join(format!("thrustbench-trace-{}.bin", std::process::id()));
+        let tracer = Tracer::create(&TraceOptions { path: path.clone(), max_requests: 2 }).unwrap();
+
+        let started = tracer.start + Duration::from_millis(5);
+        tracer.record(started, Duration::from_micros(250), b"ping", b"pong!");
+        tracer.record(started, Duration::from_micros(100), b"", b"\x00\x01");
+        tracer.record(started, Duration::from_micros(100), b"dropped", b"");
+        tracer.finish().await.unwrap();
+
+        let records = parse(&std::fs::read(&path).unwrap());
+        assert_eq!(records, [
+            (5_000_000, 250_000, b"ping".to_vec(), b"pong!".to_vec()),
+            (5_000_000, 100_000, Vec::new(), vec![0, 1]),
+        ]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
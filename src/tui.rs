@@ -6,27 +6,40 @@ use crossterm::{
 };
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Tabs, Wrap},
+    symbols,
+    widgets::{Axis, Block, Borders, Chart, Clear, Dataset, Gauge, GraphType, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Terminal, Frame,
 };
-use std::{io, sync::Arc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::Arc, time::{Duration, Instant}};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Mutex;
 use tui_textarea::TextArea;
 
-use crate::report::BenchmarkReport;
+use crate::report::{self, BenchmarkReport, ErrorSummary};
+use crate::runner::{PauseFlag, ThroughputPoint};
+use crate::theme::{Theme, ThemeName};
 use crate::config_manager::{
     BenchmarkConfigType, ConfigStore, HttpConfigSave, TcpConfigSave, UdsConfigSave,
-    get_default_config_path,
+    get_default_config_path, get_last_session_path,
 };
 
+/// Width of the Results page's list of runs
+const RUN_LIST_WIDTH: u16 = 32;
+/// How often the screen is redrawn while no key is pressed
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+/// Error messages listed in the Results page's error panel, newest first
+const RECENT_ERRORS_SHOWN: usize = 5;
+
 /// The different pages our TUI can display
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Page {
     Http,
     Tcp,
     Uds,
+    Live,
     Results,
     Configs,
     Help,
@@ -38,6 +51,7 @@ impl Page {
             Page::Http => "HTTP",
             Page::Tcp => "TCP",
             Page::Uds => "UDS",
+            Page::Live => "Live",
             Page::Results => "Results",
             Page::Configs => "Configs",
             Page::Help => "Help",
@@ -48,7 +62,8 @@ impl Page {
         match self {
             Page::Http => Page::Tcp,
             Page::Tcp => Page::Uds,
-            Page::Uds => Page::Results,
+            Page::Uds => Page::Live,
+            Page::Live => Page::Results,
             Page::Results => Page::Configs,
             Page::Configs => Page::Help,
             Page::Help => Page::Http,
@@ -60,14 +75,16 @@ impl Page {
             Page::Http => Page::Help,
             Page::Tcp => Page::Http,
             Page::Uds => Page::Tcp,
-            Page::Results => Page::Uds,
+            Page::Live => Page::Uds,
+            Page::Results => Page::Live,
             Page::Configs => Page::Results,
             Page::Help => Page::Configs,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 struct HttpOptions {
     url: String,
     method: String,
@@ -96,7 +113,8 @@ impl Default for HttpOptions {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 struct TcpOptions {
     address: String,
     data: Option<String>,
@@ -123,7 +141,8 @@ impl Default for TcpOptions {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 struct UdsOptions {
     path: String,
     data: Option<String>,
@@ -150,6 +169,33 @@ impl Default for UdsOptions {
     }
 }
 
+/// The HTTP/TCP/UDS page contents, restored on the next launch
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct LastSession {
+    http: HttpOptions,
+    tcp: TcpOptions,
+    uds: UdsOptions,
+    theme: ThemeName,
+}
+
+impl LastSession {
+    /// Reads the previous session; a missing or corrupt file yields the defaults.
+    fn load() -> Self {
+        get_last_session_path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(get_last_session_path()?, json)?;
+        Ok(())
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum FocusField {
     None,
@@ -181,6 +227,54 @@ enum ConfigAction {
     Delete,
 }
 
+/// How far the running benchmark has got, for the gauge on the Live page
+struct RunProgress {
+    started: Instant,
+    /// Time limit of the run; zero for none
+    duration: Duration,
+    /// Request limit of the run; zero for none
+    requests: usize,
+    /// Completed requests, counted by the runner
+    completed: Arc<AtomicUsize>,
+    /// Toggled by `p` to pause and resume the run
+    pause: Arc<PauseFlag>,
+}
+
+impl RunProgress {
+    /// Fraction of the run done, by whichever limit is closer to ending it
+    fn ratio(&self) -> f64 {
+        let by_time = (!self.duration.is_zero())
+            .then(|| self.elapsed().as_secs_f64() / self.duration.as_secs_f64());
+        let by_count = (self.requests > 0)
+            .then(|| self.completed.load(Ordering::Relaxed) as f64 / self.requests as f64);
+        by_time.into_iter().chain(by_count).fold(0.0, f64::max).min(1.0)
+    }
+    
+    /// Time the run has been going, not counting pauses
+    fn elapsed(&self) -> Duration {
+        self.started.elapsed().saturating_sub(self.pause.paused_time())
+    }
+    
+    /// Percent done, time and requests so far, and the latest requests/sec
+    fn label(&self, rps: Option<f64>) -> String {
+        let mut parts = vec![format!("{:.0}%", self.ratio() * 100.0)];
+        if !self.duration.is_zero() {
+            let elapsed = self.elapsed().min(self.duration);
+            parts.push(format!("{}s of {}s", elapsed.as_secs(), self.duration.as_secs()));
+        }
+        let completed = self.completed.load(Ordering::Relaxed);
+        parts.push(if self.requests > 0 {
+            format!("{}/{} requests", completed, self.requests)
+        } else {
+            format!("{} requests", completed)
+        });
+        if let Some(rps) = rps {
+            parts.push(format!("{:.0} req/s", rps));
+        }
+        parts.join("  |  ")
+    }
+}
+
 struct AppState {
     page: Page,
     http_options: HttpOptions,
@@ -190,52 +284,90 @@ struct AppState {
     mode: AppMode,
     textarea: TextArea<'static>,
     reports: Vec<BenchmarkReport>,
+    /// Run highlighted in the Results page's run list, indexing `reports`;
+    /// the latest run when `None`
+    selected_report: Option<usize>,
+    /// Runs picked on the Results page to compare, indexing `reports`
+    compare_baseline: Option<usize>,
+    compare_candidate: Option<usize>,
+    /// Requests/sec timeline of the current or last run, for the Live page
+    throughput: Vec<ThroughputPoint>,
+    /// Progress of the running benchmark; `None` once it has finished
+    progress: Option<RunProgress>,
     is_running: bool,
     current_field_value: String,
     message: Option<String>,
     config_store: ConfigStore,
+    /// Where `config_store` is saved; `None` keeps it in memory only
+    config_path: Option<PathBuf>,
     config_names: Vec<String>,
     selected_config_index: Option<usize>,
     config_action: ConfigAction,
     config_name_input: String,
+    /// Action waiting for a y/n answer in the confirmation dialog
+    confirm_pending: Option<ConfigAction>,
+    /// Substring filter for the Configs list; `selected_config_index` indexes the filtered list
+    config_filter: String,
+    filter_editing: bool,
+    /// Opened on the first copy and kept, since on X11 the copied text
+    /// disappears with the clipboard handle that owns it
+    clipboard: Option<arboard::Clipboard>,
+    /// Saved with the session so the next launch keeps it
+    theme_name: ThemeName,
+    theme: Theme,
 }
 
 impl AppState {
-    fn new() -> Self {
+    /// `theme` overrides the theme of the last session
+    fn new(theme: Option<ThemeName>) -> Self {
         // Try to load existing configs
-        let config_store = match get_default_config_path() {
-            Ok(path) => {
-                if path.exists() {
-                    match ConfigStore::load(&path) {
-                        Ok(store) => store,
-                        Err(_) => ConfigStore::new(),
-                    }
-                } else {
-                    ConfigStore::new()
-                }
-            },
-            Err(_) => ConfigStore::new(),
+        let config_path = get_default_config_path().ok();
+        let config_store = match config_path {
+            Some(ref path) if path.exists() => ConfigStore::load(path).unwrap_or_default(),
+            _ => ConfigStore::new(),
         };
 
+        Self {
+            config_path,
+            ..Self::with_session(config_store, LastSession::load(), theme)
+        }
+    }
+
+    /// State over `config_store` that starts from `last_session`, without
+    /// reading the saved ones from disk or writing changes back
+    fn with_session(config_store: ConfigStore, last_session: LastSession, theme: Option<ThemeName>) -> Self {
         let config_names = config_store.list();
+        let theme_name = theme.unwrap_or(last_session.theme);
 
         Self {
             page: Page::Http,
-            http_options: HttpOptions::default(),
-            tcp_options: TcpOptions::default(),
-            uds_options: UdsOptions::default(),
+            http_options: last_session.http,
+            tcp_options: last_session.tcp,
+            uds_options: last_session.uds,
             focus: FocusField::None,
             mode: AppMode::Normal,
             textarea: TextArea::default(),
             reports: Vec::new(),
+            selected_report: None,
+            compare_baseline: None,
+            compare_candidate: None,
+            throughput: Vec::new(),
+            progress: None,
             is_running: false,
             current_field_value: String::new(),
             message: None,
             config_store,
+            config_path: None,
             config_names,
             selected_config_index: None,
             config_action: ConfigAction::None,
             config_name_input: String::new(),
+            confirm_pending: None,
+            config_filter: String::new(),
+            filter_editing: false,
+            clipboard: None,
+            theme_name,
+            theme: Theme::new(theme_name),
         }
     }
 
@@ -293,8 +425,8 @@ impl AppState {
         self.config_store.add(name, config);
 
         // Save the config store to disk
-        if let Ok(path) = get_default_config_path() {
-            self.config_store.save(path)?;
+        if let Some(ref path) = self.config_path {
+            self.config_store.save(path.clone())?;
         }
 
         // Update the config names list
@@ -357,6 +489,181 @@ impl AppState {
         Ok(())
     }
 
+    /// Starts a benchmark of the current page's settings in the background,
+    /// unless one is already running
+    fn start_run(&mut self, app_state: &Arc<Mutex<AppState>>) {
+        if self.is_running {
+            return;
+        }
+        let app_state = app_state.clone();
+        tokio::spawn(async move {
+            run_benchmark(app_state).await;
+        });
+        self.is_running = true;
+        self.message = Some("Benchmark started...".to_string());
+    }
+
+    /// Loads the selected config and runs it straight away (`R` on the
+    /// Configs page)
+    fn load_and_run(&mut self, app_state: &Arc<Mutex<AppState>>) {
+        if self.is_running {
+            self.message = Some("A benchmark is already running".to_string());
+            return;
+        }
+        let Some(name) = self.selected_config_name() else {
+            self.message = Some("No configuration selected".to_string());
+            return;
+        };
+        match self.load_config(&name) {
+            Ok(()) => {
+                self.start_run(app_state);
+                self.message = Some(format!("Running configuration: {}", name));
+            },
+            Err(e) => self.message = Some(format!("Failed to load config: {}", e)),
+        }
+    }
+
+    fn save_last_session(&self) -> Result<()> {
+        LastSession {
+            http: self.http_options.clone(),
+            tcp: self.tcp_options.clone(),
+            uds: self.uds_options.clone(),
+            theme: self.theme_name,
+        }
+        .save()
+    }
+
+    /// Indices into `config_names` of the configs matching the current filter
+    fn visible_config_indices(&self) -> Vec<usize> {
+        filter_config_names(&self.config_names, &self.config_filter)
+    }
+
+    /// Name of the selected config, mapping the filtered selection back to the full list
+    fn selected_config_name(&self) -> Option<String> {
+        let visible = self.visible_config_indices();
+        self.selected_config_index
+            .and_then(|i| visible.get(i))
+            .map(|&i| self.config_names[i].clone())
+    }
+
+    /// Resets the selection to the first match after the filter changed
+    fn on_filter_changed(&mut self) {
+        self.selected_config_index = if self.visible_config_indices().is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Deletes the config selected in the list and reports the outcome in the status bar.
+    fn delete_selected_config(&mut self) {
+        if let Some(name) = self.selected_config_name() {
+            if let Err(e) = self.delete_config(&name) {
+                self.message = Some(format!("Failed to delete config: {}", e));
+            } else {
+                self.message = Some(format!("Deleted configuration: {}", name));
+                self.selected_config_index = None;
+            }
+        } else {
+            self.message = Some("No configuration selected".to_string());
+        }
+        self.config_action = ConfigAction::None;
+    }
+
+    /// Acts on a y/n answer to the confirmation dialog; other keys leave it open
+    fn answer_confirmation(&mut self, key: KeyCode) {
+        let Some(action) = self.confirm_pending else {
+            return;
+        };
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                self.confirm_pending = None;
+                if action == ConfigAction::Delete {
+                    self.delete_selected_config();
+                }
+            },
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.confirm_pending = None;
+                self.config_action = ConfigAction::None;
+                self.message = Some("Deletion cancelled".to_string());
+            },
+            _ => {}
+        }
+    }
+
+    /// Index of the run the Results page shows
+    fn displayed_report(&self) -> usize {
+        self.selected_report.unwrap_or(self.reports.len().saturating_sub(1))
+    }
+    
+    /// Moves the Results page's run selection up or down
+    fn navigate_reports(&mut self, key: KeyCode) {
+        if self.reports.is_empty() {
+            return;
+        }
+        let index = self.displayed_report();
+        self.selected_report = Some(match key {
+            KeyCode::Up => index.saturating_sub(1),
+            _ => (index + 1).min(self.reports.len() - 1),
+        });
+    }
+    
+    /// Picks the selected run as the comparison's baseline or candidate
+    fn pick_for_comparison(&mut self, baseline: bool) {
+        if self.reports.is_empty() {
+            self.message = Some("No runs to compare yet. Run a benchmark first.".to_string());
+            return;
+        }
+        let index = self.displayed_report();
+        let (picked, other) = if baseline {
+            (&mut self.compare_baseline, &mut self.compare_candidate)
+        } else {
+            (&mut self.compare_candidate, &mut self.compare_baseline)
+        };
+        *picked = Some(index);
+        // A run compared with itself shows no change
+        if *other == Some(index) {
+            *other = None;
+        }
+        
+        self.message = Some(match (self.compare_baseline, self.compare_candidate) {
+            (Some(b), Some(c)) => format!("Comparing run #{} (baseline) with run #{} (candidate) | x: clear", b + 1, c + 1),
+            (Some(b), None) => format!("Run #{} is the baseline; select another run and press c to compare", b + 1),
+            (_, c) => format!("Run #{} is the candidate; select another run and press b to compare", c.unwrap_or(index) + 1),
+        });
+    }
+    
+    /// Copies the report the Results page shows to the system clipboard as
+    /// JSON and reports the outcome in the status bar.
+    fn copy_report(&mut self) {
+        let Some((index, json)) = self.displayed_report_json() else {
+            self.message = Some("No report to copy yet. Run a benchmark first.".to_string());
+            return;
+        };
+        
+        self.message = Some(match json.and_then(|json| self.copy_to_clipboard(json)) {
+            Ok(()) => format!("Copied run #{} as JSON to the clipboard", index + 1),
+            Err(e) => format!("Failed to copy run #{}: {}", index + 1, e),
+        });
+    }
+    
+    /// Index and JSON of the report the Results page shows, if any
+    fn displayed_report_json(&self) -> Option<(usize, Result<String>)> {
+        let index = self.displayed_report();
+        self.reports.get(index).map(|report| (index, report_json(report)))
+    }
+    
+    fn copy_to_clipboard(&mut self, text: String) -> Result<()> {
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self.clipboard.insert(
+                arboard::Clipboard::new().map_err(|e| anyhow::anyhow!("clipboard unavailable ({})", e))?,
+            ),
+        };
+        clipboard.set_text(text)?;
+        Ok(())
+    }
+    
     fn delete_config(&mut self, name: &str) -> Result<()> {
         // Remove the config from the store
         if self.config_store.remove(name).is_none() {
@@ -364,8 +671,8 @@ impl AppState {
         }
 
         // Save the config store to disk
-        if let Ok(path) = get_default_config_path() {
-            self.config_store.save(path)?;
+        if let Some(ref path) = self.config_path {
+            self.config_store.save(path.clone())?;
         }
 
         // Update the config names list
@@ -375,7 +682,7 @@ impl AppState {
     }
 }
 
-pub async fn run_tui() -> Result<()> {
+pub async fn run_tui(theme: Option<ThemeName>) -> Result<()> {
     // Set up terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -385,7 +692,7 @@ pub async fn run_tui() -> Result<()> {
 
 
     // Create app state
-    let app_state = Arc::new(Mutex::new(AppState::new()));
+    let app_state = Arc::new(Mutex::new(AppState::new(theme)));
     let app_state_clone = app_state.clone();
 
     // Start the main loop
@@ -419,28 +726,68 @@ async fn run_app(
         // Make sure cursor is visible after each frame draw
         terminal.show_cursor()?;
 
+        // Wake up periodically so the Live page keeps updating during a run
+        if !event::poll(REDRAW_INTERVAL)? {
+            continue;
+        }
+
         // Handle input
+        #[allow(clippy::collapsible_if)]
         if let Event::Key(key) = event::read()? {
             if key.kind == KeyEventKind::Press {
                 let mut state = app_state.lock().await;
                 
+                // While typing a Configs filter, keys edit the filter instead of acting as commands
+                if state.filter_editing && state.page == Page::Configs {
+                    match key.code {
+                        KeyCode::Char(c) => {
+                            state.config_filter.push(c);
+                            state.on_filter_changed();
+                        },
+                        KeyCode::Backspace => {
+                            state.config_filter.pop();
+                            state.on_filter_changed();
+                        },
+                        KeyCode::Up | KeyCode::Down => navigate_config_list(key.code, &mut state),
+                        KeyCode::Enter => state.filter_editing = false,
+                        KeyCode::Esc => {
+                            state.filter_editing = false;
+                            state.config_filter.clear();
+                            state.on_filter_changed();
+                        },
+                        _ => {}
+                    }
+                    continue;
+                }
+                
+                // A pending confirmation dialog swallows all keys until answered
+                if state.confirm_pending.is_some() {
+                    state.answer_confirmation(key.code);
+                    continue;
+                }
+                
                 match state.mode {
                     AppMode::Normal => {
                         match key.code {
-                            KeyCode::Char('q') => return Ok(()),
+                            KeyCode::Char('q') => {
+                                // Losing the session file isn't worth blocking quit over
+                                let _ = state.save_last_session();
+                                return Ok(());
+                            },
                             KeyCode::Tab => state.page = state.page.next(),
                             KeyCode::BackTab => state.page = state.page.prev(),
                             KeyCode::Right => state.page = state.page.next(),
                             KeyCode::Left => state.page = state.page.prev(),
-                            KeyCode::Char('r') => {
-                                // Run benchmark
-                                if !state.is_running {
-                                    let app_state_clone = app_state.clone();
-                                    tokio::spawn(async move {
-                                        run_benchmark(app_state_clone).await;
+                            KeyCode::Char('r') => state.start_run(&app_state),
+                            KeyCode::Char('R') if state.page == Page::Configs => state.load_and_run(&app_state),
+                            KeyCode::Char('p') => {
+                                // Pause or resume the running benchmark
+                                if let Some(ref progress) = state.progress {
+                                    state.message = Some(if progress.pause.toggle() {
+                                        "Benchmark paused".to_string()
+                                    } else {
+                                        "Benchmark resumed".to_string()
                                     });
-                                    state.is_running = true;
-                                    state.message = Some("Benchmark started...".to_string());
                                 }
                             },
                             KeyCode::Char('i') => {
@@ -525,14 +872,11 @@ async fn run_app(
                                     Page::Configs => {
                                         match state.config_action {
                                             ConfigAction::Load => {
-                                                if let Some(index) = state.selected_config_index {
-                                                    if index < state.config_names.len() {
-                                                        let name = state.config_names[index].clone();
-                                                        if let Err(e) = state.load_config(&name) {
-                                                            state.message = Some(format!("Failed to load config: {}", e));
-                                                        } else {
-                                                            state.message = Some(format!("Loaded configuration: {}", name));
-                                                        }
+                                                if let Some(name) = state.selected_config_name() {
+                                                    if let Err(e) = state.load_config(&name) {
+                                                        state.message = Some(format!("Failed to load config: {}", e));
+                                                    } else {
+                                                        state.message = Some(format!("Loaded configuration: {}", name));
                                                     }
                                                 } else {
                                                     state.message = Some("No configuration selected".to_string());
@@ -546,20 +890,16 @@ async fn run_app(
                                                 state.message = Some("Press 'i' to enter edit mode".to_string());
                                             },
                                             ConfigAction::Delete => {
-                                                if let Some(index) = state.selected_config_index {
-                                                    if index < state.config_names.len() {
-                                                        let name = state.config_names[index].clone();
-                                                        if let Err(e) = state.delete_config(&name) {
-                                                            state.message = Some(format!("Failed to delete config: {}", e));
-                                                        } else {
-                                                            state.message = Some(format!("Deleted configuration: {}", name));
-                                                            state.selected_config_index = None;
-                                                        }
-                                                    }
-                                                } else {
-                                                    state.message = Some("No configuration selected".to_string());
+                                                // Ask before deleting; the y/n answer is handled above
+                                                match state.selected_config_name() {
+                                                    Some(_) => {
+                                                        state.confirm_pending = Some(ConfigAction::Delete);
+                                                    },
+                                                    None => {
+                                                        state.message = Some("No configuration selected".to_string());
+                                                        state.config_action = ConfigAction::None;
+                                                    },
                                                 }
-                                                state.config_action = ConfigAction::None;
                                             },
                                             ConfigAction::None => {
                                                 // Default to save action when Enter is pressed on Configs page
@@ -633,25 +973,11 @@ async fn run_app(
                             _ => {
                                 if state.page == Page::Configs {
                                     match key.code {
-                                        KeyCode::Up => {
-                                            // Navigate up in config list
-                                            if let Some(index) = state.selected_config_index {
-                                                if index > 0 {
-                                                    state.selected_config_index = Some(index - 1);
-                                                }
-                                            } else if !state.config_names.is_empty() {
-                                                state.selected_config_index = Some(state.config_names.len() - 1);
-                                            }
+                                        KeyCode::Up | KeyCode::Down => {
+                                            navigate_config_list(key.code, &mut state);
                                         },
-                                        KeyCode::Down => {
-                                            // Navigate down in config list
-                                            if let Some(index) = state.selected_config_index {
-                                                if index < state.config_names.len() - 1 {
-                                                    state.selected_config_index = Some(index + 1);
-                                                }
-                                            } else if !state.config_names.is_empty() {
-                                                state.selected_config_index = Some(0);
-                                            }
+                                        KeyCode::Char('/') => {
+                                            state.filter_editing = true;
                                         },
                                         KeyCode::Char('l') | KeyCode::Char('L') => {
                                             state.config_action = ConfigAction::Load;
@@ -664,6 +990,19 @@ async fn run_app(
                                         },
                                         _ => {}
                                     }
+                                } else if state.page == Page::Results {
+                                    match key.code {
+                                        KeyCode::Char('y') => state.copy_report(),
+                                        KeyCode::Up | KeyCode::Down => state.navigate_reports(key.code),
+                                        KeyCode::Char('b') => state.pick_for_comparison(true),
+                                        KeyCode::Char('c') => state.pick_for_comparison(false),
+                                        KeyCode::Char('x') => {
+                                            state.compare_baseline = None;
+                                            state.compare_candidate = None;
+                                            state.message = None;
+                                        },
+                                        _ => {}
+                                    }
                                 } else {
                                     handle_field_navigation(key.code, &mut state);
                                 }
@@ -803,6 +1142,34 @@ async fn run_app(
     }
 }
 
+/// Moves the selection within the (possibly filtered) config list
+fn navigate_config_list(key: KeyCode, state: &mut AppState) {
+    let visible = state.visible_config_indices().len();
+    if visible == 0 {
+        state.selected_config_index = None;
+        return;
+    }
+
+    state.selected_config_index = match (state.selected_config_index, key) {
+        (Some(index), KeyCode::Up) => Some(index.saturating_sub(1).min(visible - 1)),
+        (Some(index), KeyCode::Down) => Some((index + 1).min(visible - 1)),
+        (None, KeyCode::Up) => Some(visible - 1),
+        (None, _) => Some(0),
+        (Some(index), _) => Some(index),
+    };
+}
+
+/// Indices into `names` whose name contains `filter`, ignoring case.
+/// An empty filter matches every name.
+fn filter_config_names(names: &[String], filter: &str) -> Vec<usize> {
+    let filter = filter.to_lowercase();
+    names.iter()
+        .enumerate()
+        .filter(|(_, name)| filter.is_empty() || name.to_lowercase().contains(&filter))
+        .map(|(i, _)| i)
+        .collect()
+}
+
 fn handle_field_navigation(key: KeyCode, state: &mut AppState) {
     match key {
         KeyCode::Up | KeyCode::Down => {
@@ -872,6 +1239,11 @@ fn ui(f: &mut Frame, app_state: &Arc<Mutex<AppState>>) {
     let Ok(state) = app_state.try_lock() else {
         return;
     };
+    let theme = &state.theme;
+    
+    // Fill the screen with the theme's colors; widgets without their own
+    // colors inherit them
+    f.render_widget(Block::default().style(Style::default().fg(theme.text).bg(theme.background)), f.area());
     
     // Create a layout
     let chunks = Layout::default()
@@ -888,10 +1260,11 @@ fn ui(f: &mut Frame, app_state: &Arc<Mutex<AppState>>) {
     let titles = [Page::Http,
         Page::Tcp,
         Page::Uds,
+        Page::Live,
         Page::Results,
         Page::Configs,
         Page::Help].iter().map(|t| {
-        Span::styled(t.as_str(), Style::default().fg(Color::White))
+        Span::styled(t.as_str(), Style::default().fg(theme.text))
     }).collect::<Vec<_>>();
 
     let tabs = Tabs::new(titles)
@@ -900,12 +1273,13 @@ fn ui(f: &mut Frame, app_state: &Arc<Mutex<AppState>>) {
             Page::Http => 0,
             Page::Tcp => 1,
             Page::Uds => 2,
-            Page::Results => 3,
-            Page::Configs => 4,
-            Page::Help => 5,
+            Page::Live => 3,
+            Page::Results => 4,
+            Page::Configs => 5,
+            Page::Help => 6,
         })
-        .style(Style::default().fg(Color::White))
-        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        .style(Style::default().fg(theme.text))
+        .highlight_style(theme.focused());
         
     f.render_widget(tabs, chunks[0]);
     
@@ -914,20 +1288,25 @@ fn ui(f: &mut Frame, app_state: &Arc<Mutex<AppState>>) {
         Page::Http => render_http_page(f, chunks[1], &state),
         Page::Tcp => render_tcp_page(f, chunks[1], &state),
         Page::Uds => render_uds_page(f, chunks[1], &state),
+        Page::Live => render_live_page(f, chunks[1], &state),
         Page::Results => render_results_page(f, chunks[1], &state),
         Page::Configs => render_configs_page(f, chunks[1], &state),
-        Page::Help => render_help_page(f, chunks[1]),
+        Page::Help => render_help_page(f, chunks[1], theme),
     }
     
     // Render the status bar
+    let paused = state.progress.as_ref().is_some_and(|progress| progress.pause.is_paused());
     let status = match &state.message {
+        _ if paused => "PAUSED | p: resume | q: quit".to_string(),
         Some(msg) => msg.clone(),
         None => {
             if state.is_running {
-                "Benchmark is running...".to_string()
+                "Benchmark is running... | p: pause".to_string()
             } else {
                 // Show mode-specific status
                 match state.mode {
+                    AppMode::Normal if state.page == Page::Configs => "NORMAL MODE | Up/Down: select config | Enter: load/save | R: load and run | /: filter | q: quit".to_string(),
+                    AppMode::Normal if state.page == Page::Results => "NORMAL MODE | Up/Down: select run | b/c: compare as baseline/candidate | y: copy report JSON | r: run benchmark | q: quit".to_string(),
                     AppMode::Normal => "NORMAL MODE | i: edit | r: run benchmark | q: quit | Tab: switch pages".to_string(),
                     AppMode::Insert => "INSERT MODE | Esc: exit insert mode | Enter: confirm changes".to_string(),
                 }
@@ -936,7 +1315,7 @@ fn ui(f: &mut Frame, app_state: &Arc<Mutex<AppState>>) {
     };
     
     let status_bar = Paragraph::new(status)
-        .style(Style::default().fg(Color::White));
+        .style(Style::default().fg(theme.text));
         
     f.render_widget(status_bar, chunks[2]);
 }
@@ -946,6 +1325,7 @@ fn render_http_page(
     area: Rect,
     state: &AppState,
 ) {
+    let theme = &state.theme;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
@@ -974,7 +1354,7 @@ fn render_http_page(
 
     // URL field
     let url_style = if state.focus == FocusField::Url {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -987,7 +1367,7 @@ fn render_http_page(
     
     // Method field
     let method_style = if state.focus == FocusField::Method {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1000,7 +1380,7 @@ fn render_http_page(
     
     // Headers field
     let headers_style = if state.focus == FocusField::Headers {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1017,7 +1397,7 @@ fn render_http_page(
     
     // Body field
     let body_style = if state.focus == FocusField::Body {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1031,7 +1411,7 @@ fn render_http_page(
     
     // Concurrency field
     let concurrency_style = if state.focus == FocusField::Concurrency {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1044,7 +1424,7 @@ fn render_http_page(
     
     // Requests field
     let requests_style = if state.focus == FocusField::Requests {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1057,7 +1437,7 @@ fn render_http_page(
     
     // Duration field
     let duration_style = if state.focus == FocusField::Duration {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1070,7 +1450,7 @@ fn render_http_page(
     
     // Timeout field
     let timeout_style = if state.focus == FocusField::Timeout {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1190,6 +1570,7 @@ fn render_tcp_page(
     area: Rect,
     state: &AppState,
 ) {
+    let theme = &state.theme;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
@@ -1217,7 +1598,7 @@ fn render_tcp_page(
 
     // Address field
     let address_style = if state.focus == FocusField::Address {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1230,7 +1611,7 @@ fn render_tcp_page(
     
     // Data field
     let data_style = if state.focus == FocusField::Data {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1244,7 +1625,7 @@ fn render_tcp_page(
     
     // Expect field
     let expect_style = if state.focus == FocusField::Expect {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1258,7 +1639,7 @@ fn render_tcp_page(
     
     // Concurrency field
     let concurrency_style = if state.focus == FocusField::Concurrency {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1271,7 +1652,7 @@ fn render_tcp_page(
     
     // Requests field
     let requests_style = if state.focus == FocusField::Requests {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1284,7 +1665,7 @@ fn render_tcp_page(
     
     // Duration field
     let duration_style = if state.focus == FocusField::Duration {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1297,7 +1678,7 @@ fn render_tcp_page(
     
     // Timeout field
     let timeout_style = if state.focus == FocusField::Timeout {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1310,104 +1691,19 @@ fn render_tcp_page(
     
     // If in insert mode, render the textarea in place of the field
     if let AppMode::Insert = state.mode {
-        match state.focus {
-            FocusField::Url => {
-                let text_area = inner_chunks[0];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Method => {
-                let text_area = inner_chunks[1];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Headers => {
-                let text_area = inner_chunks[2];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Body => {
-                let text_area = inner_chunks[3];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Concurrency => {
-                let text_area = inner_chunks[4];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Requests => {
-                let text_area = inner_chunks[5];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Duration => {
-                let text_area = inner_chunks[6];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Timeout => {
-                let text_area = inner_chunks[7];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            _ => {}
+        let index = match state.focus {
+            FocusField::Address => Some(0),
+            FocusField::Data => Some(1),
+            FocusField::Expect => Some(2),
+            FocusField::Concurrency => Some(3),
+            FocusField::Requests => Some(4),
+            FocusField::Duration => Some(5),
+            FocusField::Timeout => Some(6),
+            _ => None,
+        };
+        
+        if let Some(index) = index {
+            render_textarea(f, state, inner_chunks[index]);
         }
     }
 }
@@ -1417,6 +1713,7 @@ fn render_uds_page(
     area: Rect,
     state: &AppState,
 ) {
+    let theme = &state.theme;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
@@ -1444,7 +1741,7 @@ fn render_uds_page(
 
     // Path field
     let path_style = if state.focus == FocusField::Path {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1457,7 +1754,7 @@ fn render_uds_page(
     
     // Data field
     let data_style = if state.focus == FocusField::Data {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1471,7 +1768,7 @@ fn render_uds_page(
     
     // Expect field
     let expect_style = if state.focus == FocusField::Expect {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1485,7 +1782,7 @@ fn render_uds_page(
     
     // Concurrency field
     let concurrency_style = if state.focus == FocusField::Concurrency {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1498,7 +1795,7 @@ fn render_uds_page(
     
     // Requests field
     let requests_style = if state.focus == FocusField::Requests {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1511,7 +1808,7 @@ fn render_uds_page(
     
     // Duration field
     let duration_style = if state.focus == FocusField::Duration {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1524,7 +1821,7 @@ fn render_uds_page(
     
     // Timeout field
     let timeout_style = if state.focus == FocusField::Timeout {
-        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.focused()
     } else {
         Style::default()
     };
@@ -1537,117 +1834,139 @@ fn render_uds_page(
     
     // If in insert mode, render the textarea in place of the field
     if let AppMode::Insert = state.mode {
-        match state.focus {
-            FocusField::Url => {
-                let text_area = inner_chunks[0];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Method => {
-                let text_area = inner_chunks[1];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Headers => {
-                let text_area = inner_chunks[2];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Body => {
-                let text_area = inner_chunks[3];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Concurrency => {
-                let text_area = inner_chunks[4];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Requests => {
-                let text_area = inner_chunks[5];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Duration => {
-                let text_area = inner_chunks[6];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            FocusField::Timeout => {
-                let text_area = inner_chunks[7];
-                f.render_widget(&state.textarea, text_area);
-                
-                // Show cursor at position
-                let (x, y) = state.textarea.cursor();
-                let cursor_x = text_area.x + x as u16 + 1;
-                let cursor_y = text_area.y + y as u16 + 1;
-                
-                // Set cursor position for actual terminal cursor
-                f.set_cursor_position((cursor_x, cursor_y));
-            },
-            _ => {}
+        let index = match state.focus {
+            FocusField::Path => Some(0),
+            FocusField::Data => Some(1),
+            FocusField::Expect => Some(2),
+            FocusField::Concurrency => Some(3),
+            FocusField::Requests => Some(4),
+            FocusField::Duration => Some(5),
+            FocusField::Timeout => Some(6),
+            _ => None,
+        };
+        
+        if let Some(index) = index {
+            render_textarea(f, state, inner_chunks[index]);
         }
     }
 }
 
+/// Render the insert-mode textarea over a field and place the terminal cursor in it
+fn render_textarea(f: &mut Frame, state: &AppState, text_area: Rect) {
+    f.render_widget(&state.textarea, text_area);
+    
+    let (row, col) = state.textarea.cursor();
+    let cursor_x = text_area.x + col as u16 + 1;
+    let cursor_y = text_area.y + row as u16 + 1;
+    
+    // Set cursor position for actual terminal cursor
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+/// Seconds of history shown while a run is in progress
+const LIVE_WINDOW_SECS: f64 = 60.0;
+
+fn render_live_page(
+    f: &mut Frame,
+    area: Rect,
+    state: &AppState,
+) {
+    let theme = &state.theme;
+    
+    // A progress gauge above the chart while the benchmark runs
+    let area = match state.progress {
+        Some(ref progress) => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(area);
+            let gauge = Gauge::default()
+                .block(Block::default().title("Progress").borders(Borders::ALL))
+                .gauge_style(Style::default().fg(theme.accent))
+                .ratio(progress.ratio())
+                .label(progress.label(state.throughput.last().map(|(_, rps)| *rps)));
+            f.render_widget(gauge, chunks[0]);
+            chunks[1]
+        },
+        None => area,
+    };
+    let title = match (state.is_running, state.throughput.is_empty()) {
+        (true, _) => "Live Throughput (running)",
+        (false, false) => "Live Throughput (finished)",
+        (false, true) => "Live Throughput",
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL);
+
+    if state.throughput.is_empty() {
+        let message = if state.is_running {
+            "Waiting for the first throughput sample..."
+        } else {
+            "No throughput data yet. Run a benchmark to see requests/sec over time."
+        };
+        let empty = Paragraph::new(message)
+            .style(Style::default().fg(theme.muted))
+            .block(block);
+        f.render_widget(empty, area);
+        return;
+    }
+
+    // Scroll with the run, then show the whole timeline once it has finished
+    let end = state.throughput.last().map(|(t, _)| *t).unwrap_or(0.0).max(1.0);
+    let start = if state.is_running { (end - LIVE_WINDOW_SECS).max(0.0) } else { 0.0 };
+    let peak = state.throughput.iter()
+        .filter(|(t, _)| *t >= start)
+        .map(|(_, rps)| *rps)
+        .fold(0.0, f64::max)
+        .max(1.0) * 1.1;
+
+    let datasets = vec![
+        Dataset::default()
+            .name("requests/sec")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(theme.accent))
+            .data(&state.throughput),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .legend_position(None)
+        .x_axis(
+            Axis::default()
+                .title("seconds")
+                .style(Style::default().fg(theme.muted))
+                .bounds([start, end])
+                .labels([format!("{:.0}", start), format!("{:.0}", end)]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("req/s")
+                .style(Style::default().fg(theme.muted))
+                .bounds([0.0, peak])
+                .labels(["0".to_string(), format!("{:.0}", peak / 2.0), format!("{:.0}", peak)]),
+        );
+
+    f.render_widget(chart, area);
+}
+
 fn render_results_page(
     f: &mut Frame,
     area: Rect,
     state: &AppState,
 ) {
+    let theme = &state.theme;
+    // The error panel only takes space when the shown run had failures
+    let errors = state.reports.get(state.displayed_report()).and_then(|report| report.errors.as_ref());
+    let error_height = errors
+        .map(|e| (e.counts.len() + e.recent.len().min(RECENT_ERRORS_SHOWN) + 3) as u16)
+        .unwrap_or(0);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
             Constraint::Min(0),
+            Constraint::Length(error_height),
         ])
         .split(area);
     
@@ -1658,94 +1977,121 @@ fn render_results_page(
 
     if state.reports.is_empty() {
         let no_results = Paragraph::new("No benchmark results available. Run a benchmark first.")
-            .style(Style::default().fg(Color::Gray));
+            .style(Style::default().fg(theme.muted));
         f.render_widget(no_results, chunks[0]);
         return;
     }
 
-    // Get the latest report
-    let report = &state.reports[state.reports.len() - 1];
+    if let Some(errors) = errors {
+        render_error_panel(f, chunks[1], errors, theme);
+    }
+    
+    // With more than one run, a list to pick them from sits on the left
+    let area = if state.reports.len() > 1 {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![
+                Constraint::Length(RUN_LIST_WIDTH),
+                Constraint::Min(0),
+            ])
+            .split(chunks[0]);
+        render_run_list(f, columns[0], state);
+        columns[1]
+    } else {
+        chunks[0]
+    };
+    
+    if let (Some(baseline), Some(candidate)) = (state.compare_baseline, state.compare_candidate) {
+        render_comparison(f, area, state, baseline, candidate);
+        return;
+    }
+
+    let report = &state.reports[state.displayed_report()];
     
     let content = vec![
         Line::from(vec![
-            Span::styled("Target: ", Style::default().fg(Color::White)),
-            Span::styled(&report.target, Style::default().fg(Color::Yellow))
+            Span::styled("Target: ", Style::default().fg(theme.text)),
+            Span::styled(&report.target, Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Protocol: ", Style::default().fg(Color::White)),
-            Span::styled(&report.protocol, Style::default().fg(Color::Yellow))
+            Span::styled("Protocol: ", Style::default().fg(theme.text)),
+            Span::styled(&report.protocol, Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Concurrency: ", Style::default().fg(Color::White)),
-            Span::styled(report.concurrency.to_string(), Style::default().fg(Color::Yellow))
+            Span::styled("Concurrency: ", Style::default().fg(theme.text)),
+            Span::styled(report.concurrency.to_string(), Style::default().fg(theme.highlight))
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Request Statistics:", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            Span::styled("Request Statistics:", Style::default().fg(theme.text).add_modifier(Modifier::BOLD))
+        ]),
+        Line::from(vec![
+            Span::styled("Total Requests: ", Style::default().fg(theme.text)),
+            Span::styled(report.total_requests.to_string(), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Total Requests: ", Style::default().fg(Color::White)),
-            Span::styled(report.total_requests.to_string(), Style::default().fg(Color::Yellow))
+            Span::styled("Successful Requests: ", Style::default().fg(theme.text)),
+            Span::styled(report.successful_requests.to_string(), Style::default().fg(theme.success))
         ]),
         Line::from(vec![
-            Span::styled("Successful Requests: ", Style::default().fg(Color::White)),
-            Span::styled(report.successful_requests.to_string(), Style::default().fg(Color::Green))
+            Span::styled("Failed Requests: ", Style::default().fg(theme.text)),
+            Span::styled(report.failed_requests.to_string(), Style::default().fg(theme.error))
         ]),
         Line::from(vec![
-            Span::styled("Failed Requests: ", Style::default().fg(Color::White)),
-            Span::styled(report.failed_requests.to_string(), Style::default().fg(Color::Red))
+            Span::styled("Requests/sec: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:.2}", report.requests_per_second), Style::default().fg(theme.success))
         ]),
         Line::from(vec![
-            Span::styled("Requests/sec: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:.2}", report.requests_per_second), Style::default().fg(Color::Green))
+            Span::styled("Stopped: ", Style::default().fg(theme.text)),
+            Span::styled(report.stop_reason.to_string(), Style::default().fg(theme.highlight))
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Timing Statistics:", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            Span::styled("Timing Statistics:", Style::default().fg(theme.text).add_modifier(Modifier::BOLD))
         ]),
         Line::from(vec![
-            Span::styled("Total Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.total_time), Style::default().fg(Color::Yellow))
+            Span::styled("Total Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.total_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Average Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.avg_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("Average Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.avg_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Min Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.min_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("Min Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.min_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Max Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.max_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("Max Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.max_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("p50 Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.p50_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("p50 Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.p50_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("p90 Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.p90_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("p90 Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.p90_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("p95 Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.p95_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("p95 Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.p95_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("p99 Response Time: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{:?}", report.p99_response_time), Style::default().fg(Color::Yellow))
+            Span::styled("p99 Response Time: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{:?}", report.p99_response_time), Style::default().fg(theme.highlight))
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Transfer Statistics:", Style::default().fg(Color::White).add_modifier(Modifier::BOLD))
+            Span::styled("Transfer Statistics:", Style::default().fg(theme.text).add_modifier(Modifier::BOLD))
         ]),
         Line::from(vec![
-            Span::styled("Bytes Sent: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{} bytes", report.bytes_sent), Style::default().fg(Color::Yellow))
+            Span::styled("Bytes Sent: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{} bytes", report.bytes_sent), Style::default().fg(theme.highlight))
         ]),
         Line::from(vec![
-            Span::styled("Bytes Received: ", Style::default().fg(Color::White)),
-            Span::styled(format!("{} bytes", report.bytes_received), Style::default().fg(Color::Yellow))
+            Span::styled("Bytes Received: ", Style::default().fg(theme.text)),
+            Span::styled(format!("{} bytes", report.bytes_received), Style::default().fg(theme.highlight))
         ]),
     ];
     
@@ -1753,7 +2099,119 @@ fn render_results_page(
         .block(Block::default())
         .wrap(Wrap { trim: true });
 
-    f.render_widget(report_widget, chunks[0]);
+    f.render_widget(report_widget, area);
+}
+
+/// The session's runs, oldest first, marking the comparison's baseline (B)
+/// and candidate (C)
+fn render_run_list(
+    f: &mut Frame,
+    area: Rect,
+    state: &AppState,
+) {
+    let theme = &state.theme;
+    let selected = state.displayed_report();
+    let items: Vec<ListItem> = state.reports.iter()
+        .enumerate()
+        .map(|(i, report)| {
+            let mark = if state.compare_baseline == Some(i) {
+                "B"
+            } else if state.compare_candidate == Some(i) {
+                "C"
+            } else {
+                " "
+            };
+            ListItem::new(format!("{} #{} {} {:.0} req/s", mark, i + 1, report.protocol, report.requests_per_second))
+                .style(Style::default().fg(theme.text))
+        })
+        .collect();
+    
+    // The list scrolls to keep the selected run in view
+    let list = List::new(items)
+        .block(Block::default().title("Runs").borders(Borders::ALL))
+        .highlight_style(theme.focused());
+    f.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(selected)));
+}
+
+/// Headline metrics of the baseline and candidate runs side by side, with
+/// the change between them: improvements in the success color, regressions
+/// in the error color.
+fn render_comparison(
+    f: &mut Frame,
+    area: Rect,
+    state: &AppState,
+    baseline: usize,
+    candidate: usize,
+) {
+    let theme = &state.theme;
+    let run = |label: &'static str, index: usize| Line::from(vec![
+        Span::styled(label, Style::default().fg(theme.text)),
+        Span::styled(format!("#{} {}", index + 1, state.reports[index].target), Style::default().fg(theme.highlight)),
+    ]);
+    let mut content = vec![
+        run("Baseline:  ", baseline),
+        run("Candidate: ", candidate),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("{:<8} {:>14} {:>14} {:>10}", "", "Baseline", "Candidate", "Change"),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )),
+    ];
+    
+    for delta in report::compare_reports(&state.reports[baseline], &state.reports[candidate]) {
+        let unit = if delta.metric == "rps" { "" } else { " ms" };
+        let color = if delta.regression() > 0.0 {
+            theme.error
+        } else if delta.regression() < 0.0 {
+            theme.success
+        } else {
+            theme.text
+        };
+        content.push(Line::from(vec![
+            Span::styled(
+                format!(
+                    "{:<8} {:>14} {:>14} ",
+                    delta.metric,
+                    format!("{:.2}{}", delta.baseline, unit),
+                    format!("{:.2}{}", delta.current, unit),
+                ),
+                Style::default().fg(theme.text),
+            ),
+            Span::styled(format!("{:>10}", format!("{:+.1}%", delta.change() * 100.0)), Style::default().fg(color)),
+        ]));
+    }
+    
+    let comparison = Paragraph::new(content)
+        .block(Block::default().title("Comparison").borders(Borders::ALL));
+    f.render_widget(comparison, area);
+}
+
+/// Failed requests of the latest run by error kind, followed by the most
+/// recent error messages.
+fn render_error_panel(
+    f: &mut Frame,
+    area: Rect,
+    errors: &ErrorSummary,
+    theme: &Theme,
+) {
+    let mut content: Vec<Line> = errors.counts.iter()
+        .map(|(kind, count)| Line::from(vec![
+            Span::styled(format!("{}: ", kind), Style::default().fg(theme.text)),
+            Span::styled(count.to_string(), Style::default().fg(theme.error)),
+        ]))
+        .collect();
+    
+    content.push(Line::from(vec![
+        Span::styled("Last errors:", Style::default().fg(theme.text).add_modifier(Modifier::BOLD))
+    ]));
+    let skip = errors.recent.len().saturating_sub(RECENT_ERRORS_SHOWN);
+    content.extend(errors.recent.iter().skip(skip).rev().map(|message| {
+        Line::from(Span::styled(message.as_str(), Style::default().fg(theme.muted)))
+    }));
+    
+    let panel = Paragraph::new(content)
+        .block(Block::default().title("Errors").borders(Borders::ALL).border_style(Style::default().fg(theme.error)));
+    f.render_widget(panel, area);
 }
 
 fn render_configs_page(
@@ -1761,6 +2219,7 @@ fn render_configs_page(
     area: Rect,
     state: &AppState,
 ) {
+    let theme = &state.theme;
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(vec![
@@ -1776,22 +2235,47 @@ fn render_configs_page(
         .borders(Borders::ALL);
     f.render_widget(configs_block, area);
 
-    // Title section
-    let title = Paragraph::new("Select a configuration to load, or save current settings.")
-        .style(Style::default().fg(Color::White));
+    // Title section, with the filter line underneath
+    let filter_line = if state.filter_editing || !state.config_filter.is_empty() {
+        Line::from(vec![
+            Span::styled("Filter: ", Style::default().fg(theme.text)),
+            Span::styled(state.config_filter.clone(), Style::default().fg(theme.highlight)),
+        ])
+    } else {
+        Line::from(Span::styled("Press / to filter", Style::default().fg(theme.muted)))
+    };
+    let title = Paragraph::new(vec![
+        Line::from("Select a configuration to load (Shift+R to load and run it), or save current settings."),
+        filter_line,
+    ])
+    .style(Style::default().fg(theme.text));
     f.render_widget(title, chunks[0]);
 
+    if state.filter_editing {
+        let x = chunks[0].x + "Filter: ".len() as u16 + state.config_filter.chars().count() as u16;
+        f.set_cursor_position((x, chunks[0].y + 1));
+    }
+
+    let visible = state.visible_config_indices();
+
     // Config list
     if state.config_names.is_empty() {
         let no_configs = Paragraph::new("No saved configurations found.")
-            .style(Style::default().fg(Color::Gray))
+            .style(Style::default().fg(theme.muted))
             .block(Block::default().borders(Borders::ALL));
         f.render_widget(no_configs, chunks[1]);
+    } else if visible.is_empty() {
+        let no_matches = Paragraph::new(format!("No configurations match '{}'.", state.config_filter))
+            .style(Style::default().fg(theme.muted))
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(no_matches, chunks[1]);
     } else {
-        let configs: Vec<ListItem> = state.config_names.iter().enumerate()
+        let configs: Vec<ListItem> = visible.iter()
+            .map(|&index| &state.config_names[index])
+            .enumerate()
             .map(|(i, name)| {
                 let style = if Some(i) == state.selected_config_index {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                    theme.focused()
                 } else {
                     Style::default()
                 };
@@ -1816,8 +2300,8 @@ fn render_configs_page(
     }
 
     // Action buttons
-    let action_style = Style::default().fg(Color::White);
-    let selected_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let action_style = Style::default().fg(theme.text);
+    let selected_style = theme.focused();
 
     let load_button_style = if state.config_action == ConfigAction::Load {
         selected_style
@@ -1852,7 +2336,7 @@ fn render_configs_page(
 
     // Config name input field (only shown when saving)
     if state.config_action == ConfigAction::Save {
-        let name_input_style = Style::default().fg(Color::Yellow);
+        let name_input_style = Style::default().fg(theme.highlight);
 
         let name_input = Paragraph::new(state.config_name_input.clone())
             .style(name_input_style)
@@ -1872,11 +2356,46 @@ fn render_configs_page(
             f.set_cursor_position((text_area.x + x as u16 + 1, text_area.y + y as u16 + 1));
         }
     }
+
+    // Confirmation dialog on top of everything else
+    if state.confirm_pending == Some(ConfigAction::Delete) {
+        let name = state.selected_config_name().unwrap_or_default();
+
+        let dialog_area = centered_rect(50, 3, area);
+        let dialog = Paragraph::new(vec![
+            Line::from(format!("Delete configuration '{}'?", name)),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("[y]", Style::default().fg(theme.error).add_modifier(Modifier::BOLD)),
+                Span::raw(" delete   "),
+                Span::styled("[n]", Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
+                Span::raw(" cancel"),
+            ]),
+        ])
+        .block(Block::default().borders(Borders::ALL).title("Confirm"));
+
+        f.render_widget(Clear, dialog_area);
+        f.render_widget(dialog, dialog_area);
+    }
+}
+
+/// A rectangle with room for `width` x `height` of content plus borders,
+/// centered within `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = (width + 2).min(area.width);
+    let height = (height + 2).min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
 }
 
 fn render_help_page(
     f: &mut Frame,
     area: Rect,
+    theme: &Theme,
 ) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -1892,30 +2411,32 @@ fn render_help_page(
 
     let content = vec![
         Line::from(vec![
-            Span::styled("Benchmark Tool Help", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            Span::styled("Benchmark Tool Help", Style::default().fg(theme.success).add_modifier(Modifier::BOLD))
         ]),
         Line::from(""),
         Line::from("This tool helps you benchmark HTTP, TCP, and Unix Domain Socket servers."),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Navigation:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            Span::styled("Navigation:", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
         ]),
         Line::from(" - Tab/Left/Right: Switch between tabs"),
         Line::from(" - Up/Down: Navigate through fields"),
         Line::from(" - i: Enter edit mode for the selected field"),
         Line::from(" - Esc: Exit edit mode"),
         Line::from(" - r: Run the configured benchmark"),
+        Line::from(" - R (Configs tab): Load the selected configuration and run it"),
+        Line::from(" - p: Pause or resume the running benchmark"),
         Line::from(" - q: Quit the application"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Benchmark Types:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            Span::styled("Benchmark Types:", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
         ]),
         Line::from(" - HTTP: Benchmark HTTP/HTTPS servers"),
         Line::from(" - TCP: Benchmark TCP servers (like Redis, Valkey)"),
         Line::from(" - UDS: Benchmark Unix Domain Socket servers"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Common Configuration:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            Span::styled("Common Configuration:", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
         ]),
         Line::from(" - Concurrency: Number of concurrent connections"),
         Line::from(" - Requests: Total number of requests to perform"),
@@ -1923,9 +2444,13 @@ fn render_help_page(
         Line::from(" - Timeout: Timeout for each request in milliseconds"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Results:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            Span::styled("Results:", Style::default().fg(theme.highlight).add_modifier(Modifier::BOLD))
         ]),
+        Line::from(" - The Live tab graphs requests/sec while a benchmark runs"),
         Line::from(" - The Results tab shows the outcome of the most recent benchmark"),
+        Line::from(" - If requests failed, an Errors panel below lists them by kind with the last messages"),
+        Line::from(" - Press y on the Results tab to copy the report as JSON to the clipboard"),
+        Line::from(" - After several runs, Up/Down selects a run; b and c pick the baseline and candidate to compare, x clears"),
         Line::from(" - Includes request rate, response times, and transfer statistics"),
     ];
     
@@ -1936,6 +2461,31 @@ fn render_help_page(
     f.render_widget(help_widget, chunks[0]);
 }
 
+/// Report as pretty-printed JSON, the `report` section of `--output json`
+fn report_json(report: &BenchmarkReport) -> Result<String> {
+    Ok(serde_json::to_string_pretty(report)?)
+}
+
+/// Clears the previous throughput timeline, starts the progress gauge of a
+/// run limited to `requests` and `duration` seconds and switches to the Live
+/// page. Returns the counter the runner reports completed requests in and the
+/// flag the `p` key pauses it with.
+async fn show_live_page(app_state: &Arc<Mutex<AppState>>, requests: usize, duration: u64) -> (Arc<AtomicUsize>, Arc<PauseFlag>) {
+    let completed = Arc::new(AtomicUsize::new(0));
+    let pause = Arc::new(PauseFlag::new());
+    let mut state = app_state.lock().await;
+    state.throughput.clear();
+    state.progress = Some(RunProgress {
+        started: Instant::now(),
+        duration: Duration::from_secs(duration),
+        requests,
+        completed: completed.clone(),
+        pause: pause.clone(),
+    });
+    state.page = Page::Live;
+    (completed, pause)
+}
+
 async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
     let page;
     let http_options;
@@ -1951,6 +2501,15 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
         uds_options = state.uds_options.clone();
     }
     
+    // Feed the runner's throughput samples into the Live page
+    let (throughput_tx, mut throughput_rx) = tokio::sync::mpsc::unbounded_channel();
+    let feed_state = app_state.clone();
+    let feed = tokio::spawn(async move {
+        while let Some(point) = throughput_rx.recv().await {
+            feed_state.lock().await.throughput.push(point);
+        }
+    });
+    
     // Run the appropriate benchmark
     let result = match page {
         Page::Http => {
@@ -1969,12 +2528,16 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
                 None, // body_file
                 Some(http_options.concurrency),
                 Some(http_options.requests),
-                Some(http_options.duration),
+                Some(Duration::from_secs(http_options.duration)),
                 Some(http_options.timeout),
                 http_options.keep_alive,
             );
             
-            let runner = crate::runner::HttpRunner::new(config);
+            let (completed, pause) = show_live_page(&app_state, http_options.requests, http_options.duration).await;
+            let runner = crate::runner::HttpRunner::new(config)
+                .with_throughput(throughput_tx)
+                .with_progress(completed)
+                .with_pause(pause);
             runner.run().await
         },
         Page::Tcp => {
@@ -1992,12 +2555,16 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
                 tcp_options.expect,
                 Some(tcp_options.concurrency),
                 Some(tcp_options.requests),
-                Some(tcp_options.duration),
+                Some(Duration::from_secs(tcp_options.duration)),
                 Some(tcp_options.timeout),
                 tcp_options.keep_alive,
             );
             
-            let runner = crate::runner::TcpRunner::new(config);
+            let (completed, pause) = show_live_page(&app_state, tcp_options.requests, tcp_options.duration).await;
+            let runner = crate::runner::TcpRunner::new(config)
+                .with_throughput(throughput_tx)
+                .with_progress(completed)
+                .with_pause(pause);
             runner.run().await
         },
         Page::Uds => {
@@ -2015,12 +2582,16 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
                 uds_options.expect,
                 Some(uds_options.concurrency),
                 Some(uds_options.requests),
-                Some(uds_options.duration),
+                Some(Duration::from_secs(uds_options.duration)),
                 Some(uds_options.timeout),
                 uds_options.keep_alive,
             );
             
-            let runner = crate::runner::UdsRunner::new(config);
+            let (completed, pause) = show_live_page(&app_state, uds_options.requests, uds_options.duration).await;
+            let runner = crate::runner::UdsRunner::new(config)
+                .with_throughput(throughput_tx)
+                .with_progress(completed)
+                .with_pause(pause);
             runner.run().await
         },
         _ => {
@@ -2031,13 +2602,20 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
         }
     };
     
+    // The runner dropped its sender; wait for the last samples to land
+    let _ = feed.await;
+    
     // Update the app state with the result
     let mut state = app_state.lock().await;
     state.is_running = false;
+    state.progress = None;
     
     match result {
         Ok(report) => {
             state.reports.push(report);
+            // Show the new run; a picked baseline stays for comparing it
+            state.selected_report = None;
+            state.compare_candidate = None;
             state.message = Some("Benchmark completed successfully".to_string());
             state.page = Page::Results;
         },
@@ -2047,3 +2625,402 @@ async fn run_benchmark(app_state: Arc<Mutex<AppState>>) {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh state with no saved configs or session, so tests never read or
+    /// create the user's files
+    fn app_state() -> AppState {
+        AppState::with_session(ConfigStore::new(), LastSession::default(), None)
+    }
+
+    /// State holding saved HTTP configs under `names`, none selected
+    fn with_configs(names: &[&str]) -> AppState {
+        let mut state = app_state();
+        for name in names {
+            state.config_store.add(name, BenchmarkConfigType::Http(HttpConfigSave {
+                url: format!("http://{}/", name),
+                method: None,
+                headers: None,
+                body: None,
+                concurrency: None,
+                requests: None,
+                duration: None,
+                timeout: None,
+                keep_alive: false,
+            }));
+        }
+        state.config_names = state.config_store.list();
+        state
+    }
+
+    fn report(target: &str) -> BenchmarkReport {
+        BenchmarkReport { target: target.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn copy_takes_the_selected_run() {
+        let mut state = app_state();
+        assert!(state.displayed_report_json().is_none());
+
+        state.reports = vec![report("http://first/"), report("http://second/")];
+        let (index, json) = state.displayed_report_json().unwrap();
+        assert_eq!(index, 1);
+        assert!(json.unwrap().contains("http://second/"));
+
+        state.navigate_reports(KeyCode::Up);
+        let (index, json) = state.displayed_report_json().unwrap();
+        assert_eq!(index, 0);
+        assert!(json.unwrap().contains("http://first/"));
+    }
+
+    /// Text of every row of `backend`'s buffer
+    fn rows(backend: &ratatui::backend::TestBackend) -> Vec<String> {
+        let buffer = backend.buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn live_page_shows_the_progress_of_the_run() {
+        let mut state = app_state();
+        state.is_running = true;
+        state.progress = Some(RunProgress {
+            started: Instant::now(),
+            duration: Duration::ZERO,
+            requests: 200,
+            completed: Arc::new(AtomicUsize::new(50)),
+            pause: Arc::new(PauseFlag::new()),
+        });
+        state.throughput = vec![(1.0, 120.0)];
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| render_live_page(f, f.area(), &state)).unwrap();
+
+        let rows = rows(terminal.backend());
+        let gauge = rows.iter().find(|row| row.contains('%')).expect("no gauge rendered");
+        assert!(gauge.contains("25%  |  50/200 requests  |  120 req/s"), "{}", gauge);
+    }
+
+    /// Rows of the Live page for a run at `rps` requests/sec for each of
+    /// `secs` seconds
+    fn live_rows(running: bool, secs: usize, rps: f64) -> Vec<String> {
+        let mut state = app_state();
+        state.is_running = running;
+        state.throughput = (1..=secs).map(|t| (t as f64, rps)).collect();
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| render_live_page(f, f.area(), &state)).unwrap();
+        rows(terminal.backend())
+    }
+
+    #[test]
+    fn live_page_scrolls_while_running_and_shows_everything_once_finished() {
+        let rows = live_rows(true, 90, 200.0);
+        assert!(rows[0].contains("Live Throughput (running)"), "{:#?}", rows);
+        // The last 60 seconds, up to 10% above the peak
+        let x_labels = rows.iter().rev().find(|row| row.contains("90")).expect("no x labels");
+        assert!(x_labels.contains("30") && !x_labels.contains(" 0 "), "{}", x_labels);
+        assert!(rows.iter().any(|row| row.contains("220")), "{:#?}", rows);
+        assert!(rows.iter().any(|row| row.contains("110")), "{:#?}", rows);
+        // A steady 200 req/s draws one flat line just below the 220 top
+        let line = rows.iter().position(|row| row.chars().any(|c| ('\u{2801}'..='\u{28ff}').contains(&c)));
+        assert_eq!(line, Some(2), "{:#?}", rows);
+
+        let rows = live_rows(false, 90, 200.0);
+        assert!(rows[0].contains("Live Throughput (finished)"), "{:#?}", rows);
+        let x_labels = rows.iter().rev().find(|row| row.contains("90")).expect("no x labels");
+        assert!(x_labels.contains('0') && !x_labels.contains("30"), "{}", x_labels);
+    }
+
+    #[test]
+    fn live_page_without_samples_says_why() {
+        let rows = live_rows(true, 0, 0.0);
+        assert!(rows.iter().any(|row| row.contains("Waiting for the first throughput sample")), "{:#?}", rows);
+
+        let rows = live_rows(false, 0, 0.0);
+        assert!(rows[0].contains("Live Throughput") && !rows[0].contains('('), "{:#?}", rows);
+        assert!(rows.iter().any(|row| row.contains("No throughput data yet")), "{:#?}", rows);
+    }
+
+    /// Rows of `page` drawn while editing `field` with `text` in the textarea
+    fn editing(page: Page, field: FocusField, text: &str) -> (Vec<String>, ratatui::layout::Position) {
+        let mut state = app_state();
+        state.tcp_options.address = "127.0.0.1:9000".to_string();
+        state.uds_options.path = "/tmp/app.sock".to_string();
+        state.page = page;
+        state.focus = field;
+        state.mode = AppMode::Insert;
+        state.textarea = TextArea::new(vec![text.to_string()]);
+        state.textarea.set_block(Block::default().title("Editing").borders(Borders::ALL));
+        state.textarea.move_cursor(tui_textarea::CursorMove::End);
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(60, 30)).unwrap();
+        terminal.draw(|f| match page {
+            Page::Tcp => render_tcp_page(f, f.area(), &state),
+            _ => render_uds_page(f, f.area(), &state),
+        }).unwrap();
+        let cursor = terminal.get_cursor_position().unwrap();
+        (rows(terminal.backend()), cursor)
+    }
+
+    #[test]
+    fn textarea_replaces_the_edited_tcp_address_and_uds_path() {
+        for (page, field, text) in [
+            (Page::Tcp, FocusField::Address, "10.0.0.1:7000"),
+            (Page::Uds, FocusField::Path, "/run/other.sock"),
+        ] {
+            let (rows, cursor) = editing(page, field, text);
+
+            // The first field's box is the textarea, with the cursor after the text
+            assert!(rows[0].contains("Editing"), "{:#?}", rows);
+            assert!(rows[1].contains(text), "{:#?}", rows);
+            assert!(!rows.iter().any(|row| row.contains("127.0.0.1:9000") || row.contains("/tmp/app.sock")), "{:#?}", rows);
+            assert_eq!((cursor.x, cursor.y), (1 + text.len() as u16, 1));
+            // The other fields keep their own boxes
+            assert!(rows.iter().any(|row| row.contains("Data to Send")), "{:#?}", rows);
+            assert!(rows.iter().any(|row| row.contains("Timeout (ms)")), "{:#?}", rows);
+        }
+    }
+
+    #[test]
+    fn textarea_follows_the_focused_field() {
+        let (rows, cursor) = editing(Page::Tcp, FocusField::Requests, "250");
+
+        assert!(rows[0].contains("Address (host:port)") && rows[1].contains("127.0.0.1:9000"), "{:#?}", rows);
+        // Address, Data and Expect take 3 + 5 + 5 rows, then Concurrency 3
+        assert!(rows[16].contains("Editing") && rows[17].contains("250"), "{:#?}", rows);
+        assert_eq!((cursor.x, cursor.y), (4, 17));
+    }
+
+    #[test]
+    fn error_panel_lists_each_kind_and_the_newest_errors() {
+        let mut state = app_state();
+        let errors = ErrorSummary {
+            counts: [("connection_refused", 3), ("request_timeout", 12), ("response_validation", 1)]
+                .map(|(kind, count)| (kind.to_string(), count))
+                .into(),
+            recent: (1..=7).map(|i| format!("error {}", i)).collect(),
+            ..Default::default()
+        };
+        state.reports = vec![BenchmarkReport { errors: Some(errors), ..report("http://failing/") }];
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|f| render_results_page(f, f.area(), &state)).unwrap();
+        let rows = rows(terminal.backend());
+
+        // Three kinds, the heading and five messages fill the panel exactly
+        let top = rows.iter().position(|row| row.contains("Errors")).expect("no error panel");
+        assert_eq!(top, 30 - 3 - 1 - 5 - 2, "{:#?}", rows);
+        let panel: Vec<&str> = rows[top + 1..29].iter().map(|row| row.trim_matches(['│', ' '])).collect();
+        assert_eq!(panel, [
+            "connection_refused: 3",
+            "request_timeout: 12",
+            "response_validation: 1",
+            "Last errors:",
+            "error 7",
+            "error 6",
+            "error 5",
+            "error 4",
+            "error 3",
+        ]);
+
+        // Counts stand out in the theme's error colour
+        let buffer = terminal.backend().buffer();
+        let count_x = rows[top + 2].chars().position(|c| c == '1').unwrap() as u16;
+        assert_eq!(buffer[(count_x, top as u16 + 2)].fg, state.theme.error);
+    }
+
+    #[test]
+    fn run_list_scrolls_to_the_selected_run() {
+        let mut state = app_state();
+        state.reports = (0..30).map(|i| report(&format!("http://run-{}/", i))).collect();
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|f| render_results_page(f, f.area(), &state)).unwrap();
+        let rows = rows(terminal.backend());
+
+        // The latest run is selected, far below the first rows of the panel
+        assert!(rows.iter().any(|row| row.contains("#30 ")), "{:#?}", rows);
+        assert!(!rows.iter().any(|row| row.contains("#1 ")), "{:#?}", rows);
+    }
+
+    #[test]
+    fn comparison_shows_the_change_of_each_metric() {
+        let mut state = app_state();
+        state.reports = vec![
+            BenchmarkReport { requests_per_second: 100.0, ..report("http://baseline/") },
+            BenchmarkReport { requests_per_second: 150.0, ..report("http://candidate/") },
+        ];
+        state.compare_baseline = Some(0);
+        state.compare_candidate = Some(1);
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| render_results_page(f, f.area(), &state)).unwrap();
+        let rows = rows(terminal.backend());
+
+        let header = rows.iter().find(|row| row.contains("Baseline ")).expect("no comparison header");
+        assert!(header.contains("Candidate") && header.contains("Change"), "{}", header);
+        let rps = rows.iter().find(|row| row.contains("rps ")).expect("no rps row");
+        assert!(rps.contains("100.00") && rps.contains("150.00") && rps.contains("+50.0%"), "{}", rps);
+        assert!(rows.iter().any(|row| row.contains("B #1 ")) && rows.iter().any(|row| row.contains("C #2 ")), "{:#?}", rows);
+    }
+
+    #[tokio::test]
+    async fn load_and_run_starts_the_selected_config() {
+        let app_state = Arc::new(Mutex::new(app_state()));
+        {
+            let mut state = app_state.lock().await;
+            state.page = Page::Configs;
+            // An empty URL fails the run as soon as it starts, without sending anything
+            state.config_store.add("empty", BenchmarkConfigType::Http(HttpConfigSave {
+                url: String::new(),
+                method: Some("POST".to_string()),
+                headers: None,
+                body: None,
+                concurrency: Some(3),
+                requests: Some(10),
+                duration: None,
+                timeout: None,
+                keep_alive: true,
+            }));
+            state.config_names = state.config_store.list();
+            state.selected_config_index = Some(0);
+
+            state.load_and_run(&app_state);
+
+            assert!(state.is_running);
+            assert_eq!(state.page, Page::Http);
+            assert_eq!(state.http_options.method, "POST");
+            assert_eq!(state.http_options.concurrency, 3);
+            assert_eq!(state.message.as_deref(), Some("Running configuration: empty"));
+
+            state.load_and_run(&app_state);
+            assert_eq!(state.message.as_deref(), Some("A benchmark is already running"));
+        }
+
+        // The run really started: it reports the empty URL once the lock is free
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while app_state.lock().await.is_running {
+                tokio::task::yield_now().await;
+            }
+        }).await.unwrap();
+        let state = app_state.lock().await;
+        assert_eq!(state.message.as_deref(), Some("Error: URL cannot be empty"));
+    }
+
+    #[test]
+    fn confirmed_delete_removes_the_selected_config() {
+        let mut state = with_configs(&["first", "second"]);
+        state.selected_config_index = Some(1);
+        state.config_action = ConfigAction::Delete;
+        state.confirm_pending = Some(ConfigAction::Delete);
+
+        // Keys other than an answer leave the dialog open
+        state.answer_confirmation(KeyCode::Char('x'));
+        assert_eq!(state.confirm_pending, Some(ConfigAction::Delete));
+        assert_eq!(state.config_names, ["first", "second"]);
+
+        state.answer_confirmation(KeyCode::Char('y'));
+        assert_eq!(state.confirm_pending, None);
+        assert_eq!(state.config_action, ConfigAction::None);
+        assert_eq!(state.config_names, ["first"]);
+        assert!(state.config_store.get("second").is_none());
+        assert_eq!(state.selected_config_index, None);
+        assert_eq!(state.message.as_deref(), Some("Deleted configuration: second"));
+    }
+
+    #[test]
+    fn cancelled_delete_keeps_the_config() {
+        for key in [KeyCode::Char('n'), KeyCode::Esc] {
+            let mut state = with_configs(&["first", "second"]);
+            state.selected_config_index = Some(1);
+            state.config_action = ConfigAction::Delete;
+            state.confirm_pending = Some(ConfigAction::Delete);
+
+            state.answer_confirmation(key);
+
+            assert_eq!(state.confirm_pending, None);
+            assert_eq!(state.config_action, ConfigAction::None);
+            assert_eq!(state.config_names, ["first", "second"]);
+            assert_eq!(state.selected_config_index, Some(1));
+            assert_eq!(state.message.as_deref(), Some("Deletion cancelled"));
+        }
+    }
+
+    #[test]
+    fn last_session_round_trips_through_json() {
+        let session = LastSession {
+            http: HttpOptions {
+                url: "http://localhost:8080/api".to_string(),
+                method: "POST".to_string(),
+                headers: vec!["Authorization: Bearer x".to_string()],
+                body: Some("{\"a\":1}".to_string()),
+                concurrency: 8,
+                requests: 5000,
+                duration: 30,
+                timeout: 1500,
+                keep_alive: true,
+            },
+            tcp: TcpOptions {
+                address: "127.0.0.1:9000".to_string(),
+                data: Some("PING".to_string()),
+                expect: Some("PONG".to_string()),
+                keep_alive: true,
+                ..TcpOptions::default()
+            },
+            uds: UdsOptions {
+                path: "/tmp/app.sock".to_string(),
+                concurrency: 4,
+                ..UdsOptions::default()
+            },
+            theme: ThemeName::HighContrast,
+        };
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: LastSession = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        assert_eq!(restored.http.headers, ["Authorization: Bearer x"]);
+        assert_eq!(restored.http.body.as_deref(), Some("{\"a\":1}"));
+        assert_eq!(restored.tcp.expect.as_deref(), Some("PONG"));
+        assert_eq!(restored.uds.concurrency, 4);
+        assert_eq!(restored.theme, ThemeName::HighContrast);
+    }
+
+    #[test]
+    fn last_session_fills_missing_fields_with_defaults() {
+        // A session written before some options existed
+        let restored: LastSession = serde_json::from_str(r#"{"tcp":{"address":"127.0.0.1:9000"}}"#).unwrap();
+
+        assert_eq!(restored.tcp.address, "127.0.0.1:9000");
+        assert_eq!(restored.tcp.requests, 100);
+        assert_eq!(restored.http.method, "GET");
+        assert_eq!(restored.uds.timeout, 30000);
+        assert_eq!(restored.theme, ThemeName::Dark);
+    }
+
+    #[test]
+    fn config_filter_ignores_case_and_an_empty_one_keeps_every_config() {
+        let names: Vec<String> = ["Prod-API", "staging-api", "local-tcp"].map(String::from).to_vec();
+
+        assert_eq!(filter_config_names(&names, ""), [0, 1, 2]);
+        assert_eq!(filter_config_names(&names, "api"), [0, 1]);
+        assert_eq!(filter_config_names(&names, "PROD"), [0]);
+        assert_eq!(filter_config_names(&names, "Tcp"), [2]);
+        assert!(filter_config_names(&names, "udp").is_empty());
+        assert!(filter_config_names(&[], "").is_empty());
+    }
+
+    #[test]
+    fn selection_maps_through_the_filter() {
+        let mut state = with_configs(&["alpha", "beta", "gamma"]);
+        state.config_filter = "MM".to_string();
+        state.on_filter_changed();
+
+        assert_eq!(state.selected_config_index, Some(0));
+        assert_eq!(state.selected_config_name().as_deref(), Some("gamma"));
+    }
+}
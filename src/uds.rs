@@ -2,18 +2,28 @@ use std::time::{Duration, Instant};
 use std::path::Path;
 use tokio::net::UnixStream;
 use tokio::time::timeout;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use regex::Regex;
+use crate::config::ScriptStep;
 use crate::error::BenchmarkError;
+use crate::limiter::ConnectLimiter;
+use crate::protocol::{self, Exchange, Protocol, Request, Response};
+use crate::tcp::run_setup;
+use crate::trace::Tracer;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn send_uds(
     socket_path: &Path,
-    data: Option<&[u8]>,
-    expect_pattern: Option<&str>,
-    timeout_duration: Duration,
-    buffer_size: usize,
-) -> Result<(Vec<u8>, Duration), BenchmarkError> {
-    let start_time = Instant::now();
+    setup: &[ScriptStep],
+    protocol: &dyn Protocol,
+    request: Request<'_>,
+    connect_limiter: &ConnectLimiter,
+    connect_timeout: Duration,
+    repeat: usize,
+    trace: Option<&Tracer>,
+) -> Result<Exchange, BenchmarkError> {
+    // Waiting for the connection limiter is not part of the latency
+    connect_limiter.acquire().await;
+    
+    let mut start_time = Instant::now();
     
     // Check if socket exists
     if !socket_path.exists() {
@@ -25,77 +35,37 @@ pub async fn send_uds(
     
     // Establish connection
     let mut stream = match timeout(
-        timeout_duration,
+        connect_timeout,
         UnixStream::connect(socket_path),
     ).await {
-        Ok(Ok(stream)) => stream,
-        Ok(Err(e)) => return Err(BenchmarkError::Io(e)),
-        Err(_) => return Err(BenchmarkError::ConnectionTimeout(timeout_duration)),
+        Ok(Ok(stream)) => {
+            tracing::debug!("connected");
+            connect_limiter.connected();
+            stream
+        },
+        Ok(Err(e)) => return Err(e.into()),
+        Err(_) => return Err(BenchmarkError::ConnectionTimeout(connect_timeout)),
     };
     
-    // Send data if provided
-    if let Some(bytes) = data {
-        if !bytes.is_empty() {
-            match timeout(timeout_duration, stream.write_all(bytes)).await {
-                Ok(Ok(_)) => {},
-                Ok(Err(e)) => return Err(BenchmarkError::Io(e)),
-                Err(_) => return Err(BenchmarkError::RequestTimeout(timeout_duration)),
-            }
-        }
+    // Run the setup exchanges; only the final exchange is measured
+    if !setup.is_empty() {
+        run_setup(&mut stream, setup, request.timeout, request.buffer_size).await?;
+        start_time = Instant::now();
     }
     
-    // Read response
-    let mut response = Vec::new();
-    let mut buffer = vec![0; buffer_size];
-    
-    // If we expect a pattern, read until we find it or timeout
-    if let Some(pattern) = expect_pattern {
-        let regex = Regex::new(pattern)
-            .map_err(|_| BenchmarkError::Parse(format!("Invalid regex pattern: {}", pattern)))?;
-        
-        let deadline = Instant::now() + timeout_duration;
-        let mut found = false;
-        
-        while Instant::now() < deadline && !found {
-            match stream.read(&mut buffer).await {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    response.extend_from_slice(&buffer[..n]);
-                    // Check if pattern is found
-                    if let Ok(text) = String::from_utf8(response.clone()) {
-                        if regex.is_match(&text) {
-                            found = true;
-                            break;
-                        }
-                    }
-                },
-                Err(e) => return Err(BenchmarkError::Io(e)),
-            }
-        }
-        
-        if !found {
-            return Err(BenchmarkError::ResponseValidation(
-                format!("Expected pattern '{}' not found in response", pattern)
-            ));
-        }
-    } else {
-        // Without a pattern, just read what's available within the timeout
-        match timeout(timeout_duration, async {
-            loop {
-                match stream.read(&mut buffer).await {
-                    Ok(0) => break, // EOF
-                    Ok(n) => response.extend_from_slice(&buffer[..n]),
-                    Err(e) => return Err(BenchmarkError::Io(e)),
-                }
-            }
-            Ok::<(), BenchmarkError>(())
-        }).await {
-            Ok(Ok(_)) => {},
-            Ok(Err(e)) => return Err(e),
-            Err(_) => {}, // Timeout is normal when no pattern is expected
-        }
+    // Connecting counts towards the latency unless setup steps ran first
+    let exchange_start = start_time.elapsed();
+    let Exchange { response, messages, bytes_sent } = protocol::exchange_repeated(protocol, &mut stream, &request, repeat).await?;
+    if let Some(trace) = trace {
+        trace.record(start_time, exchange_start + response.elapsed, &protocol::sent_bytes(&request, repeat), &response.data);
     }
-    
-    let elapsed = start_time.elapsed();
-    Ok((response, elapsed))
+    Ok(Exchange {
+        response: Response {
+            data: response.data,
+            first_byte: response.first_byte.map(|t| exchange_start + t),
+            elapsed: exchange_start + response.elapsed,
+        },
+        messages,
+        bytes_sent,
+    })
 }
\ No newline at end of file
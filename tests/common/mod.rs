@@ -0,0 +1,83 @@
+//! Echo servers on ephemeral ports for the integration tests
+
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::net::{TcpListener, UnixListener};
+use thrustbench::server::{self, ServerOptions};
+
+/// Starts `serve_http` on a free local port and returns its address
+pub async fn http_server(options: ServerOptions) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(server::serve_http(listener, options));
+    addr
+}
+
+/// Starts `serve_tcp` on a free local port and returns its address
+pub async fn tcp_server(options: ServerOptions) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(server::serve_tcp(listener, options));
+    addr
+}
+
+/// Starts `serve_uds` on a fresh socket path named after `name`
+pub async fn uds_server(name: &str, options: ServerOptions) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("thrustbench-{}-{}.sock", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    tokio::spawn(server::serve_uds(listener, options));
+    path
+}
+
+/// Starts a server on a free local port that accepts connections and
+/// hands each one to `handle`
+pub async fn raw_server<F, Fut>(handle: F) -> SocketAddr
+where
+    F: Fn(tokio::net::TcpStream) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle(stream));
+        }
+    });
+    addr
+}
+
+/// Starts an HTTPS server on a free local port with the self-signed
+/// `localhost` certificate in `tests/data`, answering one request per
+/// connection
+pub async fn tls_server() -> SocketAddr {
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let cert = CertificateDer::from(include_bytes!("../data/localhost.crt.der").to_vec());
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(include_bytes!("../data/localhost.key.der").to_vec()));
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(config));
+
+    raw_server(move |stream| {
+        let acceptor = acceptor.clone();
+        async move {
+            let Ok(mut stream) = acceptor.accept(stream).await else { return };
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(n) => head.extend_from_slice(&buf[..n]),
+                }
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok").await;
+            let _ = stream.shutdown().await;
+        }
+    }).await
+}
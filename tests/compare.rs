@@ -0,0 +1,86 @@
+mod common;
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use tokio::net::TcpListener;
+use tokio::process::Command;
+use thrustbench::server::ServerOptions;
+
+/// Starts a server answering HTTP/1.1 and HTTP/2 (h2c prior knowledge) on
+/// the same port
+async fn dual_protocol_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let service = service_fn(|_: Request<Incoming>| async {
+                    Ok::<_, Infallible>(Response::new(Full::new(Bytes::from_static(b"ok"))))
+                });
+                let _ = auto::Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+    addr
+}
+
+/// Runs `thrustbench --compare-protocols` against `addr`, printing `output`
+async fn compare(addr: SocketAddr, output: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_thrustbench"))
+        .args(["--no-history", "--keep-alive", "-c", "2", "-r", "20", "--output", output, "http"])
+        .arg(format!("http://{}/", addr))
+        .arg("--compare-protocols")
+        .output()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn compare_protocols_reports_both_runs_against_a_dual_protocol_server() {
+    let addr = dual_protocol_server().await;
+
+    let output = compare(addr, "json").await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let report = &json["report"];
+    assert_eq!(report["http1"]["successful_requests"], 20);
+    assert_eq!(report["http2"]["successful_requests"], 20);
+    assert!(!report["comparison"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn compare_protocols_prints_each_run_and_the_comparison_as_its_own_ndjson_line() {
+    let addr = dual_protocol_server().await;
+
+    let output = compare(addr, "ndjson").await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["report"]["successful_requests"], 20);
+    assert_eq!(lines[1]["report"]["successful_requests"], 20);
+    assert!(!lines[2]["report"]["comparison"].as_array().unwrap().is_empty());
+    assert!(lines.iter().all(|line| line["config"].is_object()));
+}
+
+#[tokio::test]
+async fn compare_protocols_refuses_a_server_without_http2() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    let output = compare(addr, "json").await;
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not support HTTP/2"));
+}
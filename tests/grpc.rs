@@ -0,0 +1,61 @@
+mod common;
+
+use std::convert::Infallible;
+use std::time::Duration;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http2;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use thrustbench::config::GrpcConfig;
+use thrustbench::runner::GrpcRunner;
+
+/// A trailers-only gRPC server: `/svc/Fail` answers grpc-status 5 after a
+/// 50ms pause, anything else grpc-status 0 at once
+async fn grpc_server() -> std::net::SocketAddr {
+    common::raw_server(|stream| async move {
+        let service = service_fn(|request: Request<Incoming>| async move {
+            let fail = request.uri().path() == "/svc/Fail";
+            let _ = request.into_body().collect().await;
+            if fail {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            let response = Response::builder()
+                .header("content-type", "application/grpc")
+                .header("grpc-status", if fail { "5" } else { "0" })
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            Ok::<_, Infallible>(response)
+        });
+        let _ = http2::Builder::new(TokioExecutor::new()).serve_connection(TokioIo::new(stream), service).await;
+    }).await
+}
+
+fn grpc_config(addr: std::net::SocketAddr, method: &str) -> GrpcConfig {
+    GrpcConfig::new(
+        format!("http://{}", addr), method.to_string(), None, Vec::new(),
+        Some(2), Some(10), Some(Duration::from_secs(10)), Some(2000), false,
+    )
+}
+
+#[tokio::test]
+async fn ok_calls_are_successful() {
+    let addr = grpc_server().await;
+    let report = GrpcRunner::new(grpc_config(addr, "svc/Ok")).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.failed_requests, 0);
+}
+
+#[tokio::test]
+async fn non_ok_status_stays_out_of_the_latencies() {
+    let addr = grpc_server().await;
+    let report = GrpcRunner::new(grpc_config(addr, "svc/Fail")).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 0);
+    assert_eq!(report.failed_requests, 10);
+    assert_eq!(report.errors.unwrap().counts["response_validation"], 10);
+    assert_eq!(report.max_response_time, Duration::ZERO);
+}
@@ -0,0 +1,1274 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use thrustbench::config::{CacheBust, ExpectedLength, HttpConfig, StreamResponse};
+use thrustbench::error::BenchmarkError;
+use thrustbench::report::StopReason;
+use thrustbench::runner::{CancelFlag, HttpRunner, PauseFlag, RawOutput};
+use thrustbench::server::ServerOptions;
+
+fn http_config(addr: std::net::SocketAddr, requests: usize) -> HttpConfig {
+    HttpConfig::new(
+        format!("http://{}/", addr), Some("POST".to_string()), None, None, None,
+        Some(2), Some(requests), Some(Duration::from_secs(10)), Some(2000), true,
+    )
+}
+
+#[tokio::test]
+async fn streamed_body_is_sent_in_full() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 4);
+    config.stream_body_size = Some(200 * 1024);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4);
+    // The echo server answers with the body it received
+    assert_eq!(report.bytes_received, 4 * 200 * 1024);
+    assert!(report.bytes_sent >= 4 * 200 * 1024);
+}
+
+#[tokio::test]
+async fn samples_are_kept_only_when_asked_for() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    let report = HttpRunner::new(http_config(addr, 6)).run().await.unwrap();
+    assert!(report.samples.is_empty());
+
+    let report = HttpRunner::new(http_config(addr, 6)).with_samples().run().await.unwrap();
+    assert_eq!(report.samples.len(), 6);
+}
+
+#[tokio::test]
+async fn output_dir_writes_durations_in_microseconds() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let config = http_config(addr, 4);
+    let report = HttpRunner::new(config.clone()).with_samples().run().await.unwrap();
+
+    let dir = std::env::temp_dir().join(format!("thrustbench-output-dir-{}", std::process::id()));
+    thrustbench::report::write_output_dir(&dir, &report, &config).unwrap();
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("config.json")).unwrap()).unwrap();
+    assert_eq!(written["timeout_us"], 2_000_000);
+    assert_eq!(written["duration_us"], 10_000_000);
+    let samples = std::fs::read_to_string(dir.join("samples.jsonl")).unwrap();
+    assert_eq!(samples.lines().count(), 4);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn strict_timeout_classifies_slow_responses_without_cutting_them_short() {
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(30),
+        ..Default::default()
+    }).await;
+
+    let mut config = http_config(addr, 4);
+    config.strict_timeout = Some(Duration::from_millis(10));
+    let report = HttpRunner::new(config).run().await.unwrap();
+    assert_eq!(report.failed_requests, 4);
+    assert_eq!(report.errors.unwrap().counts["request_timeout"], 4);
+    // Each request still waited for its response under the 2s --timeout
+    assert!(report.total_time >= Duration::from_millis(60));
+
+    let mut config = http_config(addr, 4);
+    config.strict_timeout = Some(Duration::from_secs(1));
+    let report = HttpRunner::new(config).run().await.unwrap();
+    assert_eq!(report.successful_requests, 4);
+}
+
+/// Answers every fourth request after 80ms and the rest at once
+async fn bimodal_server() -> std::net::SocketAddr {
+    let served = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    common::raw_server(move |mut stream| {
+        let served = served.clone();
+        async move {
+            while request_head(&mut stream).await.is_some() {
+                if served.fetch_add(1, std::sync::atomic::Ordering::Relaxed).is_multiple_of(4) {
+                    tokio::time::sleep(Duration::from_millis(80)).await;
+                }
+                if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                    return;
+                }
+            }
+        }
+    }).await
+}
+
+#[tokio::test]
+async fn calibrated_cutoff_lands_in_the_mode_its_percentile_falls_in() {
+    let addr = bimodal_server().await;
+    let calibrate = |percentile| thrustbench::runner::calibrate_timeout(percentile, Duration::from_millis(500), |duration| {
+        let mut probe = http_config(addr, 0);
+        probe.duration = duration;
+        let runner = HttpRunner::new(probe).with_samples();
+        async move { runner.run().await }
+    });
+
+    // A quarter of the responses are slow, so p50 is fast and p90 slow
+    let fast = calibrate(50.0).await.unwrap();
+    assert!(fast.timeout < Duration::from_millis(40), "{:?}", fast);
+    let slow = calibrate(90.0).await.unwrap();
+    assert!(slow.timeout >= Duration::from_millis(80), "{:?}", slow);
+
+    // Held to the fast cutoff every slow response times out; held to the
+    // slow one every fast response passes
+    let run = |cutoff: Duration| {
+        let mut config = http_config(addr, 40);
+        config.strict_timeout = Some(cutoff);
+        async move { HttpRunner::new(config).run().await.unwrap() }
+    };
+    let report = run(fast.timeout).await;
+    assert!(report.successful_requests <= 30, "{}", report.successful_requests);
+    assert!(report.errors.unwrap().counts["request_timeout"] >= 10);
+    let report = run(slow.timeout).await;
+    assert!(report.successful_requests >= 30, "{}", report.successful_requests);
+}
+
+#[tokio::test]
+async fn run_that_reaches_its_request_count_says_so() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    let report = HttpRunner::new(http_config(addr, 6)).run().await.unwrap();
+
+    assert_eq!(report.stop_reason, StopReason::RequestsCompleted);
+    assert_eq!(report.total_requests, 6);
+}
+
+#[tokio::test]
+async fn run_that_runs_out_of_time_first_says_so() {
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(20),
+        ..ServerOptions::default()
+    }).await;
+    let mut config = http_config(addr, 1_000_000);
+    config.duration = Duration::from_millis(300);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.stop_reason, StopReason::DurationElapsed);
+    assert!(report.total_requests < 1_000_000);
+}
+
+#[tokio::test]
+async fn zero_duration_runs_by_request_count() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 100);
+    config.duration = Duration::ZERO;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.stop_reason, StopReason::RequestsCompleted);
+    assert_eq!(report.successful_requests, 100);
+}
+
+#[tokio::test]
+async fn zero_duration_and_zero_requests_is_rejected() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 0);
+    config.duration = Duration::ZERO;
+
+    let result = HttpRunner::new(config).run().await;
+
+    assert!(matches!(result, Err(BenchmarkError::Config(_))), "{:?}", result.map(|r| r.total_requests));
+}
+
+#[tokio::test]
+async fn cancel_flag_ends_the_run_with_a_partial_report() {
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(10),
+        ..ServerOptions::default()
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.duration = Duration::from_secs(30);
+    let cancel = Arc::new(CancelFlag::default());
+    tokio::spawn({
+        let cancel = cancel.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            cancel.cancel();
+        }
+    });
+
+    let started = Instant::now();
+    let report = HttpRunner::new(config).with_cancel(cancel).run().await.unwrap();
+
+    assert_eq!(report.stop_reason, StopReason::Interrupted);
+    assert!(report.successful_requests > 0);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn fail_fast_ends_the_run_with_the_first_error() {
+    // Nothing listens on a port that was just released
+    let addr = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let mut config = http_config(addr, 0);
+    config.fail_fast = true;
+
+    let error = HttpRunner::new(config).run().await.unwrap_err();
+
+    assert!(matches!(error, BenchmarkError::ConnectionRefused), "{:?}", error);
+}
+
+#[tokio::test]
+async fn hosts_file_hosts_share_the_load_evenly() {
+    let first = common::http_server(ServerOptions::default()).await;
+    let second = common::http_server(ServerOptions::default()).await;
+
+    for keep_alive in [false, true] {
+        let mut config = http_config(first, 8);
+        config.keep_alive = keep_alive;
+        config.hosts = vec![first.to_string(), second.to_string()];
+        config.per_host = true;
+
+        let report = HttpRunner::new(config).run().await.unwrap();
+
+        let requests: Vec<usize> = report.per_host.unwrap().iter().map(|host| host.requests).collect();
+        assert_eq!(requests, [4, 4], "keep-alive {}", keep_alive);
+        if keep_alive {
+            // One connection per worker, each staying on its host
+            assert_eq!(report.connections_opened, 2);
+        }
+    }
+}
+
+#[tokio::test]
+async fn keepalive_requests_opens_a_new_connection_after_each_batch() {
+    // Requests served by each connection, added once it closes
+    let served = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let served = served.clone();
+        move |mut stream| {
+            let served = served.clone();
+            async move {
+                let mut requests = 0;
+                while request_head(&mut stream).await.is_some() {
+                    requests += 1;
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                        break;
+                    }
+                }
+                served.lock().unwrap().push(requests);
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 100);
+    config.keepalive_requests = Some(10);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 100);
+    // 50 requests per worker, 10 to a connection
+    assert_eq!(report.connections_opened, 10);
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while served.lock().unwrap().len() < 10 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(*served.lock().unwrap(), [10; 10]);
+}
+
+#[tokio::test]
+async fn report_names_the_connection_strategy_and_the_connections_it_opened() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let config = |configure: fn(&mut HttpConfig)| {
+        let mut config = http_config(addr, 12);
+        config.method = "GET".to_string();
+        configure(&mut config);
+        config
+    };
+    let cases = [
+        ("new connection per request", config(|config| config.keep_alive = false), 12),
+        ("persistent keep-alive", config(|_| {}), 2),
+        ("persistent keep-alive, reconnecting every 3 requests", config(|config| config.keepalive_requests = Some(3)), 4),
+        ("persistent, pipelining 3 requests", config(|config| config.pipeline = Some(3)), 2),
+        ("connect only, no requests sent", config(|config| config.connect_only = true), 12),
+    ];
+
+    for (strategy, config, connections) in cases {
+        let report = HttpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, 12, "{}: {:?}", strategy, report.errors);
+        assert_eq!(report.connection_strategy, strategy);
+        assert_eq!(report.connections_opened, connections, "{}", strategy);
+    }
+}
+
+// 127.0.0.2 is only a loopback address on Linux
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn local_addr_is_the_source_the_server_sees() {
+    let peers = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let peers = peers.clone();
+        move |mut stream| {
+            peers.lock().unwrap().push(stream.peer_addr().unwrap().ip());
+            async move {
+                while request_head(&mut stream).await.is_some() {
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }).await;
+    let local: std::net::IpAddr = "127.0.0.2".parse().unwrap();
+    let mut config = http_config(addr, 4);
+    config.keep_alive = false;
+    config.local_addr = Some(local);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4, "{:?}", report.errors);
+    assert_eq!(*peers.lock().unwrap(), [local; 4]);
+}
+
+#[tokio::test]
+async fn tls_sessions_are_resumed_only_when_asked_for() {
+    let addr = common::tls_server().await;
+
+    for resumption in [false, true] {
+        let mut config = http_config(addr, 6);
+        config.url = format!("https://localhost:{}/", addr.port());
+        config.method = "GET".to_string();
+        config.concurrency = 1;
+        config.keep_alive = false;
+        config.insecure = true;
+        config.tls_resumption = resumption;
+
+        let report = HttpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, 6);
+        let handshakes = report.tls_handshakes.unwrap();
+        assert_eq!(handshakes.resumption, resumption);
+        if resumption {
+            // Only the worker's first connection needs a full handshake
+            assert_eq!((handshakes.full, handshakes.resumed), (1, 5));
+        } else {
+            assert_eq!((handshakes.full, handshakes.resumed), (6, 0));
+        }
+    }
+}
+
+#[tokio::test]
+async fn abort_if_p99_above_trips_when_the_server_stops_answering() {
+    let addr = common::raw_server(|stream| async move {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        drop(stream);
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.concurrency = 20;
+    config.timeout = Duration::from_millis(200);
+    config.abort_p99_above = Some(Duration::from_millis(50));
+
+    let start = Instant::now();
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    // Only timeouts, no responses, and still stopped well before the 10s duration
+    assert_eq!(report.successful_requests, 0);
+    assert_eq!(report.stop_reason, StopReason::LatencySpike);
+    assert!(start.elapsed() < Duration::from_secs(3), "took {:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn expect_length_fails_bodies_outside_the_bounds() {
+    let addr = common::http_server(ServerOptions { response_size: Some(13), ..Default::default() }).await;
+
+    for (min, max, successful) in [(Some(13), Some(13), 4), (Some(14), None, 0), (None, Some(12), 0)] {
+        let mut config = http_config(addr, 4);
+        config.expect_length = ExpectedLength::new(None, min, max).unwrap();
+
+        let report = HttpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, successful, "{:?}..{:?}", min, max);
+        if successful == 0 {
+            assert_eq!(report.errors.unwrap().counts["response_validation"], 4);
+        }
+    }
+}
+
+#[tokio::test]
+async fn low_memory_keeps_no_samples_but_still_reports_percentiles() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 200);
+    config.low_memory = true;
+
+    let report = HttpRunner::new(config).with_samples().run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 200);
+    assert!(report.samples.is_empty());
+    assert!(report.min_response_time > Duration::ZERO);
+    assert!(report.min_response_time <= report.p50_response_time);
+    assert!(report.p50_response_time <= report.p99_response_time);
+    assert!(report.p99_response_time <= report.max_response_time);
+}
+
+#[tokio::test]
+async fn paused_time_is_left_out_of_the_duration() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 0);
+    config.duration = Duration::from_millis(400);
+    let pause = Arc::new(PauseFlag::new());
+    pause.toggle();
+    tokio::spawn({
+        let pause = pause.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            pause.toggle();
+        }
+    });
+
+    let start = Instant::now();
+    let report = HttpRunner::new(config).with_pause(pause).run().await.unwrap();
+
+    // The run lasts its duration plus the pause, but only reports the former
+    assert!(start.elapsed() >= Duration::from_millis(700), "took {:?}", start.elapsed());
+    assert!(report.total_time < Duration::from_millis(600), "{:?}", report.total_time);
+    assert!(report.successful_requests > 0);
+}
+
+#[tokio::test]
+async fn slow_response_trips_the_read_timeout_but_not_the_connect_timeout() {
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(300),
+        ..ServerOptions::default()
+    }).await;
+
+    // Connecting is instant, so only the tight read timeout fires
+    let mut config = http_config(addr, 4);
+    config.connect_timeout = Duration::from_secs(2);
+    config.timeout = Duration::from_millis(100);
+    let started = Instant::now();
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 0);
+    let errors = report.errors.unwrap();
+    assert_eq!(errors.counts.get("request_timeout"), Some(&4), "{:?}", errors.counts);
+    assert!(!errors.counts.contains_key("connection_timeout"), "{:?}", errors.counts);
+    assert_eq!((errors.connect_failures, errors.io_failures), (0, 4));
+    // Each request gives up at the read timeout instead of waiting out the delay
+    assert!(started.elapsed() < Duration::from_millis(550), "took {:?}", started.elapsed());
+
+    // A tight connect timeout doesn't cut a slow response short
+    let mut config = http_config(addr, 4);
+    config.connect_timeout = Duration::from_millis(50);
+    config.timeout = Duration::from_secs(2);
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4, "{:?}", report.errors);
+    assert!(report.min_response_time >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn self_signed_certificate_fails_verification_unless_insecure() {
+    use thrustbench::tls::{handshake, TlsOptions};
+
+    let addr = common::tls_server().await;
+    let connect = || tokio::net::TcpStream::connect(addr);
+
+    let rejected = handshake(connect().await.unwrap(), "localhost", false, &TlsOptions::default()).await;
+    assert!(matches!(rejected, Err(BenchmarkError::CertificateVerification(_))), "{:?}", rejected.err());
+
+    let options = TlsOptions { insecure: true, ..TlsOptions::default() };
+    assert!(handshake(connect().await.unwrap(), "localhost", false, &options).await.is_ok());
+}
+
+#[tokio::test]
+async fn run_counts_a_rejected_certificate_as_a_verification_failure() {
+    let addr = common::tls_server().await;
+    let mut config = http_config(addr, 2);
+    config.url = format!("https://localhost:{}/", addr.port());
+    config.method = "GET".to_string();
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 0);
+    let errors = report.errors.unwrap();
+    assert_eq!(errors.counts.get("certificate_verification"), Some(&2), "{:?}", errors.counts);
+    assert!(!errors.counts.contains_key("tls_handshake"), "{:?}", errors.counts);
+}
+
+#[tokio::test]
+async fn check_cert_reports_when_the_certificate_expires() {
+    let addr = common::tls_server().await;
+    let mut config = http_config(addr, 2);
+    config.url = format!("https://localhost:{}/", addr.port());
+    config.method = "GET".to_string();
+    config.insecure = true;
+    config.check_cert = true;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.cert_not_after.as_deref(), Some("2099-12-31T23:59:59Z"));
+    assert!(report.cert_days_remaining.unwrap() > 365 * 70);
+}
+
+/// Answers every request with a server-sent events stream that sends an
+/// event every 20ms and never ends
+async fn sse_server() -> std::net::SocketAddr {
+    common::raw_server(|mut stream| async move {
+        if request_head(&mut stream).await.is_none() {
+            return;
+        }
+        let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+        if stream.write_all(head.as_bytes()).await.is_err() {
+            return;
+        }
+        let event = "data: tick\n\n";
+        let chunk = format!("{:x}\r\n{}\r\n", event.len(), event);
+        while stream.write_all(chunk.as_bytes()).await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }).await
+}
+
+#[tokio::test]
+async fn endless_stream_ends_at_the_timeout_with_its_chunks() {
+    let addr = sse_server().await;
+    let mut config = http_config(addr, 2);
+    config.method = "GET".to_string();
+    config.timeout = Duration::from_millis(300);
+    config.stream_response = Some(StreamResponse { max_chunks: None });
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 2);
+    assert!(report.errors.is_none());
+    let streaming = report.streaming.unwrap();
+    assert!(streaming.chunks >= 2 * 5, "{} chunks", streaming.chunks);
+    assert!(streaming.interval.is_some());
+}
+
+#[tokio::test]
+async fn endless_stream_ends_with_the_run() {
+    let addr = sse_server().await;
+    let mut config = http_config(addr, 0);
+    config.method = "GET".to_string();
+    config.duration = Duration::from_millis(400);
+    config.timeout = Duration::from_secs(10);
+    config.stream_response = Some(StreamResponse { max_chunks: None });
+
+    let start = Instant::now();
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    // Each worker's one stream lasts the whole run
+    assert!(start.elapsed() < Duration::from_secs(2), "took {:?}", start.elapsed());
+    assert_eq!(report.successful_requests, 2);
+    assert!(report.streaming.unwrap().chunks >= 2 * 5);
+}
+
+/// The request line and headers of the next request on `stream`
+async fn request_head(stream: &mut tokio::net::TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+    Some(String::from_utf8_lossy(&request).into_owned())
+}
+
+#[tokio::test]
+async fn every_request_carries_its_own_request_id() {
+    let ids = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let ids = ids.clone();
+        move |mut stream| {
+            let ids = ids.clone();
+            async move {
+                let Some(request) = request_head(&mut stream).await else { return };
+                let id = request.lines()
+                    .find_map(|line| line.strip_prefix("x-request-id: ").map(str::to_string));
+                ids.lock().unwrap().push(id);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 6);
+    config.keep_alive = false;
+    config.request_id_header = Some("X-Request-Id".to_string());
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 6);
+    let ids: Vec<String> = ids.lock().unwrap().iter().map(|id| id.clone().expect("X-Request-Id missing")).collect();
+    let distinct: std::collections::HashSet<&String> = ids.iter().collect();
+    assert_eq!(distinct.len(), 6);
+}
+
+#[tokio::test]
+async fn every_request_carries_its_own_cache_bust_value() {
+    let targets = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let targets = targets.clone();
+        move |mut stream| {
+            let targets = targets.clone();
+            async move {
+                let Some(request) = request_head(&mut stream).await else { return };
+                // The request target may come in absolute form
+                let target: hyper::Uri = request.split(' ').nth(1).unwrap_or_default().parse().unwrap();
+                targets.lock().unwrap().push(target.path_and_query().unwrap().to_string());
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 8);
+    config.url = format!("http://{}/items?page=2", addr);
+    config.keep_alive = false;
+    config.seed = Some(7);
+    config.cache_bust = Some(CacheBust::Query);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 8);
+    let targets = targets.lock().unwrap().clone();
+    let values: std::collections::HashSet<&str> = targets.iter()
+        .map(|target| target.strip_prefix("/items?page=2&_cb=").unwrap_or_else(|| panic!("unexpected target {}", target)))
+        .collect();
+    assert_eq!(values.len(), 8);
+    assert!(values.iter().all(|value| value.len() == 16 && value.chars().all(|c| c.is_ascii_hexdigit())));
+}
+
+#[tokio::test]
+async fn deadline_header_carries_the_timeout_in_milliseconds() {
+    let deadlines = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let deadlines = deadlines.clone();
+        move |mut stream| {
+            let deadlines = deadlines.clone();
+            async move {
+                let Some(request) = request_head(&mut stream).await else { return };
+                let deadline = request.lines()
+                    .find_map(|line| line.strip_prefix("x-request-deadline: ").map(str::to_string));
+                deadlines.lock().unwrap().push(deadline);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 4);
+    config.keep_alive = false;
+    config.timeout = Duration::from_millis(1500);
+    config.deadline_header = Some("X-Request-Deadline".to_string());
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4);
+    assert_eq!(*deadlines.lock().unwrap(), vec![Some("1500".to_string()); 4]);
+}
+
+/// A keep-alive server that answers the first request on each connection,
+/// then reads the second and drops the connection without answering, as a
+/// server closing an idle connection just as a request arrives does
+async fn closes_after_one_response() -> std::net::SocketAddr {
+    common::raw_server(|mut stream| async move {
+        if request_head(&mut stream).await.is_none() {
+            return;
+        }
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await;
+        let _ = request_head(&mut stream).await;
+    }).await
+}
+
+#[tokio::test]
+async fn idempotent_request_on_a_dropped_connection_is_resent() {
+    let addr = closes_after_one_response().await;
+    let mut config = http_config(addr, 6);
+    config.method = "GET".to_string();
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 6);
+    // Every request after a worker's first finds its connection dropped
+    assert_eq!(report.stale_reconnects, Some(4));
+    assert_eq!(report.connections_opened, 6);
+}
+
+#[tokio::test]
+async fn post_on_a_dropped_connection_is_not_resent() {
+    let addr = closes_after_one_response().await;
+
+    let report = HttpRunner::new(http_config(addr, 6)).run().await.unwrap();
+
+    // Each worker: answered, dropped, then answered on a new connection
+    assert_eq!(report.successful_requests, 4);
+    assert_eq!(report.failed_requests, 2);
+    assert_eq!(report.stale_reconnects, Some(0));
+}
+
+#[tokio::test]
+async fn connections_closed_while_idle_are_replaced() {
+    // Closes each connection once it has been idle for 50ms
+    let addr = common::raw_server(|mut stream| async move {
+        while let Ok(Some(_)) = tokio::time::timeout(Duration::from_millis(50), request_head(&mut stream)).await {
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await;
+        }
+    }).await;
+    let mut config = http_config(addr, 6);
+    config.method = "GET".to_string();
+    // A request every 100ms per worker, so each connection sits idle past the server's timeout
+    config.rate = Some(20.0);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 6);
+    assert_eq!(report.failed_requests, 0);
+    assert!(report.connections_opened > 2, "{} connections", report.connections_opened);
+}
+
+#[tokio::test]
+async fn raw_output_progress_counts_successful_requests() {
+    // Every other connection is dropped unanswered
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let connections = connections.clone();
+        move |mut stream| {
+            let connection = connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            async move {
+                if request_head(&mut stream).await.is_none() || connection % 2 == 1 {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        }
+    }).await;
+    let path = std::env::temp_dir().join(format!("thrustbench-{}-raw.jsonl", std::process::id()));
+    let mut config = http_config(addr, 8);
+    config.keep_alive = false;
+
+    let report = HttpRunner::new(config)
+        .with_raw_output(RawOutput { path: path.clone(), progress: true, sample_rate: 1.0, seed: None })
+        .run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4);
+    assert_eq!(report.failed_requests, 4);
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.iter().filter(|line| line["type"] == "request").count(), 4);
+    let progress = lines.last().unwrap();
+    assert_eq!(progress["type"], "progress");
+    // The rate over the run so far covers the 4 successes, not all 8 requests
+    let successes = progress["rps"].as_f64().unwrap() * progress["elapsed_ms"].as_f64().unwrap() / 1000.0;
+    assert!(successes > 3.5 && successes < 4.01, "{}", successes);
+}
+
+#[tokio::test]
+async fn raw_output_write_failure_keeps_the_report() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    // Every write to /dev/full fails with ENOSPC
+    let report = HttpRunner::new(http_config(addr, 6))
+        .with_raw_output(RawOutput { path: "/dev/full".into(), progress: true, sample_rate: 1.0, seed: None })
+        .run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 6);
+}
+
+#[tokio::test]
+async fn slow_connection_is_flagged_as_imbalanced() {
+    // Every other connection answers 50ms late
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let connections = connections.clone();
+        move |mut stream| {
+            let slow = connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % 2 == 1;
+            async move {
+                while request_head(&mut stream).await.is_some() {
+                    if slow {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.method = "GET".to_string();
+    config.duration = Duration::from_millis(500);
+    config.per_worker_stats = true;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    let requests = report.per_worker_requests.unwrap();
+    assert_eq!(requests.len(), 2);
+    let imbalance = report.worker_imbalance.unwrap();
+    assert!(imbalance.imbalanced, "{:?}", requests);
+    assert!(requests.iter().min().unwrap() * 3 < *requests.iter().max().unwrap(), "{:?}", requests);
+}
+
+/// How late a sibling task's 1ms sleep ends while `check_response` matches
+/// `--expect-body` against a 4 MiB body on the same (current-thread)
+/// runtime, and how long that match takes on its own
+async fn sibling_delay_during_check(offload_above: Option<usize>) -> (Duration, Duration) {
+    const SIZE: usize = 4 * 1024 * 1024;
+    let mut body = vec![b'a'; SIZE];
+    body[SIZE - 1] = b'!';
+    let expectations = Arc::new(thrustbench::http::ResponseExpectations::new(Some("[a-z]+!"), &[], None, false).unwrap());
+    let started = Instant::now();
+    expectations.check(&body).unwrap();
+    let cost = started.elapsed();
+
+    let sibling = tokio::spawn(async {
+        let started = Instant::now();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        started.elapsed()
+    });
+    // Let the sibling start its sleep before the check takes the thread
+    tokio::task::yield_now().await;
+    let response = thrustbench::http::HttpResponse {
+        status: hyper::StatusCode::OK,
+        body,
+        header_len: 0,
+        body_sent: 0,
+        cache: thrustbench::http::CacheStatus::Unknown,
+        server_timing: Vec::new(),
+        elapsed: Duration::ZERO,
+        chunks: Vec::new(),
+    };
+
+    let response = thrustbench::http::check_response(&expectations, response, offload_above).await.unwrap();
+    assert_eq!(response.body.len(), SIZE);
+    (sibling.await.unwrap(), cost)
+}
+
+#[tokio::test]
+async fn offloaded_body_check_leaves_sibling_requests_on_time() {
+    let (inline, cost) = sibling_delay_during_check(None).await;
+    assert!(inline >= cost / 2, "sibling took {:?} behind an inline check of {:?}", inline, cost);
+
+    let (offloaded, cost) = sibling_delay_during_check(Some(1024 * 1024)).await;
+    assert!(offloaded < cost / 2, "sibling took {:?} behind an offloaded check of {:?}", offloaded, cost);
+}
+
+#[tokio::test]
+async fn only_errors_lists_each_error_status_with_its_body() {
+    let addr = common::raw_server(|mut stream| async move {
+        while request_head(&mut stream).await.is_some() {
+            let body = r#"{"error":"database unavailable"}"#;
+            let response = format!("HTTP/1.1 500 Internal Server Error\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 6);
+    config.only_errors = true;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    let details = report.error_details.unwrap();
+    assert_eq!(details.len(), 1);
+    assert_eq!(details[0].kind, "http_500");
+    assert_eq!(details[0].count, 6);
+    assert_eq!(details[0].status, Some(500));
+    assert_eq!(details[0].body.as_deref(), Some(r#"{"error":"database unavailable"}"#));
+
+    // Without the flag no responses are kept
+    let report = HttpRunner::new(http_config(addr, 2)).run().await.unwrap();
+    assert!(report.error_details.is_none());
+}
+
+#[tokio::test]
+async fn url_file_sends_each_method_and_body_in_proportion() {
+    let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let sent = sent.clone();
+        move |mut stream| {
+            let sent = sent.clone();
+            async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0; 4096];
+                loop {
+                    // One request: the head, then a Content-Length body
+                    let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                        }
+                        continue;
+                    };
+                    let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+                    let length: usize = head.lines()
+                        .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length: ").map(|v| v.parse().unwrap()))
+                        .unwrap_or(0);
+                    while buf.len() < end + 4 + length {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+                    let body = String::from_utf8_lossy(&buf[end + 4..end + 4 + length]).into_owned();
+                    let mut request_line = head.split(' ');
+                    let (method, target) = (request_line.next().unwrap().to_string(), request_line.next().unwrap());
+                    assert!(target.ends_with("/items"), "{}", target);
+                    buf.drain(..end + 4 + length);
+                    sent.lock().unwrap().push((method, body));
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }).await;
+    let path = std::env::temp_dir().join(format!("thrustbench-urls-{}.txt", std::process::id()));
+    std::fs::write(&path, "3 POST /items {\"name\":\"x\"}\n1 GET /items\n").unwrap();
+    let mut config = http_config(addr, 800);
+    config.method = "GET".to_string();
+    config.endpoints = thrustbench::config::load_endpoints(&path, &config.url).unwrap();
+    config.per_method = true;
+    config.seed = Some(7);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(report.successful_requests, 800, "{:?}", report.errors);
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent.len(), 800);
+    let posts = sent.iter().filter(|(method, _)| method == "POST").count();
+    let gets = sent.iter().filter(|(method, _)| method == "GET").count();
+    assert_eq!(posts + gets, 800);
+    // 3:1 weights, so about 600 POSTs; a fair draw lands well within this
+    assert!((540..=660).contains(&posts), "{} POSTs of 800", posts);
+    assert!(sent.iter().all(|(method, body)| body == if method == "POST" { "{\"name\":\"x\"}" } else { "" }));
+
+    let per_method = report.per_method.unwrap();
+    let counts: Vec<_> = per_method.iter().map(|m| (m.method.as_str(), m.requests)).collect();
+    assert_eq!(counts, [("POST", posts), ("GET", gets)]);
+}
+
+#[tokio::test]
+async fn connect_only_opens_connections_and_sends_nothing() {
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let received = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let (connections, received) = (connections.clone(), received.clone());
+        move |mut stream| {
+            connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let received = received.clone();
+            async move {
+                let mut buf = [0; 1024];
+                while let Ok(n @ 1..) = stream.read(&mut buf).await {
+                    received.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 10);
+    config.connect_only = true;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.bytes_sent, 0);
+    assert!(report.connects_per_second.is_some_and(|rate| rate > 0.0));
+    // Every connection is closed without a byte written to it
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(connections.load(std::sync::atomic::Ordering::Relaxed), 10);
+    assert_eq!(received.load(std::sync::atomic::Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn sample_rate_writes_about_that_share_of_the_requests() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let written = |name: &str| {
+        let path = std::env::temp_dir().join(format!("thrustbench-{}-{}.jsonl", std::process::id(), name));
+        let runner = HttpRunner::new(http_config(addr, 1000))
+            .with_raw_output(RawOutput { path: path.clone(), progress: false, sample_rate: 0.2, seed: Some(11) });
+        async move {
+            let report = runner.run().await.unwrap();
+            assert_eq!(report.successful_requests, 1000);
+            let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+            let _ = std::fs::remove_file(&path);
+            lines
+        }
+    };
+
+    let first = written("sampled-1").await;
+    // 200 expected; a fair draw lands well within this
+    assert!((140..=260).contains(&first), "{} of 1000 requests written", first);
+    // The same seed samples the same number of requests
+    assert_eq!(written("sampled-2").await, first);
+}
+
+#[tokio::test]
+async fn rate_ramp_achieves_the_target_of_each_interval() {
+    // A few milliseconds per response, so scheduling noise on a busy test
+    // machine can't double the p99 the way it can for an instant echo
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(5),
+        ..ServerOptions::default()
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.concurrency = 8;
+    config.duration = Duration::from_secs(2);
+    config.rate_ramp = Some(thrustbench::config::RateRamp { start: 100.0, end: 400.0 });
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    let ramp = report.rate_ramp.unwrap();
+    // Ten 200ms intervals
+    assert_eq!(ramp.points.len(), 10, "{:?}", ramp.points);
+    assert!(ramp.points.windows(2).all(|pair| pair[0].target_rate < pair[1].target_rate));
+    // A 200ms interval only holds 20 to 80 requests, and a busy test machine
+    // shifts a few of them into the next one
+    for point in &ramp.points {
+        let ratio = point.achieved_rate / point.target_rate;
+        assert!((0.5..=1.5).contains(&ratio), "{:.0} req/s against {:.0} at {:?}", point.achieved_rate, point.target_rate, point.offset);
+    }
+    assert!(ramp.degraded.is_none(), "{:?}", ramp.degraded);
+    // About the ramp's average of 250 req/s over the two seconds
+    assert!((400..=600).contains(&report.successful_requests), "{}", report.successful_requests);
+}
+
+#[tokio::test]
+async fn require_non_empty_fails_empty_responses() {
+    let addr = common::http_server(ServerOptions {
+        response_size: Some(0),
+        ..ServerOptions::default()
+    }).await;
+
+    for require_non_empty in [false, true] {
+        let mut config = http_config(addr, 4);
+        config.require_non_empty = require_non_empty;
+
+        let report = HttpRunner::new(config).run().await.unwrap();
+
+        if require_non_empty {
+            assert_eq!(report.successful_requests, 0);
+            assert_eq!(report.errors.unwrap().counts["response_validation"], 4);
+        } else {
+            assert_eq!(report.successful_requests, 4);
+        }
+    }
+}
+
+#[tokio::test]
+async fn achieved_concurrency_counts_hung_requests_and_gaps() {
+    // Requests that never get an answer are in flight for the whole run
+    let addr = common::raw_server(|mut stream| async move {
+        let mut request = Vec::new();
+        let _ = stream.read_to_end(&mut request).await;
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.duration = Duration::from_millis(500);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    let achieved = report.achieved_concurrency.unwrap();
+    assert_eq!(report.successful_requests, 0);
+    assert!((1.8..=2.2).contains(&achieved.avg), "{}", achieved.avg);
+    assert_eq!(achieved.max, 2);
+
+    // Paced requests leave the connections idle most of the time
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(10),
+        ..ServerOptions::default()
+    }).await;
+    let mut config = http_config(addr, 0);
+    config.duration = Duration::from_millis(500);
+    config.rate = Some(20.0);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    let achieved = report.achieved_concurrency.unwrap();
+    assert!(report.successful_requests > 0);
+    assert!(achieved.avg < 0.5, "{}", achieved.avg);
+}
+
+#[tokio::test]
+async fn paced_run_resumes_its_schedule_after_a_pause() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(addr, 20);
+    config.rate = Some(50.0);
+    let pause = Arc::new(PauseFlag::new());
+    tokio::spawn({
+        let pause = pause.clone();
+        async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            pause.toggle();
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            pause.toggle();
+        }
+    });
+
+    let report = HttpRunner::new(config).with_pause(pause).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 20);
+    // Slots due during the pause are sent on the schedule after it, not in
+    // a burst counted as 300ms late
+    let correction = report.latency_correction.unwrap();
+    assert!(correction.corrected.max < Duration::from_millis(100), "{:?}", correction.corrected);
+    // 20 slots 20ms apart, without the pause
+    assert!(report.total_time >= Duration::from_millis(360), "{:?}", report.total_time);
+}
+
+/// Answers the first `slow` requests after 150ms and the rest after 2ms, like
+/// a service warming its caches
+async fn warming_server(slow: usize) -> std::net::SocketAddr {
+    let served = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    common::raw_server(move |mut stream| {
+        let served = served.clone();
+        async move {
+            while request_head(&mut stream).await.is_some() {
+                let delay = if served.fetch_add(1, std::sync::atomic::Ordering::Relaxed) < slow { 150 } else { 2 };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                    return;
+                }
+            }
+        }
+    }).await
+}
+
+#[tokio::test]
+async fn adaptive_timeout_lets_a_warming_server_through_and_then_narrows() {
+    // A fixed timeout tight enough for the warm server fails the cold requests
+    let mut config = http_config(warming_server(6).await, 200);
+    config.timeout = Duration::from_millis(100);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert!(report.failed_requests > 0);
+    assert!(report.errors.unwrap().counts["request_timeout"] > 0);
+
+    // Starting from a generous timeout, every request passes and the timeout
+    // closes in on the warm latency
+    let mut config = http_config(warming_server(6).await, 200);
+    config.timeout = Duration::from_secs(1);
+    config.adaptive_timeout = Some(5.0);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 200);
+    assert_eq!(report.failed_requests, 0);
+    let adaptive = report.adaptive_timeout.unwrap();
+    assert!(adaptive < Duration::from_millis(500), "{:?}", adaptive);
+}
+
+#[tokio::test]
+async fn same_seed_picks_the_same_urls() {
+    let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let paths = paths.clone();
+        move |mut stream| {
+            let paths = paths.clone();
+            async move {
+                while let Some(request) = request_head(&mut stream).await {
+                    let target = request.split(' ').nth(1).unwrap_or_default();
+                    paths.lock().unwrap().push(target.rsplit('/').next().unwrap_or_default().to_string());
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }).await;
+    // One connection, so the order the server sees is the order picked
+    let picks = |seed| {
+        let paths = paths.clone();
+        async move {
+            let mut config = http_config(addr, 40);
+            config.url = format!("http://{}/a", addr);
+            config.extra_urls = vec![format!("http://{}/b", addr), format!("http://{}/c", addr)];
+            config.concurrency = 1;
+            config.seed = Some(seed);
+            let report = HttpRunner::new(config).run().await.unwrap();
+            assert_eq!(report.successful_requests, 40);
+            std::mem::take(&mut *paths.lock().unwrap())
+        }
+    };
+
+    let first = picks(7).await;
+    assert_eq!(first.len(), 40);
+    for path in ["a", "b", "c"] {
+        assert!(first.iter().any(|p| p == path), "{} never picked: {:?}", path, first);
+    }
+    assert_eq!(picks(7).await, first);
+    assert_ne!(picks(8).await, first);
+}
+
+#[tokio::test]
+async fn cache_headers_are_counted_as_hits_and_misses() {
+    // Every fourth response is a hit, the next two misses, the last one says nothing
+    let served = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server(move |mut stream| {
+        let served = served.clone();
+        async move {
+            while request_head(&mut stream).await.is_some() {
+                let cache = match served.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % 4 {
+                    0 => "X-Cache: Hit from cloudfront\r\n",
+                    1 => "X-Cache: MISS\r\n",
+                    2 => "X-Cache-Status: EXPIRED\r\n",
+                    _ => "",
+                };
+                let response = format!("HTTP/1.1 200 OK\r\n{}Content-Length: 2\r\n\r\nok", cache);
+                if stream.write_all(response.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }).await;
+    let mut config = http_config(addr, 12);
+    config.cache_stats = true;
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 12);
+    let cache = report.cache_stats.unwrap();
+    assert_eq!((cache.hits, cache.misses, cache.unknown), (3, 6, 3));
+    assert_eq!(cache.hit_ratio(), Some(1.0 / 3.0));
+}
+
+/// Log lines written to a shared buffer, for checking what a run traced
+#[derive(Clone, Default)]
+struct CapturedLog(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// A current-thread runtime keeps every task of the run under the test's subscriber
+#[tokio::test(flavor = "current_thread")]
+async fn failed_request_logs_a_warning_with_its_error_kind() {
+    let log = CapturedLog::default();
+    let writer = log.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(300),
+        ..ServerOptions::default()
+    }).await;
+    let mut config = http_config(addr, 2);
+    config.timeout = Duration::from_millis(50);
+    let report = HttpRunner::new(config).run().await.unwrap();
+    assert_eq!(report.failed_requests, 2);
+
+    let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+    let warnings: Vec<&str> = log.lines().filter(|line| line.contains("WARN")).collect();
+    assert_eq!(warnings.len(), 2, "{}", log);
+    for warning in warnings {
+        assert!(warning.contains("request failed"), "{}", warning);
+        assert!(warning.contains("kind=\"request_timeout\""), "{}", warning);
+        // Tagged with the worker that sent the request
+        assert!(warning.contains("worker{id="), "{}", warning);
+    }
+}
@@ -0,0 +1,66 @@
+//! Heap use of a run, measured by a counting allocator. It lives in its own
+//! test binary, since the allocator counts every test running alongside.
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use thrustbench::config::HttpConfig;
+use thrustbench::runner::HttpRunner;
+use thrustbench::server::ServerOptions;
+
+/// The system allocator, keeping track of the bytes in use and the most in
+/// use at once
+struct Counting;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let in_use = IN_USE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(in_use, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Most heap a run of `requests` took on top of what was in use before it
+async fn peak_heap(addr: std::net::SocketAddr, requests: usize, low_memory: bool) -> usize {
+    let mut config = HttpConfig::new(
+        format!("http://{}/", addr), Some("POST".to_string()), None, None, None,
+        Some(2), Some(requests), Some(Duration::from_secs(30)), Some(2000), true,
+    );
+    config.low_memory = low_memory;
+
+    let before = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let report = HttpRunner::new(config).run().await.unwrap();
+    assert_eq!(report.successful_requests, requests);
+    PEAK.load(Ordering::Relaxed).saturating_sub(before)
+}
+
+#[tokio::test]
+async fn low_memory_heap_stays_flat_as_the_requests_grow() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    let small = peak_heap(addr, 1_000, true).await;
+    let large = peak_heap(addr, 10_000, true).await;
+    // Ten times the requests, at most a little more heap
+    assert!(large < small + 256 * 1024, "{} bytes for 1000 requests, {} for 10000", small, large);
+
+    // Keeping every sample grows with the requests
+    let kept = peak_heap(addr, 10_000, false).await;
+    assert!(kept > large + 1024 * 1024, "{} bytes keeping the samples, {} without", kept, large);
+}
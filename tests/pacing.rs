@@ -0,0 +1,87 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use thrustbench::config::{Arrival, HttpConfig, TcpConfig};
+use thrustbench::runner::{HttpRunner, TcpRunner};
+use thrustbench::server::ServerOptions;
+
+fn http_config(url: String, requests: usize) -> HttpConfig {
+    HttpConfig::new(url, None, None, None, None, Some(2), Some(requests), Some(Duration::from_secs(10)), Some(2000), true)
+}
+
+#[tokio::test]
+async fn paced_run_follows_the_rate_and_reports_the_correction() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let mut config = http_config(format!("http://{}/", addr), 20);
+    config.rate = Some(100.0);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 20);
+    // The 20th slot is 190ms after the first
+    assert!(report.total_time >= Duration::from_millis(180), "{:?}", report.total_time);
+    let correction = report.latency_correction.expect("paced runs report the correction");
+    assert_eq!(correction.target_rate, 100.0);
+    assert!(!correction.applied);
+    assert!(correction.corrected.max >= correction.uncorrected.max);
+}
+
+#[tokio::test]
+async fn unpaced_run_has_no_correction() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let report = HttpRunner::new(http_config(format!("http://{}/", addr), 10)).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert!(report.latency_correction.is_none());
+}
+
+/// Mean and coefficient of variation, in seconds, of the gaps between the
+/// requests of a seeded TCP run paced at 200 req/s, as the server saw them
+async fn tcp_arrival_gaps(arrival: Arrival) -> (f64, f64) {
+    let arrivals = Arc::new(Mutex::new(Vec::new()));
+    let recorded = arrivals.clone();
+    let addr = common::raw_server(move |mut stream| {
+        let arrivals = recorded.clone();
+        async move {
+            let mut request = [0; 5];
+            while stream.read_exact(&mut request).await.is_ok() {
+                arrivals.lock().unwrap().push(Instant::now());
+                if stream.write_all(b"hello").await.is_err() {
+                    break;
+                }
+            }
+        }
+    }).await;
+    let mut config = TcpConfig::new(
+        addr.to_string(), Some("hello".to_string()), None, Some("hello".to_string()),
+        Some(4), Some(200), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+    config.rate = Some(200.0);
+    config.arrival = arrival;
+    config.seed = Some(7);
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 200);
+    let mut arrivals = arrivals.lock().unwrap().clone();
+    arrivals.sort();
+    let gaps: Vec<f64> = arrivals.windows(2).map(|pair| (pair[1] - pair[0]).as_secs_f64()).collect();
+    let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+    let variance = gaps.iter().map(|gap| (gap - mean).powi(2)).sum::<f64>() / gaps.len() as f64;
+    (mean, variance.sqrt() / mean)
+}
+
+#[tokio::test]
+async fn poisson_arrivals_keep_the_rate_of_constant_ones_with_more_variance() {
+    let (constant_mean, constant_cv) = tcp_arrival_gaps(Arrival::Constant).await;
+    let (poisson_mean, poisson_cv) = tcp_arrival_gaps(Arrival::Poisson).await;
+
+    // 5ms apart on average either way
+    assert!((0.004..=0.006).contains(&constant_mean), "{}", constant_mean);
+    assert!((0.004..=0.006).contains(&poisson_mean), "{}", poisson_mean);
+    // Exponential gaps vary about as much as their mean; constant ones barely
+    assert!(constant_cv < 0.4, "{}", constant_cv);
+    assert!(poisson_cv > 0.7, "{}", poisson_cv);
+}
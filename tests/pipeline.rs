@@ -0,0 +1,154 @@
+mod common;
+
+use std::time::Duration;
+use hyper::Uri;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use thrustbench::config::HttpConfig;
+use thrustbench::http::{self, PipelinedConnection};
+use thrustbench::limiter::ConnectLimiter;
+use thrustbench::runner::HttpRunner;
+use thrustbench::server::ServerOptions;
+use thrustbench::tls::TlsOptions;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Answers every request head read off `stream` with `respond(n)`, `n`
+/// counting from 0, until `respond` returns `None`; then closes
+async fn answer(mut stream: TcpStream, respond: fn(usize) -> Option<&'static [u8]>) {
+    let mut received = Vec::new();
+    let mut answered = 0;
+    let mut buffer = [0; 4096];
+    loop {
+        while let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+            received.drain(..end + 4);
+            match respond(answered) {
+                Some(response) => stream.write_all(response).await.unwrap(),
+                None => return,
+            }
+            answered += 1;
+        }
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => received.extend_from_slice(&buffer[..n]),
+        }
+    }
+}
+
+async fn pipelined(addr: std::net::SocketAddr) -> PipelinedConnection {
+    let uri: Uri = format!("http://{}/", addr).parse().unwrap();
+    PipelinedConnection::connect(&uri, &TlsOptions::default(), &ConnectLimiter::new(None), None, TIMEOUT).await.unwrap()
+}
+
+fn request(method: &str, addr: std::net::SocketAddr) -> Vec<u8> {
+    let uri: Uri = format!("http://{}/", addr).parse().unwrap();
+    http::encode_request(&uri, method, &[], None).unwrap()
+}
+
+#[tokio::test]
+async fn pipelined_run_against_the_echo_server() {
+    let addr = common::http_server(ServerOptions { response_size: Some(16), ..Default::default() }).await;
+    let mut config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(2), Some(40), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+    config.pipeline = Some(4);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 40);
+    assert_eq!(report.failed_requests, 0);
+    assert_eq!(report.bytes_received, 40 * 16);
+}
+
+#[tokio::test]
+async fn connection_close_ends_the_batch() {
+    let addr = common::raw_server(|stream| answer(stream, |n| {
+        (n == 0).then_some(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".as_slice())
+    })).await;
+
+    let mut conn = pipelined(addr).await;
+    let responses = conn.send_batch(&request("GET", addr), 4, TIMEOUT).await.unwrap();
+
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].body_len, 2);
+    assert!(conn.is_closed());
+}
+
+#[tokio::test]
+async fn connection_close_fails_the_unanswered_requests() {
+    let addr = common::raw_server(|stream| answer(stream, |n| {
+        (n == 0).then_some(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".as_slice())
+    })).await;
+    let mut config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(1), Some(8), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+    config.pipeline = Some(4);
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    // Each batch of 4 gets one answer before the server closes
+    assert_eq!(report.successful_requests, 2);
+    assert_eq!(report.failed_requests, 6);
+}
+
+#[tokio::test]
+async fn head_responses_have_no_body() {
+    let addr = common::raw_server(|stream| answer(stream, |_| {
+        Some(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".as_slice())
+    })).await;
+
+    let mut conn = pipelined(addr).await;
+    let responses = conn.send_batch(&request("HEAD", addr), 3, TIMEOUT).await.unwrap();
+
+    assert_eq!(responses.len(), 3);
+    assert!(responses.iter().all(|r| r.body_len == 0));
+    assert!(!conn.is_closed());
+}
+
+#[tokio::test]
+async fn close_delimited_body_is_counted() {
+    let addr = common::raw_server(|stream| answer(stream, |n| {
+        (n == 0).then_some(b"HTTP/1.1 200 OK\r\n\r\nhello, world".as_slice())
+    })).await;
+
+    let mut conn = pipelined(addr).await;
+    let responses = conn.send_batch(&request("GET", addr), 2, TIMEOUT).await.unwrap();
+
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].body_len, 12);
+    assert!(conn.is_closed());
+}
+
+#[tokio::test]
+async fn interim_responses_are_skipped() {
+    let addr = common::raw_server(|stream| answer(stream, |_| {
+        Some(b"HTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\nHTTP/1.1 404 Not Found\r\nContent-Length: 3\r\n\r\nnah".as_slice())
+    })).await;
+
+    let mut conn = pipelined(addr).await;
+    let responses = conn.send_batch(&request("GET", addr), 2, TIMEOUT).await.unwrap();
+
+    assert_eq!(responses.len(), 2);
+    assert!(responses.iter().all(|r| r.status == 404 && r.body_len == 3));
+}
+
+#[tokio::test]
+async fn timed_out_pipelined_responses_are_not_counted_by_status() {
+    let addr = common::http_server(ServerOptions {
+        response_delay: Duration::from_millis(20),
+        ..Default::default()
+    }).await;
+    let mut config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(1), Some(4), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+    config.pipeline = Some(2);
+    config.strict_timeout = Some(Duration::from_millis(1));
+
+    let report = HttpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.failed_requests, 4);
+    assert_eq!(report.status_codes, Some(Default::default()));
+}
@@ -0,0 +1,301 @@
+mod common;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
+use thrustbench::config::{TcpConfig, UdsConfig};
+use thrustbench::error::BenchmarkError;
+use thrustbench::protocol::{Connection, Echo, Protocol, Request, Response};
+use thrustbench::runner::{TcpRunner, UdsRunner};
+
+/// Sends the payload behind a 4-byte big-endian length and reads back one
+/// frame of the same shape
+struct LengthPrefixed;
+
+#[async_trait]
+impl Protocol for LengthPrefixed {
+    async fn exchange(
+        &self,
+        conn: &mut dyn Connection,
+        request: &Request<'_>,
+    ) -> Result<Response, BenchmarkError> {
+        let start_time = Instant::now();
+        let data = request.data.unwrap_or_default();
+
+        conn.write_u32(data.len() as u32).await?;
+        conn.write_all(data).await?;
+
+        let len = conn.read_u32().await?;
+        let first_byte = start_time.elapsed();
+        let mut response = vec![0; len as usize];
+        conn.read_exact(&mut response).await?;
+
+        Ok(Response {
+            data: response,
+            first_byte: Some(first_byte),
+            elapsed: start_time.elapsed(),
+        })
+    }
+}
+
+/// Echoes one length-prefixed frame per connection
+async fn echo_frame<S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin>(mut stream: S) {
+    let Ok(len) = stream.read_u32().await else { return };
+    let mut buf = vec![0; len as usize];
+    if stream.read_exact(&mut buf).await.is_ok() {
+        let _ = stream.write_u32(len).await;
+        let _ = stream.write_all(&buf).await;
+    }
+}
+
+#[tokio::test]
+async fn tcp_runner_drives_a_custom_protocol() {
+    let addr = common::raw_server(echo_frame).await;
+    let config = TcpConfig::new(
+        addr.to_string(), Some("hello".to_string()), None, None,
+        Some(2), Some(10), Some(Duration::from_secs(10)), Some(2000), false,
+    );
+
+    let report = TcpRunner::new(config).with_protocol(Box::new(LengthPrefixed)).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.failed_requests, 0);
+    // Only the payload of each frame counts, not its length prefix
+    assert_eq!(report.bytes_received, 10 * 5);
+}
+
+#[tokio::test]
+async fn uds_runner_drives_a_custom_protocol() {
+    let path = std::env::temp_dir().join(format!("thrustbench-protocol-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(echo_frame(stream));
+        }
+    });
+    let config = UdsConfig::new(
+        path.clone(), Some("hello".to_string()), None, None,
+        Some(2), Some(10), Some(Duration::from_secs(10)), Some(2000), false,
+    );
+
+    let report = UdsRunner::new(config).with_protocol(Box::new(LengthPrefixed)).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.failed_requests, 0);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn repeat_exchanges_on_one_socket_and_counts_the_bytes_written() {
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let frames = frames.clone();
+        move |mut stream| {
+            let frames = frames.clone();
+            async move {
+                let mut count = 0;
+                while let Ok(len) = stream.read_u32().await {
+                    let mut buf = vec![0; len as usize];
+                    if stream.read_exact(&mut buf).await.is_err()
+                        || stream.write_u32(len).await.is_err()
+                        || stream.write_all(&buf).await.is_err()
+                    {
+                        break;
+                    }
+                    count += 1;
+                }
+                frames.lock().unwrap().push(count);
+            }
+        }
+    }).await;
+    let mut config = TcpConfig::new(
+        addr.to_string(), Some("hello".to_string()), None, None,
+        Some(2), Some(4), Some(Duration::from_secs(10)), Some(2000), false,
+    );
+    config.repeat = 5;
+
+    let report = TcpRunner::new(config).with_protocol(Box::new(LengthPrefixed)).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4);
+    assert_eq!(report.per_message.unwrap().messages, 4 * 5);
+    // Each frame is the payload behind its 4-byte length prefix
+    assert_eq!(report.bytes_sent, 4 * 5 * (4 + 5));
+    // The server sees each connection close once the runner drops it
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(*frames.lock().unwrap(), vec![5; 4]);
+}
+
+/// Stream that counts the reads returning data
+struct CountingReads {
+    inner: DuplexStream,
+    reads: usize,
+}
+
+impl AsyncRead for CountingReads {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if buf.filled().len() > before {
+            self.reads += 1;
+        }
+        poll
+    }
+}
+
+impl AsyncWrite for CountingReads {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Reads one 1 MiB response with `Echo`, returning the number of reads
+async fn read_large_response(response_size: Option<usize>) -> usize {
+    const SIZE: usize = 1024 * 1024;
+    let (client, mut server) = tokio::io::duplex(SIZE);
+    server.write_all(&vec![b'x'; SIZE]).await.unwrap();
+    server.shutdown().await.unwrap();
+
+    let mut conn = CountingReads { inner: client, reads: 0 };
+    let request = Request {
+        data: None,
+        expect: None,
+        expect_length: None,
+        require_non_empty: false,
+        timeout: Duration::from_secs(5),
+        buffer_size: 8192,
+        response_size,
+        half_close: false,
+        offload_above: None,
+    };
+    let response = Echo.exchange(&mut conn, &request).await.unwrap();
+    assert_eq!(response.data.len(), SIZE);
+    conn.reads
+}
+
+#[tokio::test]
+async fn response_size_hint_reads_a_large_response_in_fewer_calls() {
+    let unhinted = read_large_response(None).await;
+    let hinted = read_large_response(Some(1024 * 1024)).await;
+
+    // Without a hint every read goes through the 8 KiB buffer
+    assert_eq!(unhinted, 1024 * 1024 / 8192);
+    assert!(hinted < unhinted / 4, "{} reads with the hint, {} without", hinted, unhinted);
+}
+
+#[tokio::test]
+async fn expect_matches_a_marker_split_across_reads_of_a_binary_response() {
+    let (client, mut server) = tokio::io::duplex(64);
+    tokio::spawn(async move {
+        server.write_all(b"\xff\xfe\x00RE").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        server.write_all(b"ADY\x00\xc3").await.unwrap();
+    });
+    let pattern = thrustbench::protocol::Pattern::new("READY").unwrap();
+    let request = Request {
+        data: None,
+        expect: Some(&pattern),
+        expect_length: None,
+        require_non_empty: false,
+        timeout: Duration::from_secs(5),
+        buffer_size: 8192,
+        response_size: None,
+        half_close: false,
+        offload_above: None,
+    };
+
+    let mut conn = client;
+    let response = Echo.exchange(&mut conn, &request).await.unwrap();
+
+    assert_eq!(response.data, b"\xff\xfe\x00READY\x00\xc3");
+}
+
+/// How late a sibling task's 1ms sleep ends while `Echo` matches a pattern
+/// against a 4 MiB response on the same (current-thread) runtime, and how
+/// long that match takes on its own
+async fn sibling_delay_during_match(offload_above: Option<usize>) -> (Duration, Duration) {
+    const SIZE: usize = 4 * 1024 * 1024;
+    let mut body = vec![b'a'; SIZE];
+    body[SIZE - 1] = b'!';
+    let pattern = thrustbench::protocol::Pattern::new("[a-z]+!").unwrap();
+    let started = Instant::now();
+    assert!(pattern.is_match(&body));
+    let cost = started.elapsed();
+
+    let (client, mut server) = tokio::io::duplex(SIZE);
+    server.write_all(&body).await.unwrap();
+    let sibling = tokio::spawn(async {
+        let started = Instant::now();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        started.elapsed()
+    });
+    // Let the sibling start its sleep before the exchange takes the thread
+    tokio::task::yield_now().await;
+    let request = Request {
+        data: None,
+        expect: Some(&pattern),
+        expect_length: None,
+        require_non_empty: false,
+        timeout: Duration::from_secs(5),
+        buffer_size: 8192,
+        response_size: Some(SIZE),
+        half_close: false,
+        offload_above,
+    };
+
+    let mut conn = client;
+    let response = Echo.exchange(&mut conn, &request).await.unwrap();
+    assert_eq!(response.data.len(), SIZE);
+    (sibling.await.unwrap(), cost)
+}
+
+#[tokio::test]
+async fn offloaded_pattern_match_leaves_sibling_requests_on_time() {
+    let (inline, cost) = sibling_delay_during_match(None).await;
+    assert!(inline >= cost / 2, "sibling took {:?} behind an inline match of {:?}", inline, cost);
+
+    let (offloaded, cost) = sibling_delay_during_match(Some(1024 * 1024)).await;
+    assert!(offloaded < cost / 2, "sibling took {:?} behind an offloaded match of {:?}", offloaded, cost);
+}
+
+/// Fails every exchange as a connect that found no local port to use
+struct NoLocalPorts;
+
+#[async_trait]
+impl Protocol for NoLocalPorts {
+    async fn exchange(
+        &self,
+        _conn: &mut dyn Connection,
+        _request: &Request<'_>,
+    ) -> Result<Response, BenchmarkError> {
+        Err(BenchmarkError::from_connect(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable)))
+    }
+}
+
+#[tokio::test]
+async fn exhausted_ports_are_tallied_as_their_own_connect_failures() {
+    let addr = common::raw_server(echo_frame).await;
+    let config = TcpConfig::new(
+        addr.to_string(), Some("hello".to_string()), None, None,
+        Some(2), Some(6), Some(Duration::from_secs(10)), Some(2000), false,
+    );
+
+    let report = TcpRunner::new(config).with_protocol(Box::new(NoLocalPorts)).run().await.unwrap();
+
+    assert_eq!(report.failed_requests, 6);
+    let errors = report.errors.unwrap();
+    assert_eq!(errors.counts.get("ports_exhausted"), Some(&6));
+    assert_eq!(errors.connect_failures, 6);
+    assert_eq!(errors.io_failures, 0);
+}
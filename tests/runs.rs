@@ -0,0 +1,424 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use thrustbench::config::HttpConfig;
+use thrustbench::runner::{self, HttpRunner};
+use thrustbench::server::ServerOptions;
+
+#[tokio::test]
+async fn repeated_runs_are_aggregated() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(2), Some(10), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+
+    let aggregate = runner::run_repeated(3, Duration::ZERO, || {
+        let runner = HttpRunner::new(config.clone());
+        async move { runner.run().await }
+    }).await.unwrap();
+
+    assert_eq!(aggregate.runs, 3);
+    assert_eq!(aggregate.reports.len(), 3);
+    assert!(aggregate.reports.iter().all(|r| r.successful_requests == 10));
+    assert!(aggregate.requests_per_second.mean > 0.0);
+}
+
+#[test]
+fn repeated_runs_on_one_runtime_leave_no_tasks_behind() {
+    // The server gets its own runtime so only the runs' tasks are counted
+    let server = tokio::runtime::Runtime::new().unwrap();
+    let addr = server.block_on(common::http_server(ServerOptions::default()));
+    let config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(4), Some(40), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+
+    let client = tokio::runtime::Runtime::new().unwrap();
+    let aggregate = client.block_on(runner::run_repeated(3, Duration::ZERO, || {
+        let runner = HttpRunner::new(config.clone());
+        async move { runner.run().await }
+    })).unwrap();
+    assert!(aggregate.reports.iter().all(|r| r.successful_requests == 40));
+
+    // Connection tasks get a moment to wind down after the last run
+    let metrics = client.metrics();
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while metrics.num_alive_tasks() > 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(metrics.num_alive_tasks(), 0);
+}
+
+#[tokio::test]
+async fn sweep_reports_every_concurrency_level_in_order() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let config = HttpConfig::new(
+        format!("http://{}/", addr), None, None, None, None,
+        Some(1), Some(8), Some(Duration::from_secs(10)), Some(2000), true,
+    );
+
+    let sweep = runner::sweep_concurrency(&[1, 2, 4], Duration::ZERO, |concurrency| {
+        let mut config = config.clone();
+        config.concurrency = concurrency;
+        let runner = HttpRunner::new(config);
+        async move { runner.run().await }
+    }).await.unwrap();
+
+    let levels: Vec<usize> = sweep.steps.iter().map(|step| step.concurrency).collect();
+    assert_eq!(levels, [1, 2, 4]);
+    assert_eq!(sweep.reports.len(), 3);
+    assert!(sweep.reports.iter().all(|r| r.successful_requests == 8));
+    assert_eq!(sweep.steps[0].scaling_efficiency, 1.0);
+}
+
+#[tokio::test]
+async fn sweep_json_output_is_one_document_on_stdout() {
+    let addr = common::http_server(ServerOptions::default()).await;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_thrustbench"))
+        .args(["--no-history", "-r", "4", "--sweep-concurrency", "1,2", "--output", "json", "http"])
+        .arg(format!("http://{}/", addr))
+        .output()
+        .await
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    // The per-level progress lines go to stderr
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let steps = json["report"]["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1]["concurrency"], 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Concurrency 2 (2/2)"));
+}
+
+/// The `--status-json` line among what the binary wrote to stderr
+fn status_line(output: &std::process::Output) -> serde_json::Value {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stderr.lines().find(|line| line.starts_with("{\"ok\"")).unwrap_or_else(|| panic!("no status line in {}", stderr));
+    serde_json::from_str(line).unwrap()
+}
+
+#[tokio::test]
+async fn status_json_counts_every_run_on_stderr() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, _) = thrustbench(&["--status-json", "--runs", "2", "-r", "4", "-c", "2", "--output", "json", "http", &url]).await;
+
+    assert!(output.status.success());
+    // stdout holds only the report
+    serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+    let status = status_line(&output);
+    assert_eq!(status["ok"], true);
+    assert_eq!(status["total"], 8);
+    assert_eq!(status["failed"], 0);
+    assert!(status["rps"].as_f64().unwrap() > 0.0);
+    assert!(status.get("error").is_none());
+}
+
+#[tokio::test]
+async fn status_json_keeps_the_counts_of_a_failed_command() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+
+    // The run completes, then comparing it against the missing baseline fails
+    let (output, _) = thrustbench(&["--status-json", "--baseline", "/nonexistent/baseline.json", "-r", "4", "-c", "2", "http", &url]).await;
+
+    assert!(!output.status.success());
+    let status = status_line(&output);
+    assert_eq!(status["ok"], false);
+    assert_eq!(status["total"], 4);
+    assert!(status["error"].as_str().unwrap().contains("baseline"), "{}", status);
+
+    // Nothing ran when the command line is rejected
+    let (output, _) = thrustbench(&["--status-json", "--runs", "0", "http", &url]).await;
+    let status = status_line(&output);
+    assert_eq!(status["ok"], false);
+    assert!(status["total"].is_null());
+}
+
+/// Runs the binary with `args`, returning its output and how long it took.
+/// Its reports stay out of the developer's history.
+async fn thrustbench(args: &[&str]) -> (std::process::Output, Duration) {
+    let started = std::time::Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_thrustbench"))
+        .arg("--no-history")
+        .args(args)
+        .output()
+        .await
+        .unwrap();
+    (output, started.elapsed())
+}
+
+#[tokio::test]
+async fn max_duration_ends_a_run_against_a_server_that_never_answers() {
+    let addr = common::raw_server(|stream| async move {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        drop(stream);
+    }).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, elapsed) = thrustbench(&["--max-duration", "1", "-t", "60000", "-r", "4", "-c", "2", "--output", "json", "http", &url]).await;
+
+    assert!(!output.status.success());
+    assert!(elapsed < Duration::from_secs(5), "took {:?}", elapsed);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["report"]["stop_reason"], "max_duration");
+    assert_eq!(json["report"]["successful_requests"], 0);
+}
+
+#[tokio::test]
+async fn max_duration_covers_every_run() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, elapsed) = thrustbench(&["--max-duration", "700ms", "--runs", "3", "-d", "500ms", "-r", "0", "-c", "2", "http", &url]).await;
+
+    // Three half-second runs, cut off during the second
+    assert!(!output.status.success());
+    assert!(elapsed < Duration::from_millis(1500), "took {:?}", elapsed);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("max-duration"), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let (output, _) = thrustbench(&["--max-duration", "0s", "http", &url]).await;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-duration must be greater than zero"));
+}
+
+#[tokio::test]
+async fn only_errors_prints_the_error_body_in_place_of_the_report() {
+    let addr = common::raw_server(|mut stream| async move {
+        let mut buf = [0; 4096];
+        while matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {
+            let body = "upstream timed out\n";
+            let response = format!("HTTP/1.1 502 Bad Gateway\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, _) = thrustbench(&["--only-errors", "-r", "4", "-c", "1", "http", "--method", "GET", &url]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("http_502"), "{}", stdout);
+    assert!(stdout.contains("Body: upstream timed out"), "{}", stdout);
+    assert!(!stdout.contains("BENCHMARK REPORT"), "{}", stdout);
+}
+
+#[tokio::test]
+async fn rate_ramp_is_recorded_in_the_config_as_a_ramp() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+    let dir = std::env::temp_dir().join(format!("thrustbench-ramp-{}", std::process::id()));
+
+    let (output, _) = thrustbench(&["--rate-ramp", "50:100", "-d", "1", "--output-dir", dir.to_str().unwrap(), "http", &url]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("config.json")).unwrap()).unwrap();
+    assert!(config["rate"].is_null(), "{}", config);
+    assert_eq!(config["rate_ramp"], serde_json::json!({ "start": 50.0, "end": 100.0 }));
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report["rate_ramp"]["points"].as_array().unwrap().len(), 10);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn invalid_expect_pattern_fails_once_at_startup() {
+    let addr = common::tcp_server(ServerOptions::default()).await;
+
+    let (output, _) = thrustbench(&["-r", "100", "tcp", &addr.to_string(), "--data", "hello", "--expect", "(unclosed"]).await;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("Invalid regex pattern '(unclosed'").count(), 1, "{}", stderr);
+    // No report, so no request was counted as failed
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Failed Requests"));
+
+    // A --script setup step's pattern is checked as the script is loaded
+    let script = std::env::temp_dir().join(format!("thrustbench-bad-setup-{}.txt", std::process::id()));
+    std::fs::write(&script, "AUTH secret\\r\\n\t(unclosed\nGET key\\r\\n\n").unwrap();
+    let (output, _) = thrustbench(&["-r", "100", "tcp", &addr.to_string(), "--script", script.to_str().unwrap()]).await;
+    let _ = std::fs::remove_file(&script);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("Invalid regex pattern '(unclosed'").count(), 1, "{}", stderr);
+}
+
+#[tokio::test]
+async fn ndjson_prints_every_run_as_its_own_line() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, _) = thrustbench(&["--runs", "3", "-r", "4", "-c", "2", "--output", "ndjson", "http", &url]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    for line in lines {
+        let json: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(json["report"]["successful_requests"], 4);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn history_records_every_run_unless_turned_off() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+    let config_home = std::env::temp_dir().join(format!("thrustbench-history-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&config_home);
+    let history = config_home.join("thrustbench").join("history.jsonl");
+    let run = |no_history: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_thrustbench"));
+        command.env("XDG_CONFIG_HOME", &config_home);
+        if no_history {
+            command.arg("--no-history");
+        }
+        command.args(["--runs", "2", "-r", "2", "http", &url]).output()
+    };
+
+    assert!(run(false).await.unwrap().status.success());
+    let entries = std::fs::read_to_string(&history).unwrap();
+    assert_eq!(entries.lines().count(), 2);
+    for entry in entries.lines() {
+        let json: serde_json::Value = serde_json::from_str(entry).unwrap();
+        assert_eq!(json["report"]["successful_requests"], 2);
+    }
+
+    assert!(run(true).await.unwrap().status.success());
+    assert_eq!(std::fs::read_to_string(&history).unwrap().lines().count(), 2);
+    let _ = std::fs::remove_dir_all(&config_home);
+}
+
+#[tokio::test]
+async fn history_file_replaces_the_default_history() {
+    let addr = common::http_server(ServerOptions::default()).await;
+    let url = format!("http://{}/", addr);
+    let path = std::env::temp_dir().join(format!("thrustbench-history-file-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path_arg = path.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_thrustbench"))
+        .args(["--history-file", path_arg, "-r", "2", "http", &url])
+        .output()
+        .await
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+    let (output, _) = thrustbench(&["--history-file", path_arg, "-r", "2", "http", &url]).await;
+    // `--no-history` and `--history-file` contradict each other
+    assert!(!output.status.success());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn insecure_runs_are_marked_in_the_report_and_warned_about() {
+    let addr = common::tls_server().await;
+    let url = format!("https://localhost:{}/", addr.port());
+
+    for insecure in [true, false] {
+        let mut args = vec!["-r", "2", "-c", "1", "--output", "json", "http", &url];
+        if insecure {
+            args.push("--insecure");
+        }
+        let (output, _) = thrustbench(&args).await;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let report = &json["report"];
+
+        assert_eq!(json["config"]["insecure"], insecure);
+        assert_eq!(report["tls_verification"], !insecure, "{}", report);
+        assert_eq!(stderr.contains("TLS certificate verification is disabled"), insecure, "{}", stderr);
+        // Only the verified run rejects the self-signed certificate
+        let successes = report["successful_requests"].as_u64().unwrap();
+        assert_eq!(successes, if insecure { 2 } else { 0 }, "{}", report);
+    }
+}
+
+#[tokio::test]
+async fn warmup_waits_out_a_warming_server_and_keeps_its_requests_out_of_the_report() {
+    // Each request answers 5ms faster than the one before, down from 150ms
+    let served = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let addr = common::raw_server({
+        let served = served.clone();
+        move |mut stream| {
+            let served = served.clone();
+            async move {
+                let mut buf = [0; 1024];
+                while let Ok(n) = stream.read(&mut buf).await && n > 0 {
+                    let n = served.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    tokio::time::sleep(Duration::from_millis(150u64.saturating_sub(n * 5))).await;
+                    if stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }).await;
+    let url = format!("http://{}/", addr);
+
+    let (output, _) = thrustbench(&[
+        "--warmup-until-stable", "--warmup-window", "2", "--warmup-cv", "10", "--warmup-max", "10",
+        "--rate", "100", "-r", "40", "-c", "2", "--output", "json", "http", &url,
+    ]).await;
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let report = &serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["report"];
+    assert_eq!(report["warmup"]["stable"], true, "{}", report);
+    // Warming up took the slow start and two steady seconds
+    assert!(report["warmup"]["duration"]["secs"].as_u64().unwrap() >= 2, "{}", report);
+    // Only the measured requests are reported, all of them warm
+    assert_eq!(report["total_requests"], 40);
+    assert_eq!(report["successful_requests"], 40);
+    assert!(served.load(std::sync::atomic::Ordering::Relaxed) > 30 + 40);
+    assert_eq!(report["max_response_time"]["secs"], 0);
+    assert!(report["max_response_time"]["nanos"].as_u64().unwrap() < 50_000_000, "{}", report);
+}
+
+#[tokio::test]
+async fn wait_for_ready_holds_the_run_until_the_server_starts() {
+    // A free port nothing listens on yet
+    let addr = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let url = format!("http://{}/", addr);
+
+    // Without waiting, every request is refused
+    let (output, _) = thrustbench(&["-r", "4", "-c", "2", "--output", "json", "http", &url]).await;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["report"]["successful_requests"], 0);
+
+    // The server comes up 600ms after the benchmark starts
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        thrustbench::server::serve_http(listener, ServerOptions::default()).await
+    });
+    let (output, elapsed) = thrustbench(&["--wait-for-ready", "5", "-r", "4", "-c", "2", "--output", "json", "http", &url]).await;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("Waiting up to 5s"), "{}", stderr);
+    assert!(elapsed >= Duration::from_millis(600), "took {:?}", elapsed);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["report"]["successful_requests"], 4);
+}
+
+#[tokio::test]
+async fn wait_for_ready_gives_up_on_a_server_that_never_starts() {
+    let addr = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let url = format!("http://{}/", addr);
+
+    let (output, elapsed) = thrustbench(&["--wait-for-ready", "1", "-r", "4", "http", &url]).await;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("was not ready within 1s"), "{}", stderr);
+    assert!((Duration::from_secs(1)..Duration::from_secs(4)).contains(&elapsed), "took {:?}", elapsed);
+}
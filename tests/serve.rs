@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::process::Command;
+use thrustbench::config::UdsConfig;
+use thrustbench::runner::UdsRunner;
+
+/// Waits until the server started by `serve` has bound `path`
+async fn wait_for_socket(path: &PathBuf) {
+    for _ in 0..100 {
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("serve never listened on {:?}", path);
+}
+
+#[tokio::test]
+async fn serve_subcommand_answers_a_benchmark_end_to_end() {
+    let path = std::env::temp_dir().join(format!("thrustbench-serve-{}.sock", std::process::id()));
+    let mut server = Command::new(env!("CARGO_BIN_EXE_thrustbench"))
+        .args(["serve", "--protocol", "uds", "--response-size", "16", "--close", "--path"])
+        .arg(&path)
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+    wait_for_socket(&path).await;
+    let config = UdsConfig::new(
+        path.clone(), Some("ping".to_string()), None, None,
+        Some(2), Some(10), Some(Duration::from_secs(10)), Some(2000), false,
+    );
+
+    let report = UdsRunner::new(config).run().await.unwrap();
+
+    server.kill().await.unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.bytes_received, 10 * 16);
+}
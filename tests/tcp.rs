@@ -0,0 +1,430 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use thrustbench::config::{ExpectedLength, ProxyProtocol, TcpConfig};
+use thrustbench::error::BenchmarkError;
+use thrustbench::runner::TcpRunner;
+use thrustbench::server::ServerOptions;
+
+fn tcp_config(addr: std::net::SocketAddr, requests: usize) -> TcpConfig {
+    TcpConfig::new(
+        addr.to_string(), Some("hello".to_string()), None, None,
+        Some(2), Some(requests), Some(Duration::from_secs(10)), Some(2000), false,
+    )
+}
+
+#[tokio::test]
+async fn reset_mid_response_is_counted_as_a_connection_reset() {
+    let addr = common::raw_server(|mut stream| async move {
+        let mut request = [0; 5];
+        if stream.read_exact(&mut request).await.is_ok() {
+            let _ = stream.write_all(b"hel").await;
+            // A zero linger turns the close into a RST
+            let _ = stream.set_zero_linger();
+        }
+    }).await;
+    let mut config = tcp_config(addr, 4);
+    config.expects = vec!["world".to_string()];
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 0);
+    assert_eq!(report.errors.unwrap().counts["connection_reset"], 4);
+}
+
+#[tokio::test]
+async fn half_close_gets_an_answer_from_a_server_reading_to_eof() {
+    let addr = common::raw_server(|mut stream| async move {
+        let mut request = Vec::new();
+        if stream.read_to_end(&mut request).await.is_ok() {
+            let _ = stream.write_all(b"done\n").await;
+        }
+    }).await;
+
+    for half_close in [false, true] {
+        let mut config = tcp_config(addr, 4);
+        config.expects = vec!["done".to_string()];
+        config.timeout = Duration::from_millis(300);
+        config.half_close = half_close;
+        
+        let report = TcpRunner::new(config).run().await.unwrap();
+        
+        if half_close {
+            assert_eq!(report.successful_requests, 4);
+            assert_eq!(report.bytes_received, 4 * 5);
+        } else {
+            // The server never sees EOF, so it never answers
+            assert_eq!(report.successful_requests, 0);
+            assert_eq!(report.errors.unwrap().counts["request_timeout"], 4);
+        }
+    }
+}
+
+#[tokio::test]
+async fn proxy_source_of_another_family_is_rejected_before_connecting() {
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let connections = connections.clone();
+        move |_stream| {
+            connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            async {}
+        }
+    }).await;
+    let mut config = tcp_config(addr, 4);
+    config.proxy_protocol = Some(ProxyProtocol::V1);
+    config.proxy_source = Some("[2001:db8::7]:4000".parse().unwrap());
+
+    let result = TcpRunner::new(config).run().await;
+
+    assert!(matches!(result, Err(BenchmarkError::Config(_))), "{:?}", result.map(|r| r.total_requests));
+    assert_eq!(connections.load(std::sync::atomic::Ordering::Relaxed), 0);
+}
+
+/// Reads a PROXY v1 or v2 header and answers with the client address it
+/// claims, followed by the payload
+async fn proxy_echo(mut stream: tokio::net::TcpStream) {
+    let mut signature = [0; 12];
+    if stream.read_exact(&mut signature).await.is_err() {
+        return;
+    }
+    let source = if signature.starts_with(b"PROXY ") {
+        let mut line = signature.to_vec();
+        while !line.ends_with(b"\r\n") {
+            let mut byte = [0; 1];
+            if stream.read_exact(&mut byte).await.is_err() {
+                return;
+            }
+            line.push(byte[0]);
+        }
+        // PROXY TCP4 <src> <dst> <sport> <dport>
+        let line = String::from_utf8_lossy(&line).into_owned();
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+        format!("{}:{}", fields[2], fields[4])
+    } else {
+        let mut head = [0; 4];
+        if stream.read_exact(&mut head).await.is_err() {
+            return;
+        }
+        let mut addresses = vec![0; u16::from_be_bytes([head[2], head[3]]) as usize];
+        if stream.read_exact(&mut addresses).await.is_err() {
+            return;
+        }
+        // AF_INET over STREAM: source, destination, source port, destination port
+        assert_eq!(head[1], 0x11);
+        let ip = std::net::Ipv4Addr::new(addresses[0], addresses[1], addresses[2], addresses[3]);
+        format!("{}:{}", ip, u16::from_be_bytes([addresses[8], addresses[9]]))
+    };
+    let mut payload = [0; 5];
+    if stream.read_exact(&mut payload).await.is_ok() {
+        let _ = stream.write_all(format!("from {} {}\n", source, String::from_utf8_lossy(&payload)).as_bytes()).await;
+    }
+}
+
+#[tokio::test]
+async fn proxy_header_announces_the_configured_source() {
+    let addr = common::raw_server(proxy_echo).await;
+
+    for version in [ProxyProtocol::V1, ProxyProtocol::V2] {
+        let mut config = tcp_config(addr, 4);
+        config.proxy_protocol = Some(version);
+        config.proxy_source = Some("203.0.113.7:4000".parse().unwrap());
+        config.expects = vec!["from 203.0.113.7:4000 hello\n".to_string()];
+
+        let report = TcpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, 4, "{:?}", version);
+    }
+}
+
+#[tokio::test]
+async fn expect_length_fails_responses_under_or_over_the_length() {
+    let addr = common::tcp_server(ServerOptions::default()).await;
+
+    // The echo server answers with the 5 bytes of "hello"; waiting for a
+    // sixth byte lasts until the timeout
+    for (exact, successful) in [(5, 4), (4, 0), (6, 0)] {
+        let mut config = tcp_config(addr, 4);
+        config.timeout = Duration::from_millis(300);
+        config.expect_length = ExpectedLength::new(Some(exact), None, None).unwrap();
+
+        let report = TcpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, successful, "--expect-length {}", exact);
+        if successful == 0 {
+            assert_eq!(report.errors.unwrap().counts["response_validation"], 4);
+        }
+    }
+}
+
+#[tokio::test]
+async fn refused_connects_and_timed_out_responses_land_in_their_own_bucket() {
+    // Nothing listens on a port once its listener is dropped
+    let refused = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let silent = common::raw_server(|mut stream| async move {
+        let _ = stream.read_to_end(&mut Vec::new()).await;
+    }).await;
+
+    let report = TcpRunner::new(tcp_config(refused, 4)).run().await.unwrap();
+    let errors = report.errors.unwrap();
+    assert_eq!((errors.connect_failures, errors.io_failures), (4, 0));
+
+    let mut config = tcp_config(silent, 4);
+    config.expects = vec!["hello".to_string()];
+    config.timeout = Duration::from_millis(200);
+    let report = TcpRunner::new(config).run().await.unwrap();
+    let errors = report.errors.unwrap();
+    assert_eq!(errors.counts["request_timeout"], 4);
+    assert_eq!((errors.connect_failures, errors.io_failures), (0, 4));
+}
+
+#[tokio::test]
+async fn connect_only_opens_connections_and_sends_nothing() {
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let received = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let (connections, received) = (connections.clone(), received.clone());
+        move |mut stream| {
+            connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let received = received.clone();
+            async move {
+                let mut buf = [0; 1024];
+                while let Ok(n @ 1..) = stream.read(&mut buf).await {
+                    received.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        }
+    }).await;
+    let mut config = tcp_config(addr, 10);
+    config.connect_only = true;
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 10);
+    assert_eq!(report.bytes_sent, 0);
+    assert!(report.connects_per_second.is_some_and(|rate| rate > 0.0));
+    // Every connection is closed without the payload written to it
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(connections.load(std::sync::atomic::Ordering::Relaxed), 10);
+    assert_eq!(received.load(std::sync::atomic::Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn invalid_expect_fails_the_run_before_connecting() {
+    let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let addr = common::raw_server({
+        let connections = connections.clone();
+        move |_stream| {
+            connections.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            async {}
+        }
+    }).await;
+    let mut config = tcp_config(addr, 100);
+    config.expects = vec!["(unclosed".to_string()];
+
+    let started = std::time::Instant::now();
+    let result = TcpRunner::new(config).run().await;
+
+    assert!(matches!(result, Err(BenchmarkError::Parse(_))), "{:?}", result.map(|r| r.total_requests));
+    assert!(started.elapsed() < Duration::from_millis(100), "took {:?}", started.elapsed());
+    assert_eq!(connections.load(std::sync::atomic::Ordering::Relaxed), 0);
+}
+
+#[tokio::test]
+async fn require_non_empty_fails_empty_responses() {
+    let addr = common::tcp_server(ServerOptions {
+        response_size: Some(0),
+        close: true,
+        ..ServerOptions::default()
+    }).await;
+
+    for require_non_empty in [false, true] {
+        let mut config = tcp_config(addr, 4);
+        config.timeout = Duration::from_millis(500);
+        config.require_non_empty = require_non_empty;
+
+        let report = TcpRunner::new(config).run().await.unwrap();
+
+        if require_non_empty {
+            assert_eq!(report.successful_requests, 0);
+            assert_eq!(report.errors.unwrap().counts["response_validation"], 4);
+        } else {
+            assert_eq!(report.successful_requests, 4);
+        }
+    }
+}
+
+/// Answers `AUTH secret` with OK and, once authenticated, `GET key` with its
+/// value; anything else closes the connection. Each connection's lines are
+/// added to `log` when it closes.
+async fn auth_server(log: Arc<Mutex<Vec<Vec<String>>>>) -> std::net::SocketAddr {
+    use tokio::io::AsyncBufReadExt;
+
+    common::raw_server(move |stream| {
+        let log = log.clone();
+        async move {
+            let (read, mut write) = stream.into_split();
+            let mut lines = tokio::io::BufReader::new(read).lines();
+            let mut received = Vec::new();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let reply: &[u8] = match line.as_str() {
+                    "AUTH secret" => b"OK\r\n",
+                    "GET key" if received.last().is_some_and(|l| l == "AUTH secret") => b"VALUE 42\r\n",
+                    _ => b"",
+                };
+                received.push(line);
+                if reply.is_empty() || write.write_all(reply).await.is_err() {
+                    break;
+                }
+            }
+            log.lock().unwrap().push(received);
+        }
+    }).await
+}
+
+/// A TCP config running `script` against `addr` the way `--script` does
+fn scripted_config(addr: std::net::SocketAddr, requests: usize, script: &str) -> TcpConfig {
+    let path = std::env::temp_dir().join(format!("thrustbench-script-{}-{}.txt", std::process::id(), addr.port()));
+    std::fs::write(&path, script).unwrap();
+    let mut steps = thrustbench::config::load_script(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let measured = steps.pop().unwrap();
+    let mut config = tcp_config(addr, requests);
+    config.payloads = vec![measured.send];
+    config.expects = measured.expect.iter().map(|p| p.as_str().to_string()).collect();
+    config.setup = steps;
+    config.timeout = Duration::from_millis(500);
+    config
+}
+
+/// Lines of every closed connection, once all `connections` have closed
+async fn closed_connections(log: &Mutex<Vec<Vec<String>>>, connections: usize) -> Vec<Vec<String>> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while log.lock().unwrap().len() < connections && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    log.lock().unwrap().clone()
+}
+
+#[tokio::test]
+async fn script_logs_in_before_every_measured_request() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let addr = auth_server(log.clone()).await;
+    let config = scripted_config(addr, 4, "# log in first\nAUTH secret\\r\\n\t^OK\nGET key\\r\\n\t^VALUE 42\n");
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4, "{:?}", report.errors);
+    // Only the measured exchange's response is counted
+    assert_eq!(report.bytes_received, 4 * "VALUE 42\r\n".len() as u64);
+    let connections = closed_connections(&log, 4).await;
+    assert_eq!(connections.len(), 4);
+    assert!(connections.iter().all(|lines| lines == &["AUTH secret", "GET key"]), "{:?}", connections);
+}
+
+#[tokio::test]
+async fn failed_setup_step_fails_the_request_before_the_measured_send() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let addr = auth_server(log.clone()).await;
+    let config = scripted_config(addr, 2, "AUTH wrong\\r\\n\t^OK\nGET key\\r\\n\t^VALUE\n");
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 0);
+    assert_eq!(report.errors.unwrap().counts["response_validation"], 2);
+    let connections = closed_connections(&log, 2).await;
+    assert!(connections.iter().all(|lines| lines == &["AUTH wrong"]), "{:?}", connections);
+}
+
+#[tokio::test]
+async fn requests_cycle_through_the_payloads_in_order() {
+    let path = std::env::temp_dir().join(format!("thrustbench-payloads-{}.txt", std::process::id()));
+    std::fs::write(&path, "alpha\n---\nbeta\n---\ngamma\n").unwrap();
+    let payloads = thrustbench::config::load_payloads(&path, "---\\n").unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    // Payloads in the order they arrived, each echoed back
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let received = received.clone();
+        move |mut stream| {
+            let received = received.clone();
+            async move {
+                let mut buf = [0; 64];
+                let Ok(n) = stream.read(&mut buf).await else { return };
+                received.lock().unwrap().push(String::from_utf8_lossy(&buf[..n]).into_owned());
+                let _ = stream.write_all(&buf[..n]).await;
+            }
+        }
+    }).await;
+
+    let mut config = tcp_config(addr, 7);
+    config.concurrency = 1;
+    config.payloads = payloads;
+    config.expects = vec!["^alpha".to_string(), "^beta".to_string(), "^gamma".to_string()];
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    // Each response matched the pattern of its own payload
+    assert_eq!(report.successful_requests, 7, "{:?}", report.errors);
+    assert_eq!(*received.lock().unwrap(), ["alpha\n", "beta\n", "gamma\n", "alpha\n", "beta\n", "gamma\n", "alpha\n"]);
+}
+
+#[tokio::test]
+async fn delayed_first_byte_shows_in_the_ttfb() {
+    // The first byte after 100ms, the rest of the response 100ms later
+    let addr = common::raw_server(|mut stream| async move {
+        let mut buf = [0; 64];
+        if stream.read(&mut buf).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if stream.write_all(b"d").await.is_err() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = stream.write_all(b"one\n").await;
+    }).await;
+    let mut config = tcp_config(addr, 4);
+    config.expects = vec!["done\n".to_string()];
+
+    let report = TcpRunner::new(config).run().await.unwrap();
+
+    assert_eq!(report.successful_requests, 4, "{:?}", report.errors);
+    let ttfb = report.ttfb.unwrap();
+    assert!(ttfb.p50 >= Duration::from_millis(100) && ttfb.max < Duration::from_millis(180), "{:?}", ttfb);
+    assert!(report.min_response_time >= Duration::from_millis(200), "{:?}", report.min_response_time);
+}
+
+// Any address in 127.0.0.0/8 is local on Linux; other systems only have 127.0.0.1
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn local_addr_is_the_source_the_server_sees() {
+    let peers = Arc::new(Mutex::new(Vec::new()));
+    let addr = common::raw_server({
+        let peers = peers.clone();
+        move |mut stream| {
+            peers.lock().unwrap().push(stream.peer_addr().unwrap().ip());
+            async move {
+                let mut buf = [0; 64];
+                if let Ok(n) = stream.read(&mut buf).await {
+                    let _ = stream.write_all(&buf[..n]).await;
+                }
+            }
+        }
+    }).await;
+
+    for local in ["127.0.0.2", "127.0.0.3"] {
+        let local: std::net::IpAddr = local.parse().unwrap();
+        peers.lock().unwrap().clear();
+        let mut config = tcp_config(addr, 4);
+        config.local_addr = Some(local);
+
+        let report = TcpRunner::new(config).run().await.unwrap();
+
+        assert_eq!(report.successful_requests, 4, "{:?}", report.errors);
+        assert_eq!(*peers.lock().unwrap(), [local; 4]);
+    }
+}